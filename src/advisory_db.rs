@@ -0,0 +1,36 @@
+//! Local snapshots of GitHub's advisory database, for offline use.
+//!
+//! A snapshot is a JSON array of advisories, in the same shape as
+//! GitHub's global advisories API returns them, e.g. as produced by
+//! `gh api --paginate '/advisories?ecosystem=actions' | jq -s add`.
+
+use anyhow::{Context, Result};
+use camino::Utf8Path;
+
+use crate::github_api::Advisory;
+
+pub(crate) struct AdvisoryDb {
+    advisories: Vec<Advisory>,
+}
+
+impl AdvisoryDb {
+    /// Loads an advisory snapshot from the given path.
+    pub(crate) fn load(path: &Utf8Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("couldn't read advisory database: {path}"))?;
+        let advisories = serde_json::from_str(&contents)
+            .with_context(|| format!("invalid advisory database: {path}"))?;
+
+        Ok(Self { advisories })
+    }
+
+    /// Returns every advisory in this snapshot for the `owner/repo` action,
+    /// regardless of affected versions.
+    pub(crate) fn advisories(&self, owner: &str, repo: &str) -> Vec<Advisory> {
+        self.advisories
+            .iter()
+            .filter(|advisory| advisory.concerns(owner, repo))
+            .cloned()
+            .collect()
+    }
+}
@@ -0,0 +1,103 @@
+//! A config-driven allowlist of trusted action (and image) publishers,
+//! shared by the audits that exempt first-party `uses:`.
+
+use serde::Deserialize;
+
+use crate::{models::Uses, utils::glob_matches};
+
+/// Patterns matched against `owner/repo`, under `trusted-actions`.
+///
+/// Each pattern may use `*` and `?` wildcards, and a leading `!` negates
+/// it. As in a `.gitignore`, the last pattern that matches wins, so
+/// `myorg/*` followed by `!myorg/legacy-*` trusts everything in `myorg`
+/// except its legacy repositories.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(transparent)]
+pub(crate) struct ActionAllowlist(Vec<String>);
+
+impl ActionAllowlist {
+    /// Returns whether the given `owner/repo` is trusted.
+    fn is_trusted_slug(&self, owner: &str, repo: &str) -> bool {
+        let (owner, repo) = (owner.to_ascii_lowercase(), repo.to_ascii_lowercase());
+
+        self.0
+            .iter()
+            .rev()
+            .find_map(|pattern| {
+                let (negated, pattern) = match pattern.strip_prefix('!') {
+                    Some(pattern) => (true, pattern),
+                    None => (false, pattern.as_str()),
+                };
+                let pattern = pattern.to_ascii_lowercase();
+                let (owner_pattern, repo_pattern) =
+                    pattern.split_once('/').unwrap_or((&pattern, "*"));
+
+                (glob_matches(owner_pattern, &owner) && glob_matches(repo_pattern, &repo))
+                    .then_some(!negated)
+            })
+            .unwrap_or(false)
+    }
+
+    /// Returns whether the given `uses:` comes from a trusted publisher.
+    ///
+    /// Docker images are matched by their name, without any registry, tag,
+    /// or digest: `ghcr.io/myorg/image:v1` is `myorg/image`. As on Docker
+    /// Hub, an image without an owner (like `node`) is `library/node`.
+    /// Local actions are never trusted, since they're part of the
+    /// repository being audited anyway.
+    pub(crate) fn is_trusted(&self, uses: &Uses) -> bool {
+        match uses {
+            Uses::Repository(uses) => self.is_trusted_slug(uses.owner, uses.repo),
+            Uses::Docker(docker) => {
+                let name = docker.image.split(':').next().unwrap_or(docker.image);
+                let (owner, repo) = name.split_once('/').unwrap_or(("library", name));
+
+                self.is_trusted_slug(owner, repo)
+            }
+            Uses::Local(_) => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ActionAllowlist;
+    use crate::models::Uses;
+
+    fn allowlist(patterns: &[&str]) -> ActionAllowlist {
+        ActionAllowlist(patterns.iter().map(|p| p.to_string()).collect())
+    }
+
+    #[test]
+    fn test_is_trusted() {
+        let empty = ActionAllowlist::default();
+        let globs = allowlist(&["actions/*", "github/codeql-*", "MyOrg"]);
+        let negated = allowlist(&["myorg/*", "!myorg/legacy-*", "myorg/legacy-ok"]);
+
+        for (uses, allowlist, expected) in [
+            // Nothing is trusted by default.
+            ("actions/checkout@v4", &empty, false),
+            ("actions/checkout@v4", &globs, true),
+            ("Actions/Checkout@v4", &globs, true),
+            ("github/codeql-action/init@v3", &globs, true),
+            ("github/super-linter@v5", &globs, false),
+            // A bare owner trusts all of its repositories.
+            ("myorg/anything@v1", &globs, true),
+            ("myorgs/anything@v1", &globs, false),
+            ("docker://ghcr.io/actions/runner:latest", &globs, true),
+            ("docker://alpine:3.8", &globs, false),
+            ("./.github/actions/local", &globs, false),
+            // The last matching pattern wins.
+            ("myorg/tool@v1", &negated, true),
+            ("myorg/legacy-tool@v1", &negated, false),
+            ("myorg/legacy-ok@v1", &negated, true),
+            ("otherorg/tool@v1", &negated, false),
+        ] {
+            let parsed = Uses::from_step(uses).unwrap();
+            assert_eq!(allowlist.is_trusted(&parsed), expected, "{uses}");
+        }
+
+        let library = allowlist(&["library/*"]);
+        assert!(library.is_trusted(&Uses::from_step("docker://node:18").unwrap()));
+    }
+}
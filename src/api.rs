@@ -0,0 +1,203 @@
+//! zizmor's public library API.
+//!
+//! Everything here is re-exported from the crate root, and is covered by
+//! zizmor's semver guarantees. See the crate docs for the full list.
+
+use std::collections::BTreeMap;
+
+use anyhow::{Context as _, Result};
+use camino::Utf8Path;
+
+use crate::{
+    audit,
+    config::Config,
+    finding::{self, Confidence, Persona, Severity},
+    models::{Action, Workflow},
+    registry::{AuditRegistry, FindingRegistry, Input, WorkflowKey},
+    state::AuditState,
+};
+
+/// Settings for [`audit_workflow`].
+///
+/// The default configuration runs every offline audit with the
+/// [`Persona::Regular`] persona, and no `zizmor.yml`.
+#[derive(Clone, Debug, Default)]
+pub struct AuditConfig {
+    config: Config,
+    persona: Persona,
+}
+
+impl AuditConfig {
+    /// Returns the default configuration.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads a configuration from the contents of a `zizmor.yml` file.
+    ///
+    /// Fails if the contents aren't a valid configuration, including if
+    /// they configure an audit that doesn't exist.
+    pub fn from_yaml(contents: &str) -> Result<Self> {
+        let config: Config = serde_yaml::from_str(contents).context("invalid configuration")?;
+        config.validate(audit::catalog().iter().map(|meta| meta.ident))?;
+
+        Ok(Self {
+            persona: config.persona().unwrap_or_default(),
+            config,
+        })
+    }
+
+    /// Sets the persona to audit with, which controls how many
+    /// low-signal findings are reported.
+    ///
+    /// This overrides any `persona` in the loaded `zizmor.yml`.
+    pub fn persona(mut self, persona: Persona) -> Self {
+        self.persona = persona;
+        self
+    }
+}
+
+/// A `(line, column)` point within an audited file. Both are 1-based.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct Point {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// A single location that a [`Finding`] refers to.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct Location {
+    /// The path of the audited file, as given to [`audit_workflow`].
+    pub path: String,
+    /// A human-readable explanation of this location's part in the finding.
+    pub annotation: String,
+    /// Where the location starts.
+    pub start: Point,
+    /// Where the location ends.
+    pub end: Point,
+    /// The byte range of the location within the audited source.
+    pub offsets: (usize, usize),
+    /// The location's text within the audited source.
+    pub feature: String,
+}
+
+/// A single finding from one of zizmor's audits.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct Finding {
+    /// The ID of the audit that produced this finding, like `template-injection`.
+    pub ident: &'static str,
+    /// A short description of the audit's finding.
+    pub desc: &'static str,
+    /// A URL to the audit's documentation.
+    pub url: &'static str,
+    pub severity: Severity,
+    pub confidence: Confidence,
+    /// The least sensitive persona that this finding is reported for.
+    pub persona: Persona,
+    /// Where the finding occurs. Always has at least one location, the
+    /// first of which is the finding's primary location.
+    pub locations: Vec<Location>,
+    /// A stable, short hex fingerprint for this finding, as recorded in
+    /// baselines.
+    pub fingerprint: String,
+    /// A human-readable suggested fix, if the audit has one.
+    pub remediation: Option<String>,
+    /// URLs with more information about this specific finding, like an
+    /// advisory. Empty for most findings.
+    pub references: Vec<String>,
+    /// Audit-specific details of how the audit reached this finding, like
+    /// the version that `known-vulnerable-actions` resolved an action's
+    /// ref to. Empty for most findings.
+    pub properties: BTreeMap<String, String>,
+}
+
+impl From<finding::Finding<'_>> for Finding {
+    fn from(finding: finding::Finding<'_>) -> Self {
+        Self {
+            ident: finding.ident,
+            desc: finding.desc,
+            url: finding.url,
+            severity: finding.determinations.severity,
+            confidence: finding.determinations.confidence,
+            persona: finding.determinations.persona,
+            fingerprint: finding.fingerprint().into(),
+            remediation: finding.remediation.map(|r| r.description),
+            references: finding.references,
+            properties: finding
+                .properties
+                .into_iter()
+                .map(|(name, value)| (name.into(), value))
+                .collect(),
+            locations: finding
+                .locations
+                .into_iter()
+                .map(|location| {
+                    let concrete = &location.concrete.location;
+                    let span = location.span;
+
+                    Location {
+                        path: location.symbolic.key.path().into(),
+                        annotation: location.symbolic.annotation,
+                        start: Point {
+                            line: span.start_line,
+                            column: span.start_col,
+                        },
+                        end: Point {
+                            line: span.end_line,
+                            column: span.end_col,
+                        },
+                        offsets: (concrete.start_offset, concrete.end_offset),
+                        feature: location.concrete.feature.into(),
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Audits a single workflow, given its YAML source.
+///
+/// `path` names the workflow in the returned findings and in `ignore`
+/// rules. If its filename is `action.yml` or `action.yaml`, the source is
+/// audited as a composite action instead. The path doesn't need to exist.
+///
+/// Only offline audits are run, and findings are returned in zizmor's
+/// usual order: by position, and then by audit. Findings that are ignored
+/// (by `config` or a `# zizmor: ignore[...]` comment) or that are beneath
+/// the configured persona aren't returned.
+pub fn audit_workflow(source: &str, path: &str, config: &AuditConfig) -> Result<Vec<Finding>> {
+    let key = WorkflowKey::local(path.into())?;
+    let (workflow, action);
+    let input = if Action::is_action_path(Utf8Path::new(path)) {
+        action = Action::from_string(source.into(), key)?;
+        Input::Action(&action)
+    } else {
+        workflow = Workflow::from_string(source.into(), key)?;
+        Input::Workflow(&workflow)
+    };
+
+    let state = AuditState::builder().config(config.config.clone()).build();
+    let audits = AuditRegistry::offline(&state, &config.config);
+
+    let mut findings = vec![];
+    for (name, audit) in audits.iter_workflow_audits() {
+        findings.extend(
+            input
+                .audit(audit.as_ref())
+                .with_context(|| format!("{name} failed on {path}"))?,
+        );
+    }
+    finding::sort(&mut findings);
+
+    let mut results = FindingRegistry::for_persona(&config.config, config.persona);
+    results.extend(findings);
+
+    Ok(results
+        .into_findings()
+        .into_iter()
+        .map(Into::into)
+        .collect())
+}
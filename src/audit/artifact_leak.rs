@@ -0,0 +1,200 @@
+//! Detects artifact uploads that include sensitive files.
+//!
+//! Artifacts can be downloaded by anyone with read access to the
+//! repository (i.e. everyone, for public repositories), so uploading a
+//! `.git` directory, a private key, or a cloud credentials file hands
+//! its contents to all of them.
+
+use std::ops::Deref;
+
+use anyhow::Result;
+use github_actions_models::{action, common::EnvValue, workflow::job::StepBody};
+use serde::Deserialize;
+
+use super::{audit_meta, WorkflowAudit};
+use crate::{
+    finding::{Confidence, Finding, Severity, SymbolicLocation},
+    models::{AuditInput, CompositeStep, Step, Uses},
+    state::AuditState,
+    utils::glob_matches,
+};
+
+/// Path components that are sensitive wherever they appear in a path.
+///
+/// Each may use `*` and `?` wildcards.
+const SENSITIVE_PATHS: &[&str] = &[
+    // Repository metadata, which may include a persisted token.
+    ".git",
+    ".git-credentials",
+    // Environment files and package manager credentials.
+    ".env",
+    ".netrc",
+    ".npmrc",
+    ".pypirc",
+    // Private keys and certificates.
+    "*.key",
+    "*.p12",
+    "*.pem",
+    "*.pfx",
+    "id_dsa",
+    "id_ecdsa",
+    "id_ed25519",
+    "id_rsa",
+    // Credential directories.
+    ".aws",
+    ".azure",
+    ".docker",
+    ".gnupg",
+    ".kube",
+    ".ssh",
+];
+
+/// Settings for this audit, under `rules.artifact-leak.config`.
+#[derive(Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+struct ArtifactLeakConfig {
+    /// Additional sensitive path patterns, on top of the defaults.
+    sensitive_paths: Vec<String>,
+}
+
+pub(crate) struct ArtifactLeak {
+    config: ArtifactLeakConfig,
+}
+
+audit_meta!(
+    ArtifactLeak,
+    "artifact-leak",
+    "artifact upload includes sensitive files"
+);
+
+/// Returns whether the given pattern matches any run of consecutive
+/// components in the given path, e.g. `.aws` matches `~/.aws/credentials`.
+fn path_matches(pattern: &str, path: &str) -> bool {
+    let pattern = pattern
+        .split('/')
+        .filter(|c| !c.is_empty())
+        .collect::<Vec<_>>();
+    let path = path
+        .split('/')
+        .filter(|c| !c.is_empty() && *c != ".")
+        .collect::<Vec<_>>();
+
+    !pattern.is_empty()
+        && path.windows(pattern.len()).any(|window| {
+            window
+                .iter()
+                .zip(&pattern)
+                .all(|(component, pattern)| glob_matches(pattern, component))
+        })
+}
+
+impl ArtifactLeak {
+    /// Returns each entry in an `upload-artifact` step's `path:` input
+    /// that matches a sensitive pattern.
+    fn sensitive_entries<'p>(&self, paths: &'p str) -> Vec<&'p str> {
+        paths
+            .lines()
+            .map(str::trim)
+            // Exclusions (`!path`) remove files from the upload, and
+            // expressions can't be checked statically.
+            .filter(|entry| !entry.is_empty() && !entry.starts_with('!') && !entry.contains("${{"))
+            .filter(|entry| {
+                SENSITIVE_PATHS
+                    .iter()
+                    .copied()
+                    .chain(self.config.sensitive_paths.iter().map(String::as_str))
+                    .any(|pattern| path_matches(pattern, entry))
+            })
+            .collect()
+    }
+
+    fn audit_upload<'w>(
+        &self,
+        uses: Option<Uses>,
+        paths: Option<&str>,
+        location: SymbolicLocation<'w>,
+        input: &'w impl AuditInput,
+    ) -> Result<Vec<Finding<'w>>> {
+        match uses {
+            Some(Uses::Repository(uses)) if uses.matches("actions/upload-artifact") => (),
+            _ => return Ok(vec![]),
+        }
+
+        let entries = paths.map(|paths| self.sensitive_entries(paths));
+        let Some(entries) = entries.filter(|entries| !entries.is_empty()) else {
+            return Ok(vec![]);
+        };
+
+        Ok(vec![Self::finding()
+            .severity(Severity::Medium)
+            .confidence(Confidence::Medium)
+            .add_location(location.with_input("path").annotated(format!(
+                "uploads potentially sensitive files: {entries}",
+                entries = entries.join(", ")
+            )))
+            .build(input)?])
+    }
+}
+
+impl WorkflowAudit for ArtifactLeak {
+    fn new(state: AuditState) -> Result<Self> {
+        Ok(Self {
+            config: state.config.audit_config(Self::ident())?,
+        })
+    }
+
+    fn audit_step<'w>(&self, step: &Step<'w>) -> Result<Vec<Finding<'w>>> {
+        let StepBody::Uses { with, .. } = &step.deref().body else {
+            return Ok(vec![]);
+        };
+
+        let paths = match with.get("path") {
+            Some(EnvValue::String(paths)) => Some(paths.as_str()),
+            _ => None,
+        };
+
+        self.audit_upload(step.uses(), paths, step.location(), step.workflow())
+    }
+
+    fn audit_composite_step<'a>(&self, step: &CompositeStep<'a>) -> Result<Vec<Finding<'a>>> {
+        let action::Step::UseAction(action::UseAction { with, .. }) = step.deref() else {
+            return Ok(vec![]);
+        };
+
+        self.audit_upload(
+            step.uses(),
+            with.get("path").map(String::as_str),
+            step.location(),
+            step.parent,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::path_matches;
+
+    #[test]
+    fn test_path_matches() {
+        for (pattern, path, expected) in [
+            (".git", ".git", true),
+            (".git", "./.git/", true),
+            (".git", "repo/.git/config", true),
+            (".git", ".", false),
+            (".git", ".github/workflows", false),
+            (".aws", "~/.aws", true),
+            (".aws", "/home/runner/.aws/credentials", true),
+            ("*.pem", "**/*.pem", true),
+            ("*.pem", "certs/server.pem", true),
+            ("*.pem", "dist/", false),
+            (".docker/config.json", "~/.docker/config.json", true),
+            (".docker/config.json", "~/.docker/cli-plugins", false),
+        ] {
+            assert_eq!(
+                path_matches(pattern, path),
+                expected,
+                "{pattern} vs. {path}"
+            );
+        }
+    }
+}
@@ -74,11 +74,20 @@ impl WorkflowAudit for Artipacked {
                         Some(EnvValue::Boolean(true)) => {
                             // If a user explicitly sets `persist-credentials: true`,
                             // they probably mean it. Only report if in auditor mode.
-                            vulnerable_checkouts.push((step, Persona::Auditor))
+                            let location = step
+                                .location()
+                                .with_input("persist-credentials")
+                                .annotated("explicitly persists credentials");
+                            vulnerable_checkouts.push((step, location, Persona::Auditor))
                         }
                         // TODO: handle expressions and literal strings here.
                         // persist-credentials is true by default.
-                        _ => vulnerable_checkouts.push((step, Persona::default())),
+                        _ => {
+                            let location = step
+                                .location()
+                                .annotated("does not set persist-credentials: false");
+                            vulnerable_checkouts.push((step, location, Persona::default()))
+                        }
                     }
                 } else if uses.starts_with("actions/upload-artifact") {
                     let Some(EnvValue::String(path)) = with.get("path") else {
@@ -96,17 +105,13 @@ impl WorkflowAudit for Artipacked {
             if vulnerable_uploads.is_empty() {
                 // If we have no vulnerable uploads, then emit lower-confidence
                 // findings for just the checkout steps.
-                for (checkout, persona) in vulnerable_checkouts {
+                for (_, location, persona) in vulnerable_checkouts {
                     findings.push(
                         Self::finding()
                             .severity(Severity::Medium)
                             .confidence(Confidence::Low)
                             .persona(persona)
-                            .add_location(
-                                checkout
-                                    .location()
-                                    .annotated("does not set persist-credentials: false"),
-                            )
+                            .add_location(location)
                             .build(workflow)?,
                     );
                 }
@@ -114,9 +119,9 @@ impl WorkflowAudit for Artipacked {
                 // Select only pairs where the vulnerable checkout precedes the
                 // vulnerable upload. There are more efficient ways to do this than
                 // a cartesian product, but this way is simple.
-                for ((checkout, persona), upload) in vulnerable_checkouts
+                for ((checkout, location, persona), upload) in vulnerable_checkouts
                     .into_iter()
-                    .cartesian_product(vulnerable_uploads.into_iter())
+                    .cartesian_product(vulnerable_uploads)
                 {
                     if checkout.index < upload.index {
                         findings.push(
@@ -124,11 +129,7 @@ impl WorkflowAudit for Artipacked {
                                 .severity(Severity::High)
                                 .confidence(Confidence::High)
                                 .persona(persona)
-                                .add_location(
-                                    checkout
-                                        .location()
-                                        .annotated("does not set persist-credentials: false"),
-                                )
+                                .add_location(location)
                                 .add_location(
                                     upload
                                         .location()
@@ -0,0 +1,211 @@
+//! Detects mutable or untrusted images in `container:` and `services:`.
+//!
+//! A job's container and service images are pulled fresh on every run,
+//! so an image referenced by tag (or implicitly by `latest`) can change
+//! underneath the workflow just like an action pinned to a branch. Only
+//! a digest (`@sha256:...`) pins an image's contents.
+
+use std::ops::Deref;
+
+use anyhow::Result;
+use github_actions_models::workflow::job::Container;
+use serde::Deserialize;
+
+use super::{audit_meta, WorkflowAudit};
+use crate::{
+    allowlist::ActionAllowlist,
+    finding::{Confidence, Finding, Severity, SymbolicLocation},
+    models::{Job, Uses},
+    state::AuditState,
+};
+
+/// The registry that Docker assumes when an image doesn't name one.
+const DEFAULT_REGISTRY: &str = "docker.io";
+
+/// Settings for this audit, under `rules.container-image.config`.
+#[derive(Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+struct ContainerImageConfig {
+    /// Registries that images may be pulled from, e.g. `ghcr.io`.
+    /// When empty, images from any registry are allowed.
+    trusted_registries: Vec<String>,
+}
+
+pub(crate) struct ContainerImage {
+    config: ContainerImageConfig,
+    trusted: ActionAllowlist,
+}
+
+audit_meta!(
+    ContainerImage,
+    "container-image",
+    "unpinned or untrusted container image"
+);
+
+/// Returns the registry that the given image reference is pulled from.
+///
+/// This follows Docker's own heuristic: the first path component is a
+/// registry only if it looks like a hostname, i.e. contains a `.` or `:`,
+/// or is `localhost`.
+fn registry(image: &str) -> &str {
+    match image.split_once('/') {
+        Some((host, _)) if host.contains(['.', ':']) || host == "localhost" => host,
+        _ => DEFAULT_REGISTRY,
+    }
+}
+
+/// Returns whether the given image reference is pinned to a digest.
+fn is_digest_pinned(image: &str) -> bool {
+    image
+        .split_once('@')
+        .and_then(|(_, digest)| digest.split_once(':'))
+        .is_some_and(|(algorithm, hex)| !algorithm.is_empty() && !hex.is_empty())
+}
+
+impl ContainerImage {
+    fn audit_container<'w>(
+        &self,
+        container: &'w Container,
+        location: SymbolicLocation<'w>,
+        job: &Job<'w>,
+    ) -> Result<Vec<Finding<'w>>> {
+        let (image, location) = match container {
+            Container::Name(image) => (image, location),
+            Container::Container { image, .. } => (image, location.with_keys(&["image".into()])),
+        };
+
+        // Images chosen by expression (e.g. from a matrix) can't be
+        // checked statically.
+        if image.is_empty() || image.contains("${{") {
+            return Ok(vec![]);
+        }
+
+        let mut findings = vec![];
+
+        if !is_digest_pinned(image) {
+            // Images from trusted publishers are still mutable, but less
+            // likely to change maliciously.
+            let trusted =
+                Uses::from_image_ref(image).is_some_and(|uses| self.trusted.is_trusted(&uses));
+
+            findings.push(
+                Self::finding()
+                    .severity(if trusted {
+                        Severity::Low
+                    } else {
+                        Severity::Medium
+                    })
+                    .confidence(Confidence::High)
+                    .add_location(
+                        location
+                            .clone()
+                            .annotated("image is not pinned to a digest"),
+                    )
+                    .build(job.parent())?,
+            );
+        }
+
+        let registry = registry(image);
+        if !self.config.trusted_registries.is_empty()
+            && !self
+                .config
+                .trusted_registries
+                .iter()
+                .any(|trusted| trusted.eq_ignore_ascii_case(registry))
+        {
+            findings.push(
+                Self::finding()
+                    .severity(Severity::Medium)
+                    .confidence(Confidence::High)
+                    .add_location(location.annotated(format!(
+                        "image is pulled from untrusted registry {registry}"
+                    )))
+                    .build(job.parent())?,
+            );
+        }
+
+        Ok(findings)
+    }
+}
+
+impl WorkflowAudit for ContainerImage {
+    fn new(state: AuditState) -> Result<Self> {
+        Ok(Self {
+            config: state.config.audit_config(Self::ident())?,
+            trusted: state.config.trusted_actions().clone(),
+        })
+    }
+
+    fn audit_normal_job<'w>(&self, job: &Job<'w>) -> Result<Vec<Finding<'w>>> {
+        let github_actions_models::workflow::Job::NormalJob(normal) = job.deref() else {
+            return Ok(vec![]);
+        };
+        let mut findings = vec![];
+
+        if let Some(container) = &normal.container {
+            findings.extend(self.audit_container(
+                container,
+                job.location().with_keys(&["container".into()]),
+                job,
+            )?);
+        }
+
+        for (name, service) in &normal.services {
+            findings.extend(
+                self.audit_container(
+                    service,
+                    job.location()
+                        .with_keys(&["services".into(), name.as_str().into()]),
+                    job,
+                )?,
+            );
+        }
+
+        Ok(findings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_digest_pinned, registry};
+
+    #[test]
+    fn test_registry() {
+        for (image, expected) in [
+            ("node", "docker.io"),
+            ("node:18", "docker.io"),
+            ("library/node:18", "docker.io"),
+            ("bitnami/redis:7.2", "docker.io"),
+            ("ghcr.io/owner/image:v1", "ghcr.io"),
+            (
+                "registry.example.com:5000/image",
+                "registry.example.com:5000",
+            ),
+            ("localhost/image", "localhost"),
+            ("localhost:5000/image", "localhost:5000"),
+        ] {
+            assert_eq!(registry(image), expected, "{image}");
+        }
+    }
+
+    #[test]
+    fn test_is_digest_pinned() {
+        for (image, pinned) in [
+            ("node", false),
+            ("node:18", false),
+            ("ghcr.io/owner/image:v1", false),
+            (
+                "node@sha256:0934e7a09e3952d3ba6f58570194ff3e1de7327e1456e0d8b2ffbcdb8b2d7bc0",
+                true,
+            ),
+            (
+                "node:18@sha256:0934e7a09e3952d3ba6f58570194ff3e1de7327e1456e0d8b2ffbcdb8b2d7bc0",
+                true,
+            ),
+            ("node@", false),
+            ("node@sha256:", false),
+        ] {
+            assert_eq!(is_digest_pinned(image), pinned, "{image}");
+        }
+    }
+}
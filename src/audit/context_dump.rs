@@ -0,0 +1,256 @@
+//! Detects workflows that expose secrets or debug information in their logs.
+//!
+//! Interpolating the entire `secrets` context (e.g. via `toJSON(secrets)`)
+//! places every secret available to the workflow into the interpolated
+//! value, where it's one `echo` away from a log or one action input away
+//! from an untrusted party. GitHub's log masking is a best-effort mitigation
+//! for this, not a guarantee.
+//!
+//! Separately, committing `ACTIONS_RUNNER_DEBUG` or `ACTIONS_STEP_DEBUG`
+//! as workflow environment variables enables verbose logging on every run,
+//! rather than deliberately on a single re-run.
+
+use std::{collections::HashMap, ops::Deref};
+
+use anyhow::Result;
+use github_actions_models::{
+    common::{expr::LoE, Env, EnvValue},
+    workflow::{
+        job::{Secrets, StepBody},
+        Job,
+    },
+};
+
+use super::{audit_meta, WorkflowAudit};
+use crate::{
+    expr::Expr,
+    finding::{Confidence, Finding, Severity, SymbolicLocation},
+    models::Workflow,
+    state::AuditState,
+    utils::extract_expressions,
+};
+
+/// Environment variables that enable debug logging on GitHub Actions.
+const DEBUG_VARIABLES: &[&str] = &["ACTIONS_RUNNER_DEBUG", "ACTIONS_STEP_DEBUG"];
+
+pub(crate) struct ContextDump;
+
+audit_meta!(
+    ContextDump,
+    "context-dump",
+    "secrets or debug information exposed in logs"
+);
+
+impl ContextDump {
+    /// Returns whether the given expression uses the entire `secrets`
+    /// context, rather than individual secrets within it.
+    fn uses_secrets_context(expr: &Expr) -> bool {
+        match expr {
+            Expr::Context(ctx) => {
+                ctx.eq_ignore_ascii_case("secrets") || ctx.eq_ignore_ascii_case("secrets.*")
+            }
+            // `secrets[...]` only accesses a single secret, although the
+            // index itself could use the whole context.
+            Expr::Index { parent, indices } => {
+                let parent_is_secrets = matches!(parent.deref(), Expr::Context(ctx) if ctx.eq_ignore_ascii_case("secrets"));
+
+                (!parent_is_secrets && Self::uses_secrets_context(parent))
+                    || indices.iter().any(Self::uses_secrets_context)
+            }
+            Expr::Call { func: _, args } => args.iter().any(Self::uses_secrets_context),
+            Expr::BinOp { lhs, op: _, rhs } => {
+                Self::uses_secrets_context(lhs) || Self::uses_secrets_context(rhs)
+            }
+            Expr::UnOp { op: _, expr } => Self::uses_secrets_context(expr),
+            Expr::Number(_) | Expr::String(_) | Expr::Boolean(_) | Expr::Null | Expr::Star => false,
+        }
+    }
+
+    /// Returns whether any expression in the given text uses the entire
+    /// `secrets` context.
+    fn dumps_secrets(text: &str) -> bool {
+        extract_expressions(text).iter().any(|expr| {
+            let Ok(parsed) = Expr::parse(expr.as_bare()) else {
+                tracing::warn!("couldn't parse expression: {expr}", expr = expr.as_bare());
+                return false;
+            };
+
+            Self::uses_secrets_context(&parsed)
+        })
+    }
+
+    fn secrets_dump<'w>(
+        workflow: &'w Workflow,
+        location: SymbolicLocation<'w>,
+    ) -> Result<Finding<'w>> {
+        Self::finding()
+            .severity(Severity::High)
+            .confidence(Confidence::High)
+            .add_location(location.annotated("expands the entire secrets context"))
+            .build(workflow)
+    }
+
+    /// Audits an `env:` (or `with:`) block, the location of which is `location`.
+    fn audit_env<'w>(
+        workflow: &'w Workflow,
+        location: SymbolicLocation<'w>,
+        env: &'w Env,
+        debug_variables: bool,
+    ) -> Result<Vec<Finding<'w>>> {
+        let mut findings = vec![];
+
+        for (name, value) in env {
+            let location = location.with_keys(&[name.as_str().into()]);
+
+            if let EnvValue::String(value) = value {
+                if Self::dumps_secrets(value) {
+                    findings.push(Self::secrets_dump(workflow, location.clone())?);
+                }
+            }
+
+            if debug_variables
+                && DEBUG_VARIABLES.contains(&name.as_str())
+                && !value.to_string().eq_ignore_ascii_case("false")
+            {
+                findings.push(
+                    Self::finding()
+                        .severity(Severity::Low)
+                        .confidence(Confidence::High)
+                        .add_location(location.annotated("debug logging enabled here"))
+                        .build(workflow)?,
+                );
+            }
+        }
+
+        Ok(findings)
+    }
+
+    fn audit_loe_env<'w>(
+        workflow: &'w Workflow,
+        location: SymbolicLocation<'w>,
+        env: &'w LoE<Env>,
+    ) -> Result<Vec<Finding<'w>>> {
+        match env {
+            LoE::Expr(expr) => {
+                if Self::dumps_secrets(expr.as_curly()) {
+                    Ok(vec![Self::secrets_dump(workflow, location)?])
+                } else {
+                    Ok(vec![])
+                }
+            }
+            LoE::Literal(env) => Self::audit_env(workflow, location, env, true),
+        }
+    }
+}
+
+impl WorkflowAudit for ContextDump {
+    fn new(_state: AuditState) -> Result<Self> {
+        Ok(Self)
+    }
+
+    fn audit_workflow<'w>(&self, workflow: &'w Workflow) -> Result<Vec<Finding<'w>>> {
+        let mut findings = vec![];
+
+        findings.extend(Self::audit_env(
+            workflow,
+            workflow.location().with_keys(&["env".into()]),
+            &workflow.env,
+            true,
+        )?);
+
+        for job in workflow.jobs() {
+            match job.deref() {
+                Job::NormalJob(normal) => {
+                    findings.extend(Self::audit_loe_env(
+                        workflow,
+                        job.location().with_keys(&["env".into()]),
+                        &normal.env,
+                    )?);
+
+                    for step in job.steps() {
+                        match &step.deref().body {
+                            StepBody::Run { run, env, .. } => {
+                                // Expressions are expanded before the shell
+                                // sees the script, so even comments count.
+                                let mut seen = HashMap::new();
+                                for line in run.lines().map(str::trim) {
+                                    let occurrence =
+                                        *seen.entry(line).and_modify(|n| *n += 1).or_insert(0);
+
+                                    if Self::dumps_secrets(line) {
+                                        findings.push(Self::secrets_dump(
+                                            workflow,
+                                            step.location()
+                                                .with_keys(&["run".into()])
+                                                .subfeature(line)
+                                                .occurrence(occurrence),
+                                        )?);
+                                    }
+                                }
+
+                                findings.extend(Self::audit_loe_env(
+                                    workflow,
+                                    step.location().with_keys(&["env".into()]),
+                                    env,
+                                )?);
+                            }
+                            StepBody::Uses { uses: _, with } => {
+                                findings.extend(Self::audit_env(
+                                    workflow,
+                                    step.location().with_keys(&["with".into()]),
+                                    with,
+                                    false,
+                                )?);
+                            }
+                        }
+                    }
+                }
+                Job::ReusableWorkflowCallJob(reusable) => {
+                    findings.extend(Self::audit_env(
+                        workflow,
+                        job.location().with_keys(&["with".into()]),
+                        &reusable.with,
+                        false,
+                    )?);
+
+                    if let Some(Secrets::Env(secrets)) = &reusable.secrets {
+                        findings.extend(Self::audit_env(
+                            workflow,
+                            job.location().with_keys(&["secrets".into()]),
+                            secrets,
+                            false,
+                        )?);
+                    }
+                }
+            }
+        }
+
+        Ok(findings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ContextDump;
+    use crate::expr::Expr;
+
+    #[test]
+    fn test_uses_secrets_context() {
+        for (case, dumps) in &[
+            ("secrets", true),
+            ("SECRETS", true),
+            ("toJSON(secrets)", true),
+            ("format('{0}', toJson(secrets))", true),
+            ("secrets.*", true),
+            ("github.token || secrets", true),
+            ("secrets.GITHUB_TOKEN", false),
+            ("secrets['GITHUB_TOKEN']", false),
+            ("secrets[matrix.secret]", false),
+            ("toJSON(github)", false),
+            ("github.event.secrets", false),
+        ] {
+            let expr = Expr::parse(case).unwrap();
+            assert_eq!(ContextDump::uses_secrets_context(&expr), *dumps, "{case}");
+        }
+    }
+}
@@ -0,0 +1,145 @@
+//! Detects `continue-on-error: true` where it can mask failures.
+//!
+//! A security scanner or linter whose step has `continue-on-error: true`
+//! can never fail the build, so whatever it's meant to gate goes ahead
+//! regardless of what it finds. Job-level `continue-on-error: true` is
+//! flagged more generally, since it lets every check in the job fail
+//! silently.
+
+use std::ops::Deref;
+
+use anyhow::Result;
+use github_actions_models::{
+    common::expr::{BoE, LoE},
+    workflow,
+};
+use serde::Deserialize;
+
+use super::{audit_meta, WorkflowAudit};
+use crate::{
+    finding::{Confidence, Finding, Severity},
+    models::{Job, Step, Uses},
+    state::AuditState,
+};
+
+/// Security-relevant actions, i.e. scanners and linters, as `owner/repo`.
+const SECURITY_ACTIONS: &[&str] = &[
+    "actions/dependency-review-action",
+    "anchore/scan-action",
+    "aquasecurity/trivy-action",
+    "bridgecrewio/checkov-action",
+    "docker/scout-action",
+    "embarkstudios/cargo-deny-action",
+    "github/codeql-action",
+    "github/super-linter",
+    "gitleaks/gitleaks-action",
+    "golangci/golangci-lint-action",
+    "ossf/scorecard-action",
+    "pypa/gh-action-pip-audit",
+    "rustsec/audit-check",
+    "securego/gosec",
+    "snyk/actions",
+    "super-linter/super-linter",
+    "trufflesecurity/trufflehog",
+];
+
+/// Settings for this audit, under `rules.continue-on-error.config`.
+#[derive(Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+struct ContinueOnErrorConfig {
+    /// Additional security-relevant actions, as `owner/repo`, on top of
+    /// the defaults.
+    security_actions: Vec<String>,
+}
+
+pub(crate) struct ContinueOnError {
+    config: ContinueOnErrorConfig,
+}
+
+audit_meta!(
+    ContinueOnError,
+    "continue-on-error",
+    "continue-on-error masks failures"
+);
+
+/// Returns whether the given `continue-on-error:` is literally `true`.
+///
+/// Expressions (e.g. `${{ matrix.experimental }}`) are usually deliberate
+/// and can't be evaluated statically, so they're ignored.
+fn is_true(continue_on_error: &BoE) -> bool {
+    matches!(continue_on_error, LoE::Literal(true))
+}
+
+impl ContinueOnError {
+    /// Returns whether the given `uses:` is a security-relevant action.
+    fn is_security_action(&self, uses: &Uses) -> bool {
+        let Uses::Repository(uses) = uses else {
+            return false;
+        };
+
+        SECURITY_ACTIONS
+            .iter()
+            .copied()
+            .chain(self.config.security_actions.iter().map(String::as_str))
+            .filter_map(|action| action.split_once('/'))
+            .any(|(owner, repo)| {
+                uses.owner.eq_ignore_ascii_case(owner) && uses.repo.eq_ignore_ascii_case(repo)
+            })
+    }
+}
+
+impl WorkflowAudit for ContinueOnError {
+    fn new(state: AuditState) -> Result<Self> {
+        Ok(Self {
+            config: state.config.audit_config(Self::ident())?,
+        })
+    }
+
+    fn audit_normal_job<'w>(&self, job: &Job<'w>) -> Result<Vec<Finding<'w>>> {
+        let mut findings = vec![];
+
+        let workflow::Job::NormalJob(normal) = job.deref() else {
+            return Ok(findings);
+        };
+
+        if is_true(&normal.continue_on_error) {
+            findings.push(
+                Self::finding()
+                    .severity(Severity::Informational)
+                    .confidence(Confidence::High)
+                    .add_location(
+                        job.location()
+                            .with_keys(&["continue-on-error".into()])
+                            .annotated("failures in this job never fail the workflow"),
+                    )
+                    .build(job.parent())?,
+            );
+        }
+
+        for step in job.steps() {
+            findings.extend(self.audit_step(&step)?);
+        }
+
+        Ok(findings)
+    }
+
+    fn audit_step<'w>(&self, step: &Step<'w>) -> Result<Vec<Finding<'w>>> {
+        if !is_true(&step.continue_on_error)
+            || !step
+                .uses()
+                .is_some_and(|uses| self.is_security_action(&uses))
+        {
+            return Ok(vec![]);
+        }
+
+        Ok(vec![Self::finding()
+            .severity(Severity::Informational)
+            .confidence(Confidence::High)
+            .add_location(
+                step.location()
+                    .with_keys(&["continue-on-error".into()])
+                    .annotated("this security check can never fail the build"),
+            )
+            .build(step.workflow())?])
+    }
+}
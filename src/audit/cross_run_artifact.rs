@@ -0,0 +1,145 @@
+//! Detects steps that download artifacts uploaded by another workflow run.
+//!
+//! An artifact is only as trustworthy as the run that uploaded it. Within
+//! a single run, that's the same code that's doing the downloading; across
+//! runs, it can be anything that managed to run in the other workflow,
+//! including a pull request from a fork.
+
+use std::ops::Deref;
+
+use anyhow::Result;
+use github_actions_models::{action, workflow::job::StepBody};
+
+use super::{audit_meta, WorkflowAudit};
+use crate::{
+    finding::{Confidence, Finding, Severity, SymbolicLocation},
+    models::{AuditInput, CompositeStep, RepositoryUses, Step, Uses},
+    state::AuditState,
+};
+
+/// `actions/download-artifact`'s inputs that select another run (or
+/// repository) to download from, instead of the current run.
+const DOWNLOAD_ARTIFACT_INPUTS: &[&str] = &["run-id", "repository", "github-token"];
+
+/// `dawidd6/action-download-artifact`'s inputs that select the run to
+/// download from.
+const DAWIDD6_INPUTS: &[&str] = &["run_id", "workflow", "repo", "github_token"];
+
+/// How a step downloads artifacts from another run.
+pub(super) enum CrossRunDownload {
+    /// The run is selected by the given input.
+    Input(&'static str),
+    /// The action downloads from another run by default.
+    Implicit,
+}
+
+impl CrossRunDownload {
+    /// Returns how the given artifact download action, with the given
+    /// inputs, downloads from another run, if it does at all.
+    pub(super) fn of(uses: &RepositoryUses, has_input: impl Fn(&str) -> bool) -> Option<Self> {
+        if uses.matches("actions/download-artifact") {
+            // Without any of these, only the current run's artifacts are
+            // downloaded.
+            DOWNLOAD_ARTIFACT_INPUTS
+                .iter()
+                .find(|name| has_input(name))
+                .map(|name| Self::Input(name))
+        } else if uses.matches("dawidd6/action-download-artifact") {
+            // This action only ever downloads from other runs: by default,
+            // from the latest successful run of the current workflow.
+            Some(
+                DAWIDD6_INPUTS
+                    .iter()
+                    .find(|name| has_input(name))
+                    .map_or(Self::Implicit, |name| Self::Input(name)),
+            )
+        } else {
+            None
+        }
+    }
+
+    /// Returns the part of the step's `location` responsible for the
+    /// download.
+    pub(super) fn locate<'w>(&self, location: SymbolicLocation<'w>) -> SymbolicLocation<'w> {
+        match self {
+            Self::Input(name) => location.with_input(name),
+            Self::Implicit => location.with_keys(&["uses".into()]),
+        }
+    }
+}
+
+pub(crate) struct CrossRunArtifact;
+
+audit_meta!(
+    CrossRunArtifact,
+    "cross-run-artifact",
+    "artifact downloaded from another workflow run"
+);
+
+impl CrossRunArtifact {
+    fn audit_download<'w>(
+        uses: Option<Uses<'_>>,
+        has_input: impl Fn(&str) -> bool,
+        location: SymbolicLocation<'w>,
+        input: &'w impl AuditInput,
+    ) -> Result<Vec<Finding<'w>>> {
+        let Some(Uses::Repository(uses)) = uses else {
+            return Ok(vec![]);
+        };
+        let Some(download) = CrossRunDownload::of(&uses, has_input) else {
+            return Ok(vec![]);
+        };
+
+        let location = download.locate(location).annotated(match download {
+            CrossRunDownload::Input(_) => "downloads artifacts from another run",
+            CrossRunDownload::Implicit => "downloads artifacts from a previous run",
+        });
+
+        Ok(vec![Self::finding()
+            .severity(Severity::Medium)
+            .confidence(Confidence::High)
+            .add_location(location)
+            .remediation(
+                "upload and download the artifact within the same run, or validate its contents before using them",
+            )
+            .build(input)?])
+    }
+}
+
+impl WorkflowAudit for CrossRunArtifact {
+    fn new(_state: AuditState) -> Result<Self> {
+        Ok(Self)
+    }
+
+    fn audit_step<'w>(&self, step: &Step<'w>) -> Result<Vec<Finding<'w>>> {
+        // In `workflow_run` workflows, these are reported (more severely)
+        // by `workflow-run-artifacts`.
+        if step.workflow().has_workflow_run() {
+            return Ok(vec![]);
+        }
+
+        let StepBody::Uses { with, .. } = &step.deref().body else {
+            return Ok(vec![]);
+        };
+
+        Self::audit_download(
+            step.uses(),
+            |name| with.contains_key(name),
+            step.location(),
+            step.workflow(),
+        )
+    }
+
+    fn audit_composite_step<'a>(&self, step: &CompositeStep<'a>) -> Result<Vec<Finding<'a>>> {
+        let action::Step::UseAction(action::UseAction { with, .. }) = step.deref() else {
+            return Ok(vec![]);
+        };
+
+        Self::audit_download(
+            step.uses(),
+            |name| with.contains_key(name),
+            step.location(),
+            step.parent,
+        )
+    }
+}
@@ -0,0 +1,210 @@
+//! Detects `run:` steps that emit deprecated or removed workflow commands.
+//!
+//! GitHub replaced the `::set-output`, `::save-state`, `::set-env`, and
+//! `::add-path` workflow commands with environment files. The first two
+//! still work (with a warning), but `::set-env` and `::add-path` were
+//! removed outright, since any step that could print to `stdout` could
+//! use them to inject environment variables or `PATH` entries.
+//!
+//! In workflows, the removed commands are left to `insecure-commands`,
+//! which reports them more severely. It doesn't audit composite actions,
+//! so they're still reported here for those.
+
+use std::{ops::Deref, sync::LazyLock};
+
+use anyhow::Result;
+use github_actions_models::{action, workflow::job::StepBody};
+use regex::Regex;
+
+use super::{audit_meta, WorkflowAudit};
+use crate::{
+    finding::{Confidence, Finding, Severity, SymbolicLocation},
+    models::{AuditInput, CompositeStep, Step},
+    state::AuditState,
+};
+
+/// Matches any of the deprecated (or removed) workflow commands.
+///
+/// Commands have to start their own line of output, so e.g. the message of
+/// a `::warning::set-output ...` command doesn't match.
+static DEPRECATED_COMMAND: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?:^|[^\w:])::(set-output|save-state|set-env|add-path)\b").unwrap()
+});
+
+pub(crate) struct DeprecatedCommands;
+
+audit_meta!(
+    DeprecatedCommands,
+    "deprecated-commands",
+    "deprecated workflow commands"
+);
+
+/// A deprecated (or removed) workflow command, and what replaces it.
+struct Command {
+    name: &'static str,
+    /// Whether the runner has stopped honoring the command entirely,
+    /// rather than just warning about it.
+    removed: bool,
+    severity: Severity,
+    /// The environment file that replaces the command.
+    file: &'static str,
+    /// The deprecation announcement.
+    announcement: &'static str,
+}
+
+const COMMANDS: &[Command] = &[
+    Command {
+        name: "set-env",
+        removed: true,
+        severity: Severity::Medium,
+        file: "GITHUB_ENV",
+        announcement: "https://github.blog/changelog/2020-10-01-github-actions-deprecating-set-env-and-add-path-commands/",
+    },
+    Command {
+        name: "add-path",
+        removed: true,
+        severity: Severity::Medium,
+        file: "GITHUB_PATH",
+        announcement: "https://github.blog/changelog/2020-10-01-github-actions-deprecating-set-env-and-add-path-commands/",
+    },
+    Command {
+        name: "save-state",
+        removed: false,
+        severity: Severity::Low,
+        file: "GITHUB_STATE",
+        announcement: "https://github.blog/changelog/2022-10-11-github-actions-deprecating-save-state-and-set-output-commands/",
+    },
+    Command {
+        name: "set-output",
+        removed: false,
+        severity: Severity::Low,
+        file: "GITHUB_OUTPUT",
+        announcement: "https://github.blog/changelog/2022-10-11-github-actions-deprecating-save-state-and-set-output-commands/",
+    },
+];
+
+impl DeprecatedCommands {
+    fn audit_script<'w>(
+        &self,
+        script: &str,
+        script_loc: SymbolicLocation<'w>,
+        input: &'w impl AuditInput,
+        skip_removed: bool,
+    ) -> Result<Vec<Finding<'w>>> {
+        let mut findings = vec![];
+
+        for line in script
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.starts_with('#'))
+        {
+            for caps in DEPRECATED_COMMAND.captures_iter(line) {
+                // NOTE: the regex only matches commands in the table.
+                let command = COMMANDS.iter().find(|c| c.name == &caps[1]).unwrap();
+                if command.removed && skip_removed {
+                    continue;
+                }
+
+                findings.push(
+                    Self::finding()
+                        .severity(command.severity)
+                        .confidence(Confidence::High)
+                        .add_location(
+                            script_loc
+                                .clone()
+                                .subfeature_within(line, format!("::{name}", name = command.name))
+                                .annotated(format!(
+                                    "uses the {status} ::{name} workflow command",
+                                    status = if command.removed {
+                                        "removed"
+                                    } else {
+                                        "deprecated"
+                                    },
+                                    name = command.name
+                                )),
+                        )
+                        .remediation(format!(
+                            "write to the ${file} environment file instead",
+                            file = command.file
+                        ))
+                        .reference(command.announcement)
+                        .build(input)?,
+                );
+            }
+        }
+
+        Ok(findings)
+    }
+}
+
+impl WorkflowAudit for DeprecatedCommands {
+    fn new(_: AuditState) -> Result<Self> {
+        Ok(Self)
+    }
+
+    fn audit_step<'w>(&self, step: &Step<'w>) -> Result<Vec<Finding<'w>>> {
+        let StepBody::Run { run, .. } = &step.deref().body else {
+            return Ok(vec![]);
+        };
+
+        self.audit_script(
+            run,
+            step.location().with_keys(&["run".into()]),
+            step.workflow(),
+            // Reported by `insecure-commands`.
+            true,
+        )
+    }
+
+    fn audit_composite_step<'a>(&self, step: &CompositeStep<'a>) -> Result<Vec<Finding<'a>>> {
+        let action::Step::RunShell(action::RunShell { run, .. }) = step.deref() else {
+            return Ok(vec![]);
+        };
+
+        self.audit_script(
+            run,
+            step.location().with_keys(&["run".into()]),
+            step.parent,
+            false,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DEPRECATED_COMMAND;
+
+    #[test]
+    fn test_deprecated_command() {
+        for (line, expected) in [
+            (
+                "echo \"::set-output name=version::1.0\"",
+                Some("set-output"),
+            ),
+            ("echo '::save-state name=pid::123'", Some("save-state")),
+            ("echo \"::set-env name=FOO::bar\"", Some("set-env")),
+            ("echo \"::add-path::$HOME/bin\"", Some("add-path")),
+            ("echo \"version=1.0\" >> \"$GITHUB_OUTPUT\"", None),
+            ("echo \"::warning::set-output is deprecated\"", None),
+            ("echo \"::set-outputs::\"", None),
+        ] {
+            assert_eq!(
+                DEPRECATED_COMMAND.captures(line).map(|c| c[1].to_string()),
+                expected.map(str::to_string),
+                "{line}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_deprecated_commands_same_line() {
+        let line = r#"echo "::set-output name=a::1"; echo "::save-state name=b::2""#;
+        assert_eq!(
+            DEPRECATED_COMMAND
+                .captures_iter(line)
+                .map(|c| c[1].to_string())
+                .collect::<Vec<_>>(),
+            ["set-output", "save-state"]
+        );
+    }
+}
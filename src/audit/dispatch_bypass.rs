@@ -0,0 +1,154 @@
+//! Detects privileged jobs that are shared between pull request triggers
+//! and `workflow_dispatch`.
+//!
+//! A workflow that runs on both `pull_request_target` and
+//! `workflow_dispatch` runs the same privileged code for two very
+//! different audiences, and any gating done for one trigger (like
+//! `pull_request` branch or path filters) doesn't apply to the other.
+//! Neither is a vulnerability on its own, but both make it much harder
+//! to reason about when, and for whom, privileged code runs.
+
+use std::ops::Deref;
+
+use anyhow::Result;
+use github_actions_models::{
+    common::{expr::LoE, EnvValue, If},
+    workflow::{
+        self,
+        job::{Secrets, StepBody},
+    },
+};
+
+use super::{audit_meta, WorkflowAudit};
+use crate::{
+    contexts::is_secret_context,
+    expr::Expr,
+    finding::{Confidence, Finding, Severity},
+    models::{Job, PermissionScopes, Workflow},
+    state::AuditState,
+    utils::extract_expressions,
+};
+
+pub(crate) struct DispatchBypass;
+
+audit_meta!(
+    DispatchBypass,
+    "dispatch-bypass",
+    "privileged jobs shared between pull request and manual triggers"
+);
+
+/// Returns whether the given text expands any secret.
+fn expands_secrets(text: &str) -> bool {
+    extract_expressions(text).iter().any(|expr| {
+        Expr::parse(expr.as_bare())
+            .is_ok_and(|parsed| parsed.contexts().iter().any(|ctx| is_secret_context(ctx)))
+    })
+}
+
+/// Returns whether the given values expand any secret.
+fn any_expands_secrets<'a>(values: impl IntoIterator<Item = &'a EnvValue>) -> bool {
+    values
+        .into_iter()
+        .any(|value| matches!(value, EnvValue::String(value) if expands_secrets(value)))
+}
+
+/// Returns whether the given job is privileged, i.e. whether it's granted
+/// write permissions or has access to secrets.
+///
+/// Default permissions are determined by repository or organization
+/// settings, so they aren't considered.
+fn is_privileged(job: &Job, workflow: &Workflow) -> bool {
+    let writes = |permissions| PermissionScopes::resolve(permissions).map(|p| p.grants_write());
+
+    match job.deref() {
+        workflow::Job::NormalJob(normal) => {
+            let grants_write = writes(&normal.permissions)
+                .or_else(|| writes(&workflow.permissions))
+                .unwrap_or(false);
+
+            grants_write
+                || job.steps().any(|step| match &step.deref().body {
+                    StepBody::Uses { with, .. } => any_expands_secrets(with.values()),
+                    StepBody::Run { run, env, .. } => {
+                        expands_secrets(run)
+                            || matches!(env, LoE::Literal(env) if any_expands_secrets(env.values()))
+                    }
+                })
+        }
+        workflow::Job::ReusableWorkflowCallJob(reusable) => {
+            writes(&reusable.permissions)
+                .or_else(|| writes(&workflow.permissions))
+                .unwrap_or(false)
+                || match &reusable.secrets {
+                    Some(Secrets::Inherit) => true,
+                    Some(Secrets::Env(secrets)) => !secrets.is_empty(),
+                    None => false,
+                }
+        }
+    }
+}
+
+/// Returns whether the given job is conditioned on the triggering event,
+/// in which case it's presumably not shared between triggers.
+fn is_event_gated(job: &Job) -> bool {
+    let cond = match job.deref() {
+        workflow::Job::NormalJob(normal) => &normal.r#if,
+        workflow::Job::ReusableWorkflowCallJob(reusable) => &reusable.r#if,
+    };
+
+    matches!(cond, Some(If::Expr(expr)) if expr.contains("github.event_name"))
+}
+
+impl WorkflowAudit for DispatchBypass {
+    fn new(_state: AuditState) -> Result<Self> {
+        Ok(Self)
+    }
+
+    fn audit_workflow<'w>(&self, workflow: &'w Workflow) -> Result<Vec<Finding<'w>>> {
+        if !workflow.has_workflow_dispatch() {
+            return Ok(vec![]);
+        }
+
+        let (severity, confidence, annotation) = if workflow.has_pull_request_target() {
+            (
+                Severity::Low,
+                Confidence::Medium,
+                "pull_request_target and workflow_dispatch run the same privileged jobs",
+            )
+        } else if workflow.has_filtered_pull_request() {
+            (
+                Severity::Informational,
+                Confidence::Low,
+                "pull_request filters don't apply to workflow_dispatch",
+            )
+        } else {
+            return Ok(vec![]);
+        };
+
+        let shared = workflow
+            .jobs()
+            .filter(|job| !is_event_gated(job) && is_privileged(job, workflow))
+            .collect::<Vec<_>>();
+        if shared.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let mut finding = Self::finding()
+            .severity(severity)
+            .confidence(confidence)
+            .add_location(
+                workflow
+                    .location()
+                    .with_keys(&["on".into()])
+                    .annotated(annotation),
+            );
+        for job in shared {
+            finding = finding.add_location(
+                job.location()
+                    .annotated("privileged job runs for either trigger"),
+            );
+        }
+
+        Ok(vec![finding.build(workflow)?])
+    }
+}
@@ -0,0 +1,72 @@
+//! Detects deployment environments used by jobs that fork pull requests
+//! can trigger.
+//!
+//! Environments protect their secrets with deployment rules like
+//! required reviewers or branch restrictions, but that protection is
+//! only as good as the jobs that use the environment. A job that runs on
+//! `pull_request` or `pull_request_target` can be triggered by anyone
+//! who can open a pull request, putting the environment (and any
+//! approval it gates) in front of untrusted changes.
+
+use std::ops::Deref;
+
+use anyhow::Result;
+use github_actions_models::workflow::{self, job::DeploymentEnvironment};
+
+use super::{audit_meta, WorkflowAudit};
+use crate::{
+    finding::{Confidence, Finding, Severity},
+    models::Job,
+    state::AuditState,
+};
+
+pub(crate) struct EnvironmentExposure;
+
+audit_meta!(
+    EnvironmentExposure,
+    "environment-exposure",
+    "deployment environment reachable from fork pull requests"
+);
+
+impl WorkflowAudit for EnvironmentExposure {
+    fn new(_state: AuditState) -> Result<Self> {
+        Ok(Self)
+    }
+
+    fn audit_normal_job<'w>(&self, job: &Job<'w>) -> Result<Vec<Finding<'w>>> {
+        let workflow::Job::NormalJob(normal) = job.deref() else {
+            return Ok(vec![]);
+        };
+
+        let name = match &normal.environment {
+            Some(DeploymentEnvironment::Name(name)) => name,
+            Some(DeploymentEnvironment::NameURL { name, .. }) => name,
+            None => return Ok(vec![]),
+        };
+
+        let workflow = job.parent();
+        let triggers = [
+            (workflow.has_pull_request(), "pull_request"),
+            (workflow.has_pull_request_target(), "pull_request_target"),
+        ]
+        .into_iter()
+        .filter_map(|(triggered, trigger)| triggered.then_some(trigger))
+        .collect::<Vec<_>>();
+        if triggers.is_empty() {
+            return Ok(vec![]);
+        }
+
+        Ok(vec![Self::finding()
+            .severity(Severity::Medium)
+            .confidence(Confidence::Medium)
+            .add_location(
+                job.location()
+                    .with_keys(&["environment".into()])
+                    .annotated(format!(
+                        "environment {name} is used on {triggers}, which fork pull requests can trigger",
+                        triggers = triggers.join(" and ")
+                    )),
+            )
+            .build(workflow)?])
+    }
+}
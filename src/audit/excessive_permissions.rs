@@ -7,8 +7,8 @@ use github_actions_models::{
 
 use super::{audit_meta, WorkflowAudit};
 use crate::{
-    finding::{Confidence, Severity},
-    AuditState,
+    finding::{Confidence, Persona, Severity},
+    state::AuditState,
 };
 
 // Subjective mapping of permissions to severities, when given `write` access.
@@ -72,11 +72,100 @@ impl WorkflowAudit for ExcessivePermissions {
             )
         }
 
-        for job in workflow.jobs() {
+        if workflow.has_pull_request() && Self::grants_contents_write(&workflow.permissions) {
+            findings.push(
+                Self::finding()
+                    .severity(Severity::Medium)
+                    .confidence(Confidence::Medium)
+                    .add_location(
+                        workflow
+                            .location()
+                            .with_keys(&["on".into()])
+                            .annotated("workflow is triggered by pull_request"),
+                    )
+                    .add_location(
+                        workflow
+                            .location()
+                            .with_path("permissions.contents")
+                            .annotated("contents: write is overly broad for pull_request"),
+                    )
+                    .build(workflow)?,
+            );
+        }
+
+        let normal_jobs = workflow
+            .jobs()
+            .filter(|job| matches!(job.deref(), Job::NormalJob(_)))
+            .collect::<Vec<_>>();
+
+        // With no `permissions:` at either level, a job gets the
+        // repository's default token permissions, which may be write-all.
+        // When nothing in the workflow sets permissions, that's a single
+        // workflow-wide problem rather than one per job.
+        let workflow_defaulted = workflow.permissions == Permissions::default();
+        let all_defaulted = workflow_defaulted
+            && !normal_jobs.is_empty()
+            && normal_jobs.iter().all(|job| {
+                matches!(job.deref(), Job::NormalJob(normal) if normal.permissions == Permissions::default())
+            });
+        if all_defaulted {
+            findings.push(
+                Self::finding()
+                    .severity(Severity::Low)
+                    .confidence(Confidence::Medium)
+                    .persona(Persona::Pedantic)
+                    .add_location(
+                        workflow
+                            .location()
+                            .annotated("default permissions used due to no permissions: block"),
+                    )
+                    .build(workflow)?,
+            );
+        }
+
+        for job in &normal_jobs {
             let Job::NormalJob(normal) = job.deref() else {
                 continue;
             };
 
+            // Only some jobs set permissions; flag the ones that don't.
+            if workflow_defaulted && !all_defaulted && normal.permissions == Permissions::default()
+            {
+                findings.push(
+                    Self::finding()
+                        .severity(Severity::Low)
+                        .confidence(Confidence::Medium)
+                        .persona(Persona::Pedantic)
+                        .add_location(
+                            job.location()
+                                .annotated("default permissions used due to no permissions: block"),
+                        )
+                        .build(workflow)?,
+                );
+            }
+
+            // `contents: write` lets a job push to the repository, which
+            // is rarely what a `pull_request` check needs.
+            if workflow.has_pull_request() && Self::grants_contents_write(&normal.permissions) {
+                findings.push(
+                    Self::finding()
+                        .severity(Severity::Medium)
+                        .confidence(Confidence::Medium)
+                        .add_location(
+                            workflow
+                                .location()
+                                .with_keys(&["on".into()])
+                                .annotated("workflow is triggered by pull_request"),
+                        )
+                        .add_location(
+                            job.location()
+                                .with_path("permissions.contents")
+                                .annotated("contents: write is overly broad for pull_request"),
+                        )
+                        .build(workflow)?,
+                );
+            }
+
             for (severity, confidence, note) in
                 self.check_permissions(&normal.permissions, Some(&workflow.permissions))
             {
@@ -99,6 +188,14 @@ impl WorkflowAudit for ExcessivePermissions {
 }
 
 impl ExcessivePermissions {
+    /// Whether `permissions` explicitly grants `contents: write`.
+    fn grants_contents_write(permissions: &Permissions) -> bool {
+        matches!(
+            permissions,
+            Permissions::Explicit(perms) if perms.get("contents") == Some(&Permission::Write)
+        )
+    }
+
     fn check_permissions(
         &self,
         permissions: &Permissions,
@@ -106,10 +203,8 @@ impl ExcessivePermissions {
     ) -> Vec<(Severity, Confidence, String)> {
         match permissions {
             Permissions::Base(base) => match base {
-                // TODO: Think more about what to do here. Flagging default
-                // permissions is likely to be noisy and is annoying to do,
-                // since it involves the *absence* of a key in the YAML
-                // rather than its presence.
+                // Absent permissions are flagged by `audit_workflow`, which
+                // can see whether any other level sets them.
                 BasePermission::Default => vec![],
                 BasePermission::ReadAll => vec![(
                     Severity::Medium,
@@ -0,0 +1,56 @@
+//! Detects `run:` steps that rely on the runner's default shell.
+//!
+//! The default shell depends on the runner: `bash` on Linux and macOS,
+//! but `pwsh` on Windows. A step that doesn't pick its shell (directly or
+//! via `defaults.run.shell`) can behave differently, or fail outright,
+//! when its job is moved to a different runner or added to an OS matrix.
+
+use std::ops::Deref;
+
+use anyhow::Result;
+use github_actions_models::workflow::job::StepBody;
+
+use super::{audit_meta, WorkflowAudit};
+use crate::{
+    finding::{Confidence, Finding, Persona, Severity},
+    models::Step,
+    state::AuditState,
+};
+
+pub(crate) struct ExplicitShell;
+
+audit_meta!(
+    ExplicitShell,
+    "explicit-shell",
+    "run step relies on the default shell"
+);
+
+impl WorkflowAudit for ExplicitShell {
+    fn new(_state: AuditState) -> Result<Self> {
+        Ok(Self)
+    }
+
+    // NOTE: Composite actions can't rely on a default shell: GitHub
+    // requires `shell:` on each of their `run:` steps.
+    fn audit_step<'w>(&self, step: &Step<'w>) -> Result<Vec<Finding<'w>>> {
+        let StepBody::Run { .. } = &step.deref().body else {
+            return Ok(vec![]);
+        };
+
+        // Either the step or the `defaults.run` of its job or workflow
+        // may set the shell.
+        if step.run_defaults().shell.is_some() {
+            return Ok(vec![]);
+        }
+
+        Ok(vec![Self::finding()
+            .severity(Severity::Informational)
+            .confidence(Confidence::High)
+            .persona(Persona::Pedantic)
+            .add_location(
+                step.location()
+                    .annotated("step has no shell, and no default shell is set"),
+            )
+            .build(step.workflow())?])
+    }
+}
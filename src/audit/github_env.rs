@@ -5,17 +5,17 @@ use crate::state::AuditState;
 use anyhow::Context;
 use github_actions_models::workflow::job::StepBody;
 use regex::Regex;
-use std::cell::RefCell;
 use std::ops::Deref;
-use std::sync::LazyLock;
+use std::sync::{LazyLock, Mutex};
 use tree_sitter::Parser;
 
 static GITHUB_ENV_WRITE_CMD: LazyLock<Regex> =
     LazyLock::new(|| Regex::new(r#"(?mi)^.+\s*>>?\s*"?%GITHUB_ENV%"?.*$"#).unwrap());
 
 pub(crate) struct GitHubEnv {
-    // NOTE: interior mutability used since Parser::parse requires &mut self
-    bash_parser: RefCell<Parser>,
+    // NOTE: interior mutability used since Parser::parse requires &mut self,
+    // and audits are shared between threads.
+    bash_parser: Mutex<Parser>,
 }
 
 audit_meta!(GitHubEnv, "github-env", "dangerous use of GITHUB_ENV");
@@ -24,7 +24,8 @@ impl GitHubEnv {
     fn bash_uses_github_env(&self, script_body: &str) -> anyhow::Result<bool> {
         let tree = &self
             .bash_parser
-            .borrow_mut()
+            .lock()
+            .unwrap()
             .parse(script_body, None)
             .context("failed to parse `run:` body as bash")?;
 
@@ -83,7 +84,7 @@ impl WorkflowAudit for GitHubEnv {
             .set_language(&bash.into())
             .context("failed to load bash parser")?;
         Ok(Self {
-            bash_parser: RefCell::new(parser),
+            bash_parser: Mutex::new(parser),
         })
     }
 
@@ -137,7 +138,7 @@ impl WorkflowAudit for GitHubEnv {
 mod tests {
     use crate::audit::github_env::{GitHubEnv, GITHUB_ENV_WRITE_CMD};
     use crate::audit::WorkflowAudit;
-    use crate::state::{AuditState, Caches};
+    use crate::state::AuditState;
 
     #[test]
     fn test_exploitable_bash_patterns() {
@@ -171,11 +172,7 @@ mod tests {
             ("echo foo >> $OTHER_ENV # not $GITHUB_ENV", false),
             ("something | tee \"${$OTHER_ENV}\" # not $GITHUB_ENV", false),
         ] {
-            let audit_state = AuditState {
-                no_online_audits: false,
-                gh_token: None,
-                caches: Caches::new(),
-            };
+            let audit_state = AuditState::builder().build();
 
             let sut = GitHubEnv::new(audit_state).expect("failed to create audit");
 
@@ -0,0 +1,130 @@
+//! Detects `run:` steps that append attacker-controlled values to
+//! `GITHUB_PATH`.
+//!
+//! Every directory in `GITHUB_PATH` is prepended to `PATH` for all
+//! subsequent steps in the job, so an attacker who controls one can plant
+//! binaries that shadow ordinary commands (`git`, `npm`, etc.) and run
+//! with the job's privileges.
+
+use std::ops::Deref;
+
+use anyhow::Result;
+use github_actions_models::{action, workflow::job::StepBody};
+
+use super::{audit_meta, WorkflowAudit};
+use crate::{
+    contexts::ContextPolicy,
+    finding::{Confidence, Finding, Severity, SymbolicLocation},
+    models::{AuditInput, CompositeStep, Step},
+    state::AuditState,
+};
+
+pub(crate) struct GitHubPathInjection {
+    contexts: ContextPolicy,
+}
+
+audit_meta!(
+    GitHubPathInjection,
+    "github-path-injection",
+    "attacker-controlled GITHUB_PATH entry"
+);
+
+/// Returns whether the given line of a script appears to write to
+/// `GITHUB_PATH`, in any of the usual shells.
+fn writes_github_path(line: &str) -> bool {
+    let line = line.to_ascii_lowercase();
+
+    // e.g. `>> $GITHUB_PATH`, `| tee -a "$GITHUB_PATH"`,
+    // `Add-Content $env:GITHUB_PATH`, or `>> %GITHUB_PATH%`.
+    line.contains("github_path")
+        && [">", "tee", "add-content", "out-file", "set-content"]
+            .iter()
+            .any(|writer| line.contains(writer))
+}
+
+impl GitHubPathInjection {
+    fn audit_script<'w>(
+        &self,
+        script: &str,
+        script_loc: SymbolicLocation<'w>,
+        input: &'w impl AuditInput,
+    ) -> Result<Vec<Finding<'w>>> {
+        let mut findings = vec![];
+
+        for line in script.lines().map(str::trim) {
+            if !writes_github_path(line) {
+                continue;
+            }
+
+            let contexts = self.contexts.untrusted_contexts(line);
+            if contexts.is_empty() {
+                continue;
+            }
+
+            findings.push(
+                Self::finding()
+                    .severity(Severity::High)
+                    .confidence(Confidence::High)
+                    .add_location(script_loc.clone().subfeature(line).annotated(format!(
+                        "{contexts} may add an attacker-controlled directory to PATH",
+                        contexts = contexts.join(", ")
+                    )))
+                    .build(input)?,
+            );
+        }
+
+        Ok(findings)
+    }
+}
+
+impl WorkflowAudit for GitHubPathInjection {
+    fn new(state: AuditState) -> Result<Self> {
+        Ok(Self {
+            contexts: state.config.contexts().clone(),
+        })
+    }
+
+    fn audit_step<'w>(&self, step: &Step<'w>) -> Result<Vec<Finding<'w>>> {
+        let StepBody::Run { run, .. } = &step.deref().body else {
+            return Ok(vec![]);
+        };
+
+        self.audit_script(
+            run,
+            step.location().with_keys(&["run".into()]),
+            step.workflow(),
+        )
+    }
+
+    fn audit_composite_step<'a>(&self, step: &CompositeStep<'a>) -> Result<Vec<Finding<'a>>> {
+        let action::Step::RunShell(action::RunShell { run, .. }) = step.deref() else {
+            return Ok(vec![]);
+        };
+
+        self.audit_script(run, step.location().with_keys(&["run".into()]), step.parent)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::writes_github_path;
+
+    #[test]
+    fn test_writes_github_path() {
+        for (line, expected) in [
+            ("echo foo >> $GITHUB_PATH", true),
+            ("echo foo >> \"${GITHUB_PATH}\"", true),
+            ("echo foo | tee -a $GITHUB_PATH", true),
+            ("Add-Content $env:GITHUB_PATH foo", true),
+            (
+                "echo foo | Out-File -FilePath $env:GITHUB_PATH -Append",
+                true,
+            ),
+            ("echo foo >> %GITHUB_PATH%", true),
+            ("echo foo >> $GITHUB_ENV", false),
+            ("cat $GITHUB_PATH", false),
+        ] {
+            assert_eq!(writes_github_path(line), expected, "{line}");
+        }
+    }
+}
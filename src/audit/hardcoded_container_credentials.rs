@@ -59,7 +59,7 @@ impl WorkflowAudit for HardcodedContainerCredentials {
                             .confidence(Confidence::High)
                             .add_location(
                                 job.location()
-                                    .with_keys(&["container".into(), "credentials".into()])
+                                    .with_path("container.credentials")
                                     .annotated("container registry password is hard-coded"),
                             )
                             .build(workflow)?,
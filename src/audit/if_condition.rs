@@ -0,0 +1,240 @@
+//! Detects `if:` conditions that gate on spoofable or attacker-controlled
+//! contexts, as if they were access control.
+//!
+//! A condition like `github.actor == 'dependabot[bot]'` looks like it
+//! restricts who can run a job, but `github.actor` is whoever caused the
+//! run (which can differ from whoever authored the code being run, e.g.
+//! when a maintainer re-runs a fork's pull request), and the likes of
+//! `github.head_ref == 'main'` can be satisfied by anyone who can open a
+//! pull request from a fork.
+
+use std::ops::Deref;
+
+use anyhow::Result;
+use github_actions_models::{
+    action,
+    common::{expr::ExplicitExpr, If},
+    workflow,
+};
+
+use super::{audit_meta, WorkflowAudit};
+use crate::{
+    contexts::ContextPolicy,
+    expr::{BinOp, Expr},
+    finding::{Confidence, Finding, Severity, SymbolicLocation},
+    models::{AuditInput, CompositeStep, Job, Step},
+    state::AuditState,
+};
+
+/// Contexts that identify who caused a run, rather than who wrote what
+/// it runs.
+const ACTOR_CONTEXTS: &[&str] = &["github.actor", "github.triggering_actor"];
+
+/// Contexts that GitHub sets for a fork which can't impersonate the base
+/// repository, making them sound to gate on.
+const FORK_CHECK_CONTEXTS: &[&str] = &[
+    "github.event.pull_request.head.repo.fork",
+    "github.event.pull_request.head.repo.full_name",
+];
+
+/// Functions that compare their arguments, like `==` does.
+const COMPARISONS: &[&str] = &["contains", "endswith", "startswith"];
+
+pub(crate) struct IfCondition {
+    contexts: ContextPolicy,
+}
+
+audit_meta!(
+    IfCondition,
+    "if-condition",
+    "if: condition gates on spoofable context"
+);
+
+impl IfCondition {
+    /// Returns whether the given context can't be relied upon to gate
+    /// access.
+    fn is_spoofable(&self, context: &str) -> bool {
+        let is = |contexts: &[&str]| {
+            contexts
+                .iter()
+                .any(|known| known.eq_ignore_ascii_case(context))
+        };
+
+        is(ACTOR_CONTEXTS)
+            || (self.contexts.is_untrusted_context(context) && !is(FORK_CHECK_CONTEXTS))
+    }
+
+    /// Collects the spoofable contexts that are compared against a
+    /// constant anywhere within the given expression.
+    fn gated_contexts<'e>(&self, expr: &'e Expr, gated: &mut Vec<&'e str>) {
+        let operands = match expr {
+            Expr::BinOp {
+                lhs,
+                op: BinOp::Eq | BinOp::Neq,
+                rhs,
+            } => Some((lhs.deref(), rhs.deref())),
+            Expr::Call { func, args }
+                if args.len() == 2 && COMPARISONS.contains(&func.to_lowercase().as_str()) =>
+            {
+                Some((&args[0], &args[1]))
+            }
+            _ => None,
+        };
+
+        if let Some((lhs, rhs)) = operands {
+            for (ours, theirs) in [(lhs, rhs), (rhs, lhs)] {
+                if theirs.contexts().is_empty() {
+                    gated.extend(
+                        ours.contexts()
+                            .into_iter()
+                            .filter(|context| self.is_spoofable(context)),
+                    );
+                }
+            }
+            return;
+        }
+
+        match expr {
+            Expr::BinOp { lhs, rhs, .. } => {
+                self.gated_contexts(lhs, gated);
+                self.gated_contexts(rhs, gated);
+            }
+            Expr::UnOp { expr, .. } => self.gated_contexts(expr, gated),
+            Expr::Call { args, .. } => {
+                for arg in args {
+                    self.gated_contexts(arg, gated);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn audit_if<'w>(
+        &self,
+        cond: Option<&If>,
+        location: SymbolicLocation<'w>,
+        input: &'w impl AuditInput,
+    ) -> Result<Vec<Finding<'w>>> {
+        let Some(If::Expr(cond)) = cond else {
+            return Ok(vec![]);
+        };
+
+        // Conditions may be either bare or curly.
+        let bare = ExplicitExpr::from_curly(cond.trim());
+        let bare = bare.as_ref().map_or(cond.as_str(), |expr| expr.as_bare());
+        let Ok(expr) = Expr::parse(bare) else {
+            tracing::debug!("couldn't parse condition: {bare}");
+            return Ok(vec![]);
+        };
+
+        let mut gated = vec![];
+        self.gated_contexts(&expr, &mut gated);
+        gated.dedup();
+        if gated.is_empty() {
+            return Ok(vec![]);
+        }
+
+        Ok(vec![Self::finding()
+            .severity(Severity::Informational)
+            .confidence(Confidence::Medium)
+            .add_location(location.with_keys(&["if".into()]).annotated(format!(
+                "{gated} can't reliably restrict who runs this",
+                gated = gated.join(", ")
+            )))
+            .build(input)?])
+    }
+}
+
+impl WorkflowAudit for IfCondition {
+    fn new(state: AuditState) -> Result<Self> {
+        Ok(Self {
+            contexts: state.config.contexts().clone(),
+        })
+    }
+
+    fn audit_normal_job<'w>(&self, job: &Job<'w>) -> Result<Vec<Finding<'w>>> {
+        let workflow::Job::NormalJob(normal) = job.deref() else {
+            return Ok(vec![]);
+        };
+
+        let mut findings = self.audit_if(normal.r#if.as_ref(), job.location(), job.parent())?;
+        for step in job.steps() {
+            findings.extend(self.audit_step(&step)?);
+        }
+
+        Ok(findings)
+    }
+
+    fn audit_reusable_job<'w>(&self, job: &Job<'w>) -> Result<Vec<Finding<'w>>> {
+        let workflow::Job::ReusableWorkflowCallJob(reusable) = job.deref() else {
+            return Ok(vec![]);
+        };
+
+        self.audit_if(reusable.r#if.as_ref(), job.location(), job.parent())
+    }
+
+    fn audit_step<'w>(&self, step: &Step<'w>) -> Result<Vec<Finding<'w>>> {
+        self.audit_if(step.r#if.as_ref(), step.location(), step.workflow())
+    }
+
+    fn audit_composite_step<'a>(&self, step: &CompositeStep<'a>) -> Result<Vec<Finding<'a>>> {
+        let cond = match step.deref() {
+            action::Step::RunShell(run) => run.r#if.as_ref(),
+            action::Step::UseAction(uses) => uses.r#if.as_ref(),
+        };
+
+        self.audit_if(cond, step.location(), step.parent)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IfCondition;
+    use crate::{contexts::ContextPolicy, expr::Expr};
+
+    #[test]
+    fn test_gated_contexts() {
+        let audit = IfCondition {
+            contexts: ContextPolicy::default(),
+        };
+
+        for (cond, expected) in [
+            ("github.actor == 'dependabot[bot]'", &["github.actor"][..]),
+            (
+                "'octocat' != github.triggering_actor",
+                &["github.triggering_actor"],
+            ),
+            (
+                "github.event_name == 'push' && github.head_ref == 'main'",
+                &["github.head_ref"],
+            ),
+            (
+                "contains(github.event.pull_request.labels.*.name, 'safe to test')",
+                &["github.event.pull_request.labels.*.name"],
+            ),
+            (
+                "!startsWith(github.event.pull_request.title, '[ci]')",
+                &["github.event.pull_request.title"],
+            ),
+            (
+                "contains(fromJSON('[\"alice\", \"bob\"]'), github.actor)",
+                &["github.actor"],
+            ),
+            // Not compared against a constant.
+            ("github.actor == github.repository_owner", &[]),
+            // Fork checks are sound.
+            (
+                "github.event.pull_request.head.repo.full_name == 'octo-org/octo-repo'",
+                &[],
+            ),
+            ("github.event.pull_request.head.repo.fork == false", &[]),
+            ("github.ref == 'refs/heads/main'", &[]),
+            ("success() && github.event_name == 'push'", &[]),
+        ] {
+            let expr = Expr::parse(cond).unwrap();
+            let mut gated = vec![];
+            audit.gated_contexts(&expr, &mut gated);
+            assert_eq!(gated, expected, "{cond}");
+        }
+    }
+}
@@ -120,7 +120,7 @@ impl WorkflowAudit for ImpostorCommit {
             return Err(anyhow!("offline audits only requested"));
         }
 
-        let Some(client) = state.github_client() else {
+        let Some(client) = state.validated_github_client()? else {
             return Err(anyhow!("can't run without a GitHub API token"));
         };
 
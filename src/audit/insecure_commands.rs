@@ -7,10 +7,19 @@ use github_actions_models::common::expr::LoE;
 use github_actions_models::common::{Env, EnvValue};
 use github_actions_models::workflow::job::StepBody;
 use github_actions_models::workflow::Job;
+use regex::Regex;
 use std::ops::Deref;
+use std::sync::LazyLock;
 
 use super::audit_meta;
 
+/// Matches the removed `::set-env` and `::add-path` workflow commands.
+///
+/// Their replacements (writing to `$GITHUB_ENV` and `$GITHUB_PATH`)
+/// don't use the `::command::` syntax, and so don't match.
+static INSECURE_COMMAND_EMITTED: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"::(set-env|add-path)\b").unwrap());
+
 pub(crate) struct InsecureCommands;
 
 audit_meta!(
@@ -53,6 +62,25 @@ impl InsecureCommands {
             .build(workflow)
     }
 
+    fn insecure_commands_emitted<'w>(
+        &self,
+        workflow: &'w Workflow,
+        location: SymbolicLocation<'w>,
+        line: &str,
+        command: &str,
+    ) -> Result<Finding<'w>> {
+        Self::finding()
+            .confidence(Confidence::High)
+            .severity(Severity::High)
+            .add_location(
+                location
+                    .with_keys(&["run".into()])
+                    .subfeature(line)
+                    .annotated(format!("uses the removed ::{command} workflow command")),
+            )
+            .build(workflow)
+    }
+
     fn has_insecure_commands_enabled(&self, env: &Env) -> bool {
         if let Some(EnvValue::String(value)) = env.get("ACTIONS_ALLOW_UNSECURE_COMMANDS") {
             !value.is_empty()
@@ -66,31 +94,45 @@ impl InsecureCommands {
         workflow: &'w Workflow,
         steps: Steps<'w>,
     ) -> Result<Vec<Finding<'w>>> {
-        steps
-            .into_iter()
-            .filter_map(|step| {
-                let StepBody::Run {
-                    run: _,
-                    working_directory: _,
-                    shell: _,
-                    ref env,
-                } = &step.deref().body
-                else {
-                    return None;
-                };
-
-                match env {
-                    // The entire environment block is an expression, which we
-                    // can't follow (for now). Emit an auditor-only finding.
-                    LoE::Expr(_) => {
-                        Some(self.insecure_commands_maybe_present(workflow, step.location()))
+        let mut results = vec![];
+
+        for step in steps {
+            let StepBody::Run {
+                ref run,
+                working_directory: _,
+                shell: _,
+                ref env,
+            } = &step.deref().body
+            else {
+                continue;
+            };
+
+            match env {
+                // The entire environment block is an expression, which we
+                // can't follow (for now). Emit an auditor-only finding.
+                LoE::Expr(_) => {
+                    results.push(self.insecure_commands_maybe_present(workflow, step.location())?)
+                }
+                LoE::Literal(env) => {
+                    if self.has_insecure_commands_enabled(env) {
+                        results.push(self.insecure_commands_allowed(workflow, step.location())?);
                     }
-                    LoE::Literal(env) => self
-                        .has_insecure_commands_enabled(env)
-                        .then(|| self.insecure_commands_allowed(workflow, step.location())),
                 }
-            })
-            .collect()
+            }
+
+            for line in run.lines().map(str::trim) {
+                for caps in INSECURE_COMMAND_EMITTED.captures_iter(line) {
+                    results.push(self.insecure_commands_emitted(
+                        workflow,
+                        step.location(),
+                        line,
+                        &caps[1],
+                    )?);
+                }
+            }
+        }
+
+        Ok(results)
     }
 }
 
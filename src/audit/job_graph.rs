@@ -0,0 +1,185 @@
+//! Detects malformed job dependency graphs.
+//!
+//! A `needs:` that references a job that doesn't exist (e.g. after a
+//! rename), or that forms a cycle, makes the workflow invalid. Worse, it
+//! often means that a job meant to gate another one (like a test or
+//! approval job gating a deployment) isn't actually doing so.
+
+use std::{collections::HashMap, ops::Deref};
+
+use anyhow::Result;
+use github_actions_models::workflow;
+
+use super::{audit_meta, WorkflowAudit};
+use crate::{
+    finding::{Confidence, Finding, Severity},
+    models::{Job, Workflow},
+    state::AuditState,
+};
+
+pub(crate) struct JobGraph;
+
+audit_meta!(
+    JobGraph,
+    "job-graph",
+    "job needs an unknown job, or forms a cycle"
+);
+
+/// Returns the given job's `needs:`.
+fn needs<'w>(job: &Job<'w>) -> &'w [String] {
+    match job.deref() {
+        workflow::Job::NormalJob(normal) => &normal.needs,
+        workflow::Job::ReusableWorkflowCallJob(reusable) => &reusable.needs,
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Visit {
+    InProgress,
+    Done,
+}
+
+/// Finds dependency cycles via depth-first search from each job.
+///
+/// Each cycle is returned as `(closing job, path)`, where the closing job
+/// is the one whose `needs:` completes the cycle, and the path lists the
+/// job IDs in the cycle, starting and ending with the same job.
+fn cycles<'w>(
+    graph: &HashMap<&'w str, &'w [String]>,
+    order: &[&'w str],
+) -> Vec<(&'w str, Vec<&'w str>)> {
+    fn visit<'w>(
+        id: &'w str,
+        graph: &HashMap<&'w str, &'w [String]>,
+        visits: &mut HashMap<&'w str, Visit>,
+        path: &mut Vec<&'w str>,
+        cycles: &mut Vec<(&'w str, Vec<&'w str>)>,
+    ) {
+        visits.insert(id, Visit::InProgress);
+        path.push(id);
+
+        for need in graph[id] {
+            // Unknown jobs are reported separately.
+            let Some((&need, _)) = graph.get_key_value(need.as_str()) else {
+                continue;
+            };
+
+            match visits.get(need) {
+                None => visit(need, graph, visits, path, cycles),
+                Some(Visit::InProgress) => {
+                    let start = path.iter().position(|job| *job == need).unwrap();
+                    let mut cycle = path[start..].to_vec();
+                    cycle.push(need);
+                    cycles.push((id, cycle));
+                }
+                Some(Visit::Done) => (),
+            }
+        }
+
+        path.pop();
+        visits.insert(id, Visit::Done);
+    }
+
+    let mut visits = HashMap::new();
+    let mut cycles = vec![];
+    for id in order {
+        if !visits.contains_key(id) {
+            visit(id, graph, &mut visits, &mut vec![], &mut cycles);
+        }
+    }
+
+    cycles
+}
+
+impl WorkflowAudit for JobGraph {
+    fn new(_state: AuditState) -> Result<Self> {
+        Ok(Self)
+    }
+
+    fn audit_workflow<'w>(&self, workflow: &'w Workflow) -> Result<Vec<Finding<'w>>> {
+        let mut findings = vec![];
+
+        let jobs = workflow.jobs().collect::<Vec<_>>();
+        let graph = jobs
+            .iter()
+            .map(|job| (job.id, needs(job)))
+            .collect::<HashMap<_, _>>();
+
+        for job in &jobs {
+            for need in needs(job) {
+                if graph.contains_key(need.as_str()) {
+                    continue;
+                }
+
+                findings.push(
+                    Self::finding()
+                        .severity(Severity::Medium)
+                        .confidence(Confidence::High)
+                        .add_location(
+                            job.location()
+                                .with_keys(&["needs".into()])
+                                .annotated(format!("needs unknown job {need}")),
+                        )
+                        .build(workflow)?,
+                );
+            }
+        }
+
+        let order = jobs.iter().map(|job| job.id).collect::<Vec<_>>();
+        for (closing, cycle) in cycles(&graph, &order) {
+            let job = jobs.iter().find(|job| job.id == closing).unwrap();
+
+            findings.push(
+                Self::finding()
+                    .severity(Severity::Medium)
+                    .confidence(Confidence::High)
+                    .add_location(
+                        job.location()
+                            .with_keys(&["needs".into()])
+                            .annotated(format!(
+                                "needs form a cycle: {cycle}",
+                                cycle = cycle.join(" → ")
+                            )),
+                    )
+                    .build(workflow)?,
+            );
+        }
+
+        Ok(findings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::cycles;
+
+    #[test]
+    fn test_cycles() {
+        let needs = |needs: &[&str]| needs.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+
+        let a = needs(&["b"]);
+        let b = needs(&["c", "missing"]);
+        let c = needs(&["a"]);
+        let d = needs(&["d"]);
+        let e = needs(&["a"]);
+
+        let graph = HashMap::from([
+            ("a", a.as_slice()),
+            ("b", b.as_slice()),
+            ("c", c.as_slice()),
+            ("d", d.as_slice()),
+            ("e", e.as_slice()),
+        ]);
+
+        assert_eq!(
+            cycles(&graph, &["a", "b", "c", "d", "e"]),
+            [("c", vec!["a", "b", "c", "a"]), ("d", vec!["d", "d"])]
+        );
+
+        // Acyclic graphs have no cycles, even with unknown jobs.
+        let graph = HashMap::from([("b", b.as_slice()), ("e", e.as_slice())]);
+        assert!(cycles(&graph, &["b", "e"]).is_empty());
+    }
+}
@@ -1,23 +1,113 @@
 //! Detects publicly disclosed action vulnerabilities.
 //!
-//! This audit uses GitHub's security advisories API as a source of
-//! ground truth.
+//! This audit uses GitHub's security advisories API (or a local snapshot
+//! of it, via `--advisory-db`) as a source of ground truth. Both step-level
+//! actions and reusable workflow calls are checked.
 //!
 //! See: <https://docs.github.com/en/rest/security-advisories/global-advisories?apiVersion=2022-11-28>
 
+use std::{ops::Deref, sync::Arc};
+
 use anyhow::{anyhow, Context, Result};
+use github_actions_models::workflow::Job as WorkflowJob;
 
 use crate::{
-    finding::{Confidence, Severity},
+    advisory_db::AdvisoryDb,
+    finding::{Confidence, Finding, Severity, SymbolicLocation},
     github_api,
-    models::{RepositoryUses, Uses},
+    models::{AuditInput, CompositeStep, Job, RepositoryUses, Step, Uses},
     state::AuditState,
+    utils::{repin_uses, Version},
 };
 
 use super::{audit_meta, WorkflowAudit};
 
+/// An advisory that affects a particular `uses:`.
+struct KnownVulnerability {
+    severity: Severity,
+    confidence: Confidence,
+    ghsa_id: String,
+    summary: String,
+    url: String,
+    /// The first version patched against the advisory, if known.
+    patched: Option<String>,
+}
+
+/// How a `uses:` ref was resolved into the version that's checked
+/// against advisories.
+#[derive(Clone, Copy)]
+enum Resolution {
+    /// The ref is itself a version tag.
+    Tag,
+    /// The ref is a commit, and the version is its tag.
+    Commit,
+    /// The ref is some other symbolic ref (like a `v1` branch or tag), and
+    /// the version is the most specific tag of the commit it points to.
+    ///
+    /// This is a heuristic: other tags may point to the same commit.
+    RefCommitTag,
+    /// The ref's commit has no tag, so the ref is used as the version.
+    Untagged,
+    /// The ref was taken at face value, without the GitHub API.
+    Offline,
+}
+
+impl Resolution {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Tag => "tag",
+            Self::Commit => "commit",
+            Self::RefCommitTag => "ref-commit-tag",
+            Self::Untagged => "untagged",
+            Self::Offline => "offline",
+        }
+    }
+}
+
+/// The version that a `uses:` ref was resolved to.
+struct Resolved {
+    version: Version,
+    /// The version as spelled by its tag (or the ref itself).
+    name: String,
+    resolution: Resolution,
+    confidence: Confidence,
+}
+
+impl Resolved {
+    /// Describes how `git_ref` was resolved, for a finding's annotation.
+    fn describe(&self, git_ref: &str) -> String {
+        let name = &self.name;
+        match self.resolution {
+            Resolution::Tag => format!("version {name}"),
+            Resolution::Commit => format!("resolved {git_ref} to {name}"),
+            Resolution::RefCommitTag => {
+                format!("resolved {git_ref} to {name} through its commit, which may be imprecise")
+            }
+            Resolution::Untagged => {
+                format!("{git_ref}'s commit has no tag, so it was checked as-is")
+            }
+            Resolution::Offline => format!("{git_ref} taken at face value, without GitHub's API"),
+        }
+    }
+}
+
+/// The outcome of checking a `uses:` against its advisories.
+enum Lookup {
+    /// The `uses:` was checked against its advisories, at the version
+    /// it was resolved to (if any).
+    Checked(Option<Resolved>, Vec<KnownVulnerability>),
+    /// The ref couldn't be resolved into a version, e.g. because the
+    /// GitHub API kept failing.
+    Unresolved,
+}
+
 pub(crate) struct KnownVulnerableActions {
-    client: github_api::Client,
+    /// Used to resolve refs into versions, if available.
+    client: Option<github_api::Client>,
+    /// Used instead of the advisories API, if available.
+    advisory_db: Option<Arc<AdvisoryDb>>,
+    /// Whether to check against withdrawn advisories too.
+    include_withdrawn: bool,
 }
 
 audit_meta!(
@@ -27,10 +117,12 @@ audit_meta!(
 );
 
 impl KnownVulnerableActions {
-    fn action_known_vulnerabilities(
-        &self,
+    /// Resolves the ref in `uses` into the most specific version it
+    /// corresponds to, if possible, along with how it was resolved.
+    fn resolve_version(
+        client: &github_api::Client,
         uses: &RepositoryUses<'_>,
-    ) -> Result<Vec<(Severity, String)>> {
+    ) -> Result<Option<(String, Resolution)>> {
         let version = match uses.git_ref {
             // If `uses` is pinned to a symbolic ref, we need to perform
             // feats of heroism to figure out what's going on.
@@ -40,42 +132,38 @@ impl KnownVulnerableActions {
             // 1. The ref is a "version", but it's something like a "v3"
             //    branch or tag. These are obnoxious to handle, but we
             //    can do so with a heuristic: resolve the ref to a commit,
-            //    then find the longest tag name that also matches that commit.
+            //    then find the most specific tag that also matches that commit.
             //    For example, branch `v1` becomes tag `v1.2.3`.
             // 2. The ref is something version-y but not itself a version,
             //    like `gh-action-pypi-publish`'s `release/v1` branch.
             //    We use the same heuristic for these.
             //
             // To handle all of the above, we convert the ref into a commit
-            // and then find the longest tag for that commit.
+            // and then find the most specific tag for that commit.
             Some(version) if !uses.ref_is_commit() => {
-                let Some(commit_ref) =
-                    self.client.commit_for_ref(uses.owner, uses.repo, version)?
+                let Some(commit_ref) = client.commit_for_ref(uses.owner, uses.repo, version)?
                 else {
                     // No `ref -> commit` means that the action's version
                     // is probably just outright invalid.
-                    return Ok(vec![]);
+                    return Ok(None);
                 };
 
-                match self
-                    .client
-                    .longest_tag_for_commit(uses.owner, uses.repo, &commit_ref)?
-                {
-                    Some(tag) => tag.name,
+                match client.tag_for_commit(uses.owner, uses.repo, &commit_ref)? {
+                    Some(tag) if tag.name == version => (tag.name, Resolution::Tag),
+                    Some(tag) => (tag.name, Resolution::RefCommitTag),
                     // Somehow we've round-tripped through a commit and ended
                     // up without a tag, which suggests we went
                     // `branch -> sha -> {no tag}`. In that case just use our
                     // original ref, since it's the best we have.
-                    None => version.to_string(),
+                    None => (version.to_string(), Resolution::Untagged),
                 }
             }
             // If `uses` is pinned to a sha-ref, we need to find the
             // tag matching that ref. In theory the action's repo could do
             // something annoying like use branches for versions instead,
             // which we should also probably support.
-            Some(commit_ref) => match self
-                .client
-                .longest_tag_for_commit(uses.owner, uses.repo, commit_ref)
+            Some(commit_ref) => match client
+                .tag_for_commit(uses.owner, uses.repo, commit_ref)
                 .with_context(|| {
                     format!(
                         "couldn't retrieve tag for {owner}/{repo}@{commit_ref}",
@@ -83,40 +171,280 @@ impl KnownVulnerableActions {
                         repo = uses.repo
                     )
                 })? {
-                Some(tag) => tag.name,
+                Some(tag) => (tag.name, Resolution::Commit),
                 // No corresponding tag means the user is maybe doing something
                 // weird, like using a commit ref off of a branch that isn't
                 // also tagged. Probably not good, but also not something
                 // we can easily discover known vulns for.
-                None => return Ok(vec![]),
+                None => return Ok(None),
+            },
+            // Handled by our caller.
+            None => return Ok(None),
+        };
+
+        Ok(Some(version))
+    }
+
+    /// Takes the ref in `uses` at face value, for when it can't be resolved
+    /// via the GitHub API.
+    ///
+    /// Only an exact version (like `v1.2.3`) is checked with confidence:
+    /// a partial version (like `v1`) is likely a moving tag, and anything
+    /// else (a commit or branch) can't be checked at all.
+    fn offline_version(uses: &RepositoryUses<'_>) -> Option<Resolved> {
+        if uses.ref_is_commit() {
+            return None;
+        }
+
+        let git_ref = uses.git_ref?;
+        let version = Version::parse(git_ref)?;
+        let exact = git_ref
+            .split(['-', '+'])
+            .next()
+            .is_some_and(|release| release.matches('.').count() == 2);
+
+        Some(Resolved {
+            version,
+            name: git_ref.to_string(),
+            resolution: Resolution::Offline,
+            confidence: if exact {
+                Confidence::High
+            } else {
+                Confidence::Unknown
             },
-            // No version means the action runs the latest default branch
-            // version. We could in theory query GHSA for this but it's
-            // unlikely to be meaningful.
-            // TODO: Maybe we need a separate (low-sev) audit for actions usage
-            // on @master/@main/etc?
-            None => return Ok(vec![]),
+        })
+    }
+
+    /// Resolves the commit for the given patched version of `uses`, if possible.
+    ///
+    /// Advisories don't record how an action's tags are spelled, so both
+    /// `1.2.3` and `v1.2.3` are tried.
+    fn resolve_patched(
+        &self,
+        uses: &RepositoryUses<'_>,
+        patched: &str,
+    ) -> Option<(String, String)> {
+        let client = self.client.as_ref()?;
+
+        [patched.to_string(), format!("v{patched}")]
+            .into_iter()
+            .find_map(|tag| {
+                // This is only used for a suggestion, so failing to resolve
+                // the tag shouldn't fail the audit.
+                let commit = client
+                    .commit_for_ref(uses.owner, uses.repo, &tag)
+                    .inspect_err(|e| {
+                        tracing::debug!("couldn't resolve {uses}: {e}", uses = uses.with_ref(&tag))
+                    })
+                    .ok()
+                    .flatten()?;
+
+                Some((tag, commit))
+            })
+    }
+
+    fn action_known_vulnerabilities(&self, uses: &RepositoryUses<'_>) -> Result<Lookup> {
+        // No version means the action runs the latest default branch
+        // version. We could in theory query GHSA for this but it's
+        // unlikely to be meaningful.
+        // TODO: Maybe we need a separate (low-sev) audit for actions usage
+        // on @master/@main/etc?
+        if uses.git_ref.is_none() {
+            return Ok(Lookup::Checked(None, vec![]));
+        }
+
+        // Advisories express affected versions as ranges, so we need to
+        // compare against our resolved version ourselves.
+        let resolved = match &self.client {
+            Some(client) => {
+                // The API has already been retried by this point, so a
+                // failure here is unlikely to go away: report it rather
+                // than failing the whole run.
+                let (version, resolution) = match Self::resolve_version(client, uses) {
+                    Ok(Some(resolved)) => resolved,
+                    Ok(None) => return Ok(Lookup::Checked(None, vec![])),
+                    Err(e) => {
+                        tracing::warn!(
+                            "couldn't resolve {owner}/{repo}@{git_ref}: {e:#}",
+                            owner = uses.owner,
+                            repo = uses.repo,
+                            git_ref = uses.git_ref.unwrap_or_default()
+                        );
+                        return Ok(Lookup::Unresolved);
+                    }
+                };
+
+                let Some(parsed) = Version::parse(&version) else {
+                    tracing::debug!(
+                        "can't check advisories for {owner}/{repo}@{version}: not a version",
+                        owner = uses.owner,
+                        repo = uses.repo
+                    );
+                    return Ok(Lookup::Checked(None, vec![]));
+                };
+
+                Some(Resolved {
+                    version: parsed,
+                    name: version,
+                    resolution,
+                    confidence: match resolution {
+                        Resolution::Tag | Resolution::Commit | Resolution::Offline => {
+                            Confidence::High
+                        }
+                        // The heuristic can pick a different tag for the same
+                        // commit, and an untagged ref may not be a release at all.
+                        Resolution::RefCommitTag => Confidence::Medium,
+                        Resolution::Untagged => Confidence::Low,
+                    },
+                })
+            }
+            None => Self::offline_version(uses),
         };
 
-        let vulns = self
-            .client
-            .gha_advisories(uses.owner, uses.repo, &version)?;
+        let advisories = match (&self.advisory_db, &self.client) {
+            (Some(db), _) => db.advisories(uses.owner, uses.repo),
+            (None, Some(client)) => client.gha_advisories(uses.owner, uses.repo)?,
+            // NOTE: Unreachable, since we require one of the two.
+            (None, None) => return Ok(Lookup::Checked(resolved, vec![])),
+        };
 
         let mut results = vec![];
 
-        for vuln in vulns {
-            let severity = match vuln.severity.as_str() {
+        for advisory in advisories {
+            // A withdrawn advisory was most likely published in error.
+            if advisory.withdrawn() && !self.include_withdrawn {
+                tracing::debug!("skipping withdrawn advisory {id}", id = advisory.ghsa_id);
+                continue;
+            }
+
+            let confidence = match &resolved {
+                Some(resolved) => {
+                    if !advisory.affects(uses.owner, uses.repo, &resolved.version) {
+                        continue;
+                    }
+
+                    resolved.confidence
+                }
+                // Without a version, any of the action's advisories
+                // might apply.
+                None => Confidence::Unknown,
+            };
+
+            let severity = match advisory.severity.as_str() {
                 "low" => Severity::Unknown,
                 "medium" => Severity::Medium,
                 "high" => Severity::High,
-                "critical" => Severity::High,
+                "critical" => Severity::Critical,
                 _ => Severity::Unknown,
             };
 
-            results.push((severity, vuln.ghsa_id));
+            let patched = advisory
+                .patched_version(
+                    uses.owner,
+                    uses.repo,
+                    resolved.as_ref().map(|resolved| &resolved.version),
+                )
+                .map(str::to_string);
+
+            let url = advisory.html_url.unwrap_or_else(|| {
+                format!("https://github.com/advisories/{id}", id = advisory.ghsa_id)
+            });
+
+            results.push(KnownVulnerability {
+                severity,
+                confidence,
+                ghsa_id: advisory.ghsa_id,
+                summary: advisory.summary,
+                url,
+                patched,
+            });
+        }
+
+        Ok(Lookup::Checked(resolved, results))
+    }
+
+    fn audit_uses<'w>(
+        &self,
+        uses: Option<Uses<'_>>,
+        location: SymbolicLocation<'w>,
+        input: &'w impl AuditInput,
+    ) -> Result<Vec<Finding<'w>>> {
+        let mut findings = vec![];
+
+        let Some(Uses::Repository(uses)) = uses else {
+            return Ok(findings);
+        };
+
+        let (resolved, vulns) = match self.action_known_vulnerabilities(&uses)? {
+            Lookup::Checked(resolved, vulns) => (resolved, vulns),
+            Lookup::Unresolved => {
+                findings.push(
+                    Self::finding()
+                        .confidence(Confidence::Unknown)
+                        .severity(Severity::Unknown)
+                        .add_location(
+                            location
+                                .with_keys(&["uses".into()])
+                                .annotated("could not resolve this ref to check it for advisories"),
+                        )
+                        .build(input)?,
+                );
+                return Ok(findings);
+            }
+        };
+
+        // NOTE: Unversioned `uses:` are never flagged, so this is never empty.
+        let git_ref = uses.git_ref.unwrap_or_default();
+
+        for vuln in vulns {
+            let mut annotation = match vuln.summary.trim() {
+                "" => vuln.ghsa_id.clone(),
+                summary => format!("{id}: {summary}", id = vuln.ghsa_id),
+            };
+            if let Some(resolved) = &resolved {
+                annotation = format!("{annotation} ({})", resolved.describe(git_ref));
+            }
+
+            let mut finding = Self::finding()
+                .confidence(vuln.confidence)
+                .severity(vuln.severity)
+                .add_location(
+                    location
+                        .with_keys(&["uses".into()])
+                        .annotated(annotation)
+                        .with_url(&vuln.url),
+                )
+                .reference(vuln.url)
+                .property("uses", uses.with_ref(git_ref));
+            if let Some(resolved) = &resolved {
+                finding = finding
+                    .property("resolved-version", &resolved.name)
+                    .property("resolution", resolved.resolution.as_str());
+            }
+
+            if let Some(patched) = vuln.patched {
+                finding = match (self.resolve_patched(&uses, &patched), uses.git_ref) {
+                    (Some((tag, commit)), Some(git_ref)) => {
+                        let remediation = format!(
+                            "upgrade to {tag} or later: {pinned} # {tag}",
+                            pinned = uses.with_ref(&commit)
+                        );
+                        let git_ref = git_ref.to_string();
+
+                        finding
+                            .remediation(remediation)
+                            .replacement(move |feature| {
+                                repin_uses(feature, &git_ref, &commit, &tag)
+                            })
+                    }
+                    _ => finding.remediation(format!("upgrade to {patched} or later")),
+                };
+            }
+
+            findings.push(finding.build(input)?);
         }
 
-        Ok(results)
+        Ok(findings)
     }
 }
 
@@ -125,39 +453,57 @@ impl WorkflowAudit for KnownVulnerableActions {
     where
         Self: Sized,
     {
-        if state.no_online_audits {
-            return Err(anyhow!("offline audits only requested"));
-        }
+        // With a local advisory snapshot we can run without the API,
+        // at the cost of not resolving refs into versions.
+        let advisory_db = state.advisory_db.clone();
 
-        let Some(client) = state.github_client() else {
-            return Err(anyhow!("can't run without a GitHub API token"));
+        let client = if state.no_online_audits {
+            None
+        } else {
+            match state.validated_github_client() {
+                Ok(client) => client,
+                Err(e) if advisory_db.is_some() => {
+                    tracing::warn!("{e}; falling back to --advisory-db only");
+                    None
+                }
+                Err(e) => return Err(e),
+            }
         };
 
-        Ok(Self { client })
+        if client.is_none() && advisory_db.is_none() {
+            return Err(if state.no_online_audits {
+                anyhow!("offline audits only requested")
+            } else {
+                anyhow!("can't run without a GitHub API token or --advisory-db")
+            });
+        }
+
+        Ok(Self {
+            client,
+            advisory_db,
+            include_withdrawn: state.include_withdrawn,
+        })
     }
 
-    fn audit_step<'w>(&self, step: &super::Step<'w>) -> Result<Vec<super::Finding<'w>>> {
-        let mut findings = vec![];
+    fn audit_step<'w>(&self, step: &Step<'w>) -> Result<Vec<Finding<'w>>> {
+        self.audit_uses(step.uses(), step.location(), step.workflow())
+    }
 
-        let Some(Uses::Repository(uses)) = step.uses() else {
-            return Ok(findings);
+    fn audit_reusable_job<'w>(&self, job: &Job<'w>) -> Result<Vec<Finding<'w>>> {
+        let WorkflowJob::ReusableWorkflowCallJob(reusable) = job.deref() else {
+            return Ok(vec![]);
         };
 
-        for (severity, id) in self.action_known_vulnerabilities(&uses)? {
-            findings.push(
-                Self::finding()
-                    .confidence(Confidence::High)
-                    .severity(severity)
-                    .add_location(
-                        step.location()
-                            .with_keys(&["uses".into()])
-                            .annotated(&id)
-                            .with_url(format!("https://github.com/advisories/{id}")),
-                    )
-                    .build(step.workflow())?,
-            );
-        }
+        // Reusable workflows are versioned like any other action, so they're
+        // resolved against the same advisories as step-level `uses:`.
+        self.audit_uses(
+            Uses::from_reusable(&reusable.uses).map(Uses::Repository),
+            job.location(),
+            job.parent(),
+        )
+    }
 
-        Ok(findings)
+    fn audit_composite_step<'a>(&self, step: &CompositeStep<'a>) -> Result<Vec<Finding<'a>>> {
+        self.audit_uses(step.uses(), step.location(), step.parent)
     }
 }
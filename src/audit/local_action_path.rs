@@ -0,0 +1,107 @@
+//! Detects local action references (`uses: ./path`) that escape the
+//! repository, or that don't point to an action at all.
+//!
+//! Local actions are resolved against the workspace root, i.e. the
+//! repository checkout. A path that traverses out of it depends on
+//! whatever happens to be on the runner's filesystem, and a path that
+//! doesn't resolve to an action fails only once the step actually runs.
+
+use anyhow::Result;
+use camino::{Utf8Path, Utf8PathBuf};
+
+use super::{audit_meta, WorkflowAudit};
+use crate::{
+    finding::{Confidence, Finding, Severity, SymbolicLocation},
+    models::{AuditInput, CompositeStep, Step, Uses},
+    state::AuditState,
+};
+
+pub(crate) struct LocalActionPath;
+
+audit_meta!(
+    LocalActionPath,
+    "local-action-path",
+    "local action path escapes the repository or doesn't exist"
+);
+
+/// Returns the action definition within `dir`, if there is one.
+fn action_definition(dir: &Utf8Path) -> Option<Utf8PathBuf> {
+    ["action.yml", "action.yaml"]
+        .into_iter()
+        .map(|name| dir.join(name))
+        .find(|path| path.is_file())
+}
+
+impl LocalActionPath {
+    /// Returns an annotation describing what's wrong with the given `uses:`,
+    /// if anything, for an input within the given workspace root (if known).
+    fn check(uses: &Uses, root: Option<&Utf8Path>) -> Option<String> {
+        match uses {
+            Uses::Local(local) => {
+                let Some(normalized) = local.normalized() else {
+                    return Some("local action path escapes the repository root".into());
+                };
+
+                // Without a workspace root (e.g. for stdin or remote inputs),
+                // we can only check the path lexically.
+                let root = root?;
+                let Some(definition) = action_definition(&root.join(&normalized)) else {
+                    return Some(format!("no action.yml or action.yaml in {normalized}"));
+                };
+
+                // A symlink within the repository can still point outside of it.
+                match (definition.canonicalize_utf8(), root.canonicalize_utf8()) {
+                    (Ok(definition), Ok(root)) if !definition.starts_with(&root) => {
+                        Some("local action path escapes the repository root via a link".into())
+                    }
+                    _ => None,
+                }
+            }
+            // A bare `path/to/action` is parsed as `owner/repo/subpath`, which
+            // GitHub rejects without a ref. If it names a local action, it's
+            // almost certainly missing its `./`.
+            Uses::Repository(repo) if repo.git_ref.is_none() => {
+                let path = match repo.subpath {
+                    Some(subpath) => format!("{}/{}/{subpath}", repo.owner, repo.repo),
+                    None => format!("{}/{}", repo.owner, repo.repo),
+                };
+
+                action_definition(&root?.join(&path))
+                    .map(|_| format!("local action path must start with ./, i.e. ./{path}"))
+            }
+            _ => None,
+        }
+    }
+
+    fn audit_uses<'w>(
+        uses: Option<Uses>,
+        location: SymbolicLocation<'w>,
+        input: &'w impl AuditInput,
+    ) -> Result<Vec<Finding<'w>>> {
+        let Some(annotation) =
+            uses.and_then(|uses| Self::check(&uses, input.key().workspace_root()))
+        else {
+            return Ok(vec![]);
+        };
+
+        Ok(vec![Self::finding()
+            .severity(Severity::Medium)
+            .confidence(Confidence::High)
+            .add_location(location.with_keys(&["uses".into()]).annotated(annotation))
+            .build(input)?])
+    }
+}
+
+impl WorkflowAudit for LocalActionPath {
+    fn new(_state: AuditState) -> Result<Self> {
+        Ok(Self)
+    }
+
+    fn audit_step<'w>(&self, step: &Step<'w>) -> Result<Vec<Finding<'w>>> {
+        Self::audit_uses(step.uses(), step.location(), step.workflow())
+    }
+
+    fn audit_composite_step<'a>(&self, step: &CompositeStep<'a>) -> Result<Vec<Finding<'a>>> {
+        Self::audit_uses(step.uses(), step.location(), step.parent)
+    }
+}
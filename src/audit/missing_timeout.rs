@@ -0,0 +1,78 @@
+//! Detects jobs without a `timeout-minutes`.
+//!
+//! Jobs default to a six hour timeout, so a hung job (e.g. one stuck
+//! waiting on a network resource) can quietly burn runner minutes and
+//! hold onto concurrency slots long after it's stopped doing useful work.
+
+use std::ops::Deref;
+
+use anyhow::Result;
+use github_actions_models::{common::expr::LoE, workflow};
+use serde::Deserialize;
+
+use super::{audit_meta, WorkflowAudit};
+use crate::{
+    finding::{Confidence, Finding, Persona, Severity},
+    models::Job,
+    state::AuditState,
+};
+
+/// Settings for this audit, under `rules.missing-timeout.config`.
+#[derive(Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+struct MissingTimeoutConfig {
+    /// The largest `timeout-minutes` that a job may set.
+    max_timeout_minutes: Option<u64>,
+}
+
+pub(crate) struct MissingTimeout {
+    config: MissingTimeoutConfig,
+}
+
+audit_meta!(
+    MissingTimeout,
+    "missing-timeout",
+    "job has no timeout, or an excessive one"
+);
+
+impl WorkflowAudit for MissingTimeout {
+    fn new(state: AuditState) -> Result<Self> {
+        Ok(Self {
+            config: state.config.audit_config(Self::ident())?,
+        })
+    }
+
+    // NOTE: Reusable workflow calls can't set `timeout-minutes`; the
+    // called workflow's own jobs are audited on their own.
+    fn audit_normal_job<'w>(&self, job: &Job<'w>) -> Result<Vec<Finding<'w>>> {
+        let workflow::Job::NormalJob(normal) = job.deref() else {
+            return Ok(vec![]);
+        };
+
+        let finding = match &normal.timeout_minutes {
+            None => Self::finding()
+                .severity(Severity::Informational)
+                .confidence(Confidence::High)
+                .persona(Persona::Pedantic)
+                .add_location(
+                    job.location()
+                        .annotated("job has no timeout-minutes, so defaults to 6 hours"),
+                ),
+            Some(LoE::Literal(timeout)) => match self.config.max_timeout_minutes {
+                Some(max) if *timeout > max => Self::finding()
+                    .severity(Severity::Low)
+                    .confidence(Confidence::High)
+                    .add_location(
+                        job.location()
+                            .with_keys(&["timeout-minutes".into()])
+                            .annotated(format!("timeout exceeds the maximum of {max} minutes")),
+                    ),
+                _ => return Ok(vec![]),
+            },
+            // We can't tell what an expanded timeout will be.
+            Some(LoE::Expr(_)) => return Ok(vec![]),
+        };
+
+        Ok(vec![finding.build(job.parent())?])
+    }
+}
@@ -4,24 +4,61 @@ use anyhow::Result;
 use tracing::instrument;
 
 use crate::{
-    finding::{Finding, FindingBuilder},
-    models::{Job, Step, Workflow},
+    finding::{Finding, FindingBuilder, Severity},
+    models::{Action, CompositeStep, Job, Step, Workflow},
     state::AuditState,
 };
 
+pub(crate) mod artifact_leak;
 pub(crate) mod artipacked;
+pub(crate) mod container_image;
+pub(crate) mod context_dump;
+pub(crate) mod continue_on_error;
+pub(crate) mod cross_run_artifact;
 pub(crate) mod dangerous_triggers;
+pub(crate) mod deprecated_commands;
+pub(crate) mod dispatch_bypass;
+pub(crate) mod environment_exposure;
 pub(crate) mod excessive_permissions;
+pub(crate) mod explicit_shell;
 pub(crate) mod github_env;
+pub(crate) mod github_path_injection;
 pub(crate) mod hardcoded_container_credentials;
+pub(crate) mod hardcoded_secret;
+pub(crate) mod if_condition;
 pub(crate) mod impostor_commit;
 pub(crate) mod insecure_commands;
+pub(crate) mod job_graph;
 pub(crate) mod known_vulnerable_actions;
+pub(crate) mod local_action_path;
+pub(crate) mod missing_timeout;
+pub(crate) mod oidc_permissions;
+pub(crate) mod pin_comment_mismatch;
+pub(crate) mod pr_cache_poisoning;
+pub(crate) mod pull_request_target_checkout;
 pub(crate) mod ref_confusion;
+pub(crate) mod required_step;
+pub(crate) mod run_shell;
+pub(crate) mod scheduled_workflow;
+pub(crate) mod secret_leak;
 pub(crate) mod self_hosted_runner;
+pub(crate) mod shell_quoting;
+pub(crate) mod sunset_actions;
+pub(crate) mod tainted_env;
 pub(crate) mod template_injection;
+pub(crate) mod token_passthrough;
+pub(crate) mod unpinned_actions;
+pub(crate) mod unpinned_reusable_workflow;
 pub(crate) mod unpinned_uses;
 pub(crate) mod use_trusted_publishing;
+pub(crate) mod widened_permissions;
+pub(crate) mod workflow_run_artifacts;
+
+/// Returns a link to the given section of GitHub's security hardening
+/// guide for Actions, for use as a [`FindingBuilder::reference`].
+pub(crate) fn hardening_guide(section: &str) -> String {
+    format!("https://docs.github.com/en/actions/security-for-github-actions/security-guides/security-hardening-for-github-actions#{section}")
+}
 
 /// A supertrait for all audits.
 ///
@@ -53,7 +90,7 @@ pub(crate) trait Audit {
 ///
 /// Example use:
 ///
-/// ```no_run
+/// ```ignore
 /// struct SomeAudit;
 ///
 /// audit_meta!(SomeAudit, "some-audit", "brief description");
@@ -83,6 +120,110 @@ macro_rules! audit_meta {
 
 pub(crate) use audit_meta;
 
+/// Whether an audit needs access to GitHub's API.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Network {
+    /// The audit never makes API requests.
+    None,
+    /// The audit makes API requests when it can, but also runs offline
+    /// (with reduced coverage).
+    Optional,
+    /// The audit is skipped when offline.
+    Required,
+}
+
+impl std::fmt::Display for Network {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::None => write!(f, "none"),
+            Self::Optional => write!(f, "optional"),
+            Self::Required => write!(f, "required"),
+        }
+    }
+}
+
+/// A single entry in the [`catalog`] of audits.
+pub(crate) struct AuditMeta {
+    pub(crate) ident: &'static str,
+    pub(crate) desc: &'static str,
+    pub(crate) url: &'static str,
+    /// The highest severity that this audit reports findings at.
+    pub(crate) severity: Severity,
+    pub(crate) network: Network,
+    /// Constructs the audit, per [`WorkflowAudit::new`].
+    pub(crate) new: fn(AuditState) -> Result<Box<dyn WorkflowAudit>>,
+}
+
+macro_rules! catalog {
+    ($($audit:path => $severity:ident, $network:ident;)*) => {
+        vec![$({
+            // HACK: https://github.com/rust-lang/rust/issues/48067
+            use $audit as base;
+            AuditMeta {
+                ident: base::ident(),
+                desc: base::desc(),
+                url: base::url(),
+                severity: Severity::$severity,
+                network: Network::$network,
+                new: |state| Ok(Box::new(base::new(state)?)),
+            }
+        }),*]
+    };
+}
+
+/// Returns every audit that zizmor knows about, in the order that they run.
+///
+/// This is the only place that audits are listed: adding an audit means
+/// adding it here.
+pub(crate) fn catalog() -> Vec<AuditMeta> {
+    catalog! {
+        artipacked::Artipacked => High, None;
+        excessive_permissions::ExcessivePermissions => High, None;
+        dangerous_triggers::DangerousTriggers => High, None;
+        impostor_commit::ImpostorCommit => High, Required;
+        ref_confusion::RefConfusion => Medium, Required;
+        use_trusted_publishing::UseTrustedPublishing => Informational, None;
+        template_injection::TemplateInjection => High, None;
+        hardcoded_container_credentials::HardcodedContainerCredentials => High, None;
+        self_hosted_runner::SelfHostedRunner => Unknown, None;
+        known_vulnerable_actions::KnownVulnerableActions => Critical, Optional;
+        unpinned_uses::UnpinnedUses => Medium, None;
+        unpinned_actions::UnpinnedActions => High, Optional;
+        unpinned_reusable_workflow::UnpinnedReusableWorkflow => Medium, None;
+        insecure_commands::InsecureCommands => High, None;
+        github_env::GitHubEnv => High, None;
+        scheduled_workflow::ScheduledWorkflow => Medium, None;
+        widened_permissions::WidenedPermissions => Medium, None;
+        context_dump::ContextDump => High, None;
+        pull_request_target_checkout::PullRequestTargetCheckout => High, None;
+        sunset_actions::SunsetActions => Medium, Optional;
+        run_shell::RunShell => Medium, None;
+        secret_leak::SecretLeak => High, None;
+        container_image::ContainerImage => Medium, None;
+        missing_timeout::MissingTimeout => Low, None;
+        pin_comment_mismatch::PinCommentMismatch => Medium, Required;
+        local_action_path::LocalActionPath => Medium, None;
+        explicit_shell::ExplicitShell => Informational, None;
+        workflow_run_artifacts::WorkflowRunArtifacts => High, None;
+        oidc_permissions::OidcPermissions => Low, None;
+        job_graph::JobGraph => Medium, None;
+        artifact_leak::ArtifactLeak => Medium, None;
+        dispatch_bypass::DispatchBypass => Low, None;
+        continue_on_error::ContinueOnError => Informational, None;
+        github_path_injection::GitHubPathInjection => High, None;
+        if_condition::IfCondition => Informational, None;
+        environment_exposure::EnvironmentExposure => Medium, None;
+        token_passthrough::TokenPassthrough => Medium, None;
+        tainted_env::TaintedEnv => High, None;
+        deprecated_commands::DeprecatedCommands => Medium, None;
+        pr_cache_poisoning::PrCachePoisoning => Informational, None;
+        hardcoded_secret::HardcodedSecret => High, None;
+        required_step::RequiredStep => Informational, None;
+        shell_quoting::ShellQuoting => Low, None;
+        cross_run_artifact::CrossRunArtifact => Medium, None;
+    }
+}
+
 /// Workflow auditing trait.
 ///
 /// Implementors of this trait can choose the level of specificity/context
@@ -97,7 +238,11 @@ pub(crate) use audit_meta;
 /// In other words, if an audit chooses to implement [`WorkflowAudit::audit`], it should implement
 /// **only** [`WorkflowAudit::audit`] and not [`WorkflowAudit::audit_normal_job`] or
 /// [`WorkflowAudit::audit_step`].
-pub(crate) trait WorkflowAudit: Audit {
+///
+/// Audits that also apply to composite actions can implement
+/// [`WorkflowAudit::audit_composite_step`] (or [`WorkflowAudit::audit_action`],
+/// for the whole action). Both default to producing no findings.
+pub(crate) trait WorkflowAudit: Audit + Send + Sync {
     fn new(state: AuditState) -> Result<Self>
     where
         Self: Sized;
@@ -143,4 +288,47 @@ pub(crate) trait WorkflowAudit: Audit {
     fn audit<'w>(&self, workflow: &'w Workflow) -> Result<Vec<Finding<'w>>> {
         self.audit_workflow(workflow)
     }
+
+    fn audit_composite_step<'a>(&self, _step: &CompositeStep<'a>) -> Result<Vec<Finding<'a>>> {
+        Ok(vec![])
+    }
+
+    fn audit_action<'a>(&self, action: &'a Action) -> Result<Vec<Finding<'a>>> {
+        let mut results = vec![];
+        for step in action.steps() {
+            results.extend(self.audit_composite_step(&step)?);
+        }
+        Ok(results)
+    }
+
+    /// The top-level action auditing function.
+    ///
+    /// Like [`WorkflowAudit::audit`], implementors **should not** override
+    /// this blanket implementation.
+    #[instrument(skip(self))]
+    fn audit_composite<'a>(&self, action: &'a Action) -> Result<Vec<Finding<'a>>> {
+        self.audit_action(action)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::catalog;
+
+    #[test]
+    fn test_catalog() {
+        let docs = include_str!("../../docs/audits.md");
+
+        let mut idents = HashSet::new();
+        for meta in catalog() {
+            assert!(idents.insert(meta.ident), "duplicate audit: {}", meta.ident);
+            assert!(
+                docs.contains(&format!("## `{ident}`", ident = meta.ident)),
+                "undocumented audit: {}",
+                meta.ident
+            );
+        }
+    }
 }
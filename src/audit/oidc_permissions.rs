@@ -0,0 +1,166 @@
+//! Detects `id-token: write` grants that are broader than they need to be.
+//!
+//! `id-token: write` lets a job request an OIDC token, which cloud
+//! providers and package indices exchange for credentials. Granting it
+//! at the workflow level hands that ability to every job, and granting
+//! it to jobs that never request a token is pure attack surface.
+
+use std::ops::Deref;
+
+use anyhow::Result;
+use github_actions_models::{
+    common::{EnvValue, Permission, Permissions},
+    workflow::{self, job::StepBody},
+};
+
+use super::{audit_meta, hardening_guide, WorkflowAudit};
+use crate::{
+    finding::{Confidence, Finding, Severity, SymbolicLocation},
+    models::{Job, PermissionScopes, Step, Uses, Workflow},
+    state::AuditState,
+};
+
+/// Actions that request an OIDC token, as `(owner, repo)`.
+const OIDC_ACTIONS: &[(&str, &str)] = &[
+    ("actions", "attest"),
+    ("actions", "attest-build-provenance"),
+    ("actions", "attest-sbom"),
+    ("aws-actions", "configure-aws-credentials"),
+    ("azure", "login"),
+    ("google-github-actions", "auth"),
+    ("hashicorp", "vault-action"),
+    ("jfrog", "setup-jfrog-cli"),
+    ("octo-sts", "action"),
+    ("pypa", "gh-action-pypi-publish"),
+    ("rubygems", "configure-rubygems-credentials"),
+    ("rubygems", "release-gem"),
+    ("sigstore", "gh-action-sigstore-python"),
+];
+
+/// Fragments of `run:` scripts that request an OIDC token, either
+/// directly or via a tool that does so (e.g. `npm publish --provenance`).
+const OIDC_RUN_FRAGMENTS: &[&str] = &["ACTIONS_ID_TOKEN_REQUEST", "--provenance"];
+
+pub(crate) struct OidcPermissions;
+
+audit_meta!(
+    OidcPermissions,
+    "oidc-permissions",
+    "overly broad or unused id-token permission"
+);
+
+/// Returns whether the given `permissions:` block explicitly grants
+/// `id-token: write`.
+///
+/// Blanket grants like `write-all` are left to `excessive-permissions`.
+fn grants_id_token(permissions: &Permissions) -> bool {
+    matches!(
+        permissions,
+        Permissions::Explicit(perms) if matches!(perms.get("id-token"), Some(Permission::Write))
+    )
+}
+
+/// Returns whether the given step appears to request an OIDC token.
+fn requests_id_token(step: &Step) -> bool {
+    match &step.deref().body {
+        StepBody::Uses { uses, with } => {
+            let Some(Uses::Repository(uses)) = Uses::from_step(uses) else {
+                return false;
+            };
+            let is = |owner: &str, repo: &str| {
+                uses.owner.eq_ignore_ascii_case(owner) && uses.repo.eq_ignore_ascii_case(repo)
+            };
+
+            if is("actions", "github-script") {
+                matches!(
+                    with.get("script"),
+                    Some(EnvValue::String(script)) if script.contains("getIDToken")
+                )
+            } else {
+                OIDC_ACTIONS.iter().any(|(owner, repo)| is(owner, repo))
+            }
+        }
+        StepBody::Run { run, .. } => OIDC_RUN_FRAGMENTS
+            .iter()
+            .any(|fragment| run.contains(fragment)),
+    }
+}
+
+/// Returns whether the given job might request an OIDC token.
+fn may_request_id_token(job: &Job) -> bool {
+    match job.deref() {
+        workflow::Job::NormalJob(_) => job.steps().any(|step| requests_id_token(&step)),
+        // We can't see into the called workflow, which may well need it.
+        workflow::Job::ReusableWorkflowCallJob(_) => true,
+    }
+}
+
+/// Returns the given job's `permissions:` block.
+fn job_permissions<'w>(job: &Job<'w>) -> &'w Permissions {
+    match job.deref() {
+        workflow::Job::NormalJob(normal) => &normal.permissions,
+        workflow::Job::ReusableWorkflowCallJob(reusable) => &reusable.permissions,
+    }
+}
+
+impl OidcPermissions {
+    fn unused<'w>(location: SymbolicLocation<'w>, workflow: &'w Workflow) -> Result<Finding<'w>> {
+        Self::finding()
+            .severity(Severity::Informational)
+            .confidence(Confidence::Medium)
+            .reference(hardening_guide(
+                "using-openid-connect-to-access-cloud-resources",
+            ))
+            .add_location(
+                location
+                    .with_keys(&["permissions".into()])
+                    .annotated("id-token: write is granted, but nothing appears to use it"),
+            )
+            .build(workflow)
+    }
+}
+
+impl WorkflowAudit for OidcPermissions {
+    fn new(_state: AuditState) -> Result<Self> {
+        Ok(Self)
+    }
+
+    fn audit_workflow<'w>(&self, workflow: &'w Workflow) -> Result<Vec<Finding<'w>>> {
+        let mut findings = vec![];
+
+        if grants_id_token(&workflow.permissions) {
+            findings.push(
+                Self::finding()
+                    .severity(Severity::Low)
+                    .confidence(Confidence::High)
+                    .reference(hardening_guide(
+                        "using-openid-connect-to-access-cloud-resources",
+                    ))
+                    .add_location(
+                        workflow
+                            .location()
+                            .with_keys(&["permissions".into()])
+                            .annotated("id-token: write is granted to every job in the workflow"),
+                    )
+                    .build(workflow)?,
+            );
+
+            // Jobs with their own `permissions:` don't inherit the grant.
+            let used = workflow
+                .jobs()
+                .filter(|job| PermissionScopes::resolve(job_permissions(job)).is_none())
+                .any(|job| may_request_id_token(&job));
+            if !used {
+                findings.push(Self::unused(workflow.location(), workflow)?);
+            }
+        }
+
+        for job in workflow.jobs() {
+            if grants_id_token(job_permissions(&job)) && !may_request_id_token(&job) {
+                findings.push(Self::unused(job.location(), workflow)?);
+            }
+        }
+
+        Ok(findings)
+    }
+}
@@ -0,0 +1,212 @@
+//! Detects commit-pinned `uses:` whose trailing version comment doesn't
+//! match the pinned commit, e.g. `actions/checkout@<sha> # v4.1.1` where
+//! `<sha>` isn't actually `v4.1.1`.
+//!
+//! Reviewers tend to trust the comment rather than the SHA, so a comment
+//! that has drifted (or been written misleadingly) hides what's really
+//! being run.
+
+use std::{ops::Deref, sync::LazyLock};
+
+use anyhow::{anyhow, Result};
+use github_actions_models::workflow;
+use regex::Regex;
+
+use super::{audit_meta, WorkflowAudit};
+use crate::{
+    finding::{Confidence, Finding, Severity, SymbolicLocation},
+    github_api,
+    models::{AuditInput, CompositeStep, Job, RepositoryUses, Step, Uses},
+    state::AuditState,
+};
+
+/// Version comments, e.g. `# v4.1.1`, `# 4.1.1` or Renovate's `# tag=v4.1.1`.
+static VERSION_COMMENT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^#\s*(?:tag=|pin @)?(v?\d+(?:\.\d+)*(?:-[0-9A-Za-z.-]+)?)(?:\s|$)").unwrap()
+});
+
+pub(crate) struct PinCommentMismatch {
+    client: github_api::Client,
+}
+
+audit_meta!(
+    PinCommentMismatch,
+    "pin-comment-mismatch",
+    "commit pin disagrees with its version comment"
+);
+
+/// Returns whether `tag` satisfies the version in a pin's comment.
+///
+/// Comments commonly abbreviate, e.g. `# v4` for a commit tagged `v4.1.1`,
+/// so the comment only needs to be a prefix of the tag's components.
+fn version_matches_tag(version: &str, tag: &str) -> bool {
+    let version = version.strip_prefix('v').unwrap_or(version);
+    let tag = tag.strip_prefix('v').unwrap_or(tag);
+
+    tag == version
+        || tag
+            .strip_prefix(version)
+            .is_some_and(|rest| rest.starts_with('.'))
+}
+
+impl PinCommentMismatch {
+    fn audit_uses<'w>(
+        &self,
+        uses: Option<Uses<'_>>,
+        location: SymbolicLocation<'w>,
+        input: &'w impl AuditInput,
+    ) -> Result<Vec<Finding<'w>>> {
+        let Some(Uses::Repository(uses)) = uses else {
+            return Ok(vec![]);
+        };
+
+        self.audit_repository_uses(uses, location, input)
+    }
+
+    fn audit_repository_uses<'w>(
+        &self,
+        uses: RepositoryUses<'_>,
+        location: SymbolicLocation<'w>,
+        input: &'w impl AuditInput,
+    ) -> Result<Vec<Finding<'w>>> {
+        let Some(commit) = uses.commit_ref() else {
+            return Ok(vec![]);
+        };
+
+        let comments = location.comments(input)?;
+        let Some(version) = comments.iter().find_map(|comment| {
+            VERSION_COMMENT
+                .captures(comment.as_str())
+                .and_then(|caps| caps.get(1))
+                .map(|version| version.as_str())
+        }) else {
+            return Ok(vec![]);
+        };
+
+        let tags = self
+            .client
+            .list_tags(uses.owner, uses.repo)?
+            .into_iter()
+            .filter(|tag| tag.commit.sha == commit)
+            .map(|tag| tag.name)
+            .collect::<Vec<_>>();
+
+        if tags.iter().any(|tag| version_matches_tag(version, tag)) {
+            return Ok(vec![]);
+        }
+
+        let annotation = if tags.is_empty() {
+            format!("commit is not tagged {version}")
+        } else {
+            format!(
+                "commit is tagged {tags}, not {version}",
+                tags = tags.join(", ")
+            )
+        };
+
+        Ok(vec![Self::finding()
+            .severity(Severity::Medium)
+            .confidence(Confidence::High)
+            .add_location(location.annotated(annotation))
+            .build(input)?])
+    }
+}
+
+impl WorkflowAudit for PinCommentMismatch {
+    fn new(state: AuditState) -> Result<Self> {
+        if state.no_online_audits {
+            return Err(anyhow!("offline audits only requested"));
+        }
+
+        let Some(client) = state.validated_github_client()? else {
+            return Err(anyhow!("can't run without a GitHub API token"));
+        };
+
+        Ok(Self { client })
+    }
+
+    fn audit_step<'w>(&self, step: &Step<'w>) -> Result<Vec<Finding<'w>>> {
+        self.audit_uses(
+            step.uses(),
+            step.location().with_keys(&["uses".into()]),
+            step.workflow(),
+        )
+    }
+
+    fn audit_reusable_job<'w>(&self, job: &Job<'w>) -> Result<Vec<Finding<'w>>> {
+        let workflow::Job::ReusableWorkflowCallJob(reusable) = job.deref() else {
+            return Ok(vec![]);
+        };
+
+        let Some(uses) = Uses::from_reusable(&reusable.uses) else {
+            return Ok(vec![]);
+        };
+
+        self.audit_repository_uses(
+            uses,
+            job.location().with_keys(&["uses".into()]),
+            job.parent(),
+        )
+    }
+
+    fn audit_composite_step<'a>(&self, step: &CompositeStep<'a>) -> Result<Vec<Finding<'a>>> {
+        self.audit_uses(
+            step.uses(),
+            step.location().with_keys(&["uses".into()]),
+            step.parent,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{version_matches_tag, VERSION_COMMENT};
+
+    #[test]
+    fn test_version_comment() {
+        for (comment, expected) in [
+            ("# v4.1.1", Some("v4.1.1")),
+            ("#v4.1.1", Some("v4.1.1")),
+            ("# 4.1.1", Some("4.1.1")),
+            ("# v4", Some("v4")),
+            ("# tag=v4.2.2", Some("v4.2.2")),
+            ("# pin @v2.0.0-rc.1", Some("v2.0.0-rc.1")),
+            ("# v4.1.1 # zizmor: ignore[artipacked]", Some("v4.1.1")),
+            ("# zizmor: ignore[unpinned-uses]", None),
+            ("# main", None),
+            ("# see v4.1.1", None),
+            ("# version 4", None),
+        ] {
+            assert_eq!(
+                VERSION_COMMENT
+                    .captures(comment)
+                    .and_then(|caps| caps.get(1))
+                    .map(|version| version.as_str()),
+                expected,
+                "{comment}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_version_matches_tag() {
+        for (version, tag, matches) in [
+            ("v4.1.1", "v4.1.1", true),
+            ("4.1.1", "v4.1.1", true),
+            ("v4.1.1", "4.1.1", true),
+            ("v4", "v4.1.1", true),
+            ("v4.1", "v4.1.1", true),
+            ("v4", "v4", true),
+            ("v4.1.1", "v4.1.2", false),
+            ("v4.1.1", "v4", false),
+            ("v4", "v41.0.0", false),
+            ("v4.1", "v4.10.0", false),
+        ] {
+            assert_eq!(
+                version_matches_tag(version, tag),
+                matches,
+                "{version} vs {tag}"
+            );
+        }
+    }
+}
@@ -0,0 +1,152 @@
+//! Detects `pull_request` jobs that run arbitrary scripts and save an
+//! `actions/cache` entry under a key that isn't specific to the pull
+//! request.
+//!
+//! `pull_request` is the "safe" trigger for fork pull requests: the
+//! job's token is read-only and secrets aren't available. But a fork's
+//! build scripts can still write anything they like into the cached
+//! paths, and if the cache key is one that other workflows also use
+//! (e.g. `${{ runner.os }}-npm-${{ hashFiles('package-lock.json') }}`),
+//! those workflows may later restore the poisoned cache with more
+//! privileges than the pull request ever had.
+
+use std::ops::Deref;
+
+use anyhow::Result;
+use github_actions_models::workflow::job::StepBody;
+
+use super::{audit_meta, WorkflowAudit};
+use crate::{
+    expr::Expr,
+    finding::{Confidence, Finding, Severity},
+    models::{Job, Uses},
+    state::AuditState,
+    utils::extract_expressions,
+};
+
+/// The actions that save (rather than only restore) a cache.
+const CACHE_SAVERS: &[&str] = &["actions/cache", "actions/cache/save"];
+
+/// Contexts that are unique to a single pull request, and so keep its
+/// cache entries out of other workflows' way.
+const PR_CONTEXTS: &[&str] = &[
+    "github.event.number",
+    "github.event.pull_request.number",
+    "github.event.pull_request.head.ref",
+    "github.event.pull_request.head.sha",
+    "github.head_ref",
+    "github.ref",
+];
+
+pub(crate) struct PrCachePoisoning;
+
+audit_meta!(
+    PrCachePoisoning,
+    "pr-cache-poisoning",
+    "pull request can populate a shared cache"
+);
+
+/// Returns whether the given cache key is namespaced by the pull request
+/// that it's saved from.
+fn is_pr_namespaced(key: &str) -> bool {
+    extract_expressions(key)
+        .iter()
+        .filter_map(|expr| Expr::parse(expr.as_bare()).ok())
+        .any(|expr| {
+            expr.contexts().into_iter().any(|context| {
+                PR_CONTEXTS
+                    .iter()
+                    .any(|pr_context| pr_context.eq_ignore_ascii_case(context))
+            })
+        })
+}
+
+impl WorkflowAudit for PrCachePoisoning {
+    fn new(_state: AuditState) -> Result<Self> {
+        Ok(Self)
+    }
+
+    fn audit_normal_job<'w>(&self, job: &Job<'w>) -> Result<Vec<Finding<'w>>> {
+        let workflow = job.parent();
+        if !workflow.has_pull_request() {
+            return Ok(vec![]);
+        }
+
+        // Without any scripts of its own, a job can only cache what its
+        // actions produce.
+        let Some(script) = job
+            .steps()
+            .find(|step| matches!(step.deref().body, StepBody::Run { .. }))
+        else {
+            return Ok(vec![]);
+        };
+
+        let mut findings = vec![];
+        for step in job.steps() {
+            let Some(Uses::Repository(uses)) = step.uses() else {
+                continue;
+            };
+            if !CACHE_SAVERS.iter().any(|saver| uses.matches(saver)) {
+                continue;
+            }
+
+            let StepBody::Uses { with, .. } = &step.deref().body else {
+                continue;
+            };
+            let Some(key) = with.get("key") else {
+                continue;
+            };
+            if is_pr_namespaced(&key.to_string()) {
+                continue;
+            }
+
+            findings.push(
+                Self::finding()
+                    .severity(Severity::Informational)
+                    .confidence(Confidence::Medium)
+                    .add_location(
+                        step.location()
+                            .with_input("key")
+                            .annotated("cache key isn't specific to the pull request"),
+                    )
+                    .add_location(
+                        script
+                            .location()
+                            .with_keys(&["run".into()])
+                            .annotated("fork pull requests can run arbitrary code here"),
+                    )
+                    .add_location(
+                        workflow
+                            .location()
+                            .with_keys(&["on".into()])
+                            .annotated("workflow is triggered by pull_request"),
+                    )
+                    .build(workflow)?,
+            );
+        }
+
+        Ok(findings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_pr_namespaced;
+
+    #[test]
+    fn test_is_pr_namespaced() {
+        for (key, expected) in [
+            ("pr-${{ github.event.pull_request.number }}", true),
+            ("${{ runner.os }}-${{ github.head_ref }}-deps", true),
+            ("${{ github.ref }}-${{ hashFiles('**/Cargo.lock') }}", true),
+            (
+                "${{ runner.os }}-npm-${{ hashFiles('package-lock.json') }}",
+                false,
+            ),
+            ("${{ github.event.pull_request.base.ref }}-deps", false),
+            ("static-key", false),
+        ] {
+            assert_eq!(is_pr_namespaced(key), expected, "{key}");
+        }
+    }
+}
@@ -0,0 +1,178 @@
+//! Reports what `actions/checkout` checks out in `pull_request_target`
+//! workflows.
+//!
+//! Under `pull_request_target`, `actions/checkout` checks out the base
+//! branch by default, which is safe. However, users frequently override
+//! this with `ref: ${{ github.event.pull_request.head.ref }}` (or similar)
+//! to get the pull request's code, which then runs with the base
+//! repository's secrets and write permissions.
+
+use std::ops::Deref;
+
+use anyhow::Result;
+use github_actions_models::{common::EnvValue, workflow::job::StepBody};
+
+use super::{audit_meta, WorkflowAudit};
+use crate::{
+    expr::Expr,
+    finding::{Confidence, Finding, Persona, Severity},
+    models::{Step, Uses},
+    state::AuditState,
+    utils::extract_expressions,
+};
+
+/// Context prefixes that refer to the pull request's (i.e. the
+/// attacker's) code, rather than the base repository's.
+const HEAD_CONTEXTS: &[&str] = &[
+    "github.event.pull_request.head",
+    "github.event.pull_request.merge_commit_sha",
+    "github.event.pull_request.number",
+    "github.event.number",
+    "github.head_ref",
+];
+
+/// The `actions/checkout` inputs that control what gets checked out.
+const CHECKOUT_INPUTS: &[&str] = &["ref", "repository"];
+
+pub(crate) struct PullRequestTargetCheckout;
+
+audit_meta!(
+    PullRequestTargetCheckout,
+    "pull-request-target-checkout",
+    "checkout in a pull_request_target workflow"
+);
+
+/// What a checkout step checks out, as far as we can tell.
+enum CheckoutTarget<'w> {
+    /// The base repository's code, via the default `ref`.
+    Base,
+    /// The pull request's code, via the given input.
+    Head(&'w str),
+    /// Something controlled by an expression we can't evaluate,
+    /// via the given input.
+    Unknown(&'w str),
+}
+
+impl PullRequestTargetCheckout {
+    fn is_head_context(context: &str) -> bool {
+        HEAD_CONTEXTS.iter().any(|head| {
+            context.len() >= head.len()
+                && context[..head.len()].eq_ignore_ascii_case(head)
+                && matches!(context.as_bytes().get(head.len()), None | Some(b'.'))
+        })
+    }
+
+    fn checkout_target<'w>(step: &Step<'w>) -> CheckoutTarget<'w> {
+        let StepBody::Uses { with, .. } = &step.deref().body else {
+            return CheckoutTarget::Base;
+        };
+
+        let mut target = CheckoutTarget::Base;
+        for input in CHECKOUT_INPUTS {
+            let Some((input, EnvValue::String(value))) = with.get_key_value(*input) else {
+                continue;
+            };
+
+            for expr in extract_expressions(value) {
+                let Ok(parsed) = Expr::parse(expr.as_bare()) else {
+                    tracing::warn!("couldn't parse expression: {expr}", expr = expr.as_bare());
+                    target = CheckoutTarget::Unknown(input);
+                    continue;
+                };
+
+                if parsed.contexts().into_iter().any(Self::is_head_context) {
+                    return CheckoutTarget::Head(input);
+                }
+
+                target = CheckoutTarget::Unknown(input);
+            }
+        }
+
+        target
+    }
+}
+
+impl WorkflowAudit for PullRequestTargetCheckout {
+    fn new(_state: AuditState) -> Result<Self> {
+        Ok(Self)
+    }
+
+    fn audit_step<'w>(&self, step: &Step<'w>) -> Result<Vec<Finding<'w>>> {
+        let workflow = step.workflow();
+        if !workflow.has_pull_request_target() {
+            return Ok(vec![]);
+        }
+
+        let Some(Uses::Repository(uses)) = step.uses() else {
+            return Ok(vec![]);
+        };
+
+        if !uses.matches("actions/checkout") {
+            return Ok(vec![]);
+        }
+
+        let finding = match Self::checkout_target(step) {
+            CheckoutTarget::Head(input) => Self::finding()
+                .severity(Severity::High)
+                .confidence(Confidence::High)
+                .add_location(
+                    step.location()
+                        .with_input(input)
+                        .annotated("checks out the pull request's code"),
+                ),
+            CheckoutTarget::Unknown(input) => Self::finding()
+                .severity(Severity::Medium)
+                .confidence(Confidence::Low)
+                .add_location(
+                    step.location()
+                        .with_input(input)
+                        .annotated("may check out the pull request's code"),
+                ),
+            CheckoutTarget::Base => Self::finding()
+                .severity(Severity::Informational)
+                .confidence(Confidence::High)
+                .persona(Persona::Auditor)
+                .add_location(
+                    step.location()
+                        .with_keys(&["uses".into()])
+                        .annotated("checks out the base branch"),
+                ),
+        };
+
+        Ok(vec![finding
+            .add_location(
+                workflow
+                    .location()
+                    .with_keys(&["on".into()])
+                    .annotated("triggered by pull_request_target"),
+            )
+            .build(workflow)?])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PullRequestTargetCheckout;
+
+    #[test]
+    fn test_is_head_context() {
+        for (context, head) in &[
+            ("github.event.pull_request.head.ref", true),
+            ("github.event.pull_request.head.sha", true),
+            ("github.event.pull_request.head.repo.full_name", true),
+            ("GITHUB.EVENT.PULL_REQUEST.HEAD.SHA", true),
+            ("github.head_ref", true),
+            ("github.event.number", true),
+            ("github.event.pull_request.base.ref", false),
+            ("github.event.pull_request.header", false),
+            ("github.ref", false),
+            ("github.sha", false),
+        ] {
+            assert_eq!(
+                PullRequestTargetCheckout::is_head_context(context),
+                *head,
+                "{context}"
+            );
+        }
+    }
+}
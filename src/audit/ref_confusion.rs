@@ -13,9 +13,9 @@ use github_actions_models::workflow::Job;
 
 use super::{audit_meta, WorkflowAudit};
 use crate::{
-    finding::{Confidence, Severity},
+    finding::{Confidence, Finding, Severity, SymbolicLocation},
     github_api,
-    models::{RepositoryUses, Uses},
+    models::{AuditInput, CompositeStep, RepositoryUses, Uses, Workflow},
     state::AuditState,
 };
 
@@ -54,6 +54,27 @@ impl RefConfusion {
         // confusable ref.
         Ok(branches_match && tags_match)
     }
+
+    /// Returns a finding for `uses` at the given `uses:` location, if its
+    /// ref is confusable.
+    fn audit_uses<'w>(
+        &self,
+        uses: &RepositoryUses,
+        location: SymbolicLocation<'w>,
+        input: &'w impl AuditInput,
+    ) -> Result<Option<Finding<'w>>> {
+        if !self.confusable(uses)? {
+            return Ok(None);
+        }
+
+        Self::finding()
+            .severity(Severity::Medium)
+            .confidence(Confidence::High)
+            .add_location(location.annotated(REF_CONFUSION_ANNOTATION))
+            .remediation("pin to a commit SHA, which can't be confused with a branch or tag")
+            .build(input)
+            .map(Some)
+    }
 }
 
 impl WorkflowAudit for RefConfusion {
@@ -65,17 +86,14 @@ impl WorkflowAudit for RefConfusion {
             return Err(anyhow!("offline audits only requested"));
         }
 
-        let Some(client) = state.github_client() else {
+        let Some(client) = state.validated_github_client()? else {
             return Err(anyhow!("can't run without a GitHub API token"));
         };
 
         Ok(Self { client })
     }
 
-    fn audit_workflow<'w>(
-        &self,
-        workflow: &'w crate::models::Workflow,
-    ) -> anyhow::Result<Vec<crate::finding::Finding<'w>>> {
+    fn audit_workflow<'w>(&self, workflow: &'w Workflow) -> Result<Vec<Finding<'w>>> {
         let mut findings = vec![];
 
         for job in workflow.jobs() {
@@ -86,19 +104,11 @@ impl WorkflowAudit for RefConfusion {
                             continue;
                         };
 
-                        if self.confusable(&uses)? {
-                            findings.push(
-                                Self::finding()
-                                    .severity(Severity::Medium)
-                                    .confidence(Confidence::High)
-                                    .add_location(
-                                        step.location()
-                                            .with_keys(&["uses".into()])
-                                            .annotated(REF_CONFUSION_ANNOTATION),
-                                    )
-                                    .build(workflow)?,
-                            );
-                        }
+                        findings.extend(self.audit_uses(
+                            &uses,
+                            step.location().with_keys(&["uses".into()]),
+                            workflow,
+                        )?);
                     }
                 }
                 Job::ReusableWorkflowCallJob(reusable) => {
@@ -106,19 +116,30 @@ impl WorkflowAudit for RefConfusion {
                         continue;
                     };
 
-                    if self.confusable(&uses)? {
-                        findings.push(
-                            Self::finding()
-                                .severity(Severity::Medium)
-                                .confidence(Confidence::High)
-                                .add_location(job.location().annotated(REF_CONFUSION_ANNOTATION))
-                                .build(workflow)?,
-                        )
-                    }
+                    findings.extend(self.audit_uses(
+                        &uses,
+                        job.location().with_keys(&["uses".into()]),
+                        workflow,
+                    )?);
                 }
             }
         }
 
         Ok(findings)
     }
+
+    fn audit_composite_step<'a>(&self, step: &CompositeStep<'a>) -> Result<Vec<Finding<'a>>> {
+        let Some(Uses::Repository(uses)) = step.uses() else {
+            return Ok(vec![]);
+        };
+
+        Ok(self
+            .audit_uses(
+                &uses,
+                step.location().with_keys(&["uses".into()]),
+                step.parent,
+            )?
+            .into_iter()
+            .collect())
+    }
 }
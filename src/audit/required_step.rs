@@ -0,0 +1,142 @@
+//! Detects jobs that are missing a step required by the user's policy,
+//! e.g. `step-security/harden-runner` in every job that can write to
+//! the repository.
+//!
+//! Nothing is required by default: requirements (and the workflows and
+//! jobs that they apply to) are configured under
+//! `rules.required-step.config`.
+
+use std::ops::Deref;
+
+use anyhow::{anyhow, Result};
+use github_actions_models::workflow;
+use serde::Deserialize;
+
+use super::{audit_meta, WorkflowAudit};
+use crate::{
+    finding::{Confidence, Finding, Severity},
+    models::{Job, PermissionScopes, ScopeLevel, Uses, Workflow},
+    state::AuditState,
+};
+
+/// A single required step.
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct Requirement {
+    /// The action that must be used, e.g. `step-security/harden-runner`.
+    uses: String,
+    /// If non-empty, only workflows triggered by at least one of these
+    /// events are required to use the action.
+    #[serde(default)]
+    triggers: Vec<String>,
+    /// If non-empty, only jobs granted `write` access to at least one of
+    /// these permission scopes are required to use the action.
+    #[serde(default)]
+    permissions: Vec<String>,
+}
+
+impl Requirement {
+    /// Returns whether this requirement applies to the given job.
+    fn applies_to(&self, job: &workflow::job::NormalJob, workflow: &Workflow) -> bool {
+        if !self.triggers.is_empty()
+            && !self
+                .triggers
+                .iter()
+                .any(|trigger| workflow.has_trigger(trigger))
+        {
+            return false;
+        }
+
+        if self.permissions.is_empty() {
+            return true;
+        }
+
+        // Default permissions are determined by repository or organization
+        // settings, so they can't be checked against.
+        let Some(scopes) = PermissionScopes::resolve(&job.permissions)
+            .or_else(|| PermissionScopes::resolve(&workflow.permissions))
+        else {
+            return false;
+        };
+
+        self.permissions
+            .iter()
+            .any(|scope| scopes.level(scope) == ScopeLevel::Write)
+    }
+}
+
+/// Settings for this audit, under `rules.required-step.config`.
+#[derive(Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+struct RequiredStepConfig {
+    requirements: Vec<Requirement>,
+}
+
+pub(crate) struct RequiredStep {
+    config: RequiredStepConfig,
+}
+
+audit_meta!(
+    RequiredStep,
+    "required-step",
+    "job is missing a required step"
+);
+
+impl WorkflowAudit for RequiredStep {
+    fn new(state: AuditState) -> Result<Self> {
+        let config: RequiredStepConfig = state.config.audit_config(Self::ident())?;
+
+        for requirement in &config.requirements {
+            if !matches!(
+                Uses::from_step(&requirement.uses),
+                Some(Uses::Repository(_))
+            ) {
+                return Err(anyhow!(
+                    "invalid configuration for {ident}: {uses} isn't an owner/repo action",
+                    ident = Self::ident(),
+                    uses = requirement.uses
+                ));
+            }
+        }
+
+        Ok(Self { config })
+    }
+
+    fn audit_normal_job<'w>(&self, job: &Job<'w>) -> Result<Vec<Finding<'w>>> {
+        let workflow::Job::NormalJob(normal) = job.deref() else {
+            return Ok(vec![]);
+        };
+        let workflow = job.parent();
+
+        let mut findings = vec![];
+        for requirement in &self.config.requirements {
+            if !requirement.applies_to(normal, workflow) {
+                continue;
+            }
+
+            let present = job.steps().any(|step| {
+                matches!(step.uses(), Some(Uses::Repository(uses)) if uses.matches(&requirement.uses))
+            });
+            if present {
+                continue;
+            }
+
+            findings.push(
+                Self::finding()
+                    .severity(Severity::Informational)
+                    .confidence(Confidence::High)
+                    .add_location(
+                        job.location()
+                            .annotated(format!("job doesn't use {uses}", uses = requirement.uses)),
+                    )
+                    .remediation(format!(
+                        "add a step that uses {uses}, as required by this repository's policy",
+                        uses = requirement.uses
+                    ))
+                    .build(workflow)?,
+            );
+        }
+
+        Ok(findings)
+    }
+}
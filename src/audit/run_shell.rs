@@ -0,0 +1,151 @@
+//! Detects `run:` steps that execute remote scripts directly, e.g.
+//! `curl ... | bash`.
+//!
+//! Piping a download straight into an interpreter means that whatever
+//! the remote server returns is executed with the job's full privileges,
+//! with no opportunity to pin or review it.
+
+use std::{ops::Deref, sync::LazyLock};
+
+use anyhow::Result;
+use github_actions_models::{action, workflow::job::StepBody};
+use regex::Regex;
+
+use super::{audit_meta, WorkflowAudit};
+use crate::{
+    finding::{Confidence, Finding, Severity, SymbolicLocation},
+    models::{AuditInput, CompositeStep, Step},
+    state::AuditState,
+};
+
+/// Commands that fetch something over the network.
+const FETCH: &str = r"(curl|wget|iwr|irm|Invoke-WebRequest|Invoke-RestMethod)";
+
+/// Commands that execute their input as a script, optionally under `sudo`.
+const EXEC: &str =
+    r"(sudo\s+(-\S+\s+)*)?(sh|bash|zsh|dash|ksh|iex|Invoke-Expression|python3?|perl|ruby|node)";
+
+static REMOTE_EXECUTION: LazyLock<Vec<Regex>> = LazyLock::new(|| {
+    [
+        // curl -fsSL https://... | sudo bash -s -- --flag
+        format!(r"(?i)\b{FETCH}\b[^|\n]*\|\s*{EXEC}\b"),
+        // bash <(curl -fsSL https://...)
+        format!(r"(?i)\b{EXEC}\s+(-\S+\s+)*<\(\s*{FETCH}\b"),
+        // sh -c "$(curl -fsSL https://...)", eval "$(wget -qO- ...)"
+        format!(r#"(?i)\b({EXEC}\s+-c|eval)\s+["']?\$\(\s*{FETCH}\b"#),
+    ]
+    .iter()
+    .map(|pattern| Regex::new(pattern).unwrap())
+    .collect()
+});
+
+pub(crate) struct RunShell;
+
+audit_meta!(RunShell, "run-shell", "remote script piped into a shell");
+
+impl RunShell {
+    /// Returns each line of `run` that executes a remote script.
+    fn remote_executions(run: &str) -> impl Iterator<Item = &str> {
+        run.lines()
+            .map(str::trim)
+            .filter(|line| !line.starts_with('#'))
+            .filter(|line| REMOTE_EXECUTION.iter().any(|re| re.is_match(line)))
+    }
+
+    fn audit_run<'w>(
+        run: &str,
+        location: SymbolicLocation<'w>,
+        input: &'w impl AuditInput,
+    ) -> Result<Vec<Finding<'w>>> {
+        Self::remote_executions(run)
+            .map(|line| {
+                Self::finding()
+                    .severity(Severity::Medium)
+                    .confidence(Confidence::High)
+                    .add_location(
+                        location
+                            .clone()
+                            .subfeature(line)
+                            .annotated("remote script is executed without verification"),
+                    )
+                    .build(input)
+            })
+            .collect()
+    }
+}
+
+impl WorkflowAudit for RunShell {
+    fn new(_state: AuditState) -> Result<Self> {
+        Ok(Self)
+    }
+
+    fn audit_step<'w>(&self, step: &Step<'w>) -> Result<Vec<Finding<'w>>> {
+        let StepBody::Run { run, .. } = &step.deref().body else {
+            return Ok(vec![]);
+        };
+
+        Self::audit_run(
+            run,
+            step.location().with_keys(&["run".into()]),
+            step.workflow(),
+        )
+    }
+
+    fn audit_composite_step<'a>(&self, step: &CompositeStep<'a>) -> Result<Vec<Finding<'a>>> {
+        let action::Step::RunShell(action::RunShell { run, .. }) = step.deref() else {
+            return Ok(vec![]);
+        };
+
+        Self::audit_run(run, step.location().with_keys(&["run".into()]), step.parent)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RunShell;
+
+    #[test]
+    fn test_remote_executions() {
+        for (run, flagged) in [
+            ("curl https://example.com/install.sh | bash", true),
+            ("curl -fsSL https://example.com/install.sh | sh", true),
+            ("curl -s https://example.com/install.sh | sudo bash", true),
+            ("curl -sSf https://sh.rustup.rs | sh -s -- -y", true),
+            (
+                "wget -qO- https://example.com/install.sh | sudo -E sh",
+                true,
+            ),
+            ("iwr https://example.com/install.ps1 | iex", true),
+            (
+                "Invoke-WebRequest https://example.com/x.ps1 | Invoke-Expression",
+                true,
+            ),
+            ("curl -L https://example.com/x.py | python3", true),
+            ("bash <(curl -s https://example.com/install.sh)", true),
+            (
+                "sh -c \"$(curl -fsSL https://example.com/install.sh)\"",
+                true,
+            ),
+            ("eval \"$(wget -qO- https://example.com/env.sh)\"", true),
+            ("echo setup\n  curl https://example.com/x.sh | bash\n", true),
+            // Not executed.
+            (
+                "curl -fsSL https://example.com/install.sh -o install.sh",
+                false,
+            ),
+            ("curl https://example.com/data.json | jq .", false),
+            ("wget https://example.com/archive.tar.gz | tar xz", false),
+            ("bash ./install.sh", false),
+            ("cat script.sh | bash", false),
+            // Commented out.
+            ("# curl https://example.com/install.sh | bash", false),
+            ("  # curl https://example.com/install.sh | bash", false),
+        ] {
+            assert_eq!(
+                RunShell::remote_executions(run).next().is_some(),
+                flagged,
+                "{run}"
+            );
+        }
+    }
+}
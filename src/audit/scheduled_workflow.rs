@@ -0,0 +1,338 @@
+//! Audits `schedule`-triggered workflows.
+//!
+//! Scheduled workflows always run against the repository's default branch,
+//! with whatever permissions the workflow grants and no human in the loop.
+//! This makes any write-capable logic in them a standing privilege, which
+//! is worth surfacing even when nothing is obviously wrong.
+//!
+//! Third-party actions in such jobs are flagged too (at a lower severity),
+//! since they routinely fetch data of their own and run with the same
+//! permissions.
+//!
+//! This audit also validates each `on.schedule` cron expression, since a
+//! malformed expression means the workflow silently never runs.
+
+use std::{ops::Deref, sync::LazyLock};
+
+use anyhow::Result;
+use github_actions_models::{
+    common::Permissions,
+    workflow::{job::StepBody, Job},
+};
+use regex::Regex;
+
+use super::{audit_meta, WorkflowAudit};
+use crate::{
+    allowlist::ActionAllowlist,
+    finding::{Confidence, Finding, Persona, Severity},
+    models::{PermissionScopes, Uses, Workflow},
+    state::AuditState,
+};
+
+/// Commands that pull external (i.e. network) input into a `run:` step.
+static FETCHES_EXTERNAL_INPUT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?m)\b(curl|wget|Invoke-WebRequest|Invoke-RestMethod|iwr|irm)\b|\bgit\s+(clone|fetch|pull)\b|\bgh\s+api\b",
+    )
+    .unwrap()
+});
+
+/// Owners whose actions are considered first-party.
+const FIRST_PARTY_OWNERS: &[&str] = &["actions", "github"];
+
+const MONTH_NAMES: &[&str] = &[
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+
+const DAY_NAMES: &[&str] = &["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// The five fields of a POSIX cron expression, as `(name, min, max, names)`.
+const CRON_FIELDS: &[(&str, u32, u32, &[&str])] = &[
+    ("minute", 0, 59, &[]),
+    ("hour", 0, 23, &[]),
+    ("day-of-month", 1, 31, &[]),
+    ("month", 1, 12, MONTH_NAMES),
+    ("day-of-week", 0, 6, DAY_NAMES),
+];
+
+pub(crate) struct ScheduledWorkflow {
+    trusted: ActionAllowlist,
+}
+
+audit_meta!(
+    ScheduledWorkflow,
+    "scheduled-workflow",
+    "insecure or malformed scheduled workflow"
+);
+
+/// Parses a single cron field value, either numeric or by name.
+fn cron_value(value: &str, min: u32, max: u32, names: &[&str]) -> Result<u32, String> {
+    let parsed = match value.parse::<u32>() {
+        Ok(num) => num,
+        Err(_) => match names
+            .iter()
+            .position(|name| name.eq_ignore_ascii_case(value))
+        {
+            Some(idx) => idx as u32 + min,
+            None => return Err(format!("'{value}' is not a valid value")),
+        },
+    };
+
+    if parsed < min || parsed > max {
+        return Err(format!("{parsed} is outside of {min}-{max}"));
+    }
+
+    Ok(parsed)
+}
+
+fn validate_cron_field(field: &str, min: u32, max: u32, names: &[&str]) -> Result<(), String> {
+    for item in field.split(',') {
+        let (range, step) = match item.split_once('/') {
+            Some((range, step)) => (range, Some(step)),
+            None => (item, None),
+        };
+
+        if let Some(step) = step {
+            match step.parse::<u32>() {
+                Ok(step) if step > 0 => (),
+                _ => return Err(format!("'{step}' is not a valid step")),
+            }
+        }
+
+        match range.split_once('-') {
+            _ if range == "*" => (),
+            Some((start, end)) => {
+                let start = cron_value(start, min, max, names)?;
+                let end = cron_value(end, min, max, names)?;
+                if start > end {
+                    return Err(format!("range {range} is backwards"));
+                }
+            }
+            None => {
+                cron_value(range, min, max, names)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Validates a POSIX cron expression, as accepted by `on.schedule`.
+///
+/// Returns a human-readable description of the first problem found, if any.
+fn validate_cron(cron: &str) -> Result<(), String> {
+    let fields = cron.split_whitespace().collect::<Vec<_>>();
+    if fields.len() != CRON_FIELDS.len() {
+        return Err(format!(
+            "expected {expected} fields, found {found}",
+            expected = CRON_FIELDS.len(),
+            found = fields.len()
+        ));
+    }
+
+    for (field, (name, min, max, names)) in fields.iter().zip(CRON_FIELDS) {
+        validate_cron_field(field, *min, *max, names)
+            .map_err(|e| format!("invalid {name} field: {e}"))?;
+    }
+
+    Ok(())
+}
+
+impl ScheduledWorkflow {
+    fn grants_write(permissions: &Permissions) -> bool {
+        PermissionScopes::resolve(permissions).is_some_and(|scopes| scopes.grants_write())
+    }
+
+    /// Returns whether the given `uses:` is a third-party action, i.e.
+    /// neither first-party, nor local, nor from a trusted publisher.
+    fn is_third_party(&self, uses: &Uses) -> bool {
+        match uses {
+            Uses::Repository(repo) => {
+                !FIRST_PARTY_OWNERS.contains(&repo.owner) && !self.trusted.is_trusted(uses)
+            }
+            Uses::Docker(_) => !self.trusted.is_trusted(uses),
+            Uses::Local(_) => false,
+        }
+    }
+}
+
+impl WorkflowAudit for ScheduledWorkflow {
+    fn new(state: AuditState) -> Result<Self> {
+        Ok(Self {
+            trusted: state.config.trusted_actions().clone(),
+        })
+    }
+
+    fn audit_workflow<'w>(&self, workflow: &'w Workflow) -> Result<Vec<Finding<'w>>> {
+        let mut findings = vec![];
+
+        let Some(schedule) = workflow.schedule() else {
+            return Ok(findings);
+        };
+
+        for (idx, cron) in schedule.iter().enumerate() {
+            if let Err(reason) = validate_cron(&cron.cron) {
+                findings.push(
+                    Self::finding()
+                        .severity(Severity::Low)
+                        .confidence(Confidence::High)
+                        .add_location(
+                            workflow
+                                .location()
+                                .with_keys(&["on".into(), "schedule".into(), idx.into()])
+                                .annotated(format!("malformed cron expression: {reason}")),
+                        )
+                        .build(workflow)?,
+                );
+            }
+        }
+
+        for job in workflow.jobs() {
+            let Job::NormalJob(normal) = job.deref() else {
+                continue;
+            };
+
+            // Jobs inherit the workflow's permissions unless they
+            // declare their own.
+            let permissions_location = if normal.permissions == Permissions::default() {
+                if !Self::grants_write(&workflow.permissions) {
+                    continue;
+                }
+
+                workflow.location().with_keys(&["permissions".into()])
+            } else {
+                if !Self::grants_write(&normal.permissions) {
+                    continue;
+                }
+
+                job.location().with_keys(&["permissions".into()])
+            }
+            .annotated("grants write permissions");
+
+            let trigger_location = workflow
+                .location()
+                .with_keys(&["on".into()])
+                .annotated("workflow runs on a schedule");
+
+            let mut fetches = false;
+            for step in job.steps() {
+                match &step.deref().body {
+                    StepBody::Run { run, .. } if FETCHES_EXTERNAL_INPUT.is_match(run) => {
+                        fetches = true;
+                        findings.push(
+                            Self::finding()
+                                .severity(Severity::Medium)
+                                .confidence(Confidence::Medium)
+                                .add_location(trigger_location.clone())
+                                .add_location(permissions_location.clone())
+                                .add_location(
+                                    step.location().with_keys(&["run".into()]).annotated(
+                                        "consumes external input with write permissions",
+                                    ),
+                                )
+                                .build(workflow)?,
+                        );
+                    }
+                    StepBody::Uses { uses, .. } => {
+                        if !step.uses().is_some_and(|uses| self.is_third_party(&uses)) {
+                            continue;
+                        }
+
+                        fetches = true;
+                        findings.push(
+                            Self::finding()
+                                .severity(Severity::Informational)
+                                .confidence(Confidence::Medium)
+                                .add_location(trigger_location.clone())
+                                .add_location(permissions_location.clone())
+                                .add_location(
+                                    step.location()
+                                        .with_keys(&["uses".into()])
+                                        .annotated(format!(
+                                            "third-party action {uses} runs with write permissions",
+                                        )),
+                                )
+                                .build(workflow)?,
+                        );
+                    }
+                    _ => continue,
+                }
+            }
+
+            // No obviously dangerous steps, but the job still holds write
+            // permissions on every scheduled run.
+            if !fetches {
+                findings.push(
+                    Self::finding()
+                        .severity(Severity::Low)
+                        .confidence(Confidence::Medium)
+                        .persona(Persona::Pedantic)
+                        .add_location(trigger_location)
+                        .add_location(permissions_location)
+                        .add_location(job.location().annotated("this job"))
+                        .build(workflow)?,
+                );
+            }
+        }
+
+        Ok(findings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{validate_cron, ScheduledWorkflow};
+    use crate::models::Uses;
+
+    #[test]
+    fn test_validate_cron() {
+        for (cron, valid) in &[
+            ("* * * * *", true),
+            ("0 0 * * *", true),
+            ("*/15 * * * *", true),
+            ("30 5,17 * * *", true),
+            ("0 9-17/2 * * mon-fri", true),
+            ("0 0 1 JAN,jul *", true),
+            ("59 23 31 12 6", true),
+            ("  0   0 * * *  ", true),
+            // Wrong number of fields.
+            ("", false),
+            ("* * * *", false),
+            ("* * * * * *", false),
+            // Out of range.
+            ("60 * * * *", false),
+            ("* 24 * * *", false),
+            ("* * 0 * *", false),
+            ("* * * 13 *", false),
+            ("* * * * 7", false),
+            // Bad syntax.
+            ("*/0 * * * *", false),
+            ("*/x * * * *", false),
+            ("5-1 * * * *", false),
+            ("* * * foo *", false),
+            ("* * * * mon-", false),
+            ("1,,2 * * * *", false),
+        ] {
+            assert_eq!(validate_cron(cron).is_ok(), *valid, "{cron}");
+        }
+    }
+
+    #[test]
+    fn test_is_third_party() {
+        let audit = ScheduledWorkflow {
+            trusted: serde_yaml::from_str("[myorg/*]").unwrap(),
+        };
+
+        for (uses, expected) in [
+            ("actions/checkout@v4", false),
+            ("github/codeql-action/init@v3", false),
+            ("myorg/deploy@v1", false),
+            ("./.github/actions/local", false),
+            ("example/fetch-data-action@v1", true),
+            ("docker://alpine:3", true),
+        ] {
+            let parsed = Uses::from_step(uses).unwrap();
+            assert_eq!(audit.is_third_party(&parsed), expected, "{uses}");
+        }
+    }
+}
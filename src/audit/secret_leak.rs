@@ -0,0 +1,164 @@
+//! Detects `${{ secrets.* }}` expansions in `run:` blocks.
+//!
+//! Secrets expanded directly into a script end up in the script's text,
+//! where they can be recovered from logs or process listings despite
+//! GitHub's masking. Secrets written into `GITHUB_ENV`, `GITHUB_OUTPUT`
+//! or similar are worse: they become available to every subsequent step
+//! in the job, including third-party actions.
+
+use std::{ops::Deref, sync::LazyLock};
+
+use anyhow::Result;
+use github_actions_models::{action, workflow::job::StepBody};
+use regex::Regex;
+
+use super::{audit_meta, WorkflowAudit};
+use crate::{
+    contexts::is_secret_context,
+    expr::Expr,
+    finding::{Confidence, Finding, Severity, SymbolicLocation},
+    models::{AuditInput, CompositeStep, Step},
+    state::AuditState,
+    utils::extract_expressions,
+};
+
+/// Writes that persist a value beyond the current step.
+static PERSISTS_VALUE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)\bGITHUB_(ENV|OUTPUT|STATE|PATH)\b|::(set-env|set-output|save-state)\b")
+        .unwrap()
+});
+
+/// Masking a secret again is harmless, so we don't flag it.
+static ADD_MASK: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"::add-mask::").unwrap());
+
+pub(crate) struct SecretLeak;
+
+audit_meta!(SecretLeak, "secret-leak", "secret exposed via run: block");
+
+impl SecretLeak {
+    /// Returns each `${{ ... }}` expression on `line` that expands an
+    /// individual secret.
+    ///
+    /// Expansions of the entire `secrets` context are left to `context-dump`.
+    fn secret_expressions(line: &str) -> Vec<String> {
+        extract_expressions(line)
+            .into_iter()
+            .filter(|expr| {
+                Expr::parse(expr.as_bare()).is_ok_and(|parsed| {
+                    parsed
+                        .contexts()
+                        .iter()
+                        .any(|ctx| ctx.contains('.') && is_secret_context(ctx))
+                })
+            })
+            .map(|expr| expr.as_raw().into())
+            .collect()
+    }
+
+    fn audit_run<'w>(
+        run: &str,
+        location: SymbolicLocation<'w>,
+        input: &'w impl AuditInput,
+    ) -> Result<Vec<Finding<'w>>> {
+        let mut findings = vec![];
+
+        for line in run.lines().map(str::trim) {
+            if line.starts_with('#') || ADD_MASK.is_match(line) {
+                continue;
+            }
+
+            let secrets = Self::secret_expressions(line);
+            if secrets.is_empty() {
+                continue;
+            }
+
+            if PERSISTS_VALUE.is_match(line) {
+                findings.push(
+                    Self::finding()
+                        .severity(Severity::High)
+                        .confidence(Confidence::High)
+                        .add_location(
+                            location
+                                .clone()
+                                .subfeature(line)
+                                .annotated("secret is persisted for subsequent steps"),
+                        )
+                        .build(input)?,
+                );
+                continue;
+            }
+
+            for secret in secrets {
+                findings.push(
+                    Self::finding()
+                        .severity(Severity::Medium)
+                        .confidence(Confidence::Medium)
+                        .add_location(
+                            location
+                                .clone()
+                                .subfeature_within(line, secret)
+                                .annotated("secret is expanded into the script"),
+                        )
+                        .build(input)?,
+                );
+            }
+        }
+
+        Ok(findings)
+    }
+}
+
+impl WorkflowAudit for SecretLeak {
+    fn new(_state: AuditState) -> Result<Self> {
+        Ok(Self)
+    }
+
+    fn audit_step<'w>(&self, step: &Step<'w>) -> Result<Vec<Finding<'w>>> {
+        let StepBody::Run { run, .. } = &step.deref().body else {
+            return Ok(vec![]);
+        };
+
+        Self::audit_run(
+            run,
+            step.location().with_keys(&["run".into()]),
+            step.workflow(),
+        )
+    }
+
+    fn audit_composite_step<'a>(&self, step: &CompositeStep<'a>) -> Result<Vec<Finding<'a>>> {
+        let action::Step::RunShell(action::RunShell { run, .. }) = step.deref() else {
+            return Ok(vec![]);
+        };
+
+        Self::audit_run(run, step.location().with_keys(&["run".into()]), step.parent)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SecretLeak;
+
+    #[test]
+    fn test_secret_expressions() {
+        for (line, expected) in [
+            ("echo ${{ secrets.TOKEN }}", &["${{ secrets.TOKEN }}"][..]),
+            ("echo ${{secrets.TOKEN}}", &["${{secrets.TOKEN}}"]),
+            ("echo ${{ SECRETS.token }}", &["${{ SECRETS.token }}"]),
+            (
+                "echo ${{ secrets.A || secrets.B }} ${{ github.sha }}",
+                &["${{ secrets.A || secrets.B }}"],
+            ),
+            (
+                "echo ${{ format('{0}', secrets.A) }}",
+                &["${{ format('{0}', secrets.A) }}"],
+            ),
+            // Whole-context dumps are `context-dump`'s responsibility.
+            ("echo ${{ toJSON(secrets) }}", &[]),
+            ("echo ${{ github.token }}", &[]),
+            ("echo $SECRET", &[]),
+            ("echo ${{ env.secrets }}", &[]),
+        ] {
+            assert_eq!(SecretLeak::secret_expressions(line), expected, "{line}");
+        }
+    }
+}
@@ -2,21 +2,40 @@
 //! which are frequently unsafe to use in public repositories
 //! due to the potential for persistence between workflow runs.
 //!
-//! This audit is "auditor" only, since zizmor can't detect
-//! whether self-hosted runners are ephemeral or not.
+//! zizmor can't detect whether self-hosted runners are ephemeral or not,
+//! so every finding is Medium severity: the runner may well be safe, but
+//! it's worth a look on any repository that accepts outside contributions.
+//!
+//! Runners are considered self-hosted if they're explicitly labeled
+//! `self-hosted` or belong to a runner group. Labels that don't match any
+//! of GitHub's standard hosted runners are also flagged, with low
+//! confidence: they may be custom self-hosted labels, but they may
+//! also be GitHub-hosted "larger" runners. A label that's an expression
+//! (like `${{ matrix.os }}`) could expand to anything, so it's flagged
+//! with unknown confidence.
 
 use crate::{
-    finding::{Confidence, Persona, Severity},
-    AuditState,
+    finding::{Confidence, Severity},
+    state::AuditState,
 };
 
-use super::{audit_meta, WorkflowAudit};
+use std::sync::LazyLock;
+
+use super::{audit_meta, hardening_guide, WorkflowAudit};
 use crate::models::Matrix;
 use anyhow::Result;
 use github_actions_models::{
     common::expr::{ExplicitExpr, LoE},
     workflow::{job::RunsOn, Job},
 };
+use regex::Regex;
+
+/// Labels for GitHub's standard hosted runners, e.g. `ubuntu-latest`,
+/// `windows-2022`, `macos-14`, or `ubuntu-24.04-arm`.
+static GITHUB_HOSTED_LABEL: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(ubuntu|windows|macos)-(latest|\d+(\.\d+)?)(-(arm|arm64|large|xlarge))?$")
+        .unwrap()
+});
 
 pub(crate) struct SelfHostedRunner;
 
@@ -52,14 +71,15 @@ impl WorkflowAudit for SelfHostedRunner {
                             continue;
                         };
 
-                        if label == "self-hosted" {
-                            // All self-hosted runners start with the 'self-hosted'
-                            // label followed by any specifiers.
+                        if labels.iter().any(|label| label == "self-hosted") {
+                            // Self-hosted runners conventionally start with the
+                            // 'self-hosted' label followed by any specifiers, but
+                            // the labels can be given in any order.
                             results.push(
                                 Self::finding()
                                     .confidence(Confidence::High)
-                                    .severity(Severity::Unknown)
-                                    .persona(Persona::Auditor)
+                                    .reference(hardening_guide("hardening-for-self-hosted-runners"))
+                                    .severity(Severity::Medium)
                                     .add_location(
                                         job.location()
                                             .with_keys(&["runs-on".into()])
@@ -74,9 +94,9 @@ impl WorkflowAudit for SelfHostedRunner {
                             // potentially expanding to self-hosted.
                             results.push(
                                 Self::finding()
-                                    .confidence(Confidence::Low)
-                                    .severity(Severity::Unknown)
-                                    .persona(Persona::Auditor)
+                                    .confidence(Confidence::Unknown)
+                                    .reference(hardening_guide("hardening-for-self-hosted-runners"))
+                                    .severity(Severity::Medium)
                                     .add_location(
                                         job.location().with_keys(&["runs-on".into()]).annotated(
                                             "expression may expand into a self-hosted runner",
@@ -84,6 +104,22 @@ impl WorkflowAudit for SelfHostedRunner {
                                     )
                                     .build(workflow)?,
                             );
+                        } else if !labels
+                            .iter()
+                            .any(|label| GITHUB_HOSTED_LABEL.is_match(label))
+                        {
+                            results.push(
+                                Self::finding()
+                                    .confidence(Confidence::Low)
+                                    .reference(hardening_guide("hardening-for-self-hosted-runners"))
+                                    .severity(Severity::Medium)
+                                    .add_location(
+                                        job.location().with_keys(&["runs-on".into()]).annotated(
+                                            "label doesn't match a GitHub-hosted runner",
+                                        ),
+                                    )
+                                    .build(workflow)?,
+                            );
                         }
                     }
                 }
@@ -95,8 +131,8 @@ impl WorkflowAudit for SelfHostedRunner {
                 LoE::Literal(RunsOn::Group { .. }) => results.push(
                     Self::finding()
                         .confidence(Confidence::Low)
-                        .severity(Severity::Unknown)
-                        .persona(Persona::Auditor)
+                        .reference(hardening_guide("hardening-for-self-hosted-runners"))
+                        .severity(Severity::Medium)
                         .add_location(
                             job.location()
                                 .with_keys(&["runs-on".into()])
@@ -121,8 +157,8 @@ impl WorkflowAudit for SelfHostedRunner {
                         results.push(
                             Self::finding()
                                 .confidence(Confidence::High)
-                                .severity(Severity::Unknown)
-                                .persona(Persona::Auditor)
+                                .reference(hardening_guide("hardening-for-self-hosted-runners"))
+                                .severity(Severity::Medium)
                                 .add_location(
                                     job.location()
                                         .with_keys(&["strategy".into()])
@@ -143,3 +179,29 @@ impl WorkflowAudit for SelfHostedRunner {
         Ok(results)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::GITHUB_HOSTED_LABEL;
+
+    #[test]
+    fn test_github_hosted_label() {
+        for (label, hosted) in &[
+            ("ubuntu-latest", true),
+            ("ubuntu-24.04", true),
+            ("ubuntu-24.04-arm", true),
+            ("windows-2022", true),
+            ("windows-latest", true),
+            ("macos-14", true),
+            ("macos-13-large", true),
+            ("macos-latest-xlarge", true),
+            ("self-hosted", false),
+            ("linux", false),
+            ("ubuntu", false),
+            ("my-ubuntu-latest", false),
+            ("gpu-runner", false),
+        ] {
+            assert_eq!(GITHUB_HOSTED_LABEL.is_match(label), *hosted, "{label}");
+        }
+    }
+}
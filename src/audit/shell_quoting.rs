@@ -0,0 +1,464 @@
+//! Detects `${{ ... }}` expressions that are expanded into `run:` scripts
+//! without being quoted for the script's shell.
+//!
+//! Expressions are expanded into the script's source before the shell ever
+//! runs it, so even a trusted value can misbehave when it isn't quoted:
+//! bash word-splits and globs an unquoted `${{ github.ref_name }}`, and
+//! pwsh evaluates `$(...)` within a double-quoted one.
+//!
+//! Quoting doesn't prevent injection (a value can always contain a closing
+//! quote), which is `template-injection`'s job. This audit is a separate,
+//! lower-severity nag, so that it can be disabled on its own.
+
+use std::{collections::HashSet, ops::Deref, sync::Mutex};
+
+use anyhow::{Context as _, Result};
+use github_actions_models::{action, workflow::job::StepBody};
+use tree_sitter::Parser;
+
+use super::{audit_meta, template_injection::TemplateInjection, WorkflowAudit};
+use crate::{
+    expr::Expr,
+    finding::{Confidence, Finding, Severity, SymbolicLocation},
+    models::{AuditInput, CompositeStep, Step},
+    state::AuditState,
+    utils::extract_expressions,
+};
+
+/// Contexts that always expand to a number, which needs no quoting.
+const NUMERIC_CONTEXTS: &[&str] = &[
+    "github.run_attempt",
+    "github.run_id",
+    "github.run_number",
+    "github.event.number",
+    "github.event.issue.number",
+    "github.event.pull_request.number",
+    "strategy.job-index",
+    "strategy.job-total",
+    "strategy.max-parallel",
+];
+
+/// How a shell quotes its arguments.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Dialect {
+    /// `bash`, `sh`, and other POSIX-like shells.
+    Posix,
+    /// `pwsh` and `powershell`.
+    Pwsh,
+    /// `cmd`.
+    Cmd,
+}
+
+impl Dialect {
+    /// Returns the dialect of the given `shell:`, if it's one we know.
+    ///
+    /// Custom shells are given as a command, e.g. `bash -e {0}`, so only
+    /// the program's name is considered.
+    fn from_shell(shell: &str) -> Option<Self> {
+        let program = shell.split_whitespace().next()?;
+        let program = program.rsplit(['/', '\\']).next()?.to_ascii_lowercase();
+
+        match program.strip_suffix(".exe").unwrap_or(&program) {
+            "bash" | "sh" | "dash" | "ksh" | "zsh" => Some(Self::Posix),
+            "pwsh" | "powershell" => Some(Self::Pwsh),
+            "cmd" => Some(Self::Cmd),
+            _ => None,
+        }
+    }
+}
+
+/// How an expression is quoted within a script.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Quoting {
+    Unquoted,
+    Single,
+    Double,
+    /// Within a comment, heredoc, or similar, where the shell doesn't
+    /// interpret the expansion at all.
+    Literal,
+}
+
+pub(crate) struct ShellQuoting {
+    // NOTE: interior mutability used since Parser::parse requires &mut self,
+    // and audits are shared between threads.
+    bash_parser: Mutex<Parser>,
+}
+
+audit_meta!(
+    ShellQuoting,
+    "shell-quoting",
+    "expression expanded into run: without quoting"
+);
+
+/// Returns whether the given expression can only ever expand to a value
+/// that's safe to leave unquoted, like a number or a boolean.
+fn needs_quoting(expr: &Expr) -> bool {
+    if TemplateInjection::expr_is_safe(expr) {
+        return false;
+    }
+
+    let contexts = expr.contexts();
+    contexts.is_empty()
+        || !contexts.iter().all(|context| {
+            NUMERIC_CONTEXTS
+                .iter()
+                .any(|numeric| numeric.eq_ignore_ascii_case(context))
+        })
+}
+
+/// Returns the quoting at `offset` within a pwsh script.
+///
+/// This understands single- and double-quoted strings (including
+/// here-strings) and comments, which is all that's needed to tell
+/// whether an expansion is quoted.
+fn pwsh_quoting(script: &str, offset: usize) -> Quoting {
+    let bytes = script.as_bytes();
+    let mut quoting = Quoting::Unquoted;
+    let mut idx = 0;
+
+    while idx < offset {
+        let rest = &script[idx..];
+        match quoting {
+            // `''` is an escaped quote, which toggling twice handles.
+            Quoting::Single if bytes[idx] == b'\'' => quoting = Quoting::Unquoted,
+            Quoting::Double if bytes[idx] == b'`' => idx += 1,
+            Quoting::Double if bytes[idx] == b'"' => quoting = Quoting::Unquoted,
+            Quoting::Unquoted => {
+                // Everything else is skipped over whole, returning early if
+                // `offset` is within it.
+                let skipped = if let Some(here) = here_string(rest) {
+                    Some(here)
+                } else if rest.starts_with("<#") {
+                    Some((
+                        rest.find("#>").map_or(rest.len(), |end| end + 2),
+                        Quoting::Literal,
+                    ))
+                } else if bytes[idx] == b'#' && is_word_start(bytes, idx) {
+                    Some((rest.find('\n').unwrap_or(rest.len()), Quoting::Literal))
+                } else {
+                    None
+                };
+
+                match skipped {
+                    Some((len, inner)) if idx + len > offset => return inner,
+                    Some((len, _)) => {
+                        idx += len;
+                        continue;
+                    }
+                    None => match bytes[idx] {
+                        b'`' => idx += 1,
+                        b'\'' => quoting = Quoting::Single,
+                        b'"' => quoting = Quoting::Double,
+                        _ => {}
+                    },
+                }
+            }
+            _ => {}
+        }
+        idx += 1;
+    }
+
+    quoting
+}
+
+/// Returns the length and quoting of the here-string (`@'...'@` or
+/// `@"..."@`) at the start of `text`, if there is one.
+///
+/// Here-strings start with a line ending in `@'` (or `@"`), and end with
+/// a line starting with `'@` (or `"@`).
+fn here_string(text: &str) -> Option<(usize, Quoting)> {
+    let (quote, quoting) = match text.as_bytes() {
+        [b'@', b'\'', ..] => ('\'', Quoting::Single),
+        [b'@', b'"', ..] => ('"', Quoting::Double),
+        _ => return None,
+    };
+
+    let body = text[2..].trim_start_matches([' ', '\t', '\r']);
+    if !body.starts_with('\n') {
+        return None;
+    }
+
+    let closer = format!("\n{quote}@");
+    let len = text
+        .find(&closer)
+        .map_or(text.len(), |end| end + closer.len());
+    Some((len, quoting))
+}
+
+/// Returns the quoting at `offset` within a cmd script.
+///
+/// cmd only has double quotes, which don't span lines.
+fn cmd_quoting(script: &str, offset: usize) -> Quoting {
+    let line_start = script[..offset].rfind('\n').map_or(0, |idx| idx + 1);
+    let line = &script[line_start..offset];
+
+    let trimmed = line.trim_start().to_ascii_lowercase();
+    if trimmed.starts_with("rem ") || trimmed.starts_with("::") {
+        return Quoting::Literal;
+    }
+
+    if line.matches('"').count() % 2 == 1 {
+        Quoting::Double
+    } else {
+        Quoting::Unquoted
+    }
+}
+
+/// Returns whether `idx` is at the start of a word, i.e. where a `#`
+/// begins a comment rather than being part of the word.
+fn is_word_start(bytes: &[u8], idx: usize) -> bool {
+    idx == 0 || bytes[idx - 1].is_ascii_whitespace() || b";|&(){}".contains(&bytes[idx - 1])
+}
+
+impl ShellQuoting {
+    /// Returns the quoting at the given byte range within a bash script.
+    fn bash_quoting(&self, script: &str, start: usize, end: usize) -> Result<Quoting> {
+        let tree = self
+            .bash_parser
+            .lock()
+            .unwrap()
+            .parse(script, None)
+            .context("failed to parse `run:` body as bash")?;
+
+        let mut node = tree.root_node().descendant_for_byte_range(start, end);
+        while let Some(current) = node {
+            match current.kind() {
+                "raw_string" | "ansi_c_string" => return Ok(Quoting::Single),
+                "string" | "translated_string" => return Ok(Quoting::Double),
+                "comment" | "heredoc_body" | "heredoc_content" => return Ok(Quoting::Literal),
+                _ => node = current.parent(),
+            }
+        }
+
+        Ok(Quoting::Unquoted)
+    }
+
+    /// Returns each distinct expression in `script` that isn't safely
+    /// quoted for the given dialect, along with how it's quoted.
+    fn unquoted_expressions(
+        &self,
+        script: &str,
+        dialect: Dialect,
+    ) -> Result<Vec<(String, Quoting)>> {
+        // Expressions are masked out before the script is parsed, since
+        // the quotes within them (e.g. `${{ hashFiles('*.lock') }}`) aren't
+        // seen by the shell.
+        let mut masked = script.to_string();
+        let mut spans = vec![];
+        let mut cursor = 0;
+        for expr in extract_expressions(script) {
+            let Some(start) = script[cursor..].find(expr.as_raw()).map(|idx| idx + cursor) else {
+                continue;
+            };
+            let end = start + expr.as_raw().len();
+            masked.replace_range(start..end, &"_".repeat(end - start));
+            spans.push((expr, start, end));
+            cursor = end;
+        }
+
+        let mut seen = HashSet::new();
+        let mut unquoted = vec![];
+        for (expr, start, end) in spans {
+            let Ok(parsed) = Expr::parse(expr.as_bare()) else {
+                continue;
+            };
+            if !needs_quoting(&parsed) || seen.contains(expr.as_raw()) {
+                continue;
+            }
+
+            let quoting = match dialect {
+                Dialect::Posix => self.bash_quoting(&masked, start, end)?,
+                Dialect::Pwsh => pwsh_quoting(&masked, start),
+                Dialect::Cmd => cmd_quoting(&masked, start),
+            };
+
+            let safe = match dialect {
+                Dialect::Posix => quoting != Quoting::Unquoted,
+                Dialect::Pwsh => matches!(quoting, Quoting::Single | Quoting::Literal),
+                Dialect::Cmd => matches!(quoting, Quoting::Double | Quoting::Literal),
+            };
+            if safe {
+                continue;
+            }
+
+            seen.insert(expr.as_raw().to_string());
+            unquoted.push((expr.as_raw().to_string(), quoting));
+        }
+
+        Ok(unquoted)
+    }
+
+    fn audit_run<'w>(
+        &self,
+        run: &str,
+        shell: &str,
+        confidence: Confidence,
+        location: SymbolicLocation<'w>,
+        input: &'w impl AuditInput,
+    ) -> Result<Vec<Finding<'w>>> {
+        let Some(dialect) = Dialect::from_shell(shell) else {
+            return Ok(vec![]);
+        };
+
+        let remediation = match dialect {
+            Dialect::Posix => "pass the expression through env:, and reference it as \"$NAME\"",
+            Dialect::Pwsh => "pass the expression through env:, and reference it as $env:NAME",
+            Dialect::Cmd => "pass the expression through env:, and reference it as \"%NAME%\"",
+        };
+
+        self.unquoted_expressions(run, dialect)?
+            .into_iter()
+            .map(|(raw, quoting)| {
+                let problem = match (dialect, quoting) {
+                    (Dialect::Pwsh, Quoting::Double) => {
+                        format!("{shell} evaluates any $ within this double-quoted expansion")
+                    }
+                    (Dialect::Pwsh, _) => {
+                        format!("unquoted expansion may be parsed as {shell} syntax")
+                    }
+                    (Dialect::Cmd, _) => format!(
+                        "unquoted expansion may be split, or have & and | interpreted, by {shell}"
+                    ),
+                    (Dialect::Posix, _) => {
+                        format!("unquoted expansion may be word-split or globbed by {shell}")
+                    }
+                };
+
+                Self::finding()
+                    .severity(Severity::Low)
+                    .confidence(confidence)
+                    .add_location(location.clone().subfeature(raw).annotated(problem))
+                    .remediation(remediation)
+                    .build(input)
+            })
+            .collect()
+    }
+}
+
+impl WorkflowAudit for ShellQuoting {
+    fn new(_state: AuditState) -> Result<Self> {
+        let bash_parser = {
+            let mut parser = Parser::new();
+            parser
+                .set_language(&tree_sitter_bash::LANGUAGE.into())
+                .context("failed to load bash parser")?;
+            parser
+        };
+
+        Ok(Self {
+            bash_parser: Mutex::new(bash_parser),
+        })
+    }
+
+    fn audit_step<'w>(&self, step: &Step<'w>) -> Result<Vec<Finding<'w>>> {
+        let StepBody::Run { run, .. } = &step.deref().body else {
+            return Ok(vec![]);
+        };
+
+        // A shell that's set explicitly (by the step or a `defaults.run`)
+        // is certain; the runner's default is only inferred.
+        let (shell, confidence) = match step.run_defaults().shell {
+            Some(shell) => (shell, Confidence::High),
+            None => match step.shell() {
+                Some(shell) => (shell, Confidence::Medium),
+                None => return Ok(vec![]),
+            },
+        };
+
+        self.audit_run(
+            run,
+            shell,
+            confidence,
+            step.location().with_keys(&["run".into()]),
+            step.workflow(),
+        )
+    }
+
+    fn audit_composite_step<'a>(&self, step: &CompositeStep<'a>) -> Result<Vec<Finding<'a>>> {
+        let action::Step::RunShell(action::RunShell { run, shell, .. }) = step.deref() else {
+            return Ok(vec![]);
+        };
+
+        self.audit_run(
+            run,
+            shell,
+            Confidence::High,
+            step.location().with_keys(&["run".into()]),
+            step.parent,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Dialect, ShellQuoting};
+    use crate::{audit::WorkflowAudit, state::AuditState};
+
+    #[test]
+    fn test_dialect_from_shell() {
+        for (shell, dialect) in [
+            ("bash", Some(Dialect::Posix)),
+            ("sh", Some(Dialect::Posix)),
+            ("bash -e {0}", Some(Dialect::Posix)),
+            ("/usr/bin/bash --noprofile --norc {0}", Some(Dialect::Posix)),
+            ("pwsh", Some(Dialect::Pwsh)),
+            ("powershell", Some(Dialect::Pwsh)),
+            ("PowerShell.exe -command \". '{0}'\"", Some(Dialect::Pwsh)),
+            ("cmd", Some(Dialect::Cmd)),
+            ("python", None),
+            ("perl {0}", None),
+        ] {
+            assert_eq!(Dialect::from_shell(shell), dialect, "{shell}");
+        }
+    }
+
+    #[test]
+    fn test_unquoted_expressions() {
+        let audit = ShellQuoting::new(AuditState::builder().build()).unwrap();
+
+        for (script, dialect, expected) in [
+            // bash
+            ("echo ${{ github.ref }}", Dialect::Posix, true),
+            ("echo refs/${{ github.ref }}/x", Dialect::Posix, true),
+            ("REF=${{ github.ref }}", Dialect::Posix, true),
+            ("echo \"${{ github.ref }}\"", Dialect::Posix, false),
+            ("echo \"ref: ${{ github.ref }}\"", Dialect::Posix, false),
+            ("echo '${{ github.ref }}'", Dialect::Posix, false),
+            ("# ${{ github.ref }}", Dialect::Posix, false),
+            (
+                "cat <<EOF\n${{ github.ref }}\nEOF\necho done",
+                Dialect::Posix,
+                false,
+            ),
+            (
+                "echo \"${{ hashFiles('a') }}\" ${{ github.ref }}",
+                Dialect::Posix,
+                true,
+            ),
+            ("echo ${{ github.run_number }}", Dialect::Posix, false),
+            ("echo ${{ 'literal' }}", Dialect::Posix, false),
+            ("echo ${{ github.ref == 'main' }}", Dialect::Posix, false),
+            // pwsh
+            ("echo ${{ github.ref }}", Dialect::Pwsh, true),
+            ("echo \"${{ github.ref }}\"", Dialect::Pwsh, true),
+            ("echo '${{ github.ref }}'", Dialect::Pwsh, false),
+            ("echo 'it''s ${{ github.ref }}'", Dialect::Pwsh, false),
+            ("# ${{ github.ref }}", Dialect::Pwsh, false),
+            ("<# ${{ github.ref }} #>", Dialect::Pwsh, false),
+            ("$x = @'\n${{ github.ref }}\n'@", Dialect::Pwsh, false),
+            ("$x = @\"\n${{ github.ref }}\n\"@", Dialect::Pwsh, true),
+            (
+                "$x = @'\nfoo\n'@\necho ${{ github.ref }}",
+                Dialect::Pwsh,
+                true,
+            ),
+            // cmd
+            ("echo ${{ github.ref }}", Dialect::Cmd, true),
+            ("echo \"${{ github.ref }}\"", Dialect::Cmd, false),
+            ("rem ${{ github.ref }}", Dialect::Cmd, false),
+        ] {
+            let unquoted = audit.unquoted_expressions(script, dialect).unwrap();
+            assert_eq!(!unquoted.is_empty(), expected, "{dialect:?}: {script}");
+        }
+    }
+}
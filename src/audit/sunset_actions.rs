@@ -0,0 +1,138 @@
+//! Detects uses of action major versions that GitHub has deprecated
+//! or retired.
+//!
+//! GitHub periodically sunsets major versions of its own actions (e.g.
+//! `actions/upload-artifact@v3`), first with brownouts and eventually by
+//! failing every workflow that still uses them.
+//!
+//! Symbolic refs (like `@v3` or `@v3.1.2`) are checked offline. Commit refs
+//! are resolved to their most specific tag when online.
+
+use anyhow::Result;
+
+use super::{audit_meta, WorkflowAudit};
+use crate::{
+    finding::{Confidence, Finding, Severity},
+    github_api,
+    models::{RepositoryUses, Step, Uses},
+    state::AuditState,
+};
+
+/// Actions with sunset major versions, as `(action, minimum supported major)`.
+///
+/// This is maintained by hand: add an entry whenever GitHub announces
+/// the retirement of an action's major version.
+const MINIMUM_SUPPORTED_MAJORS: &[(&str, u64)] = &[
+    // See: https://github.blog/changelog/2024-04-16-deprecation-notice-v3-of-the-artifact-actions/
+    ("actions/upload-artifact", 4),
+    ("actions/download-artifact", 4),
+    // These use `actions/upload-artifact` and `actions/download-artifact`
+    // internally, and were updated alongside them.
+    ("actions/upload-pages-artifact", 3),
+    ("actions/deploy-pages", 4),
+    // See: https://github.com/actions/cache/discussions/1510
+    ("actions/cache", 3),
+];
+
+pub(crate) struct SunsetActions {
+    client: Option<github_api::Client>,
+}
+
+audit_meta!(
+    SunsetActions,
+    "sunset-actions",
+    "action version is deprecated or retired"
+);
+
+impl SunsetActions {
+    /// Parses the major version from a version-like ref, like `v3` or `3.1.2`.
+    fn major(version: &str) -> Option<u64> {
+        let version = version.strip_prefix('v').unwrap_or(version);
+
+        version
+            .split(['.', '-', '+'])
+            .next()
+            .and_then(|major| major.parse().ok())
+    }
+
+    /// Returns the version that `uses` refers to, resolving commit refs
+    /// to their tag when online.
+    fn version(&self, uses: &RepositoryUses<'_>) -> Result<Option<String>> {
+        match uses.commit_ref() {
+            Some(commit) => match &self.client {
+                Some(client) => Ok(client
+                    .tag_for_commit(uses.owner, uses.repo, commit)?
+                    .map(|tag| tag.name)),
+                None => Ok(None),
+            },
+            None => Ok(uses.git_ref.map(Into::into)),
+        }
+    }
+}
+
+impl WorkflowAudit for SunsetActions {
+    fn new(state: AuditState) -> Result<Self> {
+        let client = if state.no_online_audits {
+            None
+        } else {
+            state.github_client()
+        };
+
+        Ok(Self { client })
+    }
+
+    fn audit_step<'w>(&self, step: &Step<'w>) -> Result<Vec<Finding<'w>>> {
+        let Some(Uses::Repository(uses)) = step.uses() else {
+            return Ok(vec![]);
+        };
+
+        let Some(minimum) = MINIMUM_SUPPORTED_MAJORS
+            .iter()
+            .find(|(action, _)| uses.matches(action))
+            .map(|(_, minimum)| *minimum)
+        else {
+            return Ok(vec![]);
+        };
+
+        let Some(version) = self.version(&uses)? else {
+            return Ok(vec![]);
+        };
+
+        // Non-version refs (like `@main`) are someone else's problem.
+        match Self::major(&version) {
+            Some(major) if major < minimum => Ok(vec![Self::finding()
+                .severity(Severity::Medium)
+                .confidence(Confidence::High)
+                .add_location(
+                    step.location()
+                        .with_keys(&["uses".into()])
+                        .annotated(format!(
+                            "{version} is no longer supported; upgrade to v{minimum} or later"
+                        )),
+                )
+                .build(step.workflow())?]),
+            _ => Ok(vec![]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SunsetActions;
+
+    #[test]
+    fn test_major() {
+        for (version, major) in &[
+            ("v3", Some(3)),
+            ("3", Some(3)),
+            ("v3.1.2", Some(3)),
+            ("v4-beta", Some(4)),
+            ("v10.0.0+build", Some(10)),
+            ("main", None),
+            ("release/v3", None),
+            ("", None),
+        ] {
+            assert_eq!(SunsetActions::major(version), *major, "{version}");
+        }
+    }
+}
@@ -0,0 +1,162 @@
+//! Detects `run:` steps that expand environment variables whose values
+//! come from attacker-controlled contexts.
+//!
+//! Passing a context through `env:` is the usual remediation for
+//! template injection, since the shell expands the variable without
+//! re-parsing its value. That stops being true as soon as the script
+//! evaluates the variable as code, e.g. with `eval "$TITLE"` or
+//! `bash -c "$TITLE"`: the injection has just been laundered through the
+//! environment.
+
+use std::{ops::Deref, sync::LazyLock};
+
+use anyhow::Result;
+use github_actions_models::workflow::job::StepBody;
+use regex::Regex;
+
+use super::{audit_meta, WorkflowAudit};
+use crate::{
+    contexts::ContextPolicy,
+    finding::{Confidence, Finding, Persona, Severity},
+    models::Step,
+    state::AuditState,
+};
+
+/// Commands that evaluate their arguments as code.
+static EVAL_SINK: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)\b(?:eval|(?:ba|da|k|z)?sh\s+-c|python3?\s+-c|node\s+-e|invoke-expression|iex)\b",
+    )
+    .unwrap()
+});
+
+pub(crate) struct TaintedEnv {
+    contexts: ContextPolicy,
+}
+
+audit_meta!(
+    TaintedEnv,
+    "tainted-env",
+    "run: step expands an attacker-controlled environment variable"
+);
+
+/// Returns a pattern matching expansions of the given environment
+/// variable, in any of the usual shells.
+fn var_expansion(name: &str) -> Regex {
+    // e.g. `$FOO`, `${FOO}`, `${FOO:-default}`, `$env:FOO`, or `%FOO%`.
+    Regex::new(&format!(
+        r"(?:\$\{{?|\$env:|%){name}\b",
+        name = regex::escape(name)
+    ))
+    .unwrap()
+}
+
+impl WorkflowAudit for TaintedEnv {
+    fn new(state: AuditState) -> Result<Self> {
+        Ok(Self {
+            contexts: state.config.contexts().clone(),
+        })
+    }
+
+    fn audit_step<'w>(&self, step: &Step<'w>) -> Result<Vec<Finding<'w>>> {
+        let StepBody::Run { run, .. } = &step.deref().body else {
+            return Ok(vec![]);
+        };
+
+        let tainted = step
+            .env_scope()
+            .into_iter()
+            .filter_map(|(name, (value, location))| {
+                let contexts = self.contexts.untrusted_contexts(&value.to_string());
+                (!contexts.is_empty()).then(|| (name, var_expansion(name), contexts, location))
+            })
+            .collect::<Vec<_>>();
+        if tainted.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let mut findings = vec![];
+        for line in run.lines().map(str::trim) {
+            for (name, expansion, contexts, location) in &tainted {
+                if !expansion.is_match(line) {
+                    continue;
+                }
+
+                // Plain expansions are what template-injection recommends,
+                // and are only dangerous if the script (or something it
+                // calls) goes on to evaluate them.
+                let (confidence, persona, annotation) = if EVAL_SINK.is_match(line) {
+                    (
+                        Confidence::High,
+                        Persona::Regular,
+                        format!("{name} is evaluated as code here"),
+                    )
+                } else {
+                    (
+                        Confidence::Low,
+                        Persona::Auditor,
+                        format!("{name} is expanded here"),
+                    )
+                };
+
+                findings.push(
+                    Self::finding()
+                        .severity(Severity::High)
+                        .confidence(confidence)
+                        .persona(persona)
+                        .add_location(
+                            step.location()
+                                .with_keys(&["run".into()])
+                                .subfeature(line)
+                                .annotated(annotation),
+                        )
+                        .add_location(location.clone().annotated(format!(
+                            "{name} is set from {contexts}",
+                            contexts = contexts.join(", ")
+                        )))
+                        .build(step.workflow())?,
+                );
+            }
+        }
+
+        Ok(findings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{var_expansion, EVAL_SINK};
+
+    #[test]
+    fn test_var_expansion() {
+        let expansion = var_expansion("TITLE");
+        for (line, expected) in [
+            ("echo $TITLE", true),
+            ("echo \"${TITLE}\"", true),
+            ("echo ${TITLE:-untitled}", true),
+            ("Write-Output $env:TITLE", true),
+            ("echo %TITLE%", true),
+            ("echo $TITLE_LENGTH", false),
+            ("echo $SUBTITLE", false),
+            ("echo TITLE", false),
+        ] {
+            assert_eq!(expansion.is_match(line), expected, "{line}");
+        }
+    }
+
+    #[test]
+    fn test_eval_sink() {
+        for (line, expected) in [
+            ("eval \"$CMD\"", true),
+            ("bash -c \"$CMD\"", true),
+            ("sh -c \"$CMD\"", true),
+            ("python3 -c \"$CMD\"", true),
+            ("Invoke-Expression $env:CMD", true),
+            ("echo \"$CMD\"", false),
+            ("evaluate \"$CMD\"", false),
+            ("bash script.sh \"$CMD\"", false),
+        ] {
+            assert_eq!(EVAL_SINK.is_match(line), expected, "{line}");
+        }
+    }
+}
@@ -1,7 +1,7 @@
 //! (Very) primitive template injection detection.
 //!
-//! This looks for job steps where the step contains indicators of template
-//! expansion, i.e. anything matching `${{ }}`.
+//! This looks for job steps (and composite action steps) where the step
+//! contains indicators of template expansion, i.e. anything matching `${{ }}`.
 //!
 //! The following steps are currently supported:
 //! * `run:`, indicating template expansion into a shell script or similar
@@ -10,23 +10,27 @@
 //! A small amount of additional processing is done to remove template
 //! expressions that an attacker can't control.
 
-use std::ops::Deref;
+use std::{collections::HashMap, ops::Deref};
 
 use github_actions_models::{
+    action,
     common::expr::LoE,
-    workflow::job::{NormalJob, StepBody, Strategy},
+    workflow::job::{StepBody, Strategy},
 };
 
-use super::{audit_meta, WorkflowAudit};
+use super::{audit_meta, hardening_guide, WorkflowAudit};
 use crate::{
+    contexts::{is_secret_context, ContextPolicy},
     expr::{BinOp, Expr, UnOp},
-    finding::{Confidence, Persona, Severity},
-    models,
+    finding::{Confidence, Finding, Persona, Severity, SymbolicLocation},
+    models::{self, AuditInput, CompositeStep},
     state::AuditState,
     utils::extract_expressions,
 };
 
-pub(crate) struct TemplateInjection;
+pub(crate) struct TemplateInjection {
+    contexts: ContextPolicy,
+}
 
 audit_meta!(
     TemplateInjection,
@@ -34,46 +38,6 @@ audit_meta!(
     "code injection via template expansion"
 );
 
-/// Context members that are believed to be always safe.
-const SAFE_CONTEXTS: &[&str] = &[
-    // The GitHub event name (i.e. trigger) is itself safe.
-    "github.event_name",
-    // Safe keys within the otherwise generally unsafe github.event context.
-    "github.event.issue.number",
-    "github.event.merge_group.base_sha",
-    "github.event.number",
-    "github.event.pull_request.commits", // number of commits in PR
-    "github.event.pull_request.number",  // the PR's own number
-    "github.event.workflow_run.id",
-    // Information about the GitHub repository
-    "github.repository",
-    "github.repository_id",
-    "github.repositoryUrl",
-    // Information about the GitHub repository owner (account/org or ID)
-    "github.repository_owner",
-    "github.repository_owner_id",
-    // Unique numbers assigned by GitHub for workflow runs
-    "github.run_attempt",
-    "github.run_id",
-    "github.run_number",
-    // Always a 40-char SHA-1 reference.
-    "github.sha",
-    // Like `secrets.*`: not safe to expose, but safe to interpolate.
-    "github.token",
-    // GitHub Actions-controlled local directory.
-    "github.workspace",
-    // GitHub Actions-controller runner architecture.
-    "runner.arch",
-    // Debug logging is (1) or is not (0) enabled on GitHub Actions runner.
-    "runner.debug",
-    // GitHub Actions runner operating system.
-    "runner.os",
-    // GitHub Actions temporary directory, value controlled by the runner itself.
-    "runner.temp",
-    // GitHub Actions cached tool directory, value controlled by the runner itself.
-    "runner.tool_cache",
-];
-
 impl TemplateInjection {
     /// Checks whether an expression is "safe" for the purposes of template
     /// injection.
@@ -82,7 +46,7 @@ impl TemplateInjection {
     /// can only ever return a literal node (i.e. bool, number, string, etc.).
     /// All branches/flows of the expression must uphold that invariant;
     /// no taint tracking is currently done.
-    fn expr_is_safe(expr: &Expr) -> bool {
+    pub(crate) fn expr_is_safe(expr: &Expr) -> bool {
         match expr {
             Expr::Number(_) => true,
             Expr::String(_) => true,
@@ -126,10 +90,18 @@ impl TemplateInjection {
     fn injectable_template_expressions(
         &self,
         run: &str,
-        job: &NormalJob,
-    ) -> Vec<(String, Severity, Confidence, Persona)> {
+        strategy: Option<&Strategy>,
+    ) -> Vec<(String, usize, String, Severity, Confidence, Persona)> {
         let mut bad_expressions = vec![];
+        // How many times each expression has appeared so far, so that
+        // repeated expressions are each located at their own occurrence.
+        let mut seen = HashMap::new();
         for expr in extract_expressions(run) {
+            let occurrence = *seen
+                .entry(expr.as_raw().to_string())
+                .and_modify(|n| *n += 1)
+                .or_insert(0);
+
             let Ok(parsed) = Expr::parse(expr.as_bare()) else {
                 tracing::warn!("couldn't parse expression: {expr}", expr = expr.as_bare());
                 continue;
@@ -139,6 +111,8 @@ impl TemplateInjection {
                 // Emit a pedantic finding for all expressions, since
                 // all template injections are code smells, even if unexploitable.
                 bad_expressions.push((
+                    expr.as_raw().into(),
+                    occurrence,
                     expr.as_raw().into(),
                     Severity::Unknown,
                     Confidence::Unknown,
@@ -148,17 +122,30 @@ impl TemplateInjection {
             }
 
             for context in parsed.contexts() {
-                if context.starts_with("secrets.") {
+                if context.contains('.') && is_secret_context(context) {
                     // While not ideal, secret expansion is typically not exploitable.
                     continue;
-                } else if SAFE_CONTEXTS.contains(&context) {
+                } else if self.contexts.is_trusted_context(context) {
                     continue;
+                } else if self.contexts.is_untrusted_context(context) {
+                    // TODO: Filter these more finely; not everything in the event
+                    // context is actually attacker-controllable.
+                    bad_expressions.push((
+                        expr.as_raw().into(),
+                        occurrence,
+                        context.into(),
+                        Severity::High,
+                        Confidence::High,
+                        Persona::default(),
+                    ));
                 } else if context.starts_with("inputs.") {
                     // TODO: Currently low confidence because we don't check the
                     // input's type. In the future, we should index back into
                     // the workflow's triggers and exclude input expansions
                     // from innocuous types, e.g. booleans.
                     bad_expressions.push((
+                        expr.as_raw().into(),
+                        occurrence,
                         context.into(),
                         Severity::High,
                         Confidence::Low,
@@ -167,22 +154,17 @@ impl TemplateInjection {
                 } else if context.starts_with("env.") {
                     // Almost never exploitable.
                     bad_expressions.push((
+                        expr.as_raw().into(),
+                        occurrence,
                         context.into(),
                         Severity::Low,
                         Confidence::High,
                         Persona::default(),
                     ));
-                } else if context.starts_with("github.event.") || context == "github.ref_name" {
-                    // TODO: Filter these more finely; not everything in the event
-                    // context is actually attacker-controllable.
-                    bad_expressions.push((
-                        context.into(),
-                        Severity::High,
-                        Confidence::High,
-                        Persona::default(),
-                    ));
                 } else if context.starts_with("matrix.") || context == "matrix" {
-                    if let Some(Strategy { matrix, .. }) = &job.strategy {
+                    // Composite actions have no matrix of their own, so
+                    // these are only meaningful within a job.
+                    if let Some(Strategy { matrix, .. }) = strategy {
                         let matrix_is_static = match matrix {
                             // The matrix is generated by an expression, meaning
                             // that it's trivially not static.
@@ -198,6 +180,8 @@ impl TemplateInjection {
 
                         if !matrix_is_static {
                             bad_expressions.push((
+                                expr.as_raw().into(),
+                                occurrence,
                                 context.into(),
                                 Severity::Medium,
                                 Confidence::Medium,
@@ -210,6 +194,8 @@ impl TemplateInjection {
                     // All other contexts are typically not attacker controllable,
                     // but may be in obscure cases.
                     bad_expressions.push((
+                        expr.as_raw().into(),
+                        occurrence,
                         context.into(),
                         Severity::Informational,
                         Confidence::Low,
@@ -221,55 +207,107 @@ impl TemplateInjection {
 
         bad_expressions
     }
+
+    fn audit_script<'w>(
+        &self,
+        script: &str,
+        script_loc: SymbolicLocation<'w>,
+        step_loc: SymbolicLocation<'w>,
+        strategy: Option<&Strategy>,
+        input: &'w impl AuditInput,
+    ) -> anyhow::Result<Vec<Finding<'w>>> {
+        let mut findings = vec![];
+
+        for (raw, occurrence, expr, severity, confidence, persona) in
+            self.injectable_template_expressions(script, strategy)
+        {
+            findings.push(
+                Self::finding()
+                    .severity(severity)
+                    .confidence(confidence)
+                    .reference(hardening_guide(
+                        "understanding-the-risk-of-script-injections",
+                    ))
+                    .persona(persona)
+                    .add_location(step_loc.clone())
+                    .add_location(
+                        script_loc
+                            .clone()
+                            .subfeature(raw)
+                            .occurrence(occurrence)
+                            .annotated(format!(
+                                "{expr} may expand into attacker-controllable code"
+                            )),
+                    )
+                    .build(input)?,
+            )
+        }
+
+        Ok(findings)
+    }
 }
 
 impl WorkflowAudit for TemplateInjection {
-    fn new(_state: AuditState) -> anyhow::Result<Self>
+    fn new(state: AuditState) -> anyhow::Result<Self>
     where
         Self: Sized,
     {
-        Ok(Self)
+        Ok(Self {
+            contexts: state.config.contexts().clone(),
+        })
     }
 
-    fn audit_step<'w>(&self, step: &super::Step<'w>) -> anyhow::Result<Vec<super::Finding<'w>>> {
-        let mut findings = vec![];
-
+    fn audit_step<'w>(&self, step: &super::Step<'w>) -> anyhow::Result<Vec<Finding<'w>>> {
         let (script, script_loc) = match &step.deref().body {
             StepBody::Uses { uses, with } => {
                 if uses.starts_with("actions/github-script") {
                     match with.get("script") {
-                        Some(script) => (
-                            &script.to_string(),
-                            step.location().with_keys(&["with".into(), "script".into()]),
-                        ),
-                        None => return Ok(findings),
+                        Some(script) => (&script.to_string(), step.location().with_input("script")),
+                        None => return Ok(vec![]),
                     }
                 } else {
-                    return Ok(findings);
+                    return Ok(vec![]);
                 }
             }
             StepBody::Run { run, .. } => (run, step.location().with_keys(&["run".into()])),
         };
 
-        for (expr, severity, confidence, persona) in
-            self.injectable_template_expressions(script, step.job())
-        {
-            findings.push(
-                Self::finding()
-                    .severity(severity)
-                    .confidence(confidence)
-                    .persona(persona)
-                    .add_location(step.location_with_name())
-                    .add_location(
-                        script_loc.clone().annotated(format!(
-                            "{expr} may expand into attacker-controllable code"
-                        )),
-                    )
-                    .build(step.workflow())?,
-            )
-        }
+        self.audit_script(
+            script,
+            script_loc,
+            step.location_with_name(),
+            step.job().strategy.as_ref(),
+            step.workflow(),
+        )
+    }
 
-        Ok(findings)
+    fn audit_composite_step<'a>(
+        &self,
+        step: &CompositeStep<'a>,
+    ) -> anyhow::Result<Vec<Finding<'a>>> {
+        let (script, script_loc) = match step.deref() {
+            action::Step::UseAction(action::UseAction { uses, with, .. }) => {
+                if uses.starts_with("actions/github-script") {
+                    match with.get("script") {
+                        Some(script) => (script, step.location().with_input("script")),
+                        None => return Ok(vec![]),
+                    }
+                } else {
+                    return Ok(vec![]);
+                }
+            }
+            action::Step::RunShell(action::RunShell { run, .. }) => {
+                (run, step.location().with_keys(&["run".into()]))
+            }
+        };
+
+        self.audit_script(
+            script,
+            script_loc,
+            step.location_with_name(),
+            None,
+            step.parent,
+        )
     }
 }
 
@@ -0,0 +1,147 @@
+//! Detects the workflow's `GITHUB_TOKEN` being passed explicitly to
+//! third-party actions.
+//!
+//! Actions that need the token can already default an input to
+//! `github.token` themselves. Passing `${{ secrets.GITHUB_TOKEN }}` as an
+//! input hands the token to the action whether or not it needs it, and an
+//! action that's compromised (or just careless) can then use it with all
+//! of the job's permissions.
+
+use std::ops::Deref;
+
+use anyhow::Result;
+use github_actions_models::{action, workflow::job::StepBody};
+
+use super::{audit_meta, WorkflowAudit};
+use crate::{
+    allowlist::ActionAllowlist,
+    expr::Expr,
+    finding::{Confidence, Finding, Severity, SymbolicLocation},
+    models::{AuditInput, CompositeStep, Step, Uses},
+    state::AuditState,
+    utils::extract_expressions,
+};
+
+/// Contexts that expand to the workflow's `GITHUB_TOKEN`.
+const TOKEN_CONTEXTS: &[&str] = &["github.token", "secrets.GITHUB_TOKEN"];
+
+/// Owners whose actions are considered first-party.
+const FIRST_PARTY_OWNERS: &[&str] = &["actions"];
+
+pub(crate) struct TokenPassthrough {
+    trusted: ActionAllowlist,
+}
+
+audit_meta!(
+    TokenPassthrough,
+    "token-passthrough",
+    "GITHUB_TOKEN passed to a third-party action"
+);
+
+/// Returns whether the given input value expands the `GITHUB_TOKEN`.
+fn passes_token(value: &str) -> bool {
+    extract_expressions(value)
+        .iter()
+        .filter_map(|expr| Expr::parse(expr.as_bare()).ok())
+        .any(|expr| {
+            expr.contexts().into_iter().any(|context| {
+                TOKEN_CONTEXTS
+                    .iter()
+                    .any(|token| token.eq_ignore_ascii_case(context))
+            })
+        })
+}
+
+impl TokenPassthrough {
+    fn audit_inputs<'w>(
+        &self,
+        uses: Option<Uses<'_>>,
+        inputs: impl Iterator<Item = (&'w str, String)>,
+        location: SymbolicLocation<'w>,
+        input: &'w impl AuditInput,
+    ) -> Result<Vec<Finding<'w>>> {
+        // Local actions are part of the repository being audited, and
+        // GitHub's own actions can be trusted with a token that GitHub
+        // issued in the first place.
+        match uses {
+            Some(Uses::Local(_)) | None => return Ok(vec![]),
+            Some(Uses::Repository(uses)) if FIRST_PARTY_OWNERS.contains(&uses.owner) => {
+                return Ok(vec![])
+            }
+            Some(uses) if self.trusted.is_trusted(&uses) => return Ok(vec![]),
+            Some(_) => (),
+        }
+
+        inputs
+            .filter(|(_, value)| passes_token(value))
+            .map(|(name, _)| {
+                Self::finding()
+                    .severity(Severity::Medium)
+                    .confidence(Confidence::High)
+                    .add_location(
+                        location
+                            .with_input(name)
+                            .annotated("passes the GITHUB_TOKEN to a third-party action"),
+                    )
+                    .build(input)
+            })
+            .collect()
+    }
+}
+
+impl WorkflowAudit for TokenPassthrough {
+    fn new(state: AuditState) -> Result<Self> {
+        Ok(Self {
+            trusted: state.config.trusted_actions().clone(),
+        })
+    }
+
+    fn audit_step<'w>(&self, step: &Step<'w>) -> Result<Vec<Finding<'w>>> {
+        let StepBody::Uses { with, .. } = &step.deref().body else {
+            return Ok(vec![]);
+        };
+
+        self.audit_inputs(
+            step.uses(),
+            with.iter()
+                .map(|(name, value)| (name.as_str(), value.to_string())),
+            step.location(),
+            step.workflow(),
+        )
+    }
+
+    fn audit_composite_step<'a>(&self, step: &CompositeStep<'a>) -> Result<Vec<Finding<'a>>> {
+        let action::Step::UseAction(action::UseAction { with, .. }) = step.deref() else {
+            return Ok(vec![]);
+        };
+
+        self.audit_inputs(
+            step.uses(),
+            with.iter()
+                .map(|(name, value)| (name.as_str(), value.clone())),
+            step.location(),
+            step.parent,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::passes_token;
+
+    #[test]
+    fn test_passes_token() {
+        for (value, expected) in [
+            ("${{ secrets.GITHUB_TOKEN }}", true),
+            ("${{ secrets.github_token }}", true),
+            ("${{ github.token }}", true),
+            ("token ${{ github.token }}", true),
+            ("${{ inputs.token || github.token }}", true),
+            ("${{ secrets.DEPLOY_TOKEN }}", false),
+            ("${{ github.token_url }}", false),
+            ("github.token", false),
+        ] {
+            assert_eq!(passes_token(value), expected, "{value}");
+        }
+    }
+}
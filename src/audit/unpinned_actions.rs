@@ -0,0 +1,184 @@
+//! Detects actions pinned to a mutable ref (a tag or branch) rather than
+//! a commit.
+//!
+//! Tags and branches can be moved by whoever controls the action's
+//! repository, silently changing what runs in the calling workflow.
+//! Branches move as a matter of course, so they're flagged more severely.
+//!
+//! Refless `uses:` are left to `unpinned-uses`, which in turn leaves
+//! tag- and branch-pinned actions to this audit.
+
+use std::sync::LazyLock;
+
+use anyhow::Result;
+use regex::Regex;
+use serde::Deserialize;
+
+use super::{audit_meta, hardening_guide, WorkflowAudit};
+use crate::{
+    allowlist::ActionAllowlist,
+    finding::{Confidence, Finding, Severity, SymbolicLocation},
+    github_api,
+    models::{AuditInput, CompositeStep, RepositoryUses, Step, Uses},
+    state::AuditState,
+    utils::repin_uses,
+};
+
+/// Refs that look like version tags, e.g. `v4`, `v1.2.3`, or `2.0.0-rc.1`.
+static VERSION_TAG: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^v?\d+(\.\d+)*(-[0-9A-Za-z.-]+)?$").unwrap());
+
+/// Owners whose actions are considered first-party.
+const FIRST_PARTY_OWNERS: &[&str] = &["actions"];
+
+/// Settings for this audit, under `rules.unpinned-actions.config`.
+#[derive(Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+struct UnpinnedActionsConfig {
+    /// Don't flag first-party (`actions/*`) actions.
+    exempt_first_party: bool,
+}
+
+pub(crate) struct UnpinnedActions {
+    config: UnpinnedActionsConfig,
+    trusted: ActionAllowlist,
+    /// Used to suggest the commit to pin to, if available.
+    client: Option<github_api::Client>,
+}
+
+audit_meta!(
+    UnpinnedActions,
+    "unpinned-actions",
+    "action pinned to a mutable ref"
+);
+
+impl UnpinnedActions {
+    /// Resolves the commit that `uses` is currently pinned to, if possible.
+    fn resolve_commit(&self, uses: &RepositoryUses<'_>, git_ref: &str) -> Option<String> {
+        let client = self.client.as_ref()?;
+
+        // This is only used for a suggestion, so failing to resolve the
+        // ref shouldn't fail the audit.
+        client
+            .commit_for_ref(uses.owner, uses.repo, git_ref)
+            .inspect_err(|e| {
+                tracing::debug!(
+                    "couldn't resolve {uses}: {e}",
+                    uses = uses.with_ref(git_ref)
+                )
+            })
+            .ok()
+            .flatten()
+    }
+
+    fn audit_uses<'w>(
+        &self,
+        uses: Option<Uses<'_>>,
+        location: SymbolicLocation<'w>,
+        input: &'w impl AuditInput,
+    ) -> Result<Vec<Finding<'w>>> {
+        // Trusted publishers are exempt, like first-party actions.
+        let Some(Uses::Repository(uses)) = uses.filter(|uses| !self.trusted.is_trusted(uses))
+        else {
+            return Ok(vec![]);
+        };
+
+        // Refless `uses:` are handled by `unpinned-uses`.
+        let Some(git_ref) = uses.git_ref else {
+            return Ok(vec![]);
+        };
+
+        if uses.ref_is_commit()
+            || (self.config.exempt_first_party && FIRST_PARTY_OWNERS.contains(&uses.owner))
+        {
+            return Ok(vec![]);
+        }
+
+        // We can't tell tags and branches apart without the GitHub API,
+        // so anything that doesn't look like a version is presumed to
+        // be a branch.
+        let (severity, confidence, annotation) = if VERSION_TAG.is_match(git_ref) {
+            (
+                Severity::Medium,
+                Confidence::High,
+                "action is pinned to a tag, not a commit",
+            )
+        } else {
+            (
+                Severity::High,
+                Confidence::Medium,
+                "action is pinned to a branch, not a commit",
+            )
+        };
+
+        let finding = Self::finding()
+            .severity(severity)
+            .confidence(confidence)
+            .reference(hardening_guide("using-third-party-actions"))
+            .add_location(location.with_keys(&["uses".into()]).annotated(annotation));
+
+        let finding = match self.resolve_commit(&uses, git_ref) {
+            Some(commit) => {
+                let remediation = format!(
+                    "pin to a commit: {pinned} # {git_ref}",
+                    pinned = uses.with_ref(&commit)
+                );
+                let git_ref = git_ref.to_string();
+
+                finding
+                    .remediation(remediation)
+                    .replacement(move |feature| repin_uses(feature, &git_ref, &commit, &git_ref))
+            }
+            None => finding.remediation(format!("pin to the commit that {git_ref} refers to")),
+        };
+
+        Ok(vec![finding.build(input)?])
+    }
+}
+
+impl WorkflowAudit for UnpinnedActions {
+    fn new(state: AuditState) -> Result<Self> {
+        let client = if state.no_online_audits {
+            None
+        } else {
+            state.github_client()
+        };
+
+        Ok(Self {
+            config: state.config.audit_config(Self::ident())?,
+            trusted: state.config.trusted_actions().clone(),
+            client,
+        })
+    }
+
+    fn audit_step<'w>(&self, step: &Step<'w>) -> Result<Vec<Finding<'w>>> {
+        self.audit_uses(step.uses(), step.location(), step.workflow())
+    }
+
+    fn audit_composite_step<'a>(&self, step: &CompositeStep<'a>) -> Result<Vec<Finding<'a>>> {
+        self.audit_uses(step.uses(), step.location(), step.parent)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::VERSION_TAG;
+
+    #[test]
+    fn test_version_tag() {
+        for (git_ref, is_tag) in &[
+            ("v4", true),
+            ("v4.1", true),
+            ("v1.2.3", true),
+            ("1.2.3", true),
+            ("v2.0.0-rc.1", true),
+            ("main", false),
+            ("master", false),
+            ("release/v1", false),
+            ("v4-branch-name/foo", false),
+            ("vfoo", false),
+        ] {
+            assert_eq!(VERSION_TAG.is_match(git_ref), *is_tag, "{git_ref}");
+        }
+    }
+}
@@ -0,0 +1,88 @@
+//! Detects reusable workflow calls that aren't pinned to a commit.
+//!
+//! Reusable workflows always require a `@ref`, but a branch or tag ref
+//! can be moved by whoever controls the called repository, silently
+//! changing what runs in the caller's context (including its secrets).
+
+use std::ops::Deref;
+
+use anyhow::Result;
+use github_actions_models::workflow::Job as WorkflowJob;
+
+use super::{audit_meta, WorkflowAudit};
+use crate::{
+    allowlist::ActionAllowlist,
+    finding::{Confidence, Finding, Severity},
+    models::{Job, Uses},
+    state::AuditState,
+};
+
+/// Owners whose reusable workflows are considered first-party,
+/// regardless of who owns the calling workflow.
+const TRUSTED_OWNERS: &[&str] = &["actions", "github"];
+
+pub(crate) struct UnpinnedReusableWorkflow {
+    trusted: ActionAllowlist,
+}
+
+audit_meta!(
+    UnpinnedReusableWorkflow,
+    "unpinned-reusable-workflow",
+    "reusable workflow not pinned to a commit"
+);
+
+impl WorkflowAudit for UnpinnedReusableWorkflow {
+    fn new(state: AuditState) -> Result<Self> {
+        Ok(Self {
+            trusted: state.config.trusted_actions().clone(),
+        })
+    }
+
+    fn audit_reusable_job<'w>(&self, job: &Job<'w>) -> Result<Vec<Finding<'w>>> {
+        let WorkflowJob::ReusableWorkflowCallJob(reusable) = job.deref() else {
+            return Ok(vec![]);
+        };
+
+        // Local (`./...`) reusable workflows are always pinned to the
+        // caller's own ref, so there's nothing to flag.
+        let Some(uses) = Uses::from_reusable(&reusable.uses) else {
+            return Ok(vec![]);
+        };
+
+        if uses.ref_is_commit() {
+            return Ok(vec![]);
+        }
+
+        let workflow = job.parent();
+        // Reusable workflows from trusted publishers are treated like
+        // first-party ones.
+        let first_party = TRUSTED_OWNERS.contains(&uses.owner)
+            || self.trusted.is_trusted(&Uses::Repository(uses))
+            || workflow
+                .key
+                .owner()
+                .is_some_and(|owner| owner.eq_ignore_ascii_case(uses.owner));
+
+        let (severity, annotation) = if first_party {
+            (
+                Severity::Low,
+                "first-party reusable workflow is pinned to a branch or tag",
+            )
+        } else {
+            (
+                Severity::Medium,
+                "third-party reusable workflow is pinned to a branch or tag",
+            )
+        };
+
+        Ok(vec![Self::finding()
+            .severity(severity)
+            .confidence(Confidence::High)
+            .add_location(
+                job.location()
+                    .with_keys(&["uses".into()])
+                    .annotated(annotation),
+            )
+            .build(workflow)?])
+    }
+}
@@ -1,4 +1,5 @@
-use crate::finding::{Confidence, Persona, Severity};
+use crate::finding::{Confidence, Persona, Severity, SymbolicLocation};
+use crate::models::{AuditInput, CompositeStep, Uses};
 
 use super::{audit_meta, AuditState, Finding, Step, WorkflowAudit};
 
@@ -6,18 +7,13 @@ pub(crate) struct UnpinnedUses;
 
 audit_meta!(UnpinnedUses, "unpinned-uses", "unpinned action reference");
 
-impl WorkflowAudit for UnpinnedUses {
-    fn new(_state: AuditState) -> anyhow::Result<Self>
-    where
-        Self: Sized,
-    {
-        Ok(Self)
-    }
-
-    fn audit_step<'w>(&self, step: &Step<'w>) -> anyhow::Result<Vec<Finding<'w>>> {
-        let mut findings = vec![];
-
-        let Some(uses) = step.uses() else {
+impl UnpinnedUses {
+    fn audit_uses<'w>(
+        uses: Option<Uses<'_>>,
+        location: SymbolicLocation<'w>,
+        input: &'w impl AuditInput,
+    ) -> anyhow::Result<Vec<Finding<'w>>> {
+        let Some(uses) = uses else {
             return Ok(vec![]);
         };
 
@@ -27,7 +23,9 @@ impl WorkflowAudit for UnpinnedUses {
                 Severity::Medium,
                 Persona::default(),
             )
-        } else if uses.unhashed() {
+        } else if matches!(uses, Uses::Docker(_)) && uses.unhashed() {
+            // Actions pinned to a tag or branch rather than a commit are
+            // handled by `unpinned-actions`.
             (
                 "action is not pinned to a hash ref",
                 Severity::Low,
@@ -37,19 +35,31 @@ impl WorkflowAudit for UnpinnedUses {
             return Ok(vec![]);
         };
 
-        findings.push(
-            Self::finding()
-                .confidence(Confidence::High)
-                .severity(severity)
-                .persona(persona)
-                .add_location(
-                    step.location()
-                        .with_keys(&["uses".into()])
-                        .annotated(annotation),
-                )
-                .build(step.workflow())?,
-        );
-
-        Ok(findings)
+        Ok(vec![Self::finding()
+            .confidence(Confidence::High)
+            .severity(severity)
+            .persona(persona)
+            .add_location(location.with_keys(&["uses".into()]).annotated(annotation))
+            .build(input)?])
+    }
+}
+
+impl WorkflowAudit for UnpinnedUses {
+    fn new(_state: AuditState) -> anyhow::Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self)
+    }
+
+    fn audit_step<'w>(&self, step: &Step<'w>) -> anyhow::Result<Vec<Finding<'w>>> {
+        Self::audit_uses(step.uses(), step.location(), step.workflow())
+    }
+
+    fn audit_composite_step<'a>(
+        &self,
+        step: &CompositeStep<'a>,
+    ) -> anyhow::Result<Vec<Finding<'a>>> {
+        Self::audit_uses(step.uses(), step.location(), step.parent)
     }
 }
@@ -98,7 +98,7 @@ impl WorkflowAudit for UseTrustedPublishing {
                 candidate
                     .add_location(
                         step.location()
-                            .with_keys(&["with".into(), "password".into()])
+                            .with_input("password")
                             .annotated(USES_MANUAL_CREDENTIAL),
                     )
                     .build(step.workflow())?,
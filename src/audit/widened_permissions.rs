@@ -0,0 +1,95 @@
+//! Detects jobs that widen the permissions set at the workflow level.
+//!
+//! A tight top-level `permissions:` block is only useful if jobs don't
+//! quietly redeclare broader ones, e.g. with `permissions: write-all`.
+
+use std::ops::Deref;
+
+use anyhow::Result;
+use github_actions_models::workflow::Job;
+
+use super::{audit_meta, WorkflowAudit};
+use crate::{
+    finding::{Confidence, Finding, Severity},
+    models::{PermissionScopes, Workflow},
+    state::AuditState,
+};
+
+/// The most widened scopes we'll list individually in a finding.
+const MAX_LISTED_SCOPES: usize = 3;
+
+pub(crate) struct WidenedPermissions;
+
+audit_meta!(
+    WidenedPermissions,
+    "widened-permissions",
+    "job widens the workflow's permissions"
+);
+
+impl WorkflowAudit for WidenedPermissions {
+    fn new(_state: AuditState) -> Result<Self> {
+        Ok(Self)
+    }
+
+    fn audit_workflow<'w>(&self, workflow: &'w Workflow) -> Result<Vec<Finding<'w>>> {
+        let mut findings = vec![];
+
+        // Without an explicit workflow-level baseline, there's nothing to
+        // compare against: the defaults come from repository settings.
+        let Some(baseline) = PermissionScopes::resolve(&workflow.permissions) else {
+            return Ok(findings);
+        };
+
+        for job in workflow.jobs() {
+            let Job::NormalJob(normal) = job.deref() else {
+                continue;
+            };
+
+            let Some(scopes) = PermissionScopes::resolve(&normal.permissions) else {
+                continue;
+            };
+
+            let widened = scopes.widened_from(&baseline);
+            if widened.is_empty() {
+                continue;
+            }
+
+            // Keep the annotation readable for blanket widenings,
+            // like `permissions: write-all`.
+            let annotation = if widened.len() > MAX_LISTED_SCOPES {
+                format!(
+                    "widens {nscopes} scopes beyond the workflow-level permissions",
+                    nscopes = widened.len()
+                )
+            } else {
+                let widened = widened
+                    .iter()
+                    .map(|(scope, from, to)| format!("{scope} ({from} → {to})"))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                format!("widens {widened}")
+            };
+
+            findings.push(
+                Self::finding()
+                    .severity(Severity::Medium)
+                    .confidence(Confidence::High)
+                    .add_location(
+                        job.location()
+                            .with_keys(&["permissions".into()])
+                            .annotated(annotation),
+                    )
+                    .add_location(
+                        workflow
+                            .location()
+                            .with_keys(&["permissions".into()])
+                            .annotated("workflow-level permissions set here"),
+                    )
+                    .build(workflow)?,
+            );
+        }
+
+        Ok(findings)
+    }
+}
@@ -0,0 +1,96 @@
+//! Detects `workflow_run` workflows that download artifacts from
+//! another (typically the triggering) run.
+//!
+//! A `workflow_run` workflow runs in the context of the default branch,
+//! with access to secrets and a writable token, even when the run that
+//! triggered it came from a fork. Artifacts uploaded by that triggering
+//! run are fully attacker-controlled, so downloading them (and then
+//! unpacking, executing, or otherwise trusting them) is a well-known
+//! privilege escalation path.
+
+use std::{ops::Deref, sync::LazyLock};
+
+use anyhow::Result;
+use github_actions_models::{common::EnvValue, workflow::job::StepBody};
+use regex::Regex;
+
+use super::{audit_meta, cross_run_artifact::CrossRunDownload, WorkflowAudit};
+use crate::{
+    finding::{Confidence, Finding, Severity, SymbolicLocation},
+    models::{Step, Uses},
+    state::AuditState,
+};
+
+/// `gh run download`, which downloads artifacts from any run.
+static GH_RUN_DOWNLOAD: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\bgh\s+run\s+download\b").unwrap());
+
+pub(crate) struct WorkflowRunArtifacts;
+
+audit_meta!(
+    WorkflowRunArtifacts,
+    "workflow-run-artifacts",
+    "workflow_run downloads artifacts from another run"
+);
+
+impl WorkflowRunArtifacts {
+    /// Returns the location within `step` that downloads artifacts from
+    /// another run, if any.
+    fn artifact_download<'w>(step: &Step<'w>) -> Option<SymbolicLocation<'w>> {
+        match &step.deref().body {
+            StepBody::Uses { uses, with } => {
+                let Some(Uses::Repository(uses)) = Uses::from_step(uses) else {
+                    return None;
+                };
+
+                if let Some(download) = CrossRunDownload::of(&uses, |name| with.contains_key(name))
+                {
+                    Some(download.locate(step.location()))
+                } else if uses.matches("actions/github-script") {
+                    matches!(
+                        with.get("script"),
+                        Some(EnvValue::String(script)) if script.contains("downloadArtifact")
+                    )
+                    .then(|| step.location().with_input("script"))
+                } else {
+                    None
+                }
+            }
+            StepBody::Run { run, .. } => run
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.starts_with('#'))
+                .find(|line| GH_RUN_DOWNLOAD.is_match(line))
+                .map(|line| step.location().with_keys(&["run".into()]).subfeature(line)),
+        }
+    }
+}
+
+impl WorkflowAudit for WorkflowRunArtifacts {
+    fn new(_state: AuditState) -> Result<Self> {
+        Ok(Self)
+    }
+
+    fn audit_step<'w>(&self, step: &Step<'w>) -> Result<Vec<Finding<'w>>> {
+        let workflow = step.workflow();
+        if !workflow.has_workflow_run() {
+            return Ok(vec![]);
+        }
+
+        let Some(location) = Self::artifact_download(step) else {
+            return Ok(vec![]);
+        };
+
+        Ok(vec![Self::finding()
+            .severity(Severity::High)
+            .confidence(Confidence::Medium)
+            .add_location(location.annotated("artifacts are downloaded from another run"))
+            .add_location(
+                workflow
+                    .location()
+                    .with_keys(&["on".into()])
+                    .annotated("workflow_run runs with the default branch's privileges"),
+            )
+            .build(workflow)?])
+    }
+}
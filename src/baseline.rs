@@ -0,0 +1,98 @@
+//! Baselines of known findings, for incremental adoption.
+//!
+//! A baseline records the fingerprints of every finding from a previous
+//! run (via `--write-baseline`). Subsequent runs with `--baseline` then
+//! ignore any finding whose fingerprint appears in it, so that only new
+//! findings are reported.
+
+use std::{collections::HashSet, io::Write as _};
+
+use anyhow::{anyhow, Context, Result};
+use camino::Utf8Path;
+use serde::{Deserialize, Serialize};
+
+use crate::{finding::Finding, output::AtomicFile};
+
+/// The current baseline file format version.
+const VERSION: u32 = 1;
+
+/// A single baselined finding.
+///
+/// Only the fingerprint is used for matching; the rest is recorded
+/// so that the baseline file is reviewable.
+#[derive(Deserialize, Serialize)]
+struct Entry {
+    fingerprint: String,
+    ident: String,
+    path: String,
+}
+
+#[derive(Deserialize, Serialize)]
+struct BaselineFile {
+    version: u32,
+    findings: Vec<Entry>,
+}
+
+pub(crate) struct Baseline {
+    fingerprints: HashSet<String>,
+}
+
+impl Baseline {
+    /// Loads a baseline from the given path.
+    pub(crate) fn load(path: &Utf8Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("couldn't read baseline: {path}"))?;
+        let file: BaselineFile =
+            serde_json::from_str(&contents).with_context(|| format!("invalid baseline: {path}"))?;
+
+        if file.version != VERSION {
+            return Err(anyhow!(
+                "unsupported baseline version {version} in {path} (expected {VERSION})",
+                version = file.version
+            ));
+        }
+
+        Ok(Self {
+            fingerprints: file
+                .findings
+                .into_iter()
+                .map(|entry| entry.fingerprint)
+                .collect(),
+        })
+    }
+
+    /// Writes a baseline of the given findings to the given path.
+    pub(crate) fn write(path: &Utf8Path, findings: &[Finding]) -> Result<()> {
+        let mut entries = findings
+            .iter()
+            .map(|finding| Entry {
+                fingerprint: finding.fingerprint().into(),
+                ident: finding.ident.into(),
+                // NOTE: Findings always have at least one location.
+                path: finding.locations[0].symbolic.key.relative_path().into(),
+            })
+            .collect::<Vec<_>>();
+
+        // Keep the baseline stable (and diffable) across runs.
+        entries.sort_by(|a, b| {
+            (&a.path, &a.ident, &a.fingerprint).cmp(&(&b.path, &b.ident, &b.fingerprint))
+        });
+        entries.dedup_by(|a, b| a.fingerprint == b.fingerprint);
+
+        let mut file = AtomicFile::create(path)?;
+        serde_json::to_writer_pretty(
+            &mut file,
+            &BaselineFile {
+                version: VERSION,
+                findings: entries,
+            },
+        )?;
+        writeln!(file)?;
+        file.persist()
+    }
+
+    /// Returns whether the given finding is in this baseline.
+    pub(crate) fn contains(&self, finding: &Finding) -> bool {
+        self.fingerprints.contains(finding.fingerprint())
+    }
+}
@@ -0,0 +1,939 @@
+//! zizmor's command-line interface.
+//!
+//! This is exposed only so that zizmor's own binary can call [`main`],
+//! and isn't part of the library's public API (see the crate docs).
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    io::Write as _,
+    num::NonZeroUsize,
+    process::ExitCode,
+    sync::{
+        atomic::{AtomicBool, Ordering::Relaxed},
+        mpsc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use annotate_snippets::{Level, Renderer};
+use anstream::{eprintln, stream::IsTerminal};
+use anyhow::{anyhow, Context, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use clap::{ArgAction, Parser, ValueEnum};
+use github_actions_models::workflow::Job;
+use indicatif::ProgressStyle;
+use owo_colors::OwoColorize;
+use tracing::{info_span, instrument, level_filters::LevelFilter};
+use tracing_indicatif::{span_ext::IndicatifSpanExt, IndicatifLayer};
+use tracing_subscriber::{
+    fmt::writer::BoxMakeWriter, layer::SubscriberExt as _, util::SubscriberInitExt as _, EnvFilter,
+    Layer as _,
+};
+
+use crate::{
+    advisory_db::AdvisoryDb,
+    audit::{self, known_vulnerable_actions::KnownVulnerableActions, Audit as _},
+    baseline::Baseline,
+    config::Config,
+    finding::{self, Confidence, Finding, Persona, Severity},
+    fix,
+    github_api::{self, Client},
+    models::{Action, Uses},
+    output::Output,
+    registry::{self, AuditRegistry, FindingRegistry, Input, RuleStats, WorkflowRegistry},
+    render, sarif,
+    state::AuditState,
+};
+
+/// The exit codes that zizmor uses, as shown under `--help`.
+const EXIT_CODES: &str = "\
+Exit codes:
+  0      Successful audit, with no findings (or with --error-on-findings=false)
+  1      Error during audit
+  10-15  Findings reported, where the highest severity is unknown (10),
+         informational (11), low (12), medium (13), high (14), or critical (15)";
+
+/// Finds security issues in GitHub Actions setups.
+#[derive(Parser)]
+#[command(about, version, after_help = EXIT_CODES)]
+pub(crate) struct App {
+    /// Emit 'pedantic' findings.
+    ///
+    /// This is an alias for --persona=pedantic.
+    #[arg(short, long, group = "_persona")]
+    pub(crate) pedantic: bool,
+
+    /// The persona to use while auditing.
+    ///
+    /// Overrides any `persona` in the configuration file. By default,
+    /// the regular persona is used.
+    #[arg(long, group = "_persona", value_enum)]
+    pub(crate) persona: Option<Persona>,
+
+    /// Collapse findings from different audits that share a location.
+    ///
+    /// The most severe finding is kept, and notes the other audits that
+    /// reported the same location.
+    #[arg(long)]
+    pub(crate) dedupe: bool,
+
+    /// Perform only offline operations.
+    ///
+    /// This disables all online audit rules, and prevents zizmor from
+    /// auditing remote repositories.
+    #[arg(short, long, env = "ZIZMOR_OFFLINE", group = "_offline")]
+    pub(crate) offline: bool,
+
+    /// The GitHub API token to use.
+    ///
+    /// May be given multiple times, in which case zizmor switches to the
+    /// next token whenever the current one is rate limited.
+    ///
+    /// If not given, the `GITHUB_TOKEN` and `GH_TOKEN` environment variables
+    /// are tried in that order (each may hold comma-separated tokens),
+    /// followed by `gh auth token`. Tokens from the environment are ignored
+    /// with `--offline`.
+    #[arg(long, group = "_offline")]
+    pub(crate) gh_token: Vec<String>,
+
+    /// Perform only offline audits.
+    ///
+    /// This is a weaker version of `--offline`: instead of completely
+    /// forbidding all online operations, it only disables audits that
+    /// require connectivity.
+    #[arg(long, env = "ZIZMOR_NO_ONLINE_AUDITS")]
+    pub(crate) no_online_audits: bool,
+
+    /// Run only the given audits, as a comma-separated list of audit names.
+    #[arg(
+        long,
+        value_delimiter = ',',
+        value_name = "AUDITS",
+        conflicts_with = "skip"
+    )]
+    pub(crate) select: Vec<String>,
+
+    /// Don't run the given audits, as a comma-separated list of audit names.
+    #[arg(long, value_delimiter = ',', value_name = "AUDITS")]
+    pub(crate) skip: Vec<String>,
+
+    /// Don't run any audit that requires GitHub's API.
+    ///
+    /// Audits that only optionally use the API still run.
+    #[arg(long)]
+    pub(crate) skip_network: bool,
+
+    /// Emit more diagnostics on stderr, including debug logs of each GitHub
+    /// API call and location query.
+    ///
+    /// Pass twice for trace logs. By default, only warnings and errors
+    /// are emitted.
+    #[arg(short, long, action = ArgAction::Count, conflicts_with = "quiet")]
+    pub(crate) verbose: u8,
+
+    /// Only emit findings and errors, suppressing warnings and progress.
+    #[arg(short, long)]
+    pub(crate) quiet: bool,
+
+    /// Don't show progress bars on stderr.
+    #[arg(long)]
+    pub(crate) no_progress: bool,
+
+    /// The output format to emit. By default, plain text will be emitted
+    #[arg(long, value_enum, default_value_t)]
+    pub(crate) format: OutputFormat,
+
+    /// Don't emit the summary of findings after the findings themselves.
+    ///
+    /// Only applies to the plain and concise output formats.
+    #[arg(long)]
+    pub(crate) no_summary: bool,
+
+    /// Write output to this file instead of stdout.
+    ///
+    /// The file is written atomically, and never contains color codes.
+    /// Pass `-` to write to stdout explicitly.
+    #[arg(long)]
+    pub(crate) output: Option<Utf8PathBuf>,
+
+    /// The configuration file to load. By default, a config is
+    /// discovered in the root of the repository being audited, or else
+    /// in the user's config directory.
+    #[arg(short, long, group = "conf")]
+    pub(crate) config: Option<Utf8PathBuf>,
+
+    /// Disable all configuration loading.
+    #[arg(long, group = "conf")]
+    pub(crate) no_config: bool,
+
+    /// Disable all error codes besides success and tool failure.
+    ///
+    /// This is the same as `--error-on-findings=false`.
+    #[arg(long)]
+    pub(crate) no_exit_codes: bool,
+
+    /// Exit with a non-zero code when any findings are reported.
+    ///
+    /// Only findings that survive filtering (e.g. with `--min-severity` and
+    /// `--min-confidence`) count. With `--error-on-findings=false`, zizmor
+    /// exits with 0 whenever the audit itself succeeds.
+    #[arg(
+        long,
+        value_name = "BOOL",
+        num_args = 0..=1,
+        require_equals = true,
+        default_value_t = true,
+        default_missing_value = "true",
+        action = ArgAction::Set
+    )]
+    pub(crate) error_on_findings: bool,
+
+    /// Filter all results below this severity.
+    ///
+    /// Overrides any `min-severity` in the configuration file.
+    #[arg(long)]
+    pub(crate) min_severity: Option<Severity>,
+
+    /// Filter all results below this confidence.
+    ///
+    /// Overrides any `min-confidence` in the configuration file.
+    #[arg(long)]
+    pub(crate) min_confidence: Option<Confidence>,
+
+    /// Rewrite local workflows and actions to apply the fixes suggested by
+    /// findings, where a fix is mechanical.
+    ///
+    /// A diff of each change is written to stderr. Findings are reported as
+    /// usual, whether or not they were fixed.
+    #[arg(long)]
+    pub(crate) fix: bool,
+
+    /// Only apply fixes for findings with at least this confidence.
+    #[arg(
+        long,
+        value_name = "CONFIDENCE",
+        default_value = "high",
+        requires = "fix"
+    )]
+    pub(crate) fix_confidence: Confidence,
+
+    /// Ignore all findings recorded in this baseline file.
+    #[arg(long, value_name = "FILE", conflicts_with = "write_baseline")]
+    pub(crate) baseline: Option<Utf8PathBuf>,
+
+    /// Record all reported findings to this baseline file, for
+    /// later use with `--baseline`.
+    #[arg(long, value_name = "FILE")]
+    pub(crate) write_baseline: Option<Utf8PathBuf>,
+
+    /// Check actions against this local snapshot of GitHub's advisory
+    /// database, instead of querying GitHub's advisories API.
+    ///
+    /// This allows `known-vulnerable-actions` to run offline.
+    #[arg(long, value_name = "FILE")]
+    pub(crate) advisory_db: Option<Utf8PathBuf>,
+
+    /// Also check actions against advisories that have been withdrawn.
+    #[arg(long)]
+    pub(crate) include_withdrawn: bool,
+
+    /// The maximum number of times to retry a failed GitHub API request.
+    #[arg(long, value_name = "N", default_value_t = 3)]
+    pub(crate) max_retries: u32,
+
+    /// Fail immediately when rate limited by GitHub's API, instead of
+    /// waiting for the rate limit to reset.
+    #[arg(long)]
+    pub(crate) no_rate_limit_wait: bool,
+
+    /// Don't read or write the on-disk cache of GitHub API responses.
+    #[arg(long)]
+    pub(crate) no_cache: bool,
+
+    /// How long cached GitHub API responses remain valid, in hours.
+    #[arg(long, value_name = "HOURS", default_value_t = 24)]
+    pub(crate) cache_ttl: u64,
+
+    /// The maximum number of workflows to audit concurrently.
+    ///
+    /// Defaults to the number of available CPUs.
+    #[arg(short, long, value_name = "N")]
+    pub(crate) jobs: Option<NonZeroUsize>,
+
+    /// Report each audit's total runtime and GitHub API usage on stderr,
+    /// or within the report itself with `--format json`.
+    #[arg(long)]
+    pub(crate) rule_stats: bool,
+
+    /// Report the remaining GitHub API rate limit quota on stderr,
+    /// once the run is over.
+    #[arg(long)]
+    pub(crate) show_rate_limit: bool,
+
+    /// List every audit, along with its severity and whether it needs
+    /// GitHub's API, and exit.
+    #[arg(long)]
+    pub(crate) list_audits: bool,
+
+    /// Audit the workflows of this GitHub repository, fetched with GitHub's
+    /// API rather than read from local files.
+    ///
+    /// This is equivalent to passing an `owner/repo` slug as an input, and
+    /// can't be used with `--offline`.
+    #[arg(long, value_name = "OWNER/REPO")]
+    pub(crate) repo: Option<String>,
+
+    /// The git reference (branch, tag, or commit) to audit `--repo` at,
+    /// instead of the repository's default branch.
+    #[arg(long = "ref", value_name = "REF", requires = "repo")]
+    pub(crate) git_ref: Option<String>,
+
+    /// The inputs to audit.
+    ///
+    /// These can be individual workflow or action (`action.yml`) filenames,
+    /// entire directories, or a `user/repo` slug for a GitHub repository. In the latter case,
+    /// a `@ref` can be appended to audit the repository at a particular
+    /// git reference state.
+    ///
+    /// Pass `-` to read a single workflow or action from stdin.
+    #[arg(required_unless_present_any = ["list_audits", "repo"])]
+    pub(crate) inputs: Vec<String>,
+}
+
+impl App {
+    /// Returns every input to audit, including the `--repo` slug (at
+    /// `--ref`), if given.
+    fn all_inputs(&self) -> Result<Vec<String>> {
+        let mut inputs = self.inputs.clone();
+
+        if let Some(repo) = &self.repo {
+            if self.offline {
+                return Err(anyhow!(tip(
+                    format!("can't retrieve repository: {repo}", repo = repo.green()),
+                    format!(
+                        "--repo can't be used with {offline}",
+                        offline = "--offline".yellow()
+                    ),
+                )));
+            }
+
+            match &self.git_ref {
+                Some(_) if repo.contains('@') => {
+                    return Err(anyhow!(tip(
+                        format!("ambiguous git reference: {repo}", repo = repo.green()),
+                        "pass the git reference with either --repo owner/repo@ref or --ref, not both",
+                    )));
+                }
+                Some(git_ref) => inputs.push(format!("{repo}@{git_ref}")),
+                None => inputs.push(repo.clone()),
+            }
+        }
+
+        Ok(inputs)
+    }
+
+    /// The most verbose level of diagnostics to emit, per `--quiet` and
+    /// `--verbose`.
+    fn log_level(&self) -> LevelFilter {
+        match (self.quiet, self.verbose) {
+            (true, _) => LevelFilter::ERROR,
+            (false, 0) => LevelFilter::WARN,
+            (false, 1) => LevelFilter::DEBUG,
+            (false, _) => LevelFilter::TRACE,
+        }
+    }
+}
+
+#[derive(Debug, Default, Copy, Clone, ValueEnum)]
+pub(crate) enum OutputFormat {
+    #[default]
+    Plain,
+    /// One line per finding, as `path:line:col: rule [severity]: message`.
+    Concise,
+    Json,
+    /// Newline-delimited JSON, with one finding per line.
+    Jsonl,
+    Sarif,
+    /// GitHub Actions workflow commands, rendered as annotations when
+    /// run within a workflow.
+    Github,
+    /// One line per input with findings, as `path: N findings (...)`.
+    Summary,
+}
+
+fn tip(err: impl AsRef<str>, tip: impl AsRef<str>) -> String {
+    let message = Level::Error
+        .title(err.as_ref())
+        .footer(Level::Note.title(tip.as_ref()));
+
+    let renderer = Renderer::styled();
+    format!("{}", renderer.render(message))
+}
+
+#[instrument(skip_all)]
+fn collect_inputs(inputs: &[String], state: &AuditState) -> Result<WorkflowRegistry> {
+    let mut workflow_registry = WorkflowRegistry::new(state.config.exclusions().clone());
+
+    for input in inputs {
+        let input_path = Utf8Path::new(input);
+        if input == "-" {
+            workflow_registry.register_stdin()?;
+        } else if input_path.is_file() {
+            workflow_registry
+                .register_by_path(input_path)
+                .with_context(|| format!("failed to register workflow: {input_path}"))?;
+        } else if input_path.is_dir() {
+            let absolute = input_path.canonicalize_utf8()?;
+            let workflows_dir = if absolute.ends_with(".github/workflows") {
+                absolute.clone()
+            } else {
+                absolute.join(".github/workflows")
+            };
+
+            if workflows_dir.is_dir() {
+                let mut entries = workflows_dir
+                    .read_dir_utf8()?
+                    .collect::<Result<Vec<_>, _>>()?;
+                entries.sort_by(|a, b| a.file_name().cmp(b.file_name()));
+
+                for entry in entries {
+                    let workflow_path = entry.path();
+                    match workflow_path.extension() {
+                        Some(ext) if ext == "yml" || ext == "yaml" => {
+                            register_discovered(&mut workflow_registry, workflow_path);
+                        }
+                        _ => continue,
+                    }
+                }
+            }
+
+            // Composite actions can live anywhere within a repository,
+            // so we only look for them when given something other than
+            // the workflows directory itself.
+            if workflows_dir != absolute {
+                collect_actions(&absolute, &mut workflow_registry)?;
+            }
+        } else {
+            // If this input isn't a file or directory, it's probably an
+            // `owner/repo(@ref)?` slug.
+
+            // Our pre-existing `uses: <slug>` parser does 90% of the work for us.
+            let Some(Uses::Repository(slug)) = Uses::from_step(input) else {
+                return Err(anyhow!(tip(
+                    format!("invalid input: {input}"),
+                    format!(
+                        "pass a single {file}, {directory}, or entire repo by {slug} slug",
+                        file = "file".green(),
+                        directory = "directory".green(),
+                        slug = "owner/repo".green()
+                    )
+                )));
+            };
+
+            // We don't expect subpaths here.
+            if slug.subpath.is_some() {
+                return Err(anyhow!(tip(
+                    "invalid GitHub repository reference",
+                    "pass owner/repo or owner/repo@ref"
+                )));
+            }
+
+            let client = state.github_client().ok_or_else(|| {
+                anyhow!(tip(
+                    format!("can't retrieve repository: {input}", input = input.green()),
+                    format!(
+                        "try removing {offline} or passing {gh_token}",
+                        offline = "--offline".yellow(),
+                        gh_token = "--gh-token <TOKEN>".yellow(),
+                    )
+                ))
+            })?;
+
+            for workflow in client.fetch_workflows(&slug)? {
+                workflow_registry.register(workflow)?;
+            }
+        }
+    }
+
+    collect_local_actions(&mut workflow_registry);
+
+    if workflow_registry.excluded > 0 {
+        tracing::info!(
+            "skipped {n} excluded file{s}",
+            n = workflow_registry.excluded,
+            s = if workflow_registry.excluded == 1 {
+                ""
+            } else {
+                "s"
+            }
+        );
+    }
+
+    // Having excluded everything isn't an error: there's just nothing to audit.
+    if workflow_registry.len() == 0 && workflow_registry.excluded == 0 {
+        return Err(anyhow!("no workflow or action files collected"));
+    }
+
+    Ok(workflow_registry)
+}
+
+/// Registers every local action (`uses: ./path`) referenced by the
+/// collected workflows and actions, so that they're audited too.
+///
+/// Local action paths are relative to the workspace root (see
+/// [`registry::WorkflowKey::workspace_root`]). This repeats until no new
+/// actions are found, since local composite actions can themselves use
+/// other local actions.
+fn collect_local_actions(registry: &mut WorkflowRegistry) {
+    let mut attempted = HashSet::new();
+
+    loop {
+        let mut dirs = vec![];
+        for (key, workflow) in registry.iter_workflows() {
+            for job in workflow.jobs() {
+                if let Job::NormalJob(_) = *job {
+                    dirs.extend(
+                        job.steps()
+                            .filter_map(|step| local_action_dir(key, step.uses())),
+                    );
+                }
+            }
+        }
+        for (key, action) in registry.iter_actions() {
+            dirs.extend(
+                action
+                    .steps()
+                    .filter_map(|step| local_action_dir(key, step.uses())),
+            );
+        }
+
+        let discovered = dirs
+            .into_iter()
+            .filter_map(|dir| {
+                let path = ["action.yml", "action.yaml"]
+                    .into_iter()
+                    .map(|name| dir.join(name))
+                    .find(|path| path.is_file());
+                if path.is_none() {
+                    tracing::debug!("no local action found in {dir}");
+                }
+                path?.canonicalize_utf8().ok()
+            })
+            .filter(|path| attempted.insert(path.clone()))
+            .collect::<Vec<_>>();
+
+        if discovered.is_empty() {
+            break;
+        }
+
+        for path in discovered {
+            // Actions within a directory input are already registered.
+            let registered = registry::WorkflowKey::local(path.clone())
+                .is_ok_and(|key| registry.actions.contains_key(&key));
+            if !registered {
+                register_discovered(registry, &path);
+            }
+        }
+    }
+}
+
+/// Returns the directory of the local action named by `uses`, if any.
+fn local_action_dir(key: &registry::WorkflowKey, uses: Option<Uses>) -> Option<Utf8PathBuf> {
+    let Some(Uses::Local(local)) = uses else {
+        return None;
+    };
+
+    // Paths that escape the workspace are flagged by `local-action-path`,
+    // not followed.
+    Some(key.workspace_root()?.join(local.normalized()?))
+}
+
+/// Recursively discovers and registers every action definition
+/// (`action.yml` or `action.yaml`) beneath the given directory.
+///
+/// Hidden directories other than `.github` are skipped, as are
+/// `node_modules` directories.
+fn collect_actions(dir: &Utf8Path, registry: &mut WorkflowRegistry) -> Result<()> {
+    let mut entries = dir.read_dir_utf8()?.collect::<Result<Vec<_>, _>>()?;
+    // Directory iteration order is platform-dependent, so sort
+    // to keep our output deterministic.
+    entries.sort_by(|a, b| a.file_name().cmp(b.file_name()));
+
+    for entry in entries {
+        let path = entry.path();
+        let name = entry.file_name();
+
+        if entry.file_type()?.is_dir() {
+            if (name.starts_with('.') && name != ".github") || name == "node_modules" {
+                continue;
+            }
+
+            collect_actions(path, registry)?;
+        } else if Action::is_action_path(path) {
+            register_discovered(registry, path);
+        }
+    }
+
+    Ok(())
+}
+
+/// Registers a workflow or action discovered within a directory input.
+///
+/// Unlike inputs that are passed explicitly, a discovered input that
+/// fails to load is skipped with a warning rather than failing the run,
+/// so that one broken file doesn't prevent auditing the rest.
+fn register_discovered(registry: &mut WorkflowRegistry, path: &Utf8Path) {
+    if let Err(e) = registry.register_by_path(path) {
+        tracing::warn!("skipping {path}: {e:#}");
+    }
+}
+
+/// The results of running a single audit over a single workflow.
+struct AuditRun<'w> {
+    name: &'static str,
+    findings: Vec<Finding<'w>>,
+    elapsed: Duration,
+    api_calls: usize,
+}
+
+/// Runs every registered audit over the given input.
+fn audit_input<'w>(audit_registry: &AuditRegistry, input: Input<'w>) -> Result<Vec<AuditRun<'w>>> {
+    let mut runs = Vec::with_capacity(audit_registry.len());
+    for (name, audit) in audit_registry.iter_workflow_audits() {
+        let started = Instant::now();
+        let api_calls = github_api::thread_api_calls();
+
+        let findings = input
+            .audit(audit.as_ref())
+            .with_context(|| format!("{name} failed on {input}", input = input.key().filename()))?;
+
+        runs.push(AuditRun {
+            name,
+            findings,
+            elapsed: started.elapsed(),
+            api_calls: github_api::thread_api_calls() - api_calls,
+        });
+    }
+
+    Ok(runs)
+}
+
+/// Warms the GitHub API client's caches with every repository `uses:`
+/// across the collected workflows and actions, so that online audits don't have
+/// to resolve them one-by-one.
+///
+/// Each repository's advisories are also prefetched if `advisories` is set,
+/// i.e. when `known-vulnerable-actions` will query GitHub's advisories API.
+#[instrument(skip_all)]
+fn prefetch(registry: &WorkflowRegistry, client: &Client, advisories: bool) {
+    let mut uses = vec![];
+    for (_, workflow) in registry.iter_workflows() {
+        for job in workflow.jobs() {
+            match *job {
+                Job::NormalJob(_) => {
+                    uses.extend(job.steps().filter_map(|step| match step.uses() {
+                        Some(Uses::Repository(uses)) => Some(uses),
+                        _ => None,
+                    }))
+                }
+                Job::ReusableWorkflowCallJob(reusable) => {
+                    uses.extend(Uses::from_reusable(&reusable.uses))
+                }
+            }
+        }
+    }
+
+    for (_, action) in registry.iter_actions() {
+        uses.extend(action.steps().filter_map(|step| match step.uses() {
+            Some(Uses::Repository(uses)) => Some(uses),
+            _ => None,
+        }));
+    }
+
+    client.prefetch(uses, advisories);
+}
+
+fn run() -> Result<ExitCode> {
+    human_panic::setup_panic!();
+
+    let mut app = App::parse();
+
+    // `--pedantic` is a shortcut for `--persona=pedantic`.
+    if app.pedantic {
+        app.persona = Some(Persona::Pedantic);
+    }
+
+    // Progress bars are drawn for `info` spans, independently of the level
+    // that's logged, so that they're shown by default.
+    let indicatif_layer = (!app.quiet && !app.no_progress).then(IndicatifLayer::new);
+    let writer = match &indicatif_layer {
+        Some(layer) => BoxMakeWriter::new(layer.get_stderr_writer()),
+        None => BoxMakeWriter::new(std::io::stderr),
+    };
+
+    let filter = EnvFilter::builder()
+        .with_default_directive(app.log_level().into())
+        .from_env()?;
+
+    tracing_subscriber::registry()
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_ansi(std::io::stderr().is_terminal())
+                .with_writer(writer)
+                .with_filter(filter),
+        )
+        .with(indicatif_layer.map(|layer| layer.with_filter(LevelFilter::INFO)))
+        .init();
+
+    if app.list_audits {
+        render::render_audit_list(&mut anstream::stdout())?;
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    let config = Config::new(&app)?;
+    config.validate(audit::catalog().iter().map(|meta| meta.ident))?;
+
+    let advisory_db = app
+        .advisory_db
+        .as_deref()
+        .map(AdvisoryDb::load)
+        .transpose()?;
+
+    let audit_state = AuditState::new(&app, &config, advisory_db);
+    let exit = run_audits(&app, &config, &audit_state);
+
+    // NOTE: This is reported however the run ended, since running out of
+    // quota is one of the ways that it can fail.
+    if app.show_rate_limit {
+        render::render_rate_limits(
+            &audit_state
+                .github_client()
+                .map(|client| client.rate_limits())
+                .unwrap_or_default(),
+        );
+    }
+
+    exit
+}
+
+/// Collects and audits every input, and renders the results.
+fn run_audits(app: &App, config: &Config, audit_state: &AuditState) -> Result<ExitCode> {
+    let workflow_registry = collect_inputs(&app.all_inputs()?, audit_state)?;
+
+    let audit_registry = AuditRegistry::new(app, audit_state, config)?;
+
+    if !app.offline && !app.no_online_audits {
+        if let Some(client) = audit_state.github_client() {
+            let advisories = audit_state.advisory_db.is_none()
+                && audit_registry
+                    .workflow_audits
+                    .contains_key(KnownVulnerableActions::ident());
+            prefetch(&workflow_registry, &client, advisories);
+        }
+    }
+
+    let mut output = Output::new(app.output.as_deref())?;
+
+    let baseline = app.baseline.as_deref().map(Baseline::load).transpose()?;
+
+    let mut results = FindingRegistry::new(app, config, baseline.as_ref());
+    let mut rule_stats = RuleStats::default();
+    {
+        // Note: block here so that we drop the span here at the right time.
+        let span = info_span!("audit");
+        span.pb_set_length((workflow_registry.len() * audit_registry.len()) as u64);
+        span.pb_set_style(
+            &ProgressStyle::with_template("[{elapsed_precise}] {bar:!30.cyan/blue} {msg}").unwrap(),
+        );
+
+        let _guard = span.enter();
+
+        let jobs = app.jobs.map_or_else(
+            || thread::available_parallelism().map_or(1, NonZeroUsize::get),
+            NonZeroUsize::get,
+        );
+
+        let queue = Mutex::new(
+            workflow_registry
+                .iter_workflows()
+                .map(|(_, w)| Input::Workflow(w))
+                .chain(
+                    workflow_registry
+                        .iter_actions()
+                        .map(|(_, a)| Input::Action(a)),
+                )
+                .enumerate(),
+        );
+        let failed = AtomicBool::new(false);
+        let (tx, rx) = mpsc::channel();
+
+        thread::scope(|scope| -> Result<()> {
+            for _ in 0..jobs.min(workflow_registry.len()) {
+                let (tx, span, queue, failed, audit_registry) =
+                    (tx.clone(), &span, &queue, &failed, &audit_registry);
+
+                scope.spawn(move || {
+                    let _guard = span.enter();
+
+                    while !failed.load(Relaxed) {
+                        // NOTE: Bind the next input first, so that the lock
+                        // is released before we audit it.
+                        let next = queue.lock().unwrap().next();
+                        let Some((idx, input)) = next else {
+                            break;
+                        };
+
+                        let runs = audit_input(audit_registry, input);
+                        if tx.send((idx, input, runs)).is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+            drop(tx);
+
+            // Inputs finish in whatever order the workers get to them,
+            // so we buffer them here to handle them in their original order.
+            // This keeps our output deterministic, regardless of `--jobs`.
+            let mut pending = BTreeMap::new();
+            let mut next = 0;
+            for (idx, input, runs) in rx {
+                pending.insert(idx, (input, runs));
+
+                while let Some((input, runs)) = pending.remove(&next) {
+                    next += 1;
+
+                    let runs = runs.inspect_err(|_| failed.store(true, Relaxed))?;
+                    let mut findings = vec![];
+                    for run in runs {
+                        findings.extend(run.findings);
+
+                        if app.rule_stats {
+                            rule_stats.record(run.name, run.elapsed, run.api_calls);
+                        }
+                    }
+
+                    // Sort each input's findings before recording them, so that
+                    // every output format (including streamed JSONL) presents
+                    // them in the same canonical order.
+                    finding::sort(&mut findings);
+
+                    let already_seen = results.findings().len();
+                    results.extend(findings);
+
+                    // JSONL is our only streaming format: emit each new finding
+                    // as soon as it survives filtering, rather than at the end.
+                    if matches!(app.format, OutputFormat::Jsonl) {
+                        for finding in &results.findings()[already_seen..] {
+                            serde_json::to_writer(&mut output, finding)?;
+                            writeln!(output)?;
+                        }
+                    }
+
+                    // Only online audits make API calls, so we only mention
+                    // them once there've been some.
+                    let api_calls = match github_api::total_api_calls() {
+                        0 => String::new(),
+                        calls => format!(", {calls} API calls"),
+                    };
+                    span.pb_set_message(&format!(
+                        "{next}/{total} inputs{api_calls}: {filename}",
+                        total = workflow_registry.len(),
+                        filename = input.key().filename()
+                    ));
+                    span.pb_inc(audit_registry.len() as u64);
+                    tracing::info!("🌈 completed {input}", input = input.key().path());
+                }
+            }
+
+            Ok(())
+        })?;
+    }
+
+    match app.format {
+        OutputFormat::Plain => {
+            render::render_findings(&workflow_registry, &results, &mut output)?;
+            if !app.no_summary {
+                render::render_summary(&results, &mut output)?;
+            }
+        }
+        OutputFormat::Concise => {
+            render::render_concise(&results, &mut output)?;
+            if !app.no_summary {
+                render::render_summary(&results, &mut output)?;
+            }
+        }
+        OutputFormat::Github => render::render_github(&results, &mut output)?,
+        OutputFormat::Summary => render::render_file_summary(&results, &mut output)?,
+        // The stats are part of the report, so that it stays a single
+        // JSON document.
+        OutputFormat::Json if app.rule_stats => serde_json::to_writer_pretty(
+            &mut output,
+            &serde_json::json!({ "findings": results.findings(), "rule_stats": rule_stats }),
+        )?,
+        OutputFormat::Json => serde_json::to_writer_pretty(&mut output, &results.findings())?,
+        // Already emitted above, as findings were produced.
+        OutputFormat::Jsonl => (),
+        OutputFormat::Sarif => serde_json::to_writer_pretty(
+            &mut output,
+            &sarif::build(&workflow_registry, results.findings()),
+        )?,
+    };
+
+    output.finish()?;
+
+    if let Some(path) = &app.write_baseline {
+        Baseline::write(path, results.findings())?;
+    }
+
+    if app.fix {
+        let fixed = fix::apply(
+            results.findings(),
+            app.fix_confidence,
+            &mut anstream::stderr(),
+        )?;
+        if !app.quiet {
+            eprintln!(
+                "{fixed} of {total} findings fixed",
+                total = results.findings().len()
+            );
+        }
+    }
+
+    if app.rule_stats {
+        match app.format {
+            OutputFormat::Plain | OutputFormat::Concise | OutputFormat::Summary => {
+                render::render_rule_stats(&rule_stats)
+            }
+            // Already included in the report.
+            OutputFormat::Json => (),
+            _ => {
+                serde_json::to_writer(
+                    std::io::stderr(),
+                    &serde_json::json!({ "rule_stats": rule_stats }),
+                )?;
+                eprintln!();
+            }
+        }
+    }
+
+    if !app.error_on_findings || app.no_exit_codes || matches!(app.format, OutputFormat::Sarif) {
+        Ok(ExitCode::SUCCESS)
+    } else {
+        Ok(results.into())
+    }
+}
+
+/// Runs zizmor's CLI with the process's arguments, returning its exit code.
+pub fn main() -> ExitCode {
+    // This is a little silly, but returning an ExitCode like this ensures
+    // we always exit cleanly, rather than performing a hard process exit.
+    match run() {
+        Ok(exit) => exit,
+        Err(err) => {
+            eprintln!("{err:?}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -1,8 +1,22 @@
 use anyhow::{anyhow, Context as _, Result};
-use serde::{de, Deserialize};
-use std::{collections::HashMap, fs, num::NonZeroUsize, str::FromStr};
+use camino::{Utf8Path, Utf8PathBuf};
+use serde::{
+    de::{self, DeserializeOwned},
+    Deserialize,
+};
+use std::{collections::HashMap, env, fs, num::NonZeroUsize, str::FromStr};
 
-use crate::{finding::Finding, App};
+use crate::{
+    allowlist::ActionAllowlist,
+    cli::App,
+    contexts::ContextPolicy,
+    exclusions::{Exclusions, IGNORE_FILE},
+    finding::{Confidence, Finding, Persona, Severity},
+};
+
+/// Where a configuration file is discovered, relative to the root of the
+/// repository being audited, in order of precedence.
+const DISCOVERED_PATHS: &[&str] = &[".github/zizmor.yml", "zizmor.yml", ".zizmor.yml"];
 
 #[derive(Clone, Debug, PartialEq)]
 pub(crate) struct WorkflowRule {
@@ -61,15 +75,54 @@ impl<'de> Deserialize<'de> for WorkflowRule {
     }
 }
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
 pub(crate) struct AuditRuleConfig {
+    /// Whether this audit is disabled entirely.
+    disable: bool,
+    /// Findings from this audit below this severity are ignored.
+    min_severity: Option<Severity>,
     ignore: Vec<WorkflowRule>,
+    /// Audit-specific settings, interpreted by the audit itself.
+    config: Option<serde_yaml::Value>,
 }
 
 /// Runtime configuration, corresponding to a `zizmor.yml` file.
 #[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub(crate) struct Config {
+    #[serde(default)]
     rules: HashMap<String, AuditRuleConfig>,
+    /// Audits that are disabled entirely, as a shorthand for
+    /// `disable: true` under each audit's `rules:` entry.
+    #[serde(default, rename = "disabled-audits", alias = "disabled_audits")]
+    disabled_audits: Vec<String>,
+    /// How contexts are classified by the injection audits.
+    #[serde(default)]
+    contexts: ContextPolicy,
+    /// Publishers whose actions and images are trusted by audits that
+    /// exempt first-party `uses:`.
+    #[serde(default, rename = "trusted-actions")]
+    trusted_actions: ActionAllowlist,
+    /// The persona to audit with, unless overridden by `--persona`.
+    #[serde(default)]
+    persona: Option<Persona>,
+    /// Findings below this severity are ignored, unless overridden by
+    /// `--min-severity`.
+    #[serde(default, rename = "min-severity")]
+    min_severity: Option<Severity>,
+    /// Findings below this confidence are ignored, unless overridden by
+    /// `--min-confidence`.
+    #[serde(default, rename = "min-confidence")]
+    min_confidence: Option<Confidence>,
+    /// `.gitignore`-style patterns for paths that aren't audited, relative
+    /// to the root of the repository being audited.
+    #[serde(default)]
+    exclude: Vec<String>,
+    /// The compiled `exclude` patterns, along with any from the repository's
+    /// `.zizmorignore`.
+    #[serde(skip)]
+    exclusions: Exclusions,
 }
 
 impl Config {
@@ -78,43 +131,168 @@ impl Config {
             return Ok(Self::default());
         }
 
-        let config = match &app.config {
-            Some(path) => serde_yaml::from_str(&fs::read_to_string(path)?)?,
+        let mut config = match Self::discover(app)? {
+            Some(path) => {
+                let config = Self::load(&path)?;
+                tracing::debug!("loaded config from {path}: {config:?}");
+                config
+            }
             None => {
-                // If the user didn't pass a config path explicitly with
-                // `--config`, then we attempt to discover one relative to $CWD
-                // Our procedure is to first look for `$CWD/.github/zizmor.yml`,
-                // then `$CWD/zizmor.yml`, and then bail.
-                let cwd = std::env::current_dir()
-                    .with_context(|| "config discovery couldn't access CWD")?;
-
-                let path = cwd.join(".github").join("zizmor.yml");
-                if path.is_file() {
-                    serde_yaml::from_str(&fs::read_to_string(path)?)?
-                } else {
-                    let path = cwd.join("zizmor.yml");
-                    if path.is_file() {
-                        serde_yaml::from_str(&fs::read_to_string(path)?)?
-                    } else {
-                        tracing::debug!("no config discovered; loading default");
-                        Config::default()
-                    }
-                }
+                tracing::debug!("no config discovered; loading default");
+                Self::default()
             }
         };
 
-        tracing::debug!("loaded config: {config:?}");
+        // NOTE: A repository's `.zizmorignore` applies whichever config is
+        // loaded (if any), and its patterns come last so that they can
+        // re-include paths that the config excludes.
+        let root = repo_root(&app.inputs)?;
+        let ignore_file = root.join(IGNORE_FILE);
+        let ignored = match ignore_file.is_file() {
+            true => fs::read_to_string(&ignore_file)
+                .with_context(|| format!("couldn't read ignore patterns from {ignore_file}"))?,
+            false => String::new(),
+        };
+        config.exclusions = Exclusions::new(
+            &root,
+            config
+                .exclude
+                .iter()
+                .map(String::as_str)
+                .chain(ignored.lines()),
+        );
 
         Ok(config)
     }
 
+    /// Returns the path of the configuration file to load, if any.
+    ///
+    /// In order of precedence, this is the one passed with `--config`,
+    /// then one in the root of the repository being audited (see
+    /// [`DISCOVERED_PATHS`]), then the user's own
+    /// `$XDG_CONFIG_HOME/zizmor/zizmor.yml` (or
+    /// `~/.config/zizmor/zizmor.yml`). Only one is ever loaded.
+    fn discover(app: &App) -> Result<Option<Utf8PathBuf>> {
+        if let Some(path) = &app.config {
+            return Ok(Some(path.clone()));
+        }
+
+        let root = repo_root(&app.inputs)?;
+        if let Some(path) = DISCOVERED_PATHS
+            .iter()
+            .map(|path| root.join(path))
+            .find(|path| path.is_file())
+        {
+            return Ok(Some(path));
+        }
+
+        Ok(user_config_dir()
+            .map(|dir| dir.join("zizmor").join("zizmor.yml"))
+            .filter(|path| path.is_file()))
+    }
+
+    /// Loads the configuration file at the given path.
+    ///
+    /// Unknown settings are rejected rather than ignored, so that a typo'd
+    /// key doesn't silently do nothing.
+    fn load(path: &Utf8Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("couldn't read configuration from {path}"))?;
+
+        serde_yaml::from_str(&contents).with_context(|| format!("invalid configuration in {path}"))
+    }
+
+    /// Returns the configured persona, if any.
+    pub(crate) fn persona(&self) -> Option<Persona> {
+        self.persona
+    }
+
+    /// Returns the configured minimum severity, if any.
+    pub(crate) fn min_severity(&self) -> Option<Severity> {
+        self.min_severity
+    }
+
+    /// Returns the configured minimum confidence, if any.
+    pub(crate) fn min_confidence(&self) -> Option<Confidence> {
+        self.min_confidence
+    }
+
+    /// Returns the paths that are excluded from auditing.
+    pub(crate) fn exclusions(&self) -> &Exclusions {
+        &self.exclusions
+    }
+
+    /// Returns the policy for classifying contexts as trusted or
+    /// attacker-controllable.
+    pub(crate) fn contexts(&self) -> &ContextPolicy {
+        &self.contexts
+    }
+
+    /// Returns the allowlist of trusted action publishers.
+    pub(crate) fn trusted_actions(&self) -> &ActionAllowlist {
+        &self.trusted_actions
+    }
+
+    /// Returns the audit-specific settings for the given audit, or
+    /// the audit's defaults if none are configured.
+    pub(crate) fn audit_config<T: DeserializeOwned + Default>(&self, ident: &str) -> Result<T> {
+        match self.rules.get(ident).and_then(|rule| rule.config.as_ref()) {
+            Some(config) => serde_yaml::from_value(config.clone())
+                .with_context(|| format!("invalid configuration for {ident}")),
+            None => Ok(T::default()),
+        }
+    }
+
+    /// Checks that every audit configured under `rules:` (or listed under
+    /// `disabled-audits:`) is one of the given (known) audits, so that
+    /// typos don't go unnoticed.
+    pub(crate) fn validate<'a>(&self, known: impl IntoIterator<Item = &'a str>) -> Result<()> {
+        let known = known.into_iter().collect::<Vec<_>>();
+
+        let mut unknown = self
+            .rules
+            .keys()
+            .chain(&self.disabled_audits)
+            .filter(|ident| !known.contains(&ident.as_str()))
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+        unknown.sort();
+        unknown.dedup();
+
+        if unknown.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow!(
+                "unknown audit{s} in configuration: {unknown} (see --list-audits)",
+                s = if unknown.len() == 1 { "" } else { "s" },
+                unknown = unknown.join(", ")
+            ))
+        }
+    }
+
+    /// Returns `true` if this [`Config`] disables the given audit.
+    pub(crate) fn disables(&self, ident: &str) -> bool {
+        self.disabled_audits
+            .iter()
+            .any(|disabled| disabled == ident)
+            || self.rules.get(ident).is_some_and(|rule| rule.disable)
+    }
+
     /// Returns `true` if this [`Config`] has an ignore rule for the
-    /// given finding.
+    /// given finding, or if the finding falls below the audit's
+    /// configured severity floor.
     pub(crate) fn ignores(&self, finding: &Finding<'_>) -> bool {
         let Some(rule_config) = self.rules.get(finding.ident) else {
             return false;
         };
 
+        if rule_config
+            .min_severity
+            .is_some_and(|min| min > finding.determinations.severity)
+        {
+            return true;
+        }
+
         let ignores = &rule_config.ignore;
 
         // If *any* location in the finding matches an ignore rule,
@@ -134,10 +312,8 @@ impl Config {
                         column,
                         ..
                     } => {
-                        if *line == loc.concrete.location.start_point.row + 1
-                            && column.map_or(true, |col| {
-                                col == loc.concrete.location.start_point.column + 1
-                            })
+                        if *line == loc.span.start_line
+                            && column.map_or(true, |col| col == loc.span.start_col)
                         {
                             return true;
                         } else {
@@ -159,13 +335,63 @@ impl Config {
     }
 }
 
+/// Returns the root of the repository being audited, for config discovery
+/// and exclusions.
+///
+/// This is the directory containing the first local input's `.github/`
+/// directory, or the first local input that's a directory, falling back
+/// to $CWD (e.g. for remote repositories or stdin).
+fn repo_root(inputs: &[String]) -> Result<Utf8PathBuf> {
+    for input in inputs {
+        let path = Utf8Path::new(input);
+        if !path.exists() {
+            continue;
+        }
+
+        if let Some(github) = path
+            .ancestors()
+            .find(|ancestor| ancestor.file_name() == Some(".github"))
+        {
+            return Ok(github.parent().unwrap_or(Utf8Path::new("")).to_path_buf());
+        }
+
+        if path.is_dir() {
+            return Ok(path.to_path_buf());
+        }
+    }
+
+    let cwd = env::current_dir().with_context(|| "config discovery couldn't access CWD")?;
+    Utf8PathBuf::from_path_buf(cwd)
+        .map_err(|cwd| anyhow!("config discovery can't use non-UTF-8 CWD: {cwd:?}"))
+}
+
+/// Returns the user's configuration directory, i.e. `$XDG_CONFIG_HOME`
+/// or `~/.config`, if one can be found.
+fn user_config_dir() -> Option<Utf8PathBuf> {
+    let config_home = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(Into::into)
+        .or_else(|| env::var_os("HOME").map(|home| std::path::Path::new(&home).join(".config")))?;
+
+    match Utf8PathBuf::from_path_buf(config_home) {
+        Ok(config_home) => Some(config_home),
+        Err(config_home) => {
+            tracing::debug!("not using non-UTF-8 config directory: {config_home:?}");
+            None
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::str::FromStr;
 
     use anyhow::Result;
 
-    use super::WorkflowRule;
+    use camino::Utf8Path;
+
+    use super::{repo_root, Config, WorkflowRule};
+    use crate::finding::{Confidence, Persona, Severity};
 
     #[test]
     fn test_parse_workflow_rule() -> Result<()> {
@@ -203,4 +429,146 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_parse_config() -> Result<()> {
+        let config: Config = serde_yaml::from_str(
+            r#"
+rules:
+  known-vulnerable-actions:
+    disable: true
+  template-injection:
+    min-severity: high
+  unpinned-uses:
+    ignore:
+      - ci.yml:10
+"#,
+        )?;
+
+        assert!(config.disables("known-vulnerable-actions"));
+        assert!(!config.disables("template-injection"));
+        assert!(!config.disables("artipacked"));
+
+        let rule = &config.rules["template-injection"];
+        assert_eq!(rule.min_severity, Some(Severity::High));
+        assert!(rule.ignore.is_empty());
+
+        assert_eq!(config.rules["unpinned-uses"].ignore.len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_disabled_audits() -> Result<()> {
+        for disabled in ["disabled-audits", "disabled_audits"] {
+            let config: Config = serde_yaml::from_str(&format!(
+                "{disabled}: [known-vulnerable-actions, artipacked]"
+            ))?;
+
+            assert!(config.disables("known-vulnerable-actions"));
+            assert!(config.disables("artipacked"));
+            assert!(!config.disables("template-injection"));
+        }
+
+        let config: Config = serde_yaml::from_str("disabled-audits: [artipaked]")?;
+        assert_eq!(
+            config.validate(["artipacked"]).unwrap_err().to_string(),
+            "unknown audit in configuration: artipaked (see --list-audits)"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_config() -> Result<()> {
+        let config: Config = serde_yaml::from_str(
+            r#"
+rules:
+  template-injection:
+    disable: true
+  unpinned-usess:
+    disable: true
+  artipaked:
+    disable: true
+"#,
+        )?;
+
+        assert!(config
+            .validate(["template-injection", "unpinned-usess", "artipaked"])
+            .is_ok());
+        assert_eq!(
+            config
+                .validate(["template-injection", "unpinned-uses", "artipacked"])
+                .unwrap_err()
+                .to_string(),
+            "unknown audits in configuration: artipaked, unpinned-usess (see --list-audits)"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_top_level_settings() -> Result<()> {
+        let config: Config = serde_yaml::from_str(
+            r#"
+persona: pedantic
+min-severity: medium
+min-confidence: high
+"#,
+        )?;
+
+        assert_eq!(config.persona(), Some(Persona::Pedantic));
+        assert_eq!(config.min_severity(), Some(Severity::Medium));
+        assert_eq!(config.min_confidence(), Some(Confidence::High));
+
+        let config = Config::default();
+        assert_eq!(config.persona(), None);
+        assert_eq!(config.min_severity(), None);
+
+        // Unknown settings are rejected, at every level.
+        for invalid in [
+            "min-sevrity: high",
+            "rules: { artipacked: { disabled: true } }",
+            "contexts: { untrusted-contexts: [] }",
+        ] {
+            assert!(
+                serde_yaml::from_str::<Config>(invalid).is_err(),
+                "{invalid}"
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_repo_root() -> Result<()> {
+        let local_actions = "tests/test-data/local-actions";
+
+        // A file or directory beneath `.github/` is rooted above it.
+        for input in [
+            "tests/test-data/local-actions/.github/workflows/ci.yml",
+            "tests/test-data/local-actions/.github/workflows",
+        ] {
+            assert_eq!(repo_root(&[input.into()])?, Utf8Path::new(local_actions));
+        }
+
+        // Other directories are roots themselves.
+        assert_eq!(
+            repo_root(&[local_actions.into()])?,
+            Utf8Path::new(local_actions)
+        );
+
+        // Anything else falls back to $CWD.
+        let cwd = std::env::current_dir()?;
+        for inputs in [
+            vec!["tests/test-data/artipacked.yml".into()],
+            vec!["pypa/sampleproject".into()],
+            vec!["-".into()],
+            vec![],
+        ] {
+            assert_eq!(repo_root(&inputs)?, cwd);
+        }
+
+        Ok(())
+    }
 }
@@ -0,0 +1,277 @@
+//! Classification of `${{ ... }}` contexts as trusted or
+//! attacker-controllable, shared by the injection audits.
+//!
+//! Contexts are classified against patterns of dot-separated segments,
+//! compared case-insensitively (like GitHub does). Within a pattern, `*`
+//! matches any single segment, and a trailing `*` matches the context
+//! itself and anything beneath it: `github.event.*` matches
+//! `github.event`, `github.event.issue.title`, and so on.
+
+use serde::Deserialize;
+
+use crate::{expr::Expr, utils::extract_expressions};
+
+/// Contexts that are believed to be always safe, even under an untrusted
+/// pattern.
+const TRUSTED_CONTEXTS: &[&str] = &[
+    // The GitHub event name (i.e. trigger) is itself safe.
+    "github.event_name",
+    // Safe keys within the otherwise generally unsafe github.event context.
+    "github.event.issue.number",
+    "github.event.merge_group.base_sha",
+    "github.event.number",
+    "github.event.pull_request.commits", // number of commits in PR
+    "github.event.pull_request.number",  // the PR's own number
+    "github.event.workflow_run.id",
+    // Information about the GitHub repository
+    "github.repository",
+    "github.repository_id",
+    "github.repositoryUrl",
+    // Information about the GitHub repository owner (account/org or ID)
+    "github.repository_owner",
+    "github.repository_owner_id",
+    // Unique numbers assigned by GitHub for workflow runs
+    "github.run_attempt",
+    "github.run_id",
+    "github.run_number",
+    // Always a 40-char SHA-1 reference.
+    "github.sha",
+    // Like `secrets.*`: not safe to expose, but safe to interpolate.
+    "github.token",
+    // GitHub Actions-controlled local directory.
+    "github.workspace",
+    // GitHub Actions-controller runner architecture.
+    "runner.arch",
+    // Debug logging is (1) or is not (0) enabled on GitHub Actions runner.
+    "runner.debug",
+    // GitHub Actions runner operating system.
+    "runner.os",
+    // GitHub Actions temporary directory, value controlled by the runner itself.
+    "runner.temp",
+    // GitHub Actions cached tool directory, value controlled by the runner itself.
+    "runner.tool_cache",
+];
+
+/// Context patterns that are attacker-controllable, unless trusted.
+const UNTRUSTED_CONTEXTS: &[&str] = &[
+    // The triggering event's payload: titles, bodies, branch names,
+    // commit messages, and so on.
+    "github.event.*",
+    // The source branch of a pull request.
+    "github.head_ref",
+    // The branch or tag name that triggered the run.
+    "github.ref_name",
+];
+
+/// Returns whether the given context matches the given pattern.
+fn matches(pattern: &str, context: &str) -> bool {
+    let mut pattern = pattern.split('.').peekable();
+    let mut context = context.split('.');
+
+    while let Some(expected) = pattern.next() {
+        // A trailing `*` matches the rest of the context, if any.
+        if expected == "*" && pattern.peek().is_none() {
+            return true;
+        }
+
+        match context.next() {
+            Some(segment) if expected == "*" || segment.eq_ignore_ascii_case(expected) => (),
+            _ => return false,
+        }
+    }
+
+    context.next().is_none()
+}
+
+/// Returns whether the given context is (or is within) the `secrets`
+/// context.
+pub(crate) fn is_secret_context(context: &str) -> bool {
+    context
+        .split('.')
+        .next()
+        .is_some_and(|head| head.eq_ignore_ascii_case("secrets"))
+}
+
+/// Settings for the classification of contexts, under `contexts`.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub(crate) struct ContextPolicy {
+    /// Additional attacker-controllable context patterns.
+    untrusted: Vec<String>,
+    /// Additional trusted context patterns, which take precedence over
+    /// untrusted ones.
+    trusted: Vec<String>,
+    /// Whether to replace the default patterns, rather than extend them.
+    replace_defaults: bool,
+}
+
+impl ContextPolicy {
+    fn patterns<'a>(
+        &'a self,
+        defaults: &'static [&'static str],
+        extra: &'a [String],
+    ) -> impl Iterator<Item = &'a str> {
+        let defaults = if self.replace_defaults {
+            &[][..]
+        } else {
+            defaults
+        };
+
+        defaults
+            .iter()
+            .copied()
+            .chain(extra.iter().map(String::as_str))
+    }
+
+    /// Returns whether the given context is known to be safe.
+    pub(crate) fn is_trusted_context(&self, context: &str) -> bool {
+        self.patterns(TRUSTED_CONTEXTS, &self.trusted)
+            .any(|pattern| matches(pattern, context))
+    }
+
+    /// Returns whether the given context is attacker-controllable.
+    pub(crate) fn is_untrusted_context(&self, context: &str) -> bool {
+        !self.is_trusted_context(context)
+            && self
+                .patterns(UNTRUSTED_CONTEXTS, &self.untrusted)
+                .any(|pattern| matches(pattern, context))
+    }
+
+    /// Returns the attacker-controllable contexts expanded by the
+    /// `${{ ... }}` expressions in the given text.
+    pub(crate) fn untrusted_contexts(&self, text: &str) -> Vec<String> {
+        extract_expressions(text)
+            .iter()
+            .filter_map(|expr| Expr::parse(expr.as_bare()).ok())
+            .flat_map(|expr| {
+                expr.contexts()
+                    .into_iter()
+                    .filter(|context| self.is_untrusted_context(context))
+                    .map(String::from)
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_secret_context, matches, ContextPolicy};
+
+    #[test]
+    fn test_matches() {
+        for (pattern, context, expected) in [
+            ("github.head_ref", "github.head_ref", true),
+            ("github.head_ref", "GITHUB.HEAD_REF", true),
+            ("github.head_ref", "github.head_ref_name", false),
+            ("github.head_ref", "github", false),
+            ("github.event.*", "github.event", true),
+            ("github.event.*", "github.event.issue.title", true),
+            ("github.event.*", "github.event_name", false),
+            ("github.event.*.title", "github.event.issue.title", true),
+            (
+                "github.event.*.title",
+                "github.event.pull_request.title",
+                true,
+            ),
+            (
+                "github.event.*.title",
+                "github.event.pull_request.head.title",
+                false,
+            ),
+            ("github.event.*.title", "github.event.issue", false),
+        ] {
+            assert_eq!(
+                matches(pattern, context),
+                expected,
+                "{pattern} vs. {context}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_is_untrusted_context() {
+        let policy = ContextPolicy::default();
+        for (context, expected) in [
+            ("github.event.issue.title", true),
+            ("github.event.pull_request.head.ref", true),
+            ("github.event.commits.message", true),
+            ("github.head_ref", true),
+            ("github.ref_name", true),
+            // Indexing the whole event still reaches attacker-controlled data.
+            ("github.event", true),
+            ("github.event.number", false),
+            ("github.event.pull_request.number", false),
+            ("github.event_name", false),
+            ("github.sha", false),
+            ("inputs.foo", false),
+            ("secrets.TOKEN", false),
+        ] {
+            assert_eq!(policy.is_untrusted_context(context), expected, "{context}");
+        }
+
+        let policy: ContextPolicy = serde_yaml::from_str(
+            "untrusted: [inputs.*]\ntrusted: [github.event.pull_request.head.sha]",
+        )
+        .unwrap();
+        assert!(policy.is_untrusted_context("inputs.title"));
+        assert!(!policy.is_untrusted_context("github.event.pull_request.head.sha"));
+        assert!(policy.is_untrusted_context("github.event.pull_request.head.ref"));
+
+        let policy: ContextPolicy =
+            serde_yaml::from_str("untrusted: [github.event.*.body]\nreplace-defaults: true")
+                .unwrap();
+        assert!(policy.is_untrusted_context("github.event.issue.body"));
+        assert!(!policy.is_untrusted_context("github.event.issue.title"));
+        assert!(!policy.is_untrusted_context("github.head_ref"));
+    }
+
+    #[test]
+    fn test_untrusted_contexts() {
+        let policy = ContextPolicy::default();
+        for (text, expected) in [
+            (
+                "echo ${{ github.event.pull_request.head.ref }}",
+                &["github.event.pull_request.head.ref"][..],
+            ),
+            (
+                "echo ${{ github.event.pull_request.labels[0] }}",
+                &["github.event.pull_request.labels"],
+            ),
+            (
+                "echo ${{ github.event.issue.labels.*.name }}",
+                &["github.event.issue.labels.*.name"],
+            ),
+            (
+                "echo ${{ github.event['issue']['title'] }}",
+                &["github.event"],
+            ),
+            (
+                "echo ${{ toJSON(github.event.issue.title) }}",
+                &["github.event.issue.title"],
+            ),
+            (
+                "echo ${{ fromJSON(github.event.comment.body)['cmd'] }}",
+                &["github.event.comment.body"],
+            ),
+            (
+                "echo ${{ format('{0}-{1}', github.run_id, github.head_ref) }}",
+                &["github.head_ref"],
+            ),
+            ("echo ${{ toJSON(github.event.number) }}", &[]),
+            ("echo ${{ github.workspace }}/bin", &[]),
+            ("echo $HOME", &[]),
+        ] {
+            assert_eq!(policy.untrusted_contexts(text), expected, "{text}");
+        }
+    }
+
+    #[test]
+    fn test_is_secret_context() {
+        assert!(is_secret_context("secrets.TOKEN"));
+        assert!(is_secret_context("SECRETS.token"));
+        assert!(is_secret_context("secrets"));
+        assert!(!is_secret_context("github.event.secrets"));
+        assert!(!is_secret_context("secretsfoo.bar"));
+    }
+}
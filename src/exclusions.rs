@@ -0,0 +1,192 @@
+//! Paths that are excluded from auditing, via `exclude:` in the
+//! configuration and `.zizmorignore` files.
+
+use camino::{Utf8Path, Utf8PathBuf};
+
+use crate::utils::glob_matches;
+
+/// The name of the ignore file read from the root of the repository
+/// being audited.
+pub(crate) const IGNORE_FILE: &str = ".zizmorignore";
+
+/// A single `.gitignore`-style pattern.
+#[derive(Clone, Debug)]
+struct Pattern {
+    /// The pattern's `/`-separated components, each of which may use
+    /// `*` and `?` wildcards, or be `**` to match any number of
+    /// components.
+    components: Vec<String>,
+    /// Whether the pattern re-includes paths (a leading `!`).
+    negated: bool,
+    /// Whether the pattern only matches directories (a trailing `/`).
+    dir_only: bool,
+    /// Whether the pattern is relative to the root, rather than matching
+    /// a file or directory name at any depth. As in a `.gitignore`, that's
+    /// any pattern with a `/` anywhere but at its end.
+    anchored: bool,
+}
+
+impl Pattern {
+    /// Parses a single line of a `.gitignore`-style file, or returns `None`
+    /// if it's blank or a comment.
+    fn parse(line: &str) -> Option<Self> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+
+        let (negated, pattern) = match line.strip_prefix('!') {
+            Some(pattern) => (true, pattern),
+            // A leading `\` escapes a literal `!` or `#`.
+            None => (false, line.strip_prefix('\\').unwrap_or(line)),
+        };
+        let (dir_only, pattern) = match pattern.strip_suffix('/') {
+            Some(pattern) => (true, pattern),
+            None => (false, pattern),
+        };
+        let anchored = pattern.contains('/');
+
+        let components = pattern
+            .split('/')
+            .filter(|component| !component.is_empty())
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+        if components.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            components,
+            negated,
+            dir_only,
+            anchored,
+        })
+    }
+
+    /// Returns whether this pattern matches the given path, as components
+    /// relative to the root.
+    fn matches(&self, path: &[&str], is_dir: bool) -> bool {
+        if self.dir_only && !is_dir {
+            return false;
+        }
+
+        if self.anchored {
+            Self::matches_components(&self.components, path)
+        } else {
+            path.last()
+                .is_some_and(|name| glob_matches(&self.components[0], name))
+        }
+    }
+
+    fn matches_components(patterns: &[String], path: &[&str]) -> bool {
+        match (patterns.split_first(), path.split_first()) {
+            (None, None) => true,
+            (Some((pattern, rest)), _) if pattern == "**" => {
+                Self::matches_components(rest, path)
+                    || (!path.is_empty() && Self::matches_components(patterns, &path[1..]))
+            }
+            (Some((pattern, rest)), Some((component, remaining))) => {
+                glob_matches(pattern, component) && Self::matches_components(rest, remaining)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// The paths under a repository's root that shouldn't be audited.
+///
+/// Patterns follow `.gitignore` syntax: the last pattern that matches a
+/// path wins, and a path within an excluded directory is excluded no
+/// matter what, since the directory itself is never looked into.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Exclusions {
+    root: Utf8PathBuf,
+    patterns: Vec<Pattern>,
+}
+
+impl Exclusions {
+    /// Parses the given patterns, relative to the given root.
+    pub(crate) fn new<'a>(root: &Utf8Path, patterns: impl IntoIterator<Item = &'a str>) -> Self {
+        Self {
+            root: root
+                .canonicalize_utf8()
+                .unwrap_or_else(|_| root.to_path_buf()),
+            patterns: patterns.into_iter().filter_map(Pattern::parse).collect(),
+        }
+    }
+
+    /// Returns whether the given path is excluded. Paths outside of the
+    /// root are never excluded.
+    pub(crate) fn excludes(&self, path: &Utf8Path) -> bool {
+        if self.patterns.is_empty() {
+            return false;
+        }
+
+        let path = path
+            .canonicalize_utf8()
+            .unwrap_or_else(|_| path.to_path_buf());
+        let Ok(relative) = path.strip_prefix(&self.root) else {
+            return false;
+        };
+        let components = relative.iter().collect::<Vec<_>>();
+
+        // Each enclosing directory is checked first, since nothing within an
+        // excluded directory can be re-included.
+        (1..=components.len()).any(|len| {
+            let is_dir = len < components.len();
+            self.patterns
+                .iter()
+                .rev()
+                .find(|pattern| pattern.matches(&components[..len], is_dir))
+                .is_some_and(|pattern| !pattern.negated)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use camino::Utf8Path;
+
+    use super::Exclusions;
+
+    #[test]
+    fn test_excludes() {
+        let exclusions = Exclusions::new(
+            Utf8Path::new("/nonexistent/repo"),
+            [
+                "# generated by our tooling",
+                "",
+                "generated-*.yml",
+                "!generated-keep.yml",
+                "/.github/workflows/vendored.yml",
+                "third_party/",
+                "!third_party/ours.yml",
+                "docs/**/action.yml",
+            ],
+        );
+
+        for (path, expected) in [
+            (".github/workflows/ci.yml", false),
+            (".github/workflows/generated-release.yml", true),
+            ("nested/generated-release.yml", true),
+            (".github/workflows/generated-keep.yml", false),
+            (".github/workflows/vendored.yml", true),
+            ("nested/.github/workflows/vendored.yml", false),
+            ("third_party/action.yml", true),
+            ("third_party/deep/action.yml", true),
+            // Can't be re-included, since its directory is excluded.
+            ("third_party/ours.yml", true),
+            ("not_third_party/action.yml", false),
+            ("docs/action.yml", true),
+            ("docs/examples/setup/action.yml", true),
+            ("action.yml", false),
+        ] {
+            let path = Utf8Path::new("/nonexistent/repo").join(path);
+            assert_eq!(exclusions.excludes(&path), expected, "{path}");
+        }
+
+        // Paths outside of the root are never excluded.
+        assert!(!exclusions.excludes(Utf8Path::new("/elsewhere/generated-release.yml")));
+        assert!(!Exclusions::default().excludes(Utf8Path::new("generated-release.yml")));
+    }
+}
@@ -44,7 +44,7 @@ pub(crate) enum Expr {
     ///
     /// Three different kinds of expressions can be indexed:
     ///
-    /// ```
+    /// ```text
     /// functionCall[expr]
     /// context.reference[expr]
     /// (<arbitrary expression>)[expr]
@@ -425,6 +425,17 @@ mod tests {
                     indices: vec![Expr::Star],
                 },
             ),
+            (
+                "fromJSON(steps.foo.outputs.bar)['baz']",
+                Expr::Index {
+                    parent: Expr::Call {
+                        func: "fromJSON".into(),
+                        args: vec![Expr::Context("steps.foo.outputs.bar".into())],
+                    }
+                    .into(),
+                    indices: vec![*Expr::string("baz")],
+                },
+            ),
             (
                 "vegetables.*.ediblePortions",
                 Expr::Context("vegetables.*.ediblePortions".into()),
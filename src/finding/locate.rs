@@ -1,11 +1,28 @@
 //! `tree-sitter` helpers for extracting and locating concrete features
 //! in the original YAML.
 
-use anyhow::Result;
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    sync::{Mutex, OnceLock},
+};
 
-use super::{Comment, ConcreteLocation, Feature, SymbolicLocation};
-use crate::models::Workflow;
+use anyhow::{bail, Context, Result};
+use tracing::instrument;
+use tree_sitter::{Node, Parser, Tree};
 
+use super::{Comment, ConcreteLocation, Feature, Point, Route, RouteComponent, SymbolicLocation};
+use crate::models::AuditInput;
+
+/// Concretizes [`SymbolicLocation`]s into [`Feature`]s.
+///
+/// Queries are built structurally from each route's components rather than
+/// from query text, so there's nothing to pre-compile, and keys (e.g. job
+/// names) can't be misinterpreted no matter which characters they contain.
+///
+/// Each route is only located once per input: later queries for the same
+/// route (e.g. from other findings or audits) are answered from the
+/// input's [`LocationCache`].
 pub(crate) struct Locator {}
 
 impl Locator {
@@ -15,51 +32,945 @@ impl Locator {
 
     pub(crate) fn concretize<'w>(
         &self,
-        workflow: &'w Workflow,
+        input: &'w impl AuditInput,
         location: &SymbolicLocation,
     ) -> Result<Feature<'w>> {
-        // If we don't have a path into the workflow, all
-        // we have is the workflow itself.
-        let (feature, parent_feature) = if location.route.components.is_empty() {
-            (workflow.document.root(), workflow.document.root())
-        } else {
-            let mut builder = yamlpath::QueryBuilder::new();
+        let document = input.document();
 
-            for component in &location.route.components {
-                builder = match component {
-                    super::RouteComponent::Key(key) => builder.key(key.clone()),
-                    super::RouteComponent::Index(idx) => builder.index(*idx),
-                }
+        let located = match (Self::query(input, &location.route), &location.fallback) {
+            (Ok(located), _) => located,
+            // The route may legitimately not exist, e.g. a step
+            // without the `with:` input we're looking for.
+            (Err(e), Some(fallback)) => {
+                tracing::trace!("falling back to {fallback:?}: {e}");
+                Self::query(input, fallback)?
             }
+            (Err(e), None) => return Err(e),
+        };
 
-            let query = builder.build();
-            tracing::trace!(
-                "querying {workflow}: {query:?}",
-                workflow = workflow.filename()
-            );
+        Ok(Self::feature(
+            document.source(),
+            located,
+            location.subfeature.as_deref(),
+            location.subfeature_within.as_deref(),
+            location.occurrence,
+        ))
+    }
+
+    /// Returns the feature at the given dotted key path (see
+    /// [`SymbolicLocation::with_path`]) beneath the given job and step, or
+    /// beneath the input's root if `job` is `None`.
+    ///
+    /// If the exact path isn't present, this falls back to the closest
+    /// enclosing node that is (but never above the job or step itself),
+    /// e.g. a step's `with:` when it doesn't have the requested input.
+    pub(crate) fn concretize_path<'w>(
+        &self,
+        input: &'w impl AuditInput,
+        job: Option<&str>,
+        step: Option<usize>,
+        path: &str,
+    ) -> Result<PathFeature<'w>> {
+        let mut route = Route::new();
+        match (job, step) {
+            (None, None) => (),
+            (Some(job), None) => route.components.extend(["jobs".into(), job.into()]),
+            (Some(job), Some(step)) => {
+                route
+                    .components
+                    .extend(["jobs".into(), job.into(), "steps".into(), step.into()])
+            }
+            (None, Some(_)) => bail!("API misuse: can't locate a step without its job"),
+        }
+
+        let base = route.components.len();
+        route.components.extend(Route::parse(path).components);
+        let exact = route.components.len();
 
-            let parent_feature = if let Some(parent) = query.parent() {
-                workflow.document.query(&parent)?
-            } else {
-                workflow.document.root()
+        loop {
+            match Self::query(input, &route) {
+                Ok(located) => {
+                    return Ok(PathFeature {
+                        exact: route.components.len() == exact,
+                        feature: Self::feature(input.document().source(), located, None, None, 0),
+                    })
+                }
+                Err(e) if route.components.len() > base => {
+                    tracing::trace!("falling back from {route}: {e}");
+                    route.components.pop();
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Builds the [`Feature`] for a located route, narrowed to the first
+    /// occurrence of `subfeature` within it (if given), or within the first
+    /// occurrence of `within` in it (if that's given too). `occurrence`
+    /// selects a later occurrence of `within`, or of `subfeature` if
+    /// there's no `within`.
+    fn feature<'w>(
+        source: &'w str,
+        located: Located,
+        subfeature: Option<&str>,
+        within: Option<&str>,
+        occurrence: usize,
+    ) -> Feature<'w> {
+        // If we're narrowing to a subfeature, find it within the feature's
+        // span. If it's not there verbatim (e.g. due to YAML escaping),
+        // we fall back to the whole feature.
+        let (start, end) = (located.feature.start_offset, located.feature.end_offset);
+        let find = |(start, end): (usize, usize), needle: &str, n: usize| {
+            source[start..end]
+                .match_indices(needle)
+                .nth(n)
+                .map(|(offset, _)| (start + offset, start + offset + needle.len()))
+        };
+        let (start, end, occurrence) =
+            match within.and_then(|within| find((start, end), within, occurrence)) {
+                Some((start, end)) => (start, end, 0),
+                None => (start, end, occurrence),
             };
+        let subfeature =
+            subfeature.and_then(|subfeature| find((start, end), subfeature, occurrence));
 
-            (workflow.document.query(&query)?, parent_feature)
+        let (location, extracted) = match subfeature {
+            Some((start, end)) => (
+                ConcreteLocation {
+                    start_point: Self::point_at(source, start),
+                    end_point: Self::point_at(source, end),
+                    start_offset: start,
+                    end_offset: end,
+                },
+                &source[start..end],
+            ),
+            None => (
+                located.feature.clone(),
+                Self::extract_with_leading_whitespace(source, &located.feature),
+            ),
         };
 
-        Ok(Feature {
-            location: ConcreteLocation::from(&feature.location),
-            parent_location: ConcreteLocation::from(&parent_feature.location),
-            feature: workflow.document.extract_with_leading_whitespace(&feature),
-            comments: workflow
-                .document
-                .feature_comments(&feature)
+        Feature {
+            location,
+            parent_location: located.parent.clone(),
+            feature: extracted,
+            comments: located
+                .comments
                 .into_iter()
-                .map(Comment)
+                .map(|(start, end)| Comment(&source[start..end]))
                 .collect(),
-            parent_feature: workflow
-                .document
-                .extract_with_leading_whitespace(&parent_feature),
+            parent_feature: Self::extract_with_leading_whitespace(source, &located.parent),
+        }
+    }
+
+    /// Returns the locations of the given route and its parent, along with
+    /// any comments on the route's lines.
+    fn query(input: &impl AuditInput, route: &Route) -> Result<Located> {
+        let cache = input.locations();
+        if let Some(located) = cache.get(route) {
+            return Ok(located);
+        }
+
+        // NOTE: Failures aren't cached, since they're only ever followed
+        // by a fallback query.
+        let located = Self::resolve(input, route)?;
+        cache.insert(route, located.clone());
+        Ok(located)
+    }
+
+    /// Like [`Locator::query`], but without consulting the cache.
+    #[instrument(level = "debug", skip_all, fields(route = %route))]
+    fn resolve(input: &impl AuditInput, route: &Route) -> Result<Located> {
+        let document = input.document();
+        let source = document.source();
+
+        // If we don't have a path into the input, all
+        // we have is the input itself.
+        if route.components.is_empty() {
+            let root = document.root();
+            return Ok(Located::new(
+                source,
+                &root.location,
+                &root.location,
+                document.feature_comments(&root),
+            ));
+        }
+
+        let mut builder = yamlpath::QueryBuilder::new();
+
+        for component in &route.components {
+            builder = match component {
+                RouteComponent::Key(key) => builder.key(key.clone()),
+                RouteComponent::Index(idx) => builder.index(*idx),
+            }
+        }
+
+        let mut query = builder.build();
+        tracing::debug!("querying {query:?}");
+
+        if document.query(&query).is_err() {
+            if let Some(respelled) = Self::respell(document, route) {
+                query = respelled;
+            }
+        }
+
+        let context = || {
+            format!(
+                "couldn't locate {route} in {input}",
+                input = input.key().path()
+            )
+        };
+
+        let feature = match document.query(&query) {
+            Ok(feature) => feature,
+            // yamlpath doesn't see through anchors or aliases, so the route
+            // may only exist once they're resolved.
+            Err(e) => {
+                return input
+                    .locations()
+                    .tree(source)
+                    .and_then(|tree| Anchors::query(source, tree, route))
+                    .ok_or(e)
+                    .with_context(context)
+            }
+        };
+
+        // NOTE: The parent of a route that exists always exists too.
+        let parent_feature = if let Some(parent) = query.parent() {
+            document.query(&parent)?
+        } else {
+            document.root()
+        };
+
+        Ok(Located::new(
+            source,
+            &feature.location,
+            &parent_feature.location,
+            document.feature_comments(&feature),
+        ))
+    }
+
+    /// Rebuilds a query for the given route with each key spelled the way
+    /// the document spells it, or returns `None` if some key isn't found
+    /// under any spelling.
+    ///
+    /// yamlpath matches keys against their source text, so a quoted key
+    /// like `'say "hi"'` isn't found by its value alone. This is only tried
+    /// once the plain query has failed, since nearly all keys are bare.
+    fn respell(document: &yamlpath::Document, route: &Route) -> Option<yamlpath::Query> {
+        let mut builder = yamlpath::QueryBuilder::new();
+
+        for component in &route.components {
+            builder = match component {
+                RouteComponent::Key(key) => [
+                    key.to_string(),
+                    format!("'{}'", key.replace('\'', "''")),
+                    format!("\"{}\"", key.replace('\\', "\\\\").replace('"', "\\\"")),
+                ]
+                .into_iter()
+                .map(|spelling| builder.clone().key(spelling))
+                .find(|candidate| document.query(&candidate.clone().build()).is_ok())?,
+                RouteComponent::Index(idx) => builder.index(*idx),
+            }
+        }
+
+        Some(builder.build())
+    }
+
+    /// Returns the source at the given location, along with any leading
+    /// (indentation) whitespace on its first line.
+    fn extract_with_leading_whitespace<'w>(
+        source: &'w str,
+        location: &ConcreteLocation,
+    ) -> &'w str {
+        let (mut start, end) = (location.start_offset, location.end_offset);
+        let line_start = source[..start].rfind('\n').map_or(0, |idx| idx + 1);
+        if source[line_start..start].bytes().all(|b| b == b' ') {
+            start = line_start;
+        }
+
+        &source[start..end]
+    }
+
+    /// Returns the (0-based) row and column of the given byte offset.
+    fn point_at(source: &str, offset: usize) -> Point {
+        let before = &source[..offset];
+        let row = before.matches('\n').count();
+        let column = match before.rfind('\n') {
+            Some(newline) => offset - newline - 1,
+            None => offset,
+        };
+
+        Point { row, column }
+    }
+}
+
+/// The result of [`Locator::concretize_path`].
+pub(crate) struct PathFeature<'w> {
+    pub(crate) feature: Feature<'w>,
+    /// Whether `feature` is at the exact path requested, rather than one
+    /// of its enclosing nodes.
+    pub(crate) exact: bool,
+}
+
+/// The locations found for a route; see [`Locator::query`].
+#[derive(Clone)]
+struct Located {
+    feature: ConcreteLocation,
+    parent: ConcreteLocation,
+    /// The byte ranges of any comments on the feature's lines.
+    comments: Vec<(usize, usize)>,
+}
+
+impl Located {
+    fn new(
+        source: &str,
+        feature: &yamlpath::Location,
+        parent: &yamlpath::Location,
+        comments: Vec<&str>,
+    ) -> Self {
+        Self {
+            feature: feature.into(),
+            parent: parent.into(),
+            // NOTE: yamlpath's comments are always slices of the source.
+            comments: comments
+                .into_iter()
+                .map(|comment| {
+                    let start = comment.as_ptr() as usize - source.as_ptr() as usize;
+                    (start, start + comment.len())
+                })
+                .collect(),
+        }
+    }
+}
+
+/// The routes already located within a single input, along with the
+/// syntax tree used to resolve its anchors and aliases.
+///
+/// yamlpath's own tree isn't exposed, so inputs with anchors are parsed a
+/// second time for [`Anchors`]. That happens at most once per input, and
+/// only once a route can't be found without it.
+#[derive(Default)]
+pub(crate) struct LocationCache {
+    located: Mutex<HashMap<Vec<RouteComponent<'static>>, Located>>,
+    tree: OnceLock<Option<Tree>>,
+}
+
+impl LocationCache {
+    fn key(route: &Route) -> Vec<RouteComponent<'static>> {
+        route
+            .components
+            .iter()
+            .map(|component| match component {
+                RouteComponent::Key(key) => RouteComponent::Key(Cow::Owned(key.to_string())),
+                RouteComponent::Index(idx) => RouteComponent::Index(*idx),
+            })
+            .collect()
+    }
+
+    fn get(&self, route: &Route) -> Option<Located> {
+        self.located.lock().unwrap().get(&Self::key(route)).cloned()
+    }
+
+    fn insert(&self, route: &Route, located: Located) {
+        self.located
+            .lock()
+            .unwrap()
+            .insert(Self::key(route), located);
+    }
+
+    /// Returns the syntax tree for the given source, parsing it on first
+    /// use. Sources without any anchors have nothing to resolve, and are
+    /// never parsed.
+    fn tree(&self, source: &str) -> Option<&Tree> {
+        self.tree
+            .get_or_init(|| {
+                if !source.contains('&') {
+                    return None;
+                }
+
+                tracing::debug!("parsing for anchor resolution");
+                let mut parser = Parser::new();
+                parser
+                    .set_language(&tree_sitter_yaml::language())
+                    .expect("couldn't load YAML parser?");
+                parser.parse(source, None)
+            })
+            .as_ref()
+    }
+}
+
+/// Resolves routes through YAML anchors (`&name`) and aliases (`*name`).
+///
+/// yamlpath expects a node's contents to be its first child, so it can't
+/// descend into a node with an anchor (or tag) in front of it, or through
+/// an alias at all. This descends the same way, except that anchors and
+/// tags are skipped, and each alias is followed to the node it refers to.
+///
+/// Aliased content is therefore located at its anchored definition, which
+/// is the only place it actually appears in the source.
+struct Anchors<'t> {
+    source: &'t str,
+    /// Every anchor's name and anchored node, in source order.
+    anchors: Vec<(&'t str, Node<'t>)>,
+}
+
+impl<'t> Anchors<'t> {
+    /// Like [`Locator::resolve`], but with anchors and aliases resolved
+    /// against the given syntax tree for `source`.
+    fn query(source: &'t str, tree: &'t Tree, route: &Route) -> Option<Located> {
+        let root = tree.root_node();
+
+        let mut anchors = vec![];
+        let mut stack = vec![root];
+        while let Some(node) = stack.pop() {
+            if node.kind() == "anchor" {
+                // NOTE: Anchors always annotate a parent node.
+                anchors.push((
+                    &source[node.start_byte() + 1..node.end_byte()],
+                    node.parent()?,
+                ));
+            }
+            stack.extend(node.named_children(&mut node.walk()));
+        }
+        anchors.sort_by_key(|(_, node)| node.start_byte());
+
+        let resolver = Anchors { source, anchors };
+        let (_, parent_route) = route.components.split_last()?;
+        let feature = resolver.locate(root, &route.components)?;
+        let parent = match parent_route {
+            [] => root,
+            parent_route => resolver.locate(root, parent_route)?,
+        };
+
+        Some(Located {
+            comments: Anchors::comments(
+                root,
+                feature.start_position().row,
+                feature.end_position().row,
+            ),
+            feature: (&yamlpath::Location::from(feature)).into(),
+            parent: (&yamlpath::Location::from(parent)).into(),
         })
     }
+
+    fn text(&self, node: Node) -> &'t str {
+        &self.source[node.start_byte()..node.end_byte()]
+    }
+
+    /// Returns the node at the given (non-empty) route, mirroring
+    /// yamlpath's choice of node.
+    fn locate(&self, root: Node<'t>, route: &[RouteComponent]) -> Option<Node<'t>> {
+        let document = root
+            .named_children(&mut root.walk())
+            .find(|node| node.kind() == "document")?;
+        let mut node = document
+            .named_children(&mut document.walk())
+            .find(|node| matches!(node.kind(), "block_node" | "flow_node"))?;
+
+        for component in route {
+            let contents = self.contents(node)?;
+            node = match (contents.kind(), component) {
+                ("block_mapping" | "flow_mapping", RouteComponent::Key(key)) => {
+                    self.descend_mapping(contents, key)?
+                }
+                ("block_sequence" | "flow_sequence", RouteComponent::Index(idx)) => {
+                    Self::descend_sequence(contents, *idx)?
+                }
+                _ => return None,
+            };
+        }
+
+        // Like yamlpath, we return the whole `key: value` pair for a key.
+        match route.last() {
+            Some(RouteComponent::Key(_))
+                if !matches!(node.kind(), "block_mapping_pair" | "flow_pair") =>
+            {
+                node.parent()
+            }
+            _ => Some(node),
+        }
+    }
+
+    /// Returns the contents of the given `block_node` or `flow_node`,
+    /// skipping over any anchor or tag and following any alias.
+    fn contents(&self, node: Node<'t>) -> Option<Node<'t>> {
+        let contents = node
+            .named_children(&mut node.walk())
+            .find(|child| !matches!(child.kind(), "anchor" | "tag" | "comment"))?;
+
+        if contents.kind() != "alias" {
+            return Some(contents);
+        }
+
+        // An alias refers to the most recent anchor with its name. That
+        // anchor always comes strictly earlier, so this terminates.
+        let name = &self.text(contents)[1..];
+        let (_, anchored) = self.anchors.iter().rev().find(|(anchor, anchored)| {
+            *anchor == name && anchored.start_byte() < contents.start_byte()
+        })?;
+
+        self.contents(*anchored)
+    }
+
+    fn descend_mapping(&self, mapping: Node<'t>, key: &str) -> Option<Node<'t>> {
+        let spellings = [
+            key.to_string(),
+            format!("'{}'", key.replace('\'', "''")),
+            format!("\"{}\"", key.replace('\\', "\\\\").replace('"', "\\\"")),
+        ];
+
+        mapping
+            .named_children(&mut mapping.walk())
+            .filter(|child| matches!(child.kind(), "block_mapping_pair" | "flow_pair"))
+            .find(|pair| {
+                pair.child_by_field_name("key")
+                    .is_some_and(|k| spellings.iter().any(|s| s == self.text(k)))
+            })
+            // A key without a value is terminal, so we return the pair itself.
+            .map(|pair| pair.child_by_field_name("value").unwrap_or(pair))
+    }
+
+    fn descend_sequence(sequence: Node<'t>, idx: usize) -> Option<Node<'t>> {
+        let item = sequence
+            .named_children(&mut sequence.walk())
+            .filter(|child| {
+                matches!(
+                    child.kind(),
+                    "block_sequence_item" | "flow_node" | "flow_pair"
+                )
+            })
+            .nth(idx)?;
+
+        match item.kind() {
+            "block_sequence_item" => item.named_child(0),
+            "flow_pair" => Some(item.child_by_field_name("value").unwrap_or(item)),
+            _ => Some(item),
+        }
+    }
+
+    /// Returns the byte range of every comment on the given (0-based,
+    /// inclusive) rows.
+    fn comments(root: Node, start_row: usize, end_row: usize) -> Vec<(usize, usize)> {
+        let mut comments = vec![];
+        let mut stack = vec![root];
+        while let Some(node) = stack.pop() {
+            if node.end_position().row < start_row || node.start_position().row > end_row {
+                continue;
+            }
+
+            if node.kind() == "comment" {
+                comments.push((node.start_byte(), node.end_byte()));
+            }
+            stack.extend(node.named_children(&mut node.walk()));
+        }
+
+        comments.sort();
+        comments
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Locator;
+    use crate::finding::RouteComponent;
+    use crate::{
+        models::{AuditInput, Workflow},
+        registry::WorkflowKey,
+    };
+
+    #[test]
+    fn test_point_at() {
+        let source = "foo: bar\nbaz:\n  quux\n";
+
+        for (offset, row, column) in &[(0, 0, 0), (5, 0, 5), (9, 1, 0), (16, 2, 2)] {
+            let point = Locator::point_at(source, *offset);
+            assert_eq!((point.row, point.column), (*row, *column), "{offset}");
+        }
+    }
+
+    #[test]
+    fn test_concretize_ranges() {
+        let workflow = Workflow::from_string(
+            "on: push\njobs:\n  test:\n    runs-on: ubuntu-latest\n    steps:\n      - run: |\n          echo hello\n          echo world\n".into(),
+            WorkflowKey::local("/tmp/test.yml".into()).unwrap(),
+        )
+        .unwrap();
+
+        let step = workflow.location().with_keys(&[
+            "jobs".into(),
+            "test".into(),
+            "steps".into(),
+            0.into(),
+            "run".into(),
+        ]);
+
+        // A multi-line feature spans multiple rows.
+        let feature = Locator::new().concretize(&workflow, &step).unwrap();
+        let location = feature.location;
+        assert_eq!(location.start_point.row, 5);
+        assert!(location.end_point.row > location.start_point.row);
+        assert_eq!(
+            &workflow.document.source()[location.start_offset..location.end_offset],
+            feature.feature.trim_start()
+        );
+
+        // A subfeature is narrowed to its own row and columns.
+        let feature = Locator::new()
+            .concretize(&workflow, &step.clone().subfeature("echo world"))
+            .unwrap();
+        let location = feature.location;
+        assert_eq!(feature.feature, "echo world");
+        assert_eq!(
+            (location.start_point.line(), location.start_point.col()),
+            (8, 11)
+        );
+        assert_eq!(
+            (location.end_point.line(), location.end_point.col()),
+            (8, 21)
+        );
+        assert_eq!(
+            location.end_offset - location.start_offset,
+            "echo world".len()
+        );
+
+        // A subfeature can be narrowed to a later occurrence, by finding it
+        // within something that contains it.
+        let feature = Locator::new()
+            .concretize(
+                &workflow,
+                &step.clone().subfeature_within("echo world", "echo"),
+            )
+            .unwrap();
+        assert_eq!(feature.feature, "echo");
+        assert_eq!(
+            (
+                feature.location.start_point.line(),
+                feature.location.start_point.col()
+            ),
+            (8, 11)
+        );
+
+        // ...or by picking the occurrence directly.
+        let feature = Locator::new()
+            .concretize(&workflow, &step.subfeature("echo").occurrence(1))
+            .unwrap();
+        assert_eq!(
+            (
+                feature.location.start_point.line(),
+                feature.location.start_point.col()
+            ),
+            (8, 11)
+        );
+    }
+
+    #[test]
+    fn test_concretize_input_fallback() {
+        let workflow = Workflow::from_string(
+            "on: push\njobs:\n  test:\n    runs-on: ubuntu-latest\n    steps:\n      - uses: actions/checkout@v4\n        with:\n          persist-credentials: true\n      - uses: actions/checkout@v4\n".into(),
+            WorkflowKey::local("/tmp/test.yml".into()).unwrap(),
+        )
+        .unwrap();
+
+        let steps = workflow
+            .location()
+            .with_keys(&["jobs".into(), "test".into(), "steps".into()]);
+
+        // The input exists, so we get exactly its key-value pair.
+        let feature = Locator::new()
+            .concretize(
+                &workflow,
+                &steps
+                    .with_keys(&[0.into()])
+                    .with_input("persist-credentials"),
+            )
+            .unwrap();
+        assert_eq!(feature.feature.trim(), "persist-credentials: true");
+
+        // No such input (or `with:` at all), so we get the step instead.
+        for (idx, input) in [(0, "fetch-depth"), (1, "persist-credentials")] {
+            let step = steps.with_keys(&[idx.into()]);
+            let expected = Locator::new().concretize(&workflow, &step).unwrap();
+            let feature = Locator::new()
+                .concretize(&workflow, &step.with_input(input))
+                .unwrap();
+            assert_eq!(feature.feature, expected.feature, "{idx}: {input}");
+        }
+
+        // Routes without a fallback still fail.
+        assert!(Locator::new()
+            .concretize(
+                &workflow,
+                &steps.with_keys(&[1.into(), "with".into(), "persist-credentials".into()]),
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_comments() {
+        let workflow = Workflow::from_string(
+            "on: push\njobs:\n  test:\n    runs-on: ubuntu-latest\n    steps:\n      - uses: actions/checkout@11bd71901bbe5b1630ceea73d27597364c9af683 # v4.2.2\n      - run: echo hello\n".into(),
+            WorkflowKey::local("/tmp/test.yml".into()).unwrap(),
+        )
+        .unwrap();
+
+        // A trailing comment on a `uses:` line belongs to the `uses:` itself.
+        let comments = workflow
+            .location()
+            .with_keys(&[
+                "jobs".into(),
+                "test".into(),
+                "steps".into(),
+                0.into(),
+                "uses".into(),
+            ])
+            .comments(&workflow)
+            .unwrap();
+        assert_eq!(
+            comments.iter().map(|c| c.as_str()).collect::<Vec<_>>(),
+            ["# v4.2.2"]
+        );
+
+        // ...but not to anything else.
+        let comments = workflow
+            .location()
+            .with_keys(&[
+                "jobs".into(),
+                "test".into(),
+                "steps".into(),
+                1.into(),
+                "run".into(),
+            ])
+            .comments(&workflow)
+            .unwrap();
+        assert!(comments.is_empty());
+    }
+
+    #[test]
+    fn test_concretize_special_job_names() {
+        let workflow = Workflow::from_string(
+            "on: push\njobs:\n  'say \"hi\"':\n    runs-on: one\n    steps:\n      - run: echo one\n  \"it's\":\n    runs-on: two\n    steps:\n      - run: echo two\n  '(.*) [x] #{y}':\n    runs-on: three\n    steps:\n      - run: echo three\n  __JOB_NAME__:\n    runs-on: four\n    steps:\n      - run: echo four\n  \"build\":\n    runs-on: five\n    steps:\n      - run: echo five\n  build-release:\n    runs-on: six\n    steps:\n      - run: echo six\n  \"back\\\\slash \\\"release\\\"\":\n    runs-on: seven\n    steps:\n      - run: echo seven\n"
+                .into(),
+            WorkflowKey::local("/tmp/test.yml".into()).unwrap(),
+        )
+        .unwrap();
+
+        for (job, runs_on) in [
+            ("say \"hi\"", "one"),
+            ("it's", "two"),
+            ("(.*) [x] #{y}", "three"),
+            ("__JOB_NAME__", "four"),
+            // Quoted, despite not needing to be.
+            ("build", "five"),
+            ("build-release", "six"),
+            // Double-quoted, with escapes.
+            ("back\\slash \"release\"", "seven"),
+        ] {
+            let feature = Locator::new()
+                .concretize(
+                    &workflow,
+                    &workflow
+                        .location()
+                        .with_keys(&["jobs".into(), job.into(), "runs-on".into()]),
+                )
+                .unwrap();
+            assert_eq!(
+                feature.feature.trim(),
+                format!("runs-on: {runs_on}"),
+                "{job}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_concretize_anchors() {
+        let workflow = Workflow::from_string(
+            r#"on: push
+jobs:
+  test:
+    runs-on: ubuntu-latest
+    steps:
+      - &checkout
+        uses: actions/checkout@v4 # zizmor: ignore[unpinned-uses]
+      - &greet
+        run: echo hello
+  lint:
+    runs-on: &runner ubuntu-latest
+    steps:
+      - *checkout
+      - name: lint
+        run: echo lint
+  deploy:
+    runs-on: *runner
+    steps: [*greet]
+"#
+            .into(),
+            WorkflowKey::local("/tmp/test.yml".into()).unwrap(),
+        )
+        .unwrap();
+
+        let concretize = |route: &[RouteComponent]| {
+            Locator::new()
+                .concretize(&workflow, &workflow.location().with_keys(route))
+                .unwrap()
+        };
+
+        // Within an anchored node.
+        let feature = concretize(&[
+            "jobs".into(),
+            "test".into(),
+            "steps".into(),
+            0.into(),
+            "uses".into(),
+        ]);
+        assert_eq!(feature.feature.trim(), "uses: actions/checkout@v4");
+        assert_eq!(feature.location.start_point.row, 6);
+        assert_eq!(feature.comments.len(), 1);
+
+        // Through an alias, to the anchored node.
+        let feature = concretize(&[
+            "jobs".into(),
+            "lint".into(),
+            "steps".into(),
+            0.into(),
+            "uses".into(),
+        ]);
+        assert_eq!(feature.location.start_point.row, 6);
+
+        // Aliases that aren't traversed are located as-is.
+        let feature = concretize(&["jobs".into(), "lint".into(), "steps".into(), 0.into()]);
+        assert_eq!(feature.feature.trim(), "*checkout");
+        let feature = concretize(&["jobs".into(), "deploy".into(), "runs-on".into()]);
+        assert_eq!(feature.feature.trim(), "runs-on: *runner");
+
+        // Unrelated routes are unaffected, and aliases in flow sequences
+        // are followed too.
+        let feature = concretize(&[
+            "jobs".into(),
+            "lint".into(),
+            "steps".into(),
+            1.into(),
+            "run".into(),
+        ]);
+        assert_eq!(feature.feature.trim(), "run: echo lint");
+        let feature = concretize(&[
+            "jobs".into(),
+            "deploy".into(),
+            "steps".into(),
+            0.into(),
+            "run".into(),
+        ]);
+        assert_eq!(feature.feature.trim(), "run: echo hello");
+        assert_eq!(feature.location.start_point.row, 8);
+
+        // Routes that don't exist still fail.
+        assert!(Locator::new()
+            .concretize(
+                &workflow,
+                &workflow.location().with_keys(&[
+                    "jobs".into(),
+                    "lint".into(),
+                    "steps".into(),
+                    0.into(),
+                    "with".into()
+                ])
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_concretize_path() {
+        let workflow = Workflow::from_string(
+            "on: push\npermissions: {}\njobs:\n  x:\n    runs-on: ubuntu-latest\n    steps:\n      - run: echo one\n      - run: echo two\n      - uses: actions/cache@v4\n        with:\n          path: ~/.cache\n".into(),
+            WorkflowKey::local("/tmp/test.yml".into()).unwrap(),
+        )
+        .unwrap();
+        let locator = Locator::new();
+        let located =
+            |job, step, path| locator.concretize_path(&workflow, job, step, path).unwrap();
+
+        let located_permissions = located(None, None, "permissions");
+        assert!(located_permissions.exact);
+        assert_eq!(located_permissions.feature.feature, "permissions: {}");
+
+        // Paths can be given from the root, or relative to a job or step.
+        for (job, step, path) in [(None, None, "jobs.x.runs-on"), (Some("x"), None, "runs-on")] {
+            let runs_on = located(job, step, path);
+            assert!(runs_on.exact);
+            assert_eq!(runs_on.feature.feature.trim(), "runs-on: ubuntu-latest");
+        }
+
+        for (job, step, path) in [
+            (None, None, "jobs.x.steps[2].with.path"),
+            (Some("x"), None, "steps[2].with.path"),
+            (Some("x"), Some(2), "with.path"),
+        ] {
+            let cache_path = located(job, step, path);
+            assert!(cache_path.exact);
+            assert_eq!(cache_path.feature.feature.trim(), "path: ~/.cache");
+            assert_eq!(cache_path.feature.location.start_point.row, 10);
+        }
+
+        // Missing keys fall back to the closest enclosing node...
+        let fallback = located(Some("x"), Some(2), "with.key");
+        assert!(!fallback.exact);
+        assert!(fallback.feature.feature.trim().starts_with("with:"));
+        let fallback = located(Some("x"), Some(0), "with.path");
+        assert!(!fallback.exact);
+        assert_eq!(fallback.feature.feature.trim(), "run: echo one");
+
+        // ...but never above the requested job or step.
+        assert!(locator
+            .concretize_path(&workflow, Some("x"), Some(5), "with.path")
+            .is_err());
+        assert!(locator
+            .concretize_path(&workflow, None, Some(0), "run")
+            .is_err());
+    }
+
+    #[test]
+    fn test_locations_cached() {
+        let workflow = Workflow::from_string(
+            "on: push\njobs:\n  test:\n    runs-on: ubuntu-latest\n    steps:\n      - &checkout\n        uses: actions/checkout@v4\n  lint:\n    runs-on: ubuntu-latest\n    steps:\n      - *checkout\n".into(),
+            WorkflowKey::local("/tmp/test.yml".into()).unwrap(),
+        )
+        .unwrap();
+        let cache = workflow.locations();
+
+        let runs_on =
+            workflow
+                .location()
+                .with_keys(&["jobs".into(), "test".into(), "runs-on".into()]);
+        let aliased = workflow.location().with_keys(&[
+            "jobs".into(),
+            "lint".into(),
+            "steps".into(),
+            0.into(),
+            "uses".into(),
+        ]);
+
+        // However many findings share a location, it's only located once,
+        // and routes that yamlpath can find never need a second parse.
+        for _ in 0..100 {
+            let feature = Locator::new().concretize(&workflow, &runs_on).unwrap();
+            assert_eq!(feature.feature.trim(), "runs-on: ubuntu-latest");
+        }
+        assert_eq!(cache.located.lock().unwrap().len(), 1);
+        assert!(cache.tree.get().is_none());
+
+        // Routes through aliases parse the input once, on first use.
+        for _ in 0..100 {
+            let feature = Locator::new().concretize(&workflow, &aliased).unwrap();
+            assert_eq!(feature.location.start_point.row, 6);
+        }
+        assert_eq!(cache.located.lock().unwrap().len(), 2);
+        assert!(cache.tree.get().unwrap().is_some());
+
+        // Subfeatures are narrowed from the same cached location.
+        let feature = Locator::new()
+            .concretize(&workflow, &aliased.subfeature("checkout"))
+            .unwrap();
+        assert_eq!(feature.feature, "checkout");
+        assert_eq!(cache.located.lock().unwrap().len(), 2);
+    }
 }
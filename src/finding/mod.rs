@@ -1,16 +1,18 @@
 //! Models and APIs for handling findings and their locations.
 
-use std::{borrow::Cow, sync::LazyLock};
+use std::{borrow::Cow, fmt::Display, sync::LazyLock};
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::ValueEnum;
+use indexmap::IndexMap;
 use locate::Locator;
 use regex::Regex;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use terminal_link::Link;
 
 use crate::{
-    models::{Job, Step, Workflow},
+    models::{AuditInput, Job, Step},
     registry::WorkflowKey,
 };
 
@@ -20,9 +22,21 @@ pub(crate) mod locate;
 /// finding. This is used to model the sensitivity of different use-cases
 /// to false positives.
 #[derive(
-    Copy, Clone, Debug, Default, Eq, Hash, Ord, PartialOrd, PartialEq, Serialize, ValueEnum,
+    Copy,
+    Clone,
+    Debug,
+    Default,
+    Eq,
+    Hash,
+    Ord,
+    PartialOrd,
+    PartialEq,
+    Serialize,
+    Deserialize,
+    ValueEnum,
 )]
-pub(crate) enum Persona {
+#[serde(rename_all = "lowercase")]
+pub enum Persona {
     /// The "auditor" persona (false positives OK).
     ///
     /// This persona wants all results, including results that are likely
@@ -43,10 +57,23 @@ pub(crate) enum Persona {
     Regular,
 }
 
+/// How confident an audit is that a finding is a true positive.
 #[derive(
-    Copy, Clone, Debug, Default, Eq, Hash, Ord, PartialOrd, PartialEq, Serialize, ValueEnum,
+    Copy,
+    Clone,
+    Debug,
+    Default,
+    Eq,
+    Hash,
+    Ord,
+    PartialOrd,
+    PartialEq,
+    Serialize,
+    Deserialize,
+    ValueEnum,
 )]
-pub(crate) enum Confidence {
+#[serde(rename_all = "lowercase")]
+pub enum Confidence {
     #[default]
     Unknown,
     Low,
@@ -54,36 +81,33 @@ pub(crate) enum Confidence {
     High,
 }
 
+/// How severe a finding is, assuming that it's a true positive.
 #[derive(
-    Copy, Clone, Debug, Default, Eq, Hash, Ord, PartialOrd, PartialEq, Serialize, ValueEnum,
+    Copy,
+    Clone,
+    Debug,
+    Default,
+    Eq,
+    Hash,
+    Ord,
+    PartialOrd,
+    PartialEq,
+    Serialize,
+    Deserialize,
+    ValueEnum,
 )]
-pub(crate) enum Severity {
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
     #[default]
     Unknown,
     Informational,
     Low,
     Medium,
     High,
+    Critical,
 }
 
-#[derive(Serialize, Clone, Debug)]
-pub(crate) struct StepLocation<'w> {
-    pub(crate) index: usize,
-    pub(crate) id: Option<&'w str>,
-    pub(crate) name: Option<&'w str>,
-}
-
-impl<'w> From<&Step<'w>> for StepLocation<'w> {
-    fn from(step: &Step<'w>) -> Self {
-        Self {
-            index: step.index,
-            id: step.id.as_deref(),
-            name: step.name.as_deref(),
-        }
-    }
-}
-
-#[derive(Serialize, Clone, Debug)]
+#[derive(Serialize, Clone, Debug, PartialEq, Eq, Hash)]
 pub(crate) enum RouteComponent<'w> {
     Key(Cow<'w, str>),
     Index(usize),
@@ -118,6 +142,60 @@ impl<'w> Route<'w> {
         components.extend(keys.iter().cloned());
         Route { components }
     }
+
+    /// Parses a dotted key path, like `steps[2].with.path`, into a route.
+    ///
+    /// Each `.`-separated key may be followed by any number of `[N]`
+    /// indices. A segment whose indices are malformed is taken to be a
+    /// single key, verbatim.
+    fn parse(path: &'w str) -> Route<'w> {
+        let mut components = vec![];
+
+        for segment in path.split('.').filter(|segment| !segment.is_empty()) {
+            let (key, mut rest) = segment.split_at(segment.find('[').unwrap_or(segment.len()));
+
+            let mut indices = vec![];
+            while let Some((index, remaining)) = rest
+                .strip_prefix('[')
+                .and_then(|rest| rest.split_once(']'))
+                .and_then(|(index, remaining)| Some((index.parse().ok()?, remaining)))
+            {
+                indices.push(RouteComponent::Index(index));
+                rest = remaining;
+            }
+
+            if !rest.is_empty() {
+                components.push(segment.into());
+                continue;
+            }
+
+            if !key.is_empty() {
+                components.push(key.into());
+            }
+            components.extend(indices);
+        }
+
+        Route { components }
+    }
+}
+
+/// Renders a route like `jobs.test.steps[0].run`, for diagnostics.
+impl Display for Route<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.components.is_empty() {
+            return write!(f, "<root>");
+        }
+
+        for (idx, component) in self.components.iter().enumerate() {
+            match component {
+                RouteComponent::Key(key) if idx == 0 => write!(f, "{key}")?,
+                RouteComponent::Key(key) => write!(f, ".{key}")?,
+                RouteComponent::Index(index) => write!(f, "[{index}]")?,
+            }
+        }
+
+        Ok(())
+    }
 }
 
 /// Represents a symbolic workflow location.
@@ -137,6 +215,25 @@ pub(crate) struct SymbolicLocation<'w> {
 
     /// A symbolic route (of keys and indices) to the final location.
     pub(crate) route: Route<'w>,
+
+    /// An exact substring of the routed feature to narrow the location to,
+    /// e.g. a single expression within a `run:` block.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) subfeature: Option<String>,
+
+    /// An exact substring of the routed feature to find `subfeature` within,
+    /// e.g. the line of a `run:` block that the expression is on.
+    #[serde(skip_serializing)]
+    pub(crate) subfeature_within: Option<String>,
+
+    /// Which occurrence (from zero) of `subfeature_within`, or of
+    /// `subfeature` if there's no `subfeature_within`, to narrow to.
+    #[serde(skip_serializing)]
+    pub(crate) occurrence: usize,
+
+    /// A route to use instead, if `route` doesn't exist in the input.
+    #[serde(skip_serializing)]
+    pub(crate) fallback: Option<Route<'w>>,
 }
 
 impl<'w> SymbolicLocation<'w> {
@@ -146,9 +243,28 @@ impl<'w> SymbolicLocation<'w> {
             annotation: self.annotation.clone(),
             link: None,
             route: self.route.with_keys(keys),
+            subfeature: None,
+            subfeature_within: None,
+            occurrence: 0,
+            fallback: None,
+        }
+    }
+
+    /// Routes to the `with: { name: ... }` input of the current step,
+    /// falling back to the step itself if the input isn't present.
+    pub(crate) fn with_input(&self, name: &'w str) -> SymbolicLocation<'w> {
+        SymbolicLocation {
+            fallback: Some(self.route.clone()),
+            ..self.with_keys(&["with".into(), name.into()])
         }
     }
 
+    /// Routes to the given dotted key path beneath the current location,
+    /// e.g. `container.credentials` or `steps[0].with.path`.
+    pub(crate) fn with_path(&self, path: &'w str) -> SymbolicLocation<'w> {
+        self.with_keys(&Route::parse(path).components)
+    }
+
     pub(crate) fn with_job(&self, job: &Job<'w>) -> SymbolicLocation<'w> {
         self.with_keys(&["jobs".into(), job.id.into()])
     }
@@ -163,34 +279,104 @@ impl<'w> SymbolicLocation<'w> {
         self
     }
 
+    /// Narrows the current `SymbolicLocation` to the first occurrence of
+    /// `subfeature` within its feature.
+    pub(crate) fn subfeature(mut self, subfeature: impl Into<String>) -> SymbolicLocation<'w> {
+        self.subfeature = Some(subfeature.into());
+        self
+    }
+
+    /// Narrows the current `SymbolicLocation` to the first occurrence of
+    /// `subfeature` within the first occurrence of `within`, for when the
+    /// same `subfeature` may appear more than once in the feature.
+    pub(crate) fn subfeature_within(
+        mut self,
+        within: impl Into<String>,
+        subfeature: impl Into<String>,
+    ) -> SymbolicLocation<'w> {
+        self.subfeature_within = Some(within.into());
+        self.subfeature(subfeature)
+    }
+
+    /// Narrows the current `SymbolicLocation` to the `n`th occurrence (from
+    /// zero) of its subfeature, or of the text it's found within, rather
+    /// than the first. This distinguishes e.g. identical lines in a `run:`
+    /// block.
+    pub(crate) fn occurrence(mut self, n: usize) -> SymbolicLocation<'w> {
+        self.occurrence = n;
+        self
+    }
+
     /// Adds a URL to the current `SymbolicLocation`.
     pub(crate) fn with_url(mut self, url: impl Into<String>) -> SymbolicLocation<'w> {
         self.link = Some(Link::new(&self.annotation, &url.into()).to_string());
         self
     }
 
+    /// Returns the source comments within this `SymbolicLocation`'s span,
+    /// e.g. a trailing `# v4.1.1` on a `uses:` line.
+    pub(crate) fn comments(&self, input: &'w impl AuditInput) -> Result<Vec<Comment<'w>>> {
+        Ok(Locator::new().concretize(input, self)?.comments)
+    }
+
     /// Concretize this `SymbolicLocation`, consuming it in the process.
-    pub(crate) fn concretize(self, workflow: &'w Workflow) -> Result<Location<'w>> {
-        let feature = Locator::new().concretize(workflow, &self)?;
+    ///
+    /// If this location can't be found (e.g. due to a mismatch between the
+    /// parsed model and the YAML source), this degrades to the nearest
+    /// enclosing feature that can, noting as much in the location's
+    /// annotation. The document's root always exists, so this only fails
+    /// if even that can't be concretized.
+    pub(crate) fn concretize(self, input: &'w impl AuditInput) -> Result<Location<'w>> {
+        let err = match Locator::new().concretize(input, &self) {
+            Ok(feature) => return Ok(Location::new(self, feature)),
+            Err(err) => err,
+        };
 
-        Ok(Location {
-            symbolic: self,
-            concrete: feature,
-        })
+        let mut nearest = SymbolicLocation {
+            annotation: format!("{} (location unavailable)", self.annotation),
+            subfeature: None,
+            subfeature_within: None,
+            occurrence: 0,
+            fallback: None,
+            ..self
+        };
+        while nearest.route.components.pop().is_some() {
+            if let Ok(feature) = Locator::new().concretize(input, &nearest) {
+                tracing::warn!("{err:#}; using {route} instead", route = nearest.route);
+                return Ok(Location::new(nearest, feature));
+            }
+        }
+
+        Err(err)
     }
 }
 
 /// Represents a `(row, column)` point within a file.
-#[derive(Serialize)]
+///
+/// Both components are 0-based; use [`Point::line`] and [`Point::col`]
+/// for the 1-based equivalents expected by most consumers.
+#[derive(Serialize, Clone)]
 pub(crate) struct Point {
     pub(crate) row: usize,
     pub(crate) column: usize,
 }
 
+impl Point {
+    /// Returns the 1-based line number for this point.
+    pub(crate) fn line(&self) -> usize {
+        self.row + 1
+    }
+
+    /// Returns the 1-based column number for this point.
+    pub(crate) fn col(&self) -> usize {
+        self.column + 1
+    }
+}
+
 /// A "concrete" location for some feature.
 /// Every concrete location contains two spans: a line-and-column span,
 /// and an offset range.
-#[derive(Serialize)]
+#[derive(Serialize, Clone)]
 pub(crate) struct ConcreteLocation {
     pub(crate) start_point: Point,
     pub(crate) end_point: Point,
@@ -198,6 +384,30 @@ pub(crate) struct ConcreteLocation {
     pub(crate) end_offset: usize,
 }
 
+/// A location's 1-based line-and-column span, as reported to users.
+///
+/// This is resolved once, when a location is concretized, so that every
+/// renderer (and the library API) reports the same position without
+/// re-deriving it.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct LineSpan {
+    pub(crate) start_line: usize,
+    pub(crate) start_col: usize,
+    pub(crate) end_line: usize,
+    pub(crate) end_col: usize,
+}
+
+impl From<&ConcreteLocation> for LineSpan {
+    fn from(location: &ConcreteLocation) -> Self {
+        Self {
+            start_line: location.start_point.line(),
+            start_col: location.start_point.col(),
+            end_line: location.end_point.line(),
+            end_col: location.end_point.col(),
+        }
+    }
+}
+
 impl From<&yamlpath::Location> for ConcreteLocation {
     fn from(value: &yamlpath::Location) -> Self {
         Self {
@@ -223,7 +433,12 @@ static IGNORE_EXPR: LazyLock<Regex> =
 #[serde(transparent)]
 pub(crate) struct Comment<'w>(&'w str);
 
-impl Comment<'_> {
+impl<'w> Comment<'w> {
+    /// Returns this comment's text, including the leading `#`.
+    pub(crate) fn as_str(&self) -> &'w str {
+        self.0
+    }
+
     fn ignores(&self, rule_id: &str) -> bool {
         // Extracts foo,bar from `# zizmor: ignore[foo,bar]`
         let Some(caps) = IGNORE_EXPR.captures(self.0) else {
@@ -259,6 +474,15 @@ pub(crate) struct Feature<'w> {
     pub(crate) parent_feature: &'w str,
 }
 
+impl<'w> Feature<'w> {
+    /// Returns the feature's text at exactly [`Feature::location`]'s
+    /// offsets, i.e. without any leading indentation on its first line.
+    pub(crate) fn spanned(&self) -> &'w str {
+        let len = self.location.end_offset - self.location.start_offset;
+        &self.feature[self.feature.len() - len..]
+    }
+}
+
 /// A location within a GitHub Actions workflow, with both symbolic and concrete components.
 #[derive(Serialize)]
 pub(crate) struct Location<'w> {
@@ -266,6 +490,18 @@ pub(crate) struct Location<'w> {
     pub(crate) symbolic: SymbolicLocation<'w>,
     /// The concrete location, including extracted feature.
     pub(crate) concrete: Feature<'w>,
+    /// The concrete location's line-and-column span.
+    pub(crate) span: LineSpan,
+}
+
+impl<'w> Location<'w> {
+    fn new(symbolic: SymbolicLocation<'w>, concrete: Feature<'w>) -> Self {
+        Self {
+            span: (&concrete.location).into(),
+            symbolic,
+            concrete,
+        }
+    }
 }
 
 /// A finding's "determination," i.e. its various classifications.
@@ -273,7 +509,7 @@ pub(crate) struct Location<'w> {
 pub(crate) struct Determinations {
     pub(crate) confidence: Confidence,
     pub(crate) severity: Severity,
-    pub(super) persona: Persona,
+    pub(crate) persona: Persona,
 }
 
 #[derive(Serialize)]
@@ -284,8 +520,210 @@ pub(crate) struct Finding<'w> {
     pub(crate) determinations: Determinations,
     pub(crate) locations: Vec<Location<'w>>,
     pub(crate) ignored: bool,
+    fingerprint: String,
+    /// Other audits whose findings at the same location were merged into
+    /// this one, with `--dedupe`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) also_reported_by: Vec<&'static str>,
+    /// A suggested fix for this finding, if the audit has one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) remediation: Option<Remediation>,
+    /// URLs with more information about this specific finding, like an
+    /// advisory or a relevant section of GitHub's documentation.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) references: Vec<String>,
+    /// Audit-specific details of how the audit reached this finding, e.g.
+    /// the version that an action's ref was resolved to.
+    #[serde(skip_serializing_if = "IndexMap::is_empty")]
+    pub(crate) properties: IndexMap<&'static str, String>,
+}
+
+/// A suggested fix for a [`Finding`].
+#[derive(Clone, Serialize)]
+pub(crate) struct Remediation {
+    /// A human-readable description of the fix.
+    pub(crate) description: String,
+    /// If the fix is mechanical, the text that should replace the
+    /// feature of the finding's first location.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) replacement: Option<String>,
+}
+
+/// Computes a stable, short hex fingerprint for a finding.
+///
+/// The fingerprint covers the audit's ident and, for each location,
+/// its relative path (and document index, within multi-document files),
+/// symbolic route (i.e. job ID, step index, and so on), and feature text
+/// (with each line's surrounding whitespace removed). Byte offsets and
+/// indentation are deliberately excluded, so that the fingerprint survives
+/// unrelated edits elsewhere in the same file.
+fn fingerprint(ident: &str, locations: &[Location]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(ident);
+
+    for location in locations {
+        hasher.update([0]);
+        hasher.update(location.symbolic.key.relative_path());
+        if let Some(document) = location.symbolic.key.document() {
+            hasher.update(document.to_string());
+        }
+
+        for component in &location.symbolic.route.components {
+            hasher.update([0]);
+            match component {
+                RouteComponent::Key(key) => hasher.update(key.as_bytes()),
+                RouteComponent::Index(idx) => hasher.update(idx.to_string()),
+            }
+        }
+
+        for line in location.concrete.feature.lines() {
+            hasher.update([0]);
+            hasher.update(line.trim());
+        }
+    }
+
+    hasher.finalize()[..8]
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+impl Finding<'_> {
+    /// Returns a stable, short hex fingerprint for this finding.
+    ///
+    /// See [`fingerprint`] for what the fingerprint covers.
+    pub(crate) fn fingerprint(&self) -> &str {
+        &self.fingerprint
+    }
+
+    /// Returns this finding's primary (i.e. first) location, which is the
+    /// one that single-position formats report.
+    pub(crate) fn primary_location(&self) -> &Location<'_> {
+        // NOTE: Every finding has at least one location.
+        &self.locations[0]
+    }
+
+    /// Returns this finding's description, noting any other audits that
+    /// reported the same location.
+    pub(crate) fn summary(&self) -> Cow<'static, str> {
+        if self.also_reported_by.is_empty() {
+            self.desc.into()
+        } else {
+            format!(
+                "{desc} (also reported by {others})",
+                desc = self.desc,
+                others = self.also_reported_by.join(", ")
+            )
+            .into()
+        }
+    }
+
+    /// Returns the key that identifies the issue this finding reports, for
+    /// de-duplication: its fingerprint without the audit's ident (so that
+    /// different audits can agree on it), along with the file and span of
+    /// its first location.
+    ///
+    /// Findings that merely share their first location, but differ
+    /// elsewhere, are different issues and have different keys.
+    fn dedupe_key(&self) -> (String, &str, usize, usize) {
+        let location = &self.primary_location().concrete.location;
+        (
+            fingerprint("", &self.locations),
+            self.primary_location().symbolic.key.path(),
+            location.start_offset,
+            location.end_offset,
+        )
+    }
+
+    /// Returns this finding's key within zizmor's canonical output order:
+    /// by file and position (of the first location), then by audit
+    /// and severity.
+    fn sort_key(&self) -> (&str, usize, usize, &'static str, Severity) {
+        let location = self.primary_location();
+
+        (
+            location.symbolic.key.path(),
+            location.span.start_line,
+            location.span.start_col,
+            self.ident,
+            self.determinations.severity,
+        )
+    }
 }
 
+/// Sorts the given findings into zizmor's canonical output order.
+///
+/// See [`Finding::sort_key`].
+pub(crate) fn sort(findings: &mut [Finding]) {
+    findings.sort_by(|a, b| a.sort_key().cmp(&b.sort_key()));
+}
+
+/// Collapses findings that report the same issue into a single finding.
+///
+/// Findings report the same issue when they have the same fingerprint
+/// (ignoring which audit produced them) and primary location; see
+/// [`Finding::dedupe_key`].
+///
+/// The most severe (and then most confident) finding for each issue is
+/// kept, in the position of the issue's first finding. The others'
+/// idents are recorded in [`Finding::also_reported_by`], and their
+/// locations are added to the kept finding unless they're identical to
+/// one it already has, so that no annotations are lost.
+pub(crate) fn dedupe(findings: Vec<Finding<'_>>) -> Vec<Finding<'_>> {
+    let mut groups: Vec<Vec<Finding>> = vec![];
+    for finding in findings {
+        match groups
+            .iter_mut()
+            .find(|group| group[0].dedupe_key() == finding.dedupe_key())
+        {
+            Some(group) => group.push(finding),
+            None => groups.push(vec![finding]),
+        }
+    }
+
+    groups
+        .into_iter()
+        .map(|mut group| {
+            // NOTE: `max_by_key` keeps the last maximum, so we search
+            // in reverse so that earlier findings win ties.
+            let best = group
+                .iter()
+                .enumerate()
+                .rev()
+                .max_by_key(|(_, f)| (f.determinations.severity, f.determinations.confidence))
+                .map(|(idx, _)| idx)
+                .unwrap();
+            let mut kept = group.remove(best);
+
+            for other in group {
+                let idents = std::iter::once(other.ident).chain(other.also_reported_by);
+                for ident in idents {
+                    if ident != kept.ident && !kept.also_reported_by.contains(&ident) {
+                        kept.also_reported_by.push(ident);
+                    }
+                }
+
+                for location in other.locations {
+                    let duplicate = kept.locations.iter().any(|l| {
+                        l.concrete.location.start_offset == location.concrete.location.start_offset
+                            && l.concrete.location.end_offset
+                                == location.concrete.location.end_offset
+                            && l.symbolic.annotation == location.symbolic.annotation
+                    });
+                    if !duplicate {
+                        kept.locations.push(location);
+                    }
+                }
+            }
+
+            kept
+        })
+        .collect()
+}
+
+/// Computes a [`Remediation::replacement`] from a finding's feature.
+type Replace<'w> = Box<dyn FnOnce(&str) -> Option<String> + 'w>;
+
 pub(crate) struct FindingBuilder<'w> {
     ident: &'static str,
     desc: &'static str,
@@ -294,6 +732,10 @@ pub(crate) struct FindingBuilder<'w> {
     confidence: Confidence,
     persona: Persona,
     locations: Vec<SymbolicLocation<'w>>,
+    remediation: Option<String>,
+    replacement: Option<Replace<'w>>,
+    references: Vec<String>,
+    properties: IndexMap<&'static str, String>,
 }
 
 impl<'w> FindingBuilder<'w> {
@@ -306,6 +748,10 @@ impl<'w> FindingBuilder<'w> {
             confidence: Default::default(),
             persona: Default::default(),
             locations: vec![],
+            remediation: None,
+            replacement: None,
+            references: vec![],
+            properties: IndexMap::new(),
         }
     }
 
@@ -329,16 +775,58 @@ impl<'w> FindingBuilder<'w> {
         self
     }
 
-    pub(crate) fn build(self, workflow: &'w Workflow) -> Result<Finding<'w>> {
+    /// Attaches a human-readable suggested fix to the finding.
+    pub(crate) fn remediation(mut self, description: impl Into<String>) -> Self {
+        self.remediation = Some(description.into());
+        self
+    }
+
+    /// Makes the finding's remediation mechanical: `replace` is given the
+    /// feature of the finding's first location (without its leading
+    /// indentation, see [`Feature::spanned`]), and returns the text that
+    /// should replace it.
+    ///
+    /// Has no effect without a [`FindingBuilder::remediation`], or if
+    /// `replace` returns `None`.
+    pub(crate) fn replacement(mut self, replace: impl FnOnce(&str) -> Option<String> + 'w) -> Self {
+        self.replacement = Some(Box::new(replace));
+        self
+    }
+
+    /// Adds a URL with more information about the finding.
+    pub(crate) fn reference(mut self, url: impl Into<String>) -> Self {
+        self.references.push(url.into());
+        self
+    }
+
+    /// Records an audit-specific detail of the finding, under `name`.
+    pub(crate) fn property(mut self, name: &'static str, value: impl Into<String>) -> Self {
+        self.properties.insert(name, value.into());
+        self
+    }
+
+    pub(crate) fn build(self, input: &'w impl AuditInput) -> Result<Finding<'w>> {
         let locations = self
             .locations
             .iter()
-            .map(|l| l.clone().concretize(workflow))
+            .map(|l| {
+                l.clone().concretize(input).with_context(|| {
+                    format!("{ident}: couldn't locate finding", ident = self.ident)
+                })
+            })
             .collect::<Result<Vec<_>>>()?;
 
         let should_ignore = self.ignored_from_inlined_comment(&locations, self.ident);
 
+        let remediation = self.remediation.map(|description| Remediation {
+            description,
+            replacement: self
+                .replacement
+                .and_then(|replace| replace(locations.first()?.concrete.spanned())),
+        });
+
         Ok(Finding {
+            fingerprint: fingerprint(self.ident, &locations),
             ident: self.ident,
             desc: self.desc,
             url: self.url,
@@ -349,6 +837,10 @@ impl<'w> FindingBuilder<'w> {
             },
             locations,
             ignored: should_ignore,
+            also_reported_by: vec![],
+            remediation,
+            references: self.references,
+            properties: self.properties,
         })
     }
 
@@ -362,7 +854,43 @@ impl<'w> FindingBuilder<'w> {
 
 #[cfg(test)]
 mod tests {
-    use crate::finding::Comment;
+    use crate::{
+        finding::{
+            dedupe, locate::Locator, Comment, ConcreteLocation, Confidence, FindingBuilder,
+            LineSpan, Point, Route, RouteComponent, Severity,
+        },
+        models::Workflow,
+        registry::WorkflowKey,
+    };
+
+    #[test]
+    fn test_line_span_one_based() {
+        let point = Point { row: 0, column: 0 };
+        assert_eq!((point.line(), point.col()), (1, 1));
+
+        let point = Point { row: 9, column: 4 };
+        assert_eq!((point.line(), point.col()), (10, 5));
+
+        let span = LineSpan::from(&ConcreteLocation {
+            start_point: Point { row: 2, column: 6 },
+            end_point: Point { row: 4, column: 0 },
+            start_offset: 30,
+            end_offset: 60,
+        });
+        assert_eq!(
+            (span.start_line, span.start_col, span.end_line, span.end_col),
+            (3, 7, 5, 1)
+        );
+    }
+
+    #[test]
+    fn test_severity_ordering() {
+        assert!(Severity::Unknown < Severity::Informational);
+        assert!(Severity::Informational < Severity::Low);
+        assert!(Severity::Low < Severity::Medium);
+        assert!(Severity::Medium < Severity::High);
+        assert!(Severity::High < Severity::Critical);
+    }
 
     #[test]
     fn test_comment_ignores() {
@@ -402,4 +930,278 @@ mod tests {
             )
         }
     }
+
+    #[test]
+    fn test_sort() {
+        let workflow = Workflow::from_string(
+            "on: push\njobs:\n  a:\n    runs-on: ubuntu-latest\n    steps:\n      - run: one\n  b:\n    runs-on: ubuntu-latest\n    steps:\n      - run: two\n".into(),
+            WorkflowKey::local("/tmp/test.yml".into()).unwrap(),
+        )
+        .unwrap();
+
+        let finding = |ident, job, severity| {
+            FindingBuilder::new(ident, "", "")
+                .severity(severity)
+                .add_location(workflow.location().with_keys(&[
+                    "jobs".into(),
+                    job,
+                    "steps".into(),
+                    0.into(),
+                ]))
+                .build(&workflow)
+                .unwrap()
+        };
+
+        let expected = [
+            ("audit-a", "a", Severity::Low),
+            ("audit-a", "a", Severity::High),
+            ("audit-b", "a", Severity::Low),
+            ("audit-a", "b", Severity::Medium),
+            ("audit-b", "b", Severity::Low),
+        ];
+
+        // Every rotation (and its reverse) of the input sorts identically.
+        for rotation in 0..expected.len() {
+            for reverse in [false, true] {
+                let mut input = expected.to_vec();
+                input.rotate_left(rotation);
+                if reverse {
+                    input.reverse();
+                }
+
+                let mut findings = input
+                    .iter()
+                    .map(|(ident, job, severity)| finding(ident, (*job).into(), *severity))
+                    .collect::<Vec<_>>();
+                super::sort(&mut findings);
+
+                let sorted = findings
+                    .iter()
+                    .map(|f| {
+                        (
+                            f.ident,
+                            f.locations[0].concrete.location.start_point.row,
+                            f.determinations.severity,
+                        )
+                    })
+                    .collect::<Vec<_>>();
+
+                assert_eq!(
+                    sorted,
+                    [
+                        ("audit-a", 5, Severity::Low),
+                        ("audit-a", 5, Severity::High),
+                        ("audit-b", 5, Severity::Low),
+                        ("audit-a", 9, Severity::Medium),
+                        ("audit-b", 9, Severity::Low),
+                    ],
+                    "rotation {rotation}, reversed: {reverse}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        let key = || WorkflowKey::local("/tmp/test.yml".into()).unwrap();
+        let compact = Workflow::from_string(
+            "on: push\njobs:\n  a:\n    runs-on: ubuntu-latest\n    steps:\n      - run: echo hello\n  b:\n    runs-on: ubuntu-latest\n    steps:\n      - run: echo hello\n".into(),
+            key(),
+        )
+        .unwrap();
+        // The same workflow, shifted down and indented differently.
+        let spacious = Workflow::from_string(
+            "# a comment\n\non: push\n\njobs:\n    a:\n        runs-on: ubuntu-latest\n        steps:\n            -   run: echo hello\n    b:\n        runs-on: ubuntu-latest\n        steps:\n            -   run: echo hello\n".into(),
+            key(),
+        )
+        .unwrap();
+
+        let fingerprint = |workflow: &Workflow, ident, job: &str| {
+            FindingBuilder::new(ident, "", "")
+                .add_location(workflow.location().with_keys(&[
+                    "jobs".into(),
+                    job.into(),
+                    "steps".into(),
+                    0.into(),
+                    "run".into(),
+                ]))
+                .build(workflow)
+                .unwrap()
+                .fingerprint()
+                .to_string()
+        };
+
+        let fp = fingerprint(&compact, "audit", "a");
+        assert_eq!(fp.len(), 16);
+        assert!(fp.chars().all(|c| c.is_ascii_hexdigit()));
+
+        // Offsets and indentation don't matter...
+        assert_eq!(fp, fingerprint(&spacious, "audit", "a"));
+        // ...but the audit and the finding's structural position do.
+        assert_ne!(fp, fingerprint(&compact, "other-audit", "a"));
+        assert_ne!(fp, fingerprint(&compact, "audit", "b"));
+    }
+
+    #[test]
+    fn test_dedupe() {
+        let workflow = Workflow::from_string(
+            "on: push\njobs:\n  a:\n    runs-on: ubuntu-latest\n    steps:\n      - uses: actions/checkout@v4\n      - run: echo hello\n".into(),
+            WorkflowKey::local("/tmp/test.yml".into()).unwrap(),
+        )
+        .unwrap();
+        let step = |idx: usize| {
+            workflow
+                .location()
+                .with_keys(&["jobs".into(), "a".into(), "steps".into(), idx.into()])
+        };
+        let finding = |ident, severity, confidence, idx, annotation: &str| {
+            FindingBuilder::new(ident, "", "")
+                .severity(severity)
+                .confidence(confidence)
+                .add_location(step(idx).annotated(annotation))
+                .build(&workflow)
+                .unwrap()
+        };
+
+        // Shares its first location with the others, but also points
+        // elsewhere, so it's a different issue.
+        let broader = FindingBuilder::new("fifth", "", "")
+            .severity(Severity::High)
+            .confidence(Confidence::High)
+            .add_location(step(0).annotated("four"))
+            .add_location(step(1).annotated("five"))
+            .build(&workflow)
+            .unwrap();
+
+        let findings = dedupe(vec![
+            finding("first", Severity::Low, Confidence::High, 0, "one"),
+            finding("second", Severity::High, Confidence::Low, 0, "two"),
+            finding("third", Severity::High, Confidence::Low, 0, "two"),
+            finding("fourth", Severity::Medium, Confidence::High, 1, "three"),
+            broader,
+        ]);
+
+        assert_eq!(findings.len(), 3);
+
+        // The most severe finding wins, with ties going to the earliest.
+        assert_eq!(findings[0].ident, "second");
+        assert_eq!(findings[0].also_reported_by, ["first", "third"]);
+        // Distinct annotations are kept; identical ones are merged.
+        let annotations = findings[0]
+            .locations
+            .iter()
+            .map(|l| l.symbolic.annotation.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(annotations, ["two", "one"]);
+
+        // Findings elsewhere are untouched.
+        assert_eq!(findings[1].ident, "fourth");
+        assert!(findings[1].also_reported_by.is_empty());
+
+        // So are different issues at the same location.
+        assert_eq!(findings[2].ident, "fifth");
+        assert!(findings[2].also_reported_by.is_empty());
+    }
+
+    #[test]
+    fn test_unresolvable_location() {
+        let workflow = Workflow::from_string(
+            "on: push\njobs:\n  test:\n    runs-on: ubuntu-latest\n    steps:\n      - run: echo hello\n".into(),
+            WorkflowKey::local("/tmp/test.yml".into()).unwrap(),
+        )
+        .unwrap();
+        let missing = workflow
+            .location()
+            .with_keys(&[
+                "jobs".into(),
+                "test".into(),
+                "steps".into(),
+                3.into(),
+                "run".into(),
+            ])
+            .annotated("hello");
+
+        // Locating the feature directly is an error, with context...
+        let err = Locator::new()
+            .concretize(&workflow, &missing)
+            .err()
+            .unwrap();
+        assert!(format!("{err:#}")
+            .starts_with("couldn't locate jobs.test.steps[3].run in /tmp/test.yml"));
+
+        // ...but a finding there degrades to the nearest enclosing feature.
+        let finding = FindingBuilder::new("audit", "", "")
+            .add_location(missing)
+            .build(&workflow)
+            .unwrap();
+        let location = &finding.locations[0];
+        assert_eq!(location.symbolic.route.to_string(), "jobs.test.steps");
+        assert_eq!(location.symbolic.annotation, "hello (location unavailable)");
+        assert!(location.concrete.feature.contains("- run: echo hello"));
+    }
+
+    #[test]
+    fn test_route_parse() {
+        for (path, expected) in [
+            ("permissions", "permissions"),
+            ("jobs.x.runs-on", "jobs.x.runs-on"),
+            ("steps[2].with.path", "steps[2].with.path"),
+            ("matrix[0][1]", "matrix[0][1]"),
+            ("[3].run", "[3].run"),
+            ("", "<root>"),
+            // Malformed indices are kept as (odd) keys.
+            ("steps[x].run", "steps[x].run"),
+        ] {
+            assert_eq!(Route::parse(path).to_string(), expected, "{path}");
+        }
+
+        let route = Route::parse("steps[x].run");
+        assert!(matches!(&route.components[0], RouteComponent::Key(key) if key == "steps[x]"));
+    }
+
+    #[test]
+    fn test_remediation() {
+        let workflow = Workflow::from_string(
+            "on: push\njobs:\n  a:\n    runs-on: ubuntu-latest\n    steps:\n      - uses: actions/checkout@v4\n".into(),
+            WorkflowKey::local("/tmp/test.yml".into()).unwrap(),
+        )
+        .unwrap();
+        let finding = |from: &'static str| {
+            FindingBuilder::new("audit", "", "")
+                .add_location(workflow.location().with_keys(&[
+                    "jobs".into(),
+                    "a".into(),
+                    "steps".into(),
+                    0.into(),
+                    "uses".into(),
+                ]))
+                .remediation("pin it")
+                .replacement(move |feature| {
+                    feature
+                        .contains(from)
+                        .then(|| feature.replacen(from, "@abcd", 1))
+                })
+                .build(&workflow)
+                .unwrap()
+        };
+
+        let remediation = finding("@v4").remediation.unwrap();
+        assert_eq!(remediation.description, "pin it");
+        assert_eq!(
+            remediation.replacement.as_deref(),
+            Some("uses: actions/checkout@abcd")
+        );
+
+        // A replacement that doesn't apply to the feature is dropped.
+        let remediation = finding("@v3").remediation.unwrap();
+        assert_eq!(remediation.replacement, None);
+
+        // No remediation means no replacement, either.
+        let finding = FindingBuilder::new("audit", "", "")
+            .add_location(workflow.location())
+            .replacement(|feature| Some(feature.replace("push", "pull_request")))
+            .build(&workflow)
+            .unwrap();
+        assert!(finding.remediation.is_none());
+    }
 }
@@ -0,0 +1,288 @@
+//! Applies findings' mechanical remediations to local files, for `--fix`.
+
+use std::{
+    fs,
+    io::{self, Write},
+};
+
+use anyhow::{Context, Result};
+use camino::Utf8Path;
+use indexmap::IndexMap;
+use owo_colors::OwoColorize;
+
+use crate::{
+    finding::{Confidence, Finding},
+    output::AtomicFile,
+};
+
+/// A single replacement within a file.
+struct Edit<'a> {
+    start: usize,
+    end: usize,
+    /// The text expected at `start..end`, i.e. the finding's feature
+    /// without its leading indentation.
+    feature: &'a str,
+    replacement: &'a str,
+}
+
+/// Returns the edits for every finding that has a mechanical remediation
+/// and at least the given confidence, grouped by the file they apply to.
+///
+/// Only local files can be fixed.
+fn collect<'a>(
+    findings: &'a [Finding],
+    min_confidence: Confidence,
+) -> IndexMap<&'a Utf8Path, Vec<Edit<'a>>> {
+    let mut edits: IndexMap<_, Vec<_>> = IndexMap::new();
+
+    for finding in findings {
+        if finding.determinations.confidence < min_confidence {
+            continue;
+        }
+
+        let Some(replacement) = finding
+            .remediation
+            .as_ref()
+            .and_then(|remediation| remediation.replacement.as_deref())
+        else {
+            continue;
+        };
+
+        // NOTE: Replacements always apply to the first location.
+        let location = &finding.locations[0];
+        let Some(path) = location.symbolic.key.local_path() else {
+            continue;
+        };
+
+        edits.entry(path).or_default().push(Edit {
+            start: location.concrete.location.start_offset,
+            end: location.concrete.location.end_offset,
+            feature: location.concrete.spanned(),
+            replacement,
+        });
+    }
+
+    edits
+}
+
+/// Selects the edits that can be applied to `contents`, in order.
+///
+/// Edits whose text no longer matches the feature (e.g. because the file
+/// changed after it was audited), or that overlap an earlier edit, are
+/// dropped.
+fn applicable<'a>(path: &Utf8Path, contents: &str, mut edits: Vec<Edit<'a>>) -> Vec<Edit<'a>> {
+    edits.sort_by_key(|edit| (edit.start, edit.end));
+
+    let mut applicable: Vec<Edit> = vec![];
+    for edit in edits {
+        if contents.get(edit.start..edit.end) != Some(edit.feature) {
+            tracing::warn!("{path} changed after it was audited; not fixing it");
+            return vec![];
+        }
+
+        if applicable.last().is_some_and(|prev| edit.start < prev.end) {
+            tracing::debug!("{path}: skipping overlapping fix at offset {}", edit.start);
+            continue;
+        }
+
+        applicable.push(edit);
+    }
+
+    applicable
+}
+
+/// Returns `contents` with the given (applicable) edits applied.
+fn splice(contents: &str, edits: &[Edit]) -> String {
+    // Splice from the end, so that earlier offsets remain valid.
+    let mut updated = contents.to_string();
+    for edit in edits.iter().rev() {
+        updated.replace_range(edit.start..edit.end, edit.replacement);
+    }
+
+    updated
+}
+
+/// Returns the byte range of the complete lines spanned by `start..end`.
+fn line_range(contents: &str, start: usize, end: usize) -> (usize, usize) {
+    let line_start = contents[..start].rfind('\n').map_or(0, |idx| idx + 1);
+    let line_end = contents[end..]
+        .find('\n')
+        .map_or(contents.len(), |idx| end + idx);
+
+    (line_start, line_end)
+}
+
+/// Renders the given edits to `contents` as a unified diff, without
+/// context lines.
+fn render_diff(
+    path: &Utf8Path,
+    contents: &str,
+    edits: &[Edit],
+    out: &mut impl Write,
+) -> io::Result<()> {
+    writeln!(out, "{}", format!("--- {path}").bold())?;
+    writeln!(out, "{}", format!("+++ {path}").bold())?;
+
+    // Each edit may change the number of lines, shifting the
+    // new file's line numbers for every edit after it.
+    let mut shift = 0isize;
+    for edit in edits {
+        let (line_start, line_end) = line_range(contents, edit.start, edit.end);
+        let old = &contents[line_start..line_end];
+        let new = format!(
+            "{before}{replacement}{after}",
+            before = &contents[line_start..edit.start],
+            replacement = edit.replacement,
+            after = &contents[edit.end..line_end]
+        );
+
+        let old_line = contents[..line_start].matches('\n').count() + 1;
+        let (old_len, new_len) = (old.lines().count(), new.lines().count());
+        writeln!(
+            out,
+            "{}",
+            format!(
+                "@@ -{old_line},{old_len} +{new_line},{new_len} @@",
+                new_line = old_line as isize + shift
+            )
+            .cyan()
+        )?;
+        for line in old.lines() {
+            writeln!(out, "{}", format!("-{line}").red())?;
+        }
+        for line in new.lines() {
+            writeln!(out, "{}", format!("+{line}").green())?;
+        }
+
+        shift += new_len as isize - old_len as isize;
+    }
+
+    Ok(())
+}
+
+/// Applies every applicable fix among the given findings, rewriting each
+/// affected file atomically and rendering a diff of the changes to `out`.
+///
+/// Returns the number of findings that were fixed.
+pub(crate) fn apply(
+    findings: &[Finding],
+    min_confidence: Confidence,
+    out: &mut impl Write,
+) -> Result<usize> {
+    let mut fixed = 0;
+
+    for (path, edits) in collect(findings, min_confidence) {
+        let contents =
+            fs::read_to_string(path).with_context(|| format!("couldn't read {path} to fix it"))?;
+
+        let edits = applicable(path, &contents, edits);
+        if edits.is_empty() {
+            continue;
+        }
+
+        let updated = splice(&contents, &edits);
+
+        let mut file = AtomicFile::create(path)?;
+        file.write_all(updated.as_bytes())?;
+        file.persist()?;
+
+        render_diff(path, &contents, &edits, out)?;
+        fixed += edits.len();
+    }
+
+    Ok(fixed)
+}
+
+#[cfg(test)]
+mod tests {
+    use camino::Utf8Path;
+
+    use super::{applicable, collect, render_diff, splice, Edit};
+    use crate::{
+        finding::{Confidence, FindingBuilder},
+        models::Workflow,
+        registry::WorkflowKey,
+    };
+
+    #[test]
+    fn test_applicable_and_diff() {
+        let contents = "steps:\n  - uses: foo/bar@v1\n  - uses: foo/baz@v2\n";
+        let edit = |feature: &'static str, replacement| {
+            let start = contents.find(feature).unwrap();
+            Edit {
+                start,
+                end: start + feature.len(),
+                feature,
+                replacement,
+            }
+        };
+
+        let path = Utf8Path::new("ci.yml");
+        let edits = applicable(
+            path,
+            contents,
+            vec![
+                edit("uses: foo/baz@v2", "uses: foo/baz@abcd # v2"),
+                edit("uses: foo/bar@v1", "uses: foo/bar@1234\n    # v1"),
+                // Overlaps the previous edit, so it's skipped.
+                edit("foo/bar@v1", "foo/bar@5678"),
+            ],
+        );
+        assert_eq!(edits.len(), 2);
+
+        let mut diff = vec![];
+        render_diff(path, contents, &edits, &mut diff).unwrap();
+        let diff = anstream::adapter::strip_str(std::str::from_utf8(&diff).unwrap()).to_string();
+        assert_eq!(
+            diff,
+            "--- ci.yml\n+++ ci.yml\n\
+             @@ -2,1 +2,2 @@\n-  - uses: foo/bar@v1\n+  - uses: foo/bar@1234\n+    # v1\n\
+             @@ -3,1 +4,1 @@\n-  - uses: foo/baz@v2\n+  - uses: foo/baz@abcd # v2\n"
+        );
+
+        // A stale feature means the file changed, so nothing is applied.
+        let stale = Edit {
+            feature: "uses: foo/bar@v3",
+            ..edit("uses: foo/bar@v1", "")
+        };
+        assert!(applicable(path, contents, vec![stale]).is_empty());
+    }
+
+    #[test]
+    fn test_fix_indented_feature() {
+        let contents = "on: push\njobs:\n  a:\n    runs-on: ubuntu-latest\n    steps:\n      - name: checkout\n        uses: actions/checkout@v4\n";
+        let workflow = Workflow::from_string(
+            contents.into(),
+            WorkflowKey::local("/tmp/test.yml".into()).unwrap(),
+        )
+        .unwrap();
+
+        // Not the first key of its step, so its feature is indented.
+        let finding = FindingBuilder::new("audit", "", "")
+            .confidence(Confidence::High)
+            .add_location(workflow.location().with_keys(&[
+                "jobs".into(),
+                "a".into(),
+                "steps".into(),
+                0.into(),
+                "uses".into(),
+            ]))
+            .remediation("pin it")
+            .replacement(|feature| Some(feature.replacen("@v4", "@abcd # v4", 1)))
+            .build(&workflow)
+            .unwrap();
+        assert!(finding.locations[0]
+            .concrete
+            .feature
+            .starts_with("        uses:"));
+
+        let findings = [finding];
+        let (path, edits) = collect(&findings, Confidence::High).pop().unwrap();
+        let edits = applicable(path, contents, edits);
+        assert_eq!(edits.len(), 1);
+        assert_eq!(
+            splice(contents, &edits),
+            contents.replace("checkout@v4", "checkout@abcd # v4")
+        );
+    }
+}
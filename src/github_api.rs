@@ -3,38 +3,308 @@
 //! Build on synchronous reqwest to avoid octocrab's need to taint
 //! the whole codebase with async.
 
+use std::{
+    cell::Cell,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
 use anyhow::{anyhow, Result};
+use indexmap::IndexSet;
+use indicatif::ProgressStyle;
 use reqwest::{
     blocking::{self},
-    header::{HeaderMap, ACCEPT, AUTHORIZATION, USER_AGENT},
-    StatusCode,
+    header::{
+        HeaderMap, HeaderValue, ACCEPT, AUTHORIZATION, ETAG, IF_NONE_MATCH, LINK, RETRY_AFTER,
+        USER_AGENT,
+    },
+    IntoUrl, StatusCode,
 };
-use serde::{de::DeserializeOwned, Deserialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use tracing::instrument;
+use tracing_indicatif::span_ext::IndicatifSpanExt;
 
 use crate::{
     models::{RepositoryUses, Workflow},
     registry::WorkflowKey,
     state::Caches,
-    utils::PipeSelf,
+    utils::{version_in_range, PipeSelf, Version},
 };
 
+/// The maximum number of concurrent requests made while prefetching.
+///
+/// This is deliberately conservative, since GitHub's secondary rate limits
+/// penalize bursts of concurrent requests.
+const PREFETCH_CONCURRENCY: usize = 8;
+
+thread_local! {
+    /// The number of GitHub API requests made by the current thread.
+    static API_CALLS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// The number of GitHub API requests made by all threads.
+static TOTAL_API_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+/// Returns the number of GitHub API requests made so far by every
+/// thread, across all clients.
+pub(crate) fn total_api_calls() -> usize {
+    TOTAL_API_CALLS.load(Ordering::Relaxed)
+}
+
+/// Returns the number of GitHub API requests made so far by the
+/// current thread, across all clients.
+///
+/// This is tracked per-thread so that requests can be attributed to
+/// individual audits, even when audits run concurrently.
+pub(crate) fn thread_api_calls() -> usize {
+    API_CALLS.with(Cell::get)
+}
+
+/// The longest we're willing to sleep for a rate limit to reset, before
+/// giving up on the request entirely.
+const MAX_RATE_LIMIT_WAIT: Duration = Duration::from_secs(15 * 60);
+
+/// How long to wait on a secondary rate limit that doesn't tell us
+/// when to retry, per GitHub's guidance.
+const DEFAULT_RATE_LIMIT_WAIT: Duration = Duration::from_secs(60);
+
+/// How the [`Client`] retries requests that fail transiently.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct RetryPolicy {
+    /// The maximum number of retries for a single request.
+    pub(crate) max_retries: u32,
+    /// Whether to sleep until a rate limit resets and then retry, rather
+    /// than failing immediately.
+    pub(crate) wait_on_rate_limit: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            wait_on_rate_limit: true,
+        }
+    }
+}
+
+/// Returns how long to wait before retrying a rate-limited request,
+/// per the response's `Retry-After` or `X-RateLimit-Reset` headers.
+fn rate_limit_wait(headers: &HeaderMap, now: SystemTime) -> Duration {
+    let header = |name| {
+        headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<u64>().ok())
+    };
+
+    if let Some(secs) = header(RETRY_AFTER.as_str()) {
+        return Duration::from_secs(secs);
+    }
+
+    match header("x-ratelimit-reset") {
+        Some(reset) => (UNIX_EPOCH + Duration::from_secs(reset))
+            .duration_since(now)
+            .unwrap_or_default(),
+        None => DEFAULT_RATE_LIMIT_WAIT,
+    }
+}
+
+/// Returns whether the given request error is likely transient, i.e.
+/// a timeout, a failure to connect, or a connection dropped mid-request.
+fn is_transient(err: &reqwest::Error) -> bool {
+    if err.is_timeout() || err.is_connect() {
+        return true;
+    }
+
+    let mut source = std::error::Error::source(err);
+    while let Some(err) = source {
+        if let Some(err) = err.downcast_ref::<std::io::Error>() {
+            return matches!(
+                err.kind(),
+                std::io::ErrorKind::ConnectionReset
+                    | std::io::ErrorKind::ConnectionAborted
+                    | std::io::ErrorKind::BrokenPipe
+            );
+        }
+        source = err.source();
+    }
+
+    false
+}
+
+/// Returns the exponential backoff for the given (0-based) retry attempt,
+/// plus up to a second of jitter.
+fn backoff(attempt: u32, now: SystemTime) -> Duration {
+    // NOTE: The clock's sub-second component is good enough jitter for
+    // spreading out retries; we don't need a real source of randomness.
+    let jitter = now
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .subsec_millis();
+
+    Duration::from_secs(1 << attempt.min(6)) + Duration::from_millis(jitter.into())
+}
+
+/// A single GitHub API token, along with what we last heard about its
+/// primary rate limit.
+struct Token {
+    authorization: HeaderValue,
+    /// Requests left before the rate limit resets, or `u64::MAX` if unknown.
+    remaining: AtomicU64,
+    /// When the rate limit resets, in seconds since the epoch.
+    reset: AtomicU64,
+}
+
+impl Token {
+    fn has_quota(&self, now: SystemTime) -> bool {
+        let now = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        self.remaining.load(Ordering::Relaxed) > 0 || self.reset.load(Ordering::Relaxed) <= now
+    }
+}
+
+/// The last known primary rate limit state for a GitHub API token.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct RateLimit {
+    /// Requests left before the rate limit resets.
+    pub(crate) remaining: u64,
+    /// When the rate limit resets.
+    pub(crate) reset: SystemTime,
+}
+
+/// The GitHub API tokens available to a [`Client`].
+///
+/// Requests are made with one token at a time, moving on to the next
+/// once the current token's primary rate limit is exhausted. This is
+/// shared between clients, so that every audit sees the same quotas.
+pub(crate) struct Tokens {
+    tokens: Vec<Token>,
+    current: AtomicUsize,
+}
+
+impl Tokens {
+    /// Returns a pool of the given tokens, or `None` if there are none.
+    pub(crate) fn new(tokens: &[String]) -> Option<Self> {
+        let tokens = tokens
+            .iter()
+            .map(|token| Token {
+                authorization: format!("Bearer {token}")
+                    .parse()
+                    .expect("couldn't build authorization header for GitHub client?"),
+                remaining: AtomicU64::new(u64::MAX),
+                reset: AtomicU64::new(0),
+            })
+            .collect::<Vec<_>>();
+
+        (!tokens.is_empty()).then_some(Self {
+            tokens,
+            current: AtomicUsize::new(0),
+        })
+    }
+
+    /// Returns the index of the token to make the next request with.
+    ///
+    /// This is the current token, unless it's out of quota and another
+    /// token isn't. With every token exhausted, the current token is
+    /// returned anyways, and the caller waits on its reset as usual.
+    fn select(&self, now: SystemTime) -> usize {
+        let current = self.current.load(Ordering::Relaxed);
+        let Some(next) = (0..self.tokens.len())
+            .map(|offset| (current + offset) % self.tokens.len())
+            .find(|&index| self.tokens[index].has_quota(now))
+        else {
+            return current;
+        };
+
+        if next != current {
+            tracing::info!(
+                "GitHub API token {current} is rate limited, switching to token {next}",
+                current = current + 1,
+                next = next + 1
+            );
+            self.current.store(next, Ordering::Relaxed);
+        }
+        next
+    }
+
+    /// Returns the last known rate limit state for each token, in order,
+    /// or `None` for tokens that haven't seen a response yet.
+    pub(crate) fn rate_limits(&self) -> Vec<Option<RateLimit>> {
+        self.tokens
+            .iter()
+            .map(|token| match token.remaining.load(Ordering::Relaxed) {
+                u64::MAX => None,
+                remaining => Some(RateLimit {
+                    remaining,
+                    reset: UNIX_EPOCH + Duration::from_secs(token.reset.load(Ordering::Relaxed)),
+                }),
+            })
+            .collect()
+    }
+
+    /// Records the rate limit state reported in a response made with the
+    /// given token.
+    fn record(&self, index: usize, headers: &HeaderMap, now: SystemTime) {
+        let header = |name| {
+            headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse::<u64>().ok())
+        };
+
+        let Some(remaining) = header("x-ratelimit-remaining") else {
+            return;
+        };
+
+        let token = &self.tokens[index];
+        token.remaining.store(remaining, Ordering::Relaxed);
+        // Without a reset time, assume the usual wait.
+        let reset = header("x-ratelimit-reset").unwrap_or_else(|| {
+            (now + DEFAULT_RATE_LIMIT_WAIT)
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs()
+        });
+        token.reset.store(reset, Ordering::Relaxed);
+    }
+}
+
+/// A response body, cached alongside its `ETag` for conditional requests.
+#[derive(Deserialize, Serialize)]
+struct Tagged {
+    etag: String,
+    body: String,
+}
+
+/// A single unit of work for [`Client::prefetch`].
+enum PrefetchTask<'a> {
+    /// List an `owner/repo`'s branches and tags.
+    Repo { owner: &'a str, repo: &'a str },
+    /// Resolve a symbolic ref within `owner/repo` to a commit.
+    Ref {
+        owner: &'a str,
+        repo: &'a str,
+        git_ref: &'a str,
+    },
+    /// Fetch the GHSA advisories that affect `owner/repo`.
+    Advisories { owner: &'a str, repo: &'a str },
+}
+
 pub(crate) struct Client {
     api_base: &'static str,
     http: blocking::Client,
+    tokens: Arc<Tokens>,
     caches: Caches,
+    retry: RetryPolicy,
 }
 
 impl Client {
-    pub(crate) fn new(token: &str, caches: Caches) -> Self {
+    pub(crate) fn new(tokens: Arc<Tokens>, caches: Caches, retry: RetryPolicy) -> Self {
         let mut headers = HeaderMap::new();
         headers.insert(USER_AGENT, "zizmor".parse().unwrap());
-        headers.insert(
-            AUTHORIZATION,
-            format!("Bearer {token}")
-                .parse()
-                .expect("couldn't build authorization header for GitHub client?"),
-        );
         headers.insert("X-GitHub-Api-Version", "2022-11-28".parse().unwrap());
         headers.insert(ACCEPT, "application/vnd.github+json".parse().unwrap());
 
@@ -44,11 +314,231 @@ impl Client {
                 .default_headers(headers)
                 .build()
                 .expect("couldn't build GitHub client?"),
+            tokens,
             caches,
+            retry,
         }
     }
 
-    fn paginate<T: DeserializeOwned>(&self, endpoint: &str) -> reqwest::Result<Vec<T>> {
+    /// Returns the last known rate limit state for each of this client's
+    /// tokens; see [`Tokens::rate_limits`].
+    pub(crate) fn rate_limits(&self) -> Vec<Option<RateLimit>> {
+        self.tokens.rate_limits()
+    }
+
+    fn get(&self, url: impl IntoUrl) -> blocking::RequestBuilder {
+        self.http.get(url)
+    }
+
+    /// Checks that GitHub's API is reachable and accepts this client's token.
+    ///
+    /// This is meant to be called once, before any audits depend on the API,
+    /// so that a bad token fails with an actionable error rather than
+    /// somewhere in the middle of a scan.
+    pub(crate) fn validate(&self) -> Result<()> {
+        let url = format!("{api_base}/rate_limit", api_base = self.api_base);
+
+        let resp = self.send(self.get(url)).map_err(|e| {
+            if e.is_connect() || e.is_timeout() {
+                anyhow!("couldn't reach GitHub's API: network unreachable")
+            } else {
+                anyhow!("couldn't reach GitHub's API: {e}")
+            }
+        })?;
+
+        match resp.status() {
+            status if status.is_success() => Ok(()),
+            StatusCode::UNAUTHORIZED => Err(anyhow!("GitHub API token is invalid or expired")),
+            StatusCode::FORBIDDEN if !is_rate_limited(&resp) => {
+                #[derive(Deserialize)]
+                struct Error {
+                    message: String,
+                }
+
+                let reason = resp
+                    .json::<Error>()
+                    .map(|e| format!(": {message}", message = e.message))
+                    .unwrap_or_default();
+                Err(anyhow!(
+                    "GitHub API token lacks the required scopes or permissions{reason}"
+                ))
+            }
+            status => Err(anyhow!(
+                "error from GitHub API while validating token: {status}"
+            )),
+        }
+    }
+
+    /// Sends the given request, retrying according to this client's
+    /// [`RetryPolicy`] on rate limits, server errors, and connection failures.
+    ///
+    /// Every attempt is recorded in the API call count. Once retries are
+    /// exhausted, the last response (or error) is returned as-is.
+    ///
+    /// A request whose token runs out of quota is first retried with
+    /// the next token that has quota left, if any. Only once every token
+    /// is exhausted do we fall back to waiting for a reset.
+    fn send(&self, req: blocking::RequestBuilder) -> reqwest::Result<blocking::Response> {
+        // NOTE: `try_clone` only fails on streaming bodies, which we never send.
+        let built = req.try_clone().expect("request isn't cloneable?").build()?;
+        let _span = tracing::debug_span!(
+            "request",
+            method = %built.method(),
+            url = %built.url()
+        )
+        .entered();
+
+        let mut attempt = 0;
+        loop {
+            API_CALLS.with(|calls| calls.set(calls.get() + 1));
+            TOTAL_API_CALLS.fetch_add(1, Ordering::Relaxed);
+
+            let token = self.tokens.select(SystemTime::now());
+
+            // NOTE: `try_clone` only fails on streaming bodies, which we never send.
+            let resp = req
+                .try_clone()
+                .expect("request isn't cloneable?")
+                .header(AUTHORIZATION, &self.tokens.tokens[token].authorization)
+                .send();
+
+            match &resp {
+                Ok(resp) => tracing::debug!(status = %resp.status(), token, attempt),
+                Err(e) => tracing::debug!(error = %e, token, attempt),
+            }
+
+            let now = SystemTime::now();
+            if let Ok(resp) = &resp {
+                self.tokens.record(token, resp.headers(), now);
+
+                // Switching tokens doesn't count as a retry.
+                if is_quota_exhausted(resp) && self.tokens.select(now) != token {
+                    continue;
+                }
+            }
+
+            let wait = match &resp {
+                Ok(resp) if is_rate_limited(resp) => self
+                    .retry
+                    .wait_on_rate_limit
+                    .then(|| rate_limit_wait(resp.headers(), now))
+                    .filter(|wait| *wait <= MAX_RATE_LIMIT_WAIT),
+                Ok(resp) if resp.status().is_server_error() => Some(backoff(attempt, now)),
+                Err(e) if is_transient(e) => Some(backoff(attempt, now)),
+                _ => None,
+            };
+
+            match wait {
+                Some(wait) if attempt < self.retry.max_retries => {
+                    tracing::warn!(
+                        "GitHub API request failed, retrying in {secs}s ({attempt}/{max})",
+                        secs = wait.as_secs(),
+                        attempt = attempt + 1,
+                        max = self.retry.max_retries
+                    );
+                    thread::sleep(wait);
+                    attempt += 1;
+                }
+                _ => {
+                    if matches!(&resp, Ok(resp) if is_rate_limited(resp)) {
+                        tracing::error!("giving up: rate limited by GitHub's API");
+                    }
+                    return resp;
+                }
+            }
+        }
+    }
+
+    /// Sends the given request conditionally, using the `ETag` of the last
+    /// successful response for `key` (if any).
+    ///
+    /// This complements [`Client::disk_cached`]: once a cached value goes
+    /// stale, we can often revalidate it with a `304 Not Modified` rather
+    /// than fetching it again, which GitHub doesn't count against the
+    /// primary rate limit. A `304` is returned to the caller as the cached
+    /// `200` response.
+    fn send_conditional(
+        &self,
+        key: &[&str],
+        req: blocking::RequestBuilder,
+    ) -> reqwest::Result<blocking::Response> {
+        let Some(disk) = &self.caches.disk else {
+            return self.send(req);
+        };
+
+        let key = [&["etags"], key].concat();
+        let cached = disk.get_stale::<Tagged>(&key);
+
+        let req = match &cached {
+            Some(cached) => req.header(IF_NONE_MATCH, &cached.etag),
+            None => req,
+        };
+        let resp = self.send(req)?;
+
+        match (resp.status(), cached) {
+            (StatusCode::NOT_MODIFIED, Some(cached)) => {
+                tracing::debug!("revalidated {key:?}");
+                Ok(Self::synthesize(resp.headers().clone(), cached.body))
+            }
+            (StatusCode::OK, _) => {
+                let Some(etag) = resp.headers().get(ETAG).and_then(|e| e.to_str().ok()) else {
+                    return Ok(resp);
+                };
+
+                let etag = etag.to_string();
+                let headers = resp.headers().clone();
+                let tagged = Tagged {
+                    etag,
+                    body: resp.text()?,
+                };
+
+                if let Err(e) = disk.put(&key, &tagged) {
+                    tracing::debug!("couldn't write ETag cache entry {key:?}: {e}");
+                }
+
+                Ok(Self::synthesize(headers, tagged.body))
+            }
+            _ => Ok(resp),
+        }
+    }
+
+    /// Builds a `200 OK` response with the given headers and body.
+    fn synthesize(headers: HeaderMap, body: String) -> blocking::Response {
+        let mut resp = http::Response::new(body);
+        *resp.headers_mut() = headers;
+        resp.into()
+    }
+
+    /// Returns the value for `key` from the on-disk cache, if present,
+    /// or computes it with `fetch` and stores it for subsequent runs.
+    ///
+    /// Failures to write the cache are logged, but are otherwise not fatal.
+    fn disk_cached<T, E>(&self, key: &[&str], fetch: impl FnOnce() -> Result<T, E>) -> Result<T, E>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        let Some(disk) = &self.caches.disk else {
+            return fetch();
+        };
+
+        if let Some(value) = disk.get(key) {
+            tracing::debug!("disk cache hit: {key:?}");
+            return Ok(value);
+        }
+
+        let value = fetch()?;
+        if let Err(e) = disk.put(key, &value) {
+            tracing::debug!("couldn't write disk cache entry {key:?}: {e}");
+        }
+
+        Ok(value)
+    }
+
+    fn paginate<T: DeserializeOwned>(
+        &self,
+        key: &[&str],
+        endpoint: &str,
+    ) -> reqwest::Result<Vec<T>> {
         let mut dest = vec![];
         let url = format!("{api_base}/{endpoint}", api_base = self.api_base);
 
@@ -58,11 +548,12 @@ impl Client {
         // and returns empty results.
         let mut pageno = 0;
         loop {
+            let page = pageno.to_string();
             let resp = self
-                .http
-                .get(&url)
-                .query(&[("page", pageno), ("per_page", 100)])
-                .send()?
+                .send_conditional(
+                    &[key, &[&page]].concat(),
+                    self.get(&url).query(&[("page", pageno), ("per_page", 100)]),
+                )?
                 .error_for_status()?;
 
             let page = resp.json::<Vec<T>>()?;
@@ -82,7 +573,12 @@ impl Client {
         self.caches
             .branch_cache
             .try_get_with((owner.into(), repo.into()), || {
-                self.paginate(&format!("repos/{owner}/{repo}/branches"))
+                self.disk_cached(&["branches", owner, repo], || {
+                    self.paginate(
+                        &["branches", owner, repo],
+                        &format!("repos/{owner}/{repo}/branches"),
+                    )
+                })
             })
             .map_err(Into::into)
     }
@@ -92,7 +588,12 @@ impl Client {
         self.caches
             .tag_cache
             .try_get_with((owner.into(), repo.into()), || {
-                self.paginate(&format!("repos/{owner}/{repo}/tags"))
+                self.disk_cached(&["tags", owner, repo], || {
+                    self.paginate(
+                        &["tags", owner, repo],
+                        &format!("repos/{owner}/{repo}/tags"),
+                    )
+                })
             })
             .map_err(Into::into)
     }
@@ -104,39 +605,173 @@ impl Client {
         repo: &str,
         git_ref: &str,
     ) -> Result<Option<String>> {
-        // GitHub Actions generally resolves branches before tags, so try
-        // the repo's branches first.
+        self.caches
+            .ref_cache
+            .try_get_with((owner.into(), repo.into(), git_ref.into()), || {
+                self.disk_cached(&["refs", owner, repo, git_ref], || {
+                    // GitHub Actions generally resolves branches before tags, so try
+                    // the repo's branches first.
+                    match self.git_ref(owner, repo, &format!("heads/{git_ref}"))? {
+                        Some(sha) => Ok(Some(sha)),
+                        None => self.git_ref(owner, repo, &format!("tags/{git_ref}")),
+                    }
+                })
+            })
+            // NOTE: the cache shares errors behind an `Arc`, which
+            // doesn't convert back into an `anyhow::Error`.
+            .map_err(|e| anyhow!("{e:#}"))
+    }
+
+    fn git_ref(&self, owner: &str, repo: &str, git_ref: &str) -> Result<Option<String>> {
         let url = format!(
-            "{api_base}/repos/{owner}/{repo}/git/ref/heads/{git_ref}",
+            "{api_base}/repos/{owner}/{repo}/git/ref/{git_ref}",
             api_base = self.api_base
         );
 
-        let resp = self.http.get(url).send()?;
+        let resp = self.send_conditional(&["refs", owner, repo, git_ref], self.get(url))?;
         match resp.status() {
-            StatusCode::OK => Ok(Some(resp.json::<GitRef>()?.object.sha)),
-            StatusCode::NOT_FOUND => {
-                let url = format!(
-                    "{api_base}/repos/{owner}/{repo}/git/ref/tags/{git_ref}",
-                    api_base = self.api_base
-                );
-
-                let resp = self.http.get(url).send()?;
-                match resp.status() {
-                    StatusCode::OK => Ok(Some(resp.json::<GitRef>()?.object.sha)),
-                    StatusCode::NOT_FOUND => Ok(None),
-                    s => Err(anyhow!(
-                        "{owner}/{repo}: error from GitHub API while accessing ref {git_ref}: {s}"
-                    )),
-                }
-            }
+            StatusCode::OK => self
+                .peel(owner, repo, resp.json::<GitRef>()?.object)
+                .map(Some)
+                .map_err(Into::into),
+            StatusCode::NOT_FOUND => Ok(None),
             s => Err(anyhow!(
                 "{owner}/{repo}: error from GitHub API while accessing ref {git_ref}: {s}"
             )),
         }
     }
 
+    /// Peels the given ref target to the commit it refers to.
+    ///
+    /// Lightweight tags (and branches) point directly to a commit, but
+    /// annotated tags point to a tag object, which in turn points to the
+    /// commit (or, rarely, to another tag object).
+    fn peel(&self, owner: &str, repo: &str, mut object: GitRefObject) -> reqwest::Result<String> {
+        while object.kind == "tag" {
+            let url = format!(
+                "{api_base}/repos/{owner}/{repo}/git/tags/{sha}",
+                api_base = self.api_base,
+                sha = object.sha
+            );
+
+            object = self
+                .send_conditional(&["tag-objects", owner, repo, &object.sha], self.get(url))?
+                .error_for_status()?
+                .json::<GitRef>()?
+                .object;
+        }
+
+        Ok(object.sha)
+    }
+
+    /// Concurrently warms this client's caches with the branches, tags,
+    /// and ref resolutions for each of the given `uses:` clauses, along with
+    /// their advisories if `advisories` is set.
+    ///
+    /// This is purely an optimization: audits perform the same lookups
+    /// lazily, so prefetching stops early (and silently falls back to
+    /// lazy resolution) on the first error, including rate limiting.
+    #[instrument(skip_all)]
+    pub(crate) fn prefetch<'a>(
+        &self,
+        uses: impl IntoIterator<Item = RepositoryUses<'a>>,
+        advisories: bool,
+    ) {
+        let mut repos = IndexSet::new();
+        let mut refs = IndexSet::new();
+        for uses in uses {
+            repos.insert((uses.owner, uses.repo));
+            match uses.git_ref {
+                Some(git_ref) if !uses.ref_is_commit() => {
+                    refs.insert((uses.owner, uses.repo, git_ref));
+                }
+                _ => (),
+            }
+        }
+
+        // Repository listings go first, since every online audit uses them.
+        // Advisories are a single request per repository, so they
+        // go before the (more numerous) ref resolutions.
+        let tasks = repos
+            .iter()
+            .map(|&(owner, repo)| PrefetchTask::Repo { owner, repo })
+            .chain(
+                repos
+                    .iter()
+                    .filter(|_| advisories)
+                    .map(|&(owner, repo)| PrefetchTask::Advisories { owner, repo }),
+            )
+            .chain(
+                refs.into_iter()
+                    .map(|(owner, repo, git_ref)| PrefetchTask::Ref {
+                        owner,
+                        repo,
+                        git_ref,
+                    }),
+            )
+            .collect::<Vec<_>>();
+
+        tracing::debug!("prefetching {ntasks} resolutions", ntasks = tasks.len());
+
+        let span = tracing::Span::current();
+        span.pb_set_length(tasks.len() as u64);
+        span.pb_set_style(
+            &ProgressStyle::with_template(
+                "[{elapsed_precise}] {bar:!30.cyan/blue} {pos}/{len} actions resolved: {msg}",
+            )
+            .unwrap(),
+        );
+
+        let tasks = Mutex::new(tasks.into_iter());
+        let interrupted = AtomicBool::new(false);
+
+        thread::scope(|scope| {
+            for _ in 0..PREFETCH_CONCURRENCY {
+                scope.spawn(|| {
+                    while !interrupted.load(Ordering::Relaxed) {
+                        // NOTE: Bind the task first, so that the lock is
+                        // released before we perform any requests.
+                        let task = tasks.lock().unwrap().next();
+                        let Some(task) = task else {
+                            break;
+                        };
+
+                        let (PrefetchTask::Repo { owner, repo }
+                        | PrefetchTask::Ref { owner, repo, .. }
+                        | PrefetchTask::Advisories { owner, repo }) = task;
+                        span.pb_set_message(&format!("{owner}/{repo}"));
+
+                        let result = match task {
+                            PrefetchTask::Repo { owner, repo } => self
+                                .list_branches(owner, repo)
+                                .and_then(|_| self.list_tags(owner, repo))
+                                .map(|_| ()),
+                            PrefetchTask::Ref {
+                                owner,
+                                repo,
+                                git_ref,
+                            } => self.commit_for_ref(owner, repo, git_ref).map(|_| ()),
+                            PrefetchTask::Advisories { owner, repo } => {
+                                self.gha_advisories(owner, repo).map(|_| ())
+                            }
+                        };
+                        span.pb_inc(1);
+
+                        if let Err(e) = result {
+                            if !interrupted.swap(true, Ordering::Relaxed) {
+                                tracing::warn!(
+                                    "prefetch interrupted, falling back to lazy resolution: {e}"
+                                );
+                            }
+                        }
+                    }
+                });
+            }
+        });
+    }
+
     #[instrument(skip(self))]
-    pub(crate) fn longest_tag_for_commit(
+    pub(crate) fn tag_for_commit(
         &self,
         owner: &str,
         repo: &str,
@@ -149,13 +784,9 @@ impl Client {
         // is not pulling every tag eagerly before scanning them.
         let tags = self.list_tags(owner, repo)?;
 
-        // Heuristic: there can be multiple tags for a commit, so we pick
-        // the longest one. This isn't super sound, but it gets us from
-        // `sha -> v1.2.3` instead of `sha -> v1`.
-        Ok(tags
-            .into_iter()
-            .filter(|t| t.commit.sha == commit)
-            .max_by_key(|t| t.name.len()))
+        Ok(Tag::most_specific(
+            tags.into_iter().filter(|t| t.commit.sha == commit),
+        ))
     }
 
     #[instrument(skip(self))]
@@ -169,43 +800,69 @@ impl Client {
         self.caches
             .ref_comparison_cache
             .try_get_with((base.into(), head.into()), || {
-                let url = format!(
-                    "{api_base}/repos/{owner}/{repo}/compare/{base}...{head}",
-                    api_base = self.api_base
-                );
+                self.disk_cached(&["comparisons", owner, repo, base, head], || {
+                    let url = format!(
+                        "{api_base}/repos/{owner}/{repo}/compare/{base}...{head}",
+                        api_base = self.api_base
+                    );
 
-                let resp = self.http.get(url).send()?;
+                    let resp = self.send_conditional(
+                        &["comparisons", owner, repo, base, head],
+                        self.get(url),
+                    )?;
 
-                match resp.status() {
-                    StatusCode::OK => {
-                        Ok::<_, reqwest::Error>(Some(resp.json::<Comparison>()?.status))
+                    match resp.status() {
+                        StatusCode::OK => Ok(Some(resp.json::<Comparison>()?.status)),
+                        StatusCode::NOT_FOUND => Ok(None),
+                        s => Err(anyhow!(
+                            "{owner}/{repo}: error from GitHub API while comparing {base}...{head}: {s}"
+                        )),
                     }
-                    StatusCode::NOT_FOUND => Ok(None),
-                    _ => Err(resp.error_for_status().unwrap_err()),
-                }
+                })
             })
-            .map_err(Into::into)
+            .map_err(|e| anyhow!("{e:#}"))
     }
 
+    /// Returns every GHSA advisory for the given action, regardless of
+    /// which versions it affects.
+    ///
+    /// Use [`Advisory::affects`] to check a specific version.
     #[instrument(skip(self))]
-    pub(crate) fn gha_advisories(
-        &self,
-        owner: &str,
-        repo: &str,
-        version: &str,
-    ) -> Result<Vec<Advisory>> {
-        // TODO: Paginate this as well.
+    pub(crate) fn gha_advisories(&self, owner: &str, repo: &str) -> Result<Vec<Advisory>> {
         let url = format!("{api_base}/advisories", api_base = self.api_base);
 
-        self.http
-            .get(url)
-            .query(&[
-                ("ecosystem", "actions"),
-                ("affects", &format!("{owner}/{repo}@{version}")),
-            ])
-            .send()?
-            .error_for_status()?
-            .json()
+        self.caches
+            .advisory_cache
+            .try_get_with((owner.into(), repo.into()), || {
+                self.disk_cached(&["advisories", owner, repo], || {
+                    // Unlike most listings, advisories are paginated with an
+                    // opaque cursor, so follow each page's `Link` to the next.
+                    let mut advisories = vec![];
+                    let mut req = self.get(&url).query(&[
+                        ("ecosystem", "actions"),
+                        ("affects", &format!("{owner}/{repo}")),
+                        ("per_page", "100"),
+                    ]);
+                    let mut pageno = 0;
+                    loop {
+                        let page = pageno.to_string();
+                        let resp = self
+                            .send_conditional(&["advisories", owner, repo, &page], req)?
+                            .error_for_status()?;
+
+                        let next = next_page(&resp);
+                        advisories.extend(resp.json::<Vec<Advisory>>()?);
+
+                        match next {
+                            Some(next) => req = self.get(next),
+                            None => break,
+                        }
+                        pageno += 1;
+                    }
+
+                    Ok::<_, reqwest::Error>(advisories)
+                })
+            })
             .map_err(Into::into)
     }
 
@@ -226,16 +883,23 @@ impl Client {
             "{api_base}/repos/{owner}/{repo}/contents/.github/workflows",
             api_base = self.api_base
         );
-        let resp: Vec<File> = self
-            .http
-            .get(&url)
-            .pipe(|req| match git_ref {
-                Some(g) => req.query(&[("ref", g)]),
-                None => req,
-            })
-            .send()?
-            .error_for_status()?
-            .json()?;
+        let resp = self.send(self.get(&url).pipe(|req| match git_ref {
+            Some(g) => req.query(&[("ref", g)]),
+            None => req,
+        }))?;
+
+        // A repository without any workflows is unremarkable, so this
+        // isn't an error; if nothing else is collected either, the run
+        // fails for having nothing to audit.
+        if resp.status() == StatusCode::NOT_FOUND {
+            tracing::warn!(
+                "no .github/workflows directory in {owner}/{repo}{at}",
+                at = git_ref.map(|g| format!("@{g}")).unwrap_or_default()
+            );
+            return Ok(vec![]);
+        }
+
+        let resp: Vec<File> = resp.error_for_status()?.json()?;
 
         let mut workflows = vec![];
         for file in resp
@@ -246,18 +910,18 @@ impl Client {
             tracing::debug!("fetching {file_url}");
 
             let contents = self
-                .http
-                .get(file_url)
-                .header(ACCEPT, "application/vnd.github.raw+json")
-                .pipe(|req| match git_ref {
-                    Some(g) => req.query(&[("ref", g)]),
-                    None => req,
-                })
-                .send()?
+                .send(
+                    self.get(file_url)
+                        .header(ACCEPT, "application/vnd.github.raw+json")
+                        .pipe(|req| match git_ref {
+                            Some(g) => req.query(&[("ref", g)]),
+                            None => req,
+                        }),
+                )?
                 .error_for_status()?
                 .text()?;
 
-            workflows.push(Workflow::from_string(
+            workflows.extend(Workflow::all_from_string(
                 contents,
                 WorkflowKey::remote(slug, file.path)?,
             )?);
@@ -267,12 +931,59 @@ impl Client {
     }
 }
 
+/// Returns the URL of the next page of a paginated response, from its
+/// `Link: <...>; rel="next"` header, if there is one.
+fn next_page(resp: &blocking::Response) -> Option<String> {
+    let link = resp.headers().get(LINK)?.to_str().ok()?;
+
+    link.split(',').find_map(|link| {
+        let (url, params) = link.split_once(';')?;
+        params
+            .split(';')
+            .any(|param| param.trim() == r#"rel="next""#)
+            .then(|| {
+                url.trim()
+                    .trim_start_matches('<')
+                    .trim_end_matches('>')
+                    .to_string()
+            })
+    })
+}
+
+/// Returns whether the given response indicates a primary or secondary
+/// rate limit, rather than an ordinary permissions failure.
+fn is_rate_limited(resp: &blocking::Response) -> bool {
+    match resp.status() {
+        StatusCode::TOO_MANY_REQUESTS => true,
+        StatusCode::FORBIDDEN => {
+            let headers = resp.headers();
+            headers.contains_key(RETRY_AFTER)
+                || headers
+                    .get("x-ratelimit-remaining")
+                    .is_some_and(|remaining| remaining == "0")
+        }
+        _ => false,
+    }
+}
+
+/// Returns whether the given response failed because its token's primary
+/// rate limit is exhausted, as opposed to a secondary rate limit.
+fn is_quota_exhausted(resp: &blocking::Response) -> bool {
+    matches!(
+        resp.status(),
+        StatusCode::FORBIDDEN | StatusCode::TOO_MANY_REQUESTS
+    ) && resp
+        .headers()
+        .get("x-ratelimit-remaining")
+        .is_some_and(|remaining| remaining == "0")
+}
+
 /// A single branch, as returned by GitHub's branches endpoints.
 ///
 /// This model is intentionally incomplete.
 ///
 /// See <https://docs.github.com/en/rest/branches/branches?apiVersion=2022-11-28>.
-#[derive(Deserialize, Clone)]
+#[derive(Deserialize, Serialize, Clone)]
 pub(crate) struct Branch {
     pub(crate) name: String,
     pub(crate) commit: Object,
@@ -281,24 +992,76 @@ pub(crate) struct Branch {
 /// A single tag, as returned by GitHub's tags endpoints.
 ///
 /// This model is intentionally incomplete.
-#[derive(Deserialize, Clone)]
+#[derive(Deserialize, Serialize, Clone)]
 pub(crate) struct Tag {
     pub(crate) name: String,
     pub(crate) commit: Object,
 }
 
+impl Tag {
+    /// Parses this tag's name as a (potentially partial) semantic version,
+    /// like `v1`, `1.2`, or `v1.2.3-rc.1`.
+    ///
+    /// Returns the parsed components and whether the version is a
+    /// prerelease, or `None` if the tag doesn't look like a version.
+    fn semver(&self) -> Option<(Vec<u64>, bool)> {
+        let version = self.name.strip_prefix('v').unwrap_or(&self.name);
+        let (version, prerelease) = match version.split_once(['-', '+']) {
+            Some((version, _)) => (version, true),
+            None => (version, false),
+        };
+
+        let components = version
+            .split('.')
+            .map(|c| c.parse::<u64>().ok())
+            .collect::<Option<Vec<_>>>()?;
+
+        (components.len() <= 3).then_some((components, prerelease))
+    }
+
+    /// Picks the most specific tag from a set of tags for the same commit.
+    ///
+    /// Tags that look like semantic versions are preferred, with more
+    /// components winning (so `v1.2.3` beats `v1`), and releases winning
+    /// over prereleases. If no tag looks like a version, the longest tag
+    /// name is picked instead.
+    pub(crate) fn most_specific(tags: impl IntoIterator<Item = Tag>) -> Option<Tag> {
+        let (versioned, unversioned): (Vec<_>, Vec<_>) =
+            tags.into_iter().partition(|t| t.semver().is_some());
+
+        match versioned.into_iter().max_by_key(|t| {
+            // NOTE: unwrap is safe, since we've partitioned on semver above.
+            let (components, prerelease) = t.semver().unwrap();
+            (components.len(), !prerelease, components)
+        }) {
+            Some(tag) => Some(tag),
+            None => unversioned.into_iter().max_by_key(|t| t.name.len()),
+        }
+    }
+}
+
 /// Represents a git object.
-#[derive(Deserialize, Clone)]
+#[derive(Deserialize, Serialize, Clone)]
 pub(crate) struct Object {
     pub(crate) sha: String,
 }
 
+/// A git ref, or an annotated tag object: both have the same shape.
 #[derive(Deserialize)]
 pub(crate) struct GitRef {
-    pub(crate) object: Object,
+    pub(crate) object: GitRefObject,
+}
+
+/// The object that a [`GitRef`] points to.
+#[derive(Deserialize)]
+pub(crate) struct GitRefObject {
+    pub(crate) sha: String,
+    /// The object's type, e.g. `commit` or `tag`.
+    #[serde(rename = "type", default)]
+    pub(crate) kind: String,
 }
 
-#[derive(Clone, Deserialize)]
+#[derive(Clone, Deserialize, Serialize)]
 #[serde(rename_all = "lowercase")]
 pub(crate) enum ComparisonStatus {
     Ahead,
@@ -316,10 +1079,112 @@ pub(crate) struct Comparison {
 }
 
 /// Represents a GHSA advisory.
-#[derive(Deserialize)]
+#[derive(Clone, Deserialize, Serialize)]
 pub(crate) struct Advisory {
     pub(crate) ghsa_id: String,
     pub(crate) severity: String,
+    #[serde(default)]
+    pub(crate) summary: String,
+    pub(crate) html_url: Option<String>,
+    /// When this advisory was withdrawn, if it has been.
+    pub(crate) withdrawn_at: Option<String>,
+    #[serde(default)]
+    pub(crate) vulnerabilities: Vec<Vulnerability>,
+}
+
+impl Advisory {
+    /// Returns whether this advisory has been withdrawn, e.g. because it
+    /// turned out not to be a vulnerability after all.
+    pub(crate) fn withdrawn(&self) -> bool {
+        self.withdrawn_at.is_some()
+    }
+
+    /// Returns this advisory's vulnerabilities for the `owner/repo` action.
+    fn vulnerabilities_for<'a>(
+        &'a self,
+        owner: &str,
+        repo: &str,
+    ) -> impl Iterator<Item = &'a Vulnerability> {
+        let package = format!("{owner}/{repo}");
+
+        self.vulnerabilities
+            .iter()
+            .filter(move |vuln| vuln.package.name.eq_ignore_ascii_case(&package))
+    }
+
+    /// Returns whether this advisory concerns the `owner/repo` action
+    /// at all, regardless of affected versions.
+    pub(crate) fn concerns(&self, owner: &str, repo: &str) -> bool {
+        self.vulnerabilities_for(owner, repo).next().is_some()
+    }
+
+    /// Returns whether this advisory affects the given version of
+    /// the `owner/repo` action.
+    ///
+    /// A version is affected if it falls within any of the advisory's
+    /// vulnerable version ranges for the action, and isn't at or above
+    /// the corresponding first patched version. Constraints that can't be
+    /// parsed are ignored.
+    pub(crate) fn affects(&self, owner: &str, repo: &str, version: &Version) -> bool {
+        self.vulnerabilities_for(owner, repo)
+            .any(|vuln| vuln.affects(version))
+    }
+
+    /// Returns the lowest version of the `owner/repo` action that's patched
+    /// against every one of this advisory's vulnerabilities affecting the
+    /// given version, if known.
+    ///
+    /// Without a version, every vulnerability for the action is considered.
+    pub(crate) fn patched_version(
+        &self,
+        owner: &str,
+        repo: &str,
+        version: Option<&Version>,
+    ) -> Option<&str> {
+        self.vulnerabilities_for(owner, repo)
+            .filter(|vuln| version.map_or(true, |version| vuln.affects(version)))
+            .filter_map(|vuln| {
+                let patched = vuln.first_patched_version.as_deref()?;
+                Some((Version::parse(patched)?, patched))
+            })
+            .max_by(|(a, _), (b, _)| a.cmp(b))
+            .map(|(_, patched)| patched)
+    }
+}
+
+/// A single vulnerable package within a GHSA advisory.
+#[derive(Clone, Deserialize, Serialize)]
+pub(crate) struct Vulnerability {
+    pub(crate) package: Package,
+    pub(crate) vulnerable_version_range: Option<String>,
+    pub(crate) first_patched_version: Option<String>,
+}
+
+impl Vulnerability {
+    /// Returns whether this vulnerability affects the given version.
+    fn affects(&self, version: &Version) -> bool {
+        let in_range = self
+            .vulnerable_version_range
+            .as_deref()
+            .and_then(|range| version_in_range(version, range));
+        let unpatched = self
+            .first_patched_version
+            .as_deref()
+            .and_then(Version::parse)
+            .map(|patched| *version < patched);
+
+        match (in_range, unpatched) {
+            (Some(in_range), Some(unpatched)) => in_range && unpatched,
+            (Some(affected), None) | (None, Some(affected)) => affected,
+            (None, None) => false,
+        }
+    }
+}
+
+/// The package affected by a [`Vulnerability`].
+#[derive(Clone, Deserialize, Serialize)]
+pub(crate) struct Package {
+    pub(crate) name: String,
 }
 
 /// Represents a file listing from GitHub's contents API.
@@ -328,3 +1193,619 @@ pub(crate) struct File {
     name: String,
     path: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+        thread,
+        time::{Duration, SystemTime, UNIX_EPOCH},
+    };
+
+    use camino::Utf8PathBuf;
+    use reqwest::header::{HeaderMap, HeaderName, AUTHORIZATION, IF_NONE_MATCH};
+
+    use super::{
+        backoff, rate_limit_wait, thread_api_calls, Advisory, Client, Object, RateLimit,
+        RetryPolicy, Tag, Tokens, DEFAULT_RATE_LIMIT_WAIT,
+    };
+    use crate::{
+        models::{AuditInput as _, Uses},
+        state::{Caches, DiskCache},
+        utils::Version,
+    };
+
+    fn tags(names: &[&str]) -> Vec<Tag> {
+        names
+            .iter()
+            .map(|name| Tag {
+                name: name.to_string(),
+                commit: Object {
+                    sha: "deadbeef".into(),
+                },
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_tag_most_specific() {
+        for (names, expected) in [
+            (&[][..], None),
+            (&["v1"][..], Some("v1")),
+            // Semver tags beat longer non-version tags.
+            (&["v1", "latest-stable"][..], Some("v1")),
+            (&["latest-stable", "v1.2", "v1"][..], Some("v1.2")),
+            // More specific versions win.
+            (&["v1", "v1.2", "v1.2.3"][..], Some("v1.2.3")),
+            (&["1.2.3", "v1"][..], Some("1.2.3")),
+            (&["v1", "v1.0"][..], Some("v1.0")),
+            // Releases win over prereleases of the same specificity.
+            (&["v1.2.3-rc.1", "v1.2.3"][..], Some("v1.2.3")),
+            (&["v2.0.0-beta", "v1"][..], Some("v2.0.0-beta")),
+            (
+                &["v1.2.3-rc1", "release-candidate-final", "v1.2", "v1.2.3"][..],
+                Some("v1.2.3"),
+            ),
+            // Higher versions win when equally specific.
+            (&["v1.2.3", "v1.10.0"][..], Some("v1.10.0")),
+            // Not versions: fall back to the longest name.
+            (&["latest", "stable-release"][..], Some("stable-release")),
+            (&["v1.2.3.4", "vnext"][..], Some("v1.2.3.4")),
+        ] {
+            assert_eq!(
+                Tag::most_specific(tags(names)).map(|t| t.name).as_deref(),
+                expected,
+                "{names:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_advisory_affects() {
+        let advisory: Advisory = serde_json::from_value(serde_json::json!({
+            "ghsa_id": "GHSA-xxxx-xxxx-xxxx",
+            "severity": "high",
+            "vulnerabilities": [
+                {
+                    "package": {"ecosystem": "actions", "name": "Foo/Bar"},
+                    "vulnerable_version_range": ">= 1.0.0, < 1.4.2",
+                    "first_patched_version": "1.4.2"
+                },
+                {
+                    "package": {"ecosystem": "actions", "name": "foo/bar"},
+                    "vulnerable_version_range": null,
+                    "first_patched_version": "0.5.0"
+                },
+                {
+                    "package": {"ecosystem": "actions", "name": "other/repo"},
+                    "vulnerable_version_range": "< 99",
+                    "first_patched_version": null
+                }
+            ]
+        }))
+        .unwrap();
+
+        for (version, affected) in [
+            ("v1.3.0", true),
+            ("1.0.0", true),
+            ("v1.4.2", false),
+            ("v2", false),
+            // Only covered by the `first_patched_version` of the second entry.
+            ("0.4.9", true),
+            ("0.5.0", false),
+        ] {
+            assert_eq!(
+                advisory.affects("foo", "bar", &Version::parse(version).unwrap()),
+                affected,
+                "{version}"
+            );
+        }
+
+        for (version, patched) in [
+            (Some("v1.3.0"), Some("1.4.2")),
+            (Some("0.4.9"), Some("0.5.0")),
+            (Some("v2"), None),
+            (None, Some("1.4.2")),
+        ] {
+            let version = version.map(|v| Version::parse(v).unwrap());
+            assert_eq!(
+                advisory.patched_version("foo", "bar", version.as_ref()),
+                patched,
+                "{version:?}"
+            );
+        }
+        assert_eq!(advisory.patched_version("other", "repo", None), None);
+    }
+
+    #[test]
+    fn test_rate_limit_wait() {
+        let now = UNIX_EPOCH + Duration::from_secs(1000);
+        let headers = |pairs: &[(&'static str, &str)]| {
+            let mut headers = HeaderMap::new();
+            for (name, value) in pairs {
+                headers.insert(*name, value.parse().unwrap());
+            }
+            headers
+        };
+
+        assert_eq!(
+            rate_limit_wait(&headers(&[("retry-after", "30")]), now),
+            Duration::from_secs(30)
+        );
+        // `Retry-After` takes precedence over the reset time.
+        assert_eq!(
+            rate_limit_wait(
+                &headers(&[("retry-after", "30"), ("x-ratelimit-reset", "1100")]),
+                now
+            ),
+            Duration::from_secs(30)
+        );
+        assert_eq!(
+            rate_limit_wait(&headers(&[("x-ratelimit-reset", "1100")]), now),
+            Duration::from_secs(100)
+        );
+        // A reset in the past means we can retry immediately.
+        assert_eq!(
+            rate_limit_wait(&headers(&[("x-ratelimit-reset", "900")]), now),
+            Duration::ZERO
+        );
+        assert_eq!(rate_limit_wait(&headers(&[]), now), DEFAULT_RATE_LIMIT_WAIT);
+    }
+
+    #[test]
+    fn test_backoff() {
+        let now = UNIX_EPOCH + Duration::from_millis(250);
+
+        assert_eq!(backoff(0, now), Duration::from_millis(1250));
+        assert_eq!(backoff(1, now), Duration::from_millis(2250));
+        assert_eq!(backoff(3, now), Duration::from_millis(8250));
+        // Backoff is capped, rather than growing without bound.
+        assert_eq!(backoff(100, now), backoff(6, now));
+    }
+
+    /// Serves each of the given raw HTTP responses to a single connection,
+    /// in order. The returned handle yields each request's headers.
+    fn serve(responses: Vec<String>) -> (&'static str, thread::JoinHandle<Vec<HeaderMap>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!("http://{addr}", addr = listener.local_addr().unwrap());
+
+        // Responses can refer back to the server, e.g. in `Link` headers.
+        let responses = responses
+            .into_iter()
+            .map(|resp| resp.replace("@@API_BASE@@", &base))
+            .collect::<Vec<_>>();
+
+        let server = thread::spawn(move || {
+            let mut requests = vec![];
+            for (resp, stream) in responses.into_iter().zip(listener.incoming()) {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+                let mut headers = HeaderMap::new();
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    let line = line.trim_end();
+                    if line.is_empty() {
+                        break;
+                    }
+                    if let Some((name, value)) = line.split_once(':') {
+                        headers.insert(
+                            HeaderName::from_bytes(name.as_bytes()).unwrap(),
+                            value.trim().parse().unwrap(),
+                        );
+                    }
+                }
+
+                stream.write_all(resp.as_bytes()).unwrap();
+                requests.push(headers);
+            }
+            requests
+        });
+
+        (Box::leak(base.into_boxed_str()), server)
+    }
+
+    fn response(status: &str, headers: &[&str], body: &str) -> String {
+        format!(
+            "HTTP/1.1 {status}\r\n{headers}Content-Length: {len}\r\nConnection: close\r\n\r\n{body}",
+            headers = headers.iter().map(|h| format!("{h}\r\n")).collect::<String>(),
+            len = body.len()
+        )
+    }
+
+    fn client(api_base: &'static str, caches: Caches) -> Client {
+        let retry = RetryPolicy {
+            max_retries: 0,
+            wait_on_rate_limit: false,
+        };
+        let tokens = Tokens::new(&["fake".into()]).unwrap();
+        let mut client = Client::new(tokens.into(), caches, retry);
+        client.api_base = api_base;
+        client
+    }
+
+    #[test]
+    fn test_conditional_request_revalidates() {
+        let (api_base, server) = serve(vec![
+            response(
+                "200 OK",
+                &["ETag: \"v1\""],
+                r#"{"object":{"sha":"abcdef"}}"#,
+            ),
+            response("304 Not Modified", &["ETag: \"v1\""], ""),
+        ]);
+
+        let root = std::env::temp_dir().join(format!(
+            "zizmor-test-conditional-{pid}",
+            pid = std::process::id()
+        ));
+        let root = Utf8PathBuf::from_path_buf(root).unwrap();
+
+        let caches = Caches {
+            disk: Some(DiskCache::new(root.clone(), Duration::ZERO)),
+            ..Caches::new()
+        };
+        let client = client(api_base, caches);
+
+        assert_eq!(
+            client
+                .git_ref("foo", "bar", "heads/main")
+                .unwrap()
+                .as_deref(),
+            Some("abcdef")
+        );
+        // The second request is revalidated, and reuses the cached body.
+        assert_eq!(
+            client
+                .git_ref("foo", "bar", "heads/main")
+                .unwrap()
+                .as_deref(),
+            Some("abcdef")
+        );
+
+        let requests = server.join().unwrap();
+        assert_eq!(requests[0].get(IF_NONE_MATCH), None);
+        assert_eq!(requests[1].get(IF_NONE_MATCH).unwrap(), "\"v1\"");
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_retries_server_errors() {
+        let (api_base, server) = serve(vec![
+            response("502 Bad Gateway", &[], ""),
+            response("200 OK", &[], r#"{"object":{"sha":"abcdef"}}"#),
+        ]);
+
+        let mut client = client(api_base, Caches::new());
+        client.retry.max_retries = 1;
+
+        // The 502 is retried, rather than failing the lookup.
+        assert_eq!(
+            client
+                .commit_for_ref("foo", "bar", "main")
+                .unwrap()
+                .as_deref(),
+            Some("abcdef")
+        );
+        assert_eq!(server.join().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_unexpected_success_status() {
+        // A success status other than 200 is still an error, not a panic.
+        let (api_base, server) = serve(vec![
+            response("204 No Content", &[], ""),
+            response("202 Accepted", &[], ""),
+        ]);
+        let client = client(api_base, Caches::new());
+
+        assert_eq!(
+            client
+                .commit_for_ref("foo", "bar", "main")
+                .unwrap_err()
+                .to_string(),
+            "foo/bar: error from GitHub API while accessing ref heads/main: 204 No Content"
+        );
+        assert_eq!(
+            client
+                .compare_commits("foo", "bar", "abc", "def")
+                .err()
+                .map(|e| e.to_string())
+                .as_deref(),
+            Some("foo/bar: error from GitHub API while comparing abc...def: 202 Accepted")
+        );
+
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_annotated_tag_peeled() {
+        let (api_base, server) = serve(vec![
+            // No `v4` branch...
+            response("404 Not Found", &[], "{}"),
+            // ...but an annotated `v4` tag, pointing to a tag object...
+            response("200 OK", &[], r#"{"object":{"sha":"7a61e5","type":"tag"}}"#),
+            // ...which points to the commit.
+            response(
+                "200 OK",
+                &[],
+                r#"{"object":{"sha":"abcdef","type":"commit"}}"#,
+            ),
+            response(
+                "200 OK",
+                &[],
+                r#"[
+                    {"name":"v4","commit":{"sha":"abcdef"}},
+                    {"name":"v4.1.0","commit":{"sha":"abcdef"}},
+                    {"name":"v4.0.0","commit":{"sha":"012345"}}
+                ]"#,
+            ),
+            response("200 OK", &[], "[]"),
+        ]);
+        let client = client(api_base, Caches::new());
+
+        let commit = client.commit_for_ref("foo", "bar", "v4").unwrap().unwrap();
+        assert_eq!(commit, "abcdef");
+
+        // The peeled commit matches the tag listing, so `v4` resolves
+        // to its most specific version.
+        let tag = client.tag_for_commit("foo", "bar", &commit).unwrap();
+        assert_eq!(tag.map(|t| t.name).as_deref(), Some("v4.1.0"));
+
+        assert_eq!(server.join().unwrap().len(), 5);
+    }
+
+    #[test]
+    fn test_advisories_memoized() {
+        // Just one response: a second request would fail to connect.
+        let (api_base, server) = serve(vec![response(
+            "200 OK",
+            &[],
+            r#"[{"ghsa_id":"GHSA-aaaa-aaaa-aaaa","severity":"high"}]"#,
+        )]);
+        // No disk cache, so only the in-memory cache can help.
+        let client = client(api_base, Caches::new());
+
+        let before = thread_api_calls();
+        for _ in 0..2 {
+            let advisories = client.gha_advisories("foo", "bar").unwrap();
+            assert_eq!(advisories.len(), 1);
+            assert_eq!(advisories[0].ghsa_id, "GHSA-aaaa-aaaa-aaaa");
+        }
+        assert_eq!(thread_api_calls() - before, 1);
+
+        assert_eq!(server.join().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_advisories_paginated() {
+        let (api_base, server) = serve(vec![
+            response(
+                "200 OK",
+                &[r#"Link: <@@API_BASE@@/advisories?after=abc>; rel="next""#],
+                r#"[{"ghsa_id":"GHSA-aaaa-aaaa-aaaa","severity":"high"}]"#,
+            ),
+            response(
+                "200 OK",
+                &[],
+                r#"[{"ghsa_id":"GHSA-bbbb-bbbb-bbbb","severity":"low"}]"#,
+            ),
+        ]);
+        let client = client(api_base, Caches::new());
+
+        let advisories = client.gha_advisories("foo", "bar").unwrap();
+        assert_eq!(
+            advisories
+                .iter()
+                .map(|a| a.ghsa_id.as_str())
+                .collect::<Vec<_>>(),
+            ["GHSA-aaaa-aaaa-aaaa", "GHSA-bbbb-bbbb-bbbb"]
+        );
+
+        assert_eq!(server.join().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_advisories_withdrawn() {
+        let (api_base, server) = serve(vec![response(
+            "200 OK",
+            &[],
+            r#"[
+                {
+                    "ghsa_id": "GHSA-aaaa-aaaa-aaaa",
+                    "severity": "high",
+                    "summary": "Code injection in foo/bar",
+                    "html_url": "https://github.com/advisories/GHSA-aaaa-aaaa-aaaa",
+                    "withdrawn_at": null
+                },
+                {
+                    "ghsa_id": "GHSA-bbbb-bbbb-bbbb",
+                    "severity": "low",
+                    "withdrawn_at": "2024-06-01T00:00:00Z"
+                }
+            ]"#,
+        )]);
+        let client = client(api_base, Caches::new());
+
+        let advisories = client.gha_advisories("foo", "bar").unwrap();
+        assert!(!advisories[0].withdrawn());
+        assert_eq!(advisories[0].summary, "Code injection in foo/bar");
+        assert_eq!(
+            advisories[0].html_url.as_deref(),
+            Some("https://github.com/advisories/GHSA-aaaa-aaaa-aaaa")
+        );
+        assert!(advisories[1].withdrawn());
+        assert_eq!(advisories[1].summary, "");
+
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_validate() {
+        for (resp, expected) in [
+            (response("200 OK", &[], "{}"), None),
+            (
+                response("401 Unauthorized", &[], r#"{"message":"Bad credentials"}"#),
+                Some("GitHub API token is invalid or expired"),
+            ),
+            (
+                response(
+                    "403 Forbidden",
+                    &[],
+                    r#"{"message":"Resource protected by organization SAML enforcement."}"#,
+                ),
+                Some("GitHub API token lacks the required scopes or permissions: Resource protected by organization SAML enforcement."),
+            ),
+            (
+                response("500 Internal Server Error", &[], ""),
+                Some("error from GitHub API while validating token: 500 Internal Server Error"),
+            ),
+        ] {
+            let (api_base, server) = serve(vec![resp]);
+            let result = client(api_base, Caches::new()).validate();
+            server.join().unwrap();
+
+            assert_eq!(
+                result.err().map(|e| e.to_string()).as_deref(),
+                expected
+            );
+        }
+
+        // Nothing is listening on this port once the listener is dropped.
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let api_base = Box::leak(format!("http://127.0.0.1:{port}").into_boxed_str());
+        assert_eq!(
+            client(api_base, Caches::new())
+                .validate()
+                .unwrap_err()
+                .to_string(),
+            "couldn't reach GitHub's API: network unreachable"
+        );
+    }
+
+    #[test]
+    fn test_token_rotation() {
+        let reset_secs = (SystemTime::now() + Duration::from_secs(3600))
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let reset = format!("X-RateLimit-Reset: {reset_secs}");
+        let (api_base, server) = serve(vec![
+            // The first token is exhausted, so the request is retried
+            // with the second, even though retries are disabled.
+            response(
+                "403 Forbidden",
+                &["X-RateLimit-Remaining: 0", &reset],
+                r#"{"message":"API rate limit exceeded"}"#,
+            ),
+            // The second token's last request: subsequent requests go
+            // to the third token without being rejected first.
+            response(
+                "200 OK",
+                &["X-RateLimit-Remaining: 0", &reset],
+                r#"{"object":{"sha":"abcdef"}}"#,
+            ),
+            response(
+                "200 OK",
+                &["X-RateLimit-Remaining: 4999", &reset],
+                r#"{"object":{"sha":"fedcba"}}"#,
+            ),
+        ]);
+
+        let retry = RetryPolicy {
+            max_retries: 0,
+            wait_on_rate_limit: false,
+        };
+        let tokens = Tokens::new(&["one".into(), "two".into(), "three".into()]).unwrap();
+        let mut client = Client::new(tokens.into(), Caches::new(), retry);
+        client.api_base = api_base;
+
+        // Nothing is known until a response has been seen.
+        assert_eq!(client.rate_limits(), [None, None, None]);
+
+        assert_eq!(
+            client
+                .git_ref("foo", "bar", "heads/main")
+                .unwrap()
+                .as_deref(),
+            Some("abcdef")
+        );
+        assert_eq!(
+            client
+                .git_ref("foo", "bar", "heads/dev")
+                .unwrap()
+                .as_deref(),
+            Some("fedcba")
+        );
+
+        let requests = server.join().unwrap();
+        let tokens = requests
+            .iter()
+            .map(|headers| headers.get(AUTHORIZATION).unwrap().to_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(tokens, ["Bearer one", "Bearer two", "Bearer three"]);
+
+        let limit = |remaining| {
+            Some(RateLimit {
+                remaining,
+                reset: UNIX_EPOCH + Duration::from_secs(reset_secs),
+            })
+        };
+        assert_eq!(client.rate_limits(), [limit(0), limit(0), limit(4999)]);
+    }
+
+    #[test]
+    fn test_fetch_workflows() {
+        let Some(Uses::Repository(slug)) = Uses::from_step("foo/bar@v1") else {
+            panic!("invalid slug?");
+        };
+
+        let (api_base, server) = serve(vec![
+            response(
+                "200 OK",
+                &[],
+                r#"[
+                    {"name": "ci.yml", "path": ".github/workflows/ci.yml"},
+                    {"name": "README.md", "path": ".github/workflows/README.md"}
+                ]"#,
+            ),
+            response(
+                "200 OK",
+                &[],
+                "on: push\njobs:\n  test:\n    runs-on: ubuntu-latest\n    steps:\n      - run: make test\n",
+            ),
+        ]);
+        let workflows = client(api_base, Caches::new())
+            .fetch_workflows(&slug)
+            .unwrap();
+        assert_eq!(server.join().unwrap().len(), 2);
+
+        // Only YAML files are fetched, and keep their remote path.
+        assert_eq!(workflows.len(), 1);
+        assert_eq!(workflows[0].key().path(), ".github/workflows/ci.yml");
+        assert_eq!(
+            workflows[0].key().to_string(),
+            "https://github.com/foo/bar/blob/v1/.github/workflows/ci.yml"
+        );
+
+        // A repository without a workflows directory has no workflows,
+        // rather than failing.
+        let (api_base, server) = serve(vec![response(
+            "404 Not Found",
+            &[],
+            r#"{"message":"Not Found"}"#,
+        )]);
+        let workflows = client(api_base, Caches::new())
+            .fetch_workflows(&slug)
+            .unwrap();
+        assert!(workflows.is_empty());
+        server.join().unwrap();
+    }
+}
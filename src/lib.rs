@@ -0,0 +1,65 @@
+//! Static analysis for GitHub Actions.
+//!
+//! Besides its CLI, zizmor can be embedded as a library, by auditing
+//! workflows (and composite actions) from their YAML source:
+//!
+//! ```
+//! use zizmor::{audit_workflow, AuditConfig, Severity};
+//!
+//! let source = r#"
+//! on: pull_request_target
+//! jobs:
+//!   test:
+//!     runs-on: ubuntu-latest
+//!     steps:
+//!       - run: echo "${{ github.event.pull_request.title }}"
+//! "#;
+//!
+//! let findings = audit_workflow(source, ".github/workflows/ci.yml", &AuditConfig::new())?;
+//! assert!(findings
+//!     .iter()
+//!     .any(|f| f.ident == "template-injection" && f.severity == Severity::High));
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+//!
+//! # Stability
+//!
+//! The public API consists of exactly the following items, which follow
+//! semver:
+//!
+//! * [`audit_workflow`] and [`AuditConfig`];
+//! * [`Finding`], [`Location`], and [`Point`];
+//! * [`Severity`], [`Confidence`], and [`Persona`].
+//!
+//! New fields may be added to [`Finding`], [`Location`], and [`Point`]
+//! (which are `#[non_exhaustive]`) in minor releases. New audits may also
+//! be added, and existing audits may change what they report, so callers
+//! shouldn't depend on the exact findings for a given input.
+//!
+//! The [`cli`] module is only exposed for zizmor's own binary, and isn't
+//! part of the public API.
+
+mod advisory_db;
+mod allowlist;
+mod api;
+mod audit;
+mod baseline;
+#[doc(hidden)]
+pub mod cli;
+mod config;
+mod contexts;
+mod exclusions;
+mod expr;
+mod finding;
+mod fix;
+mod github_api;
+mod models;
+mod output;
+mod registry;
+mod render;
+mod sarif;
+mod state;
+mod utils;
+
+pub use api::{audit_workflow, AuditConfig, Finding, Location, Point};
+pub use finding::{Confidence, Persona, Severity};
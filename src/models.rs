@@ -1,12 +1,17 @@
 //! Enriching/context-bearing wrappers over GitHub Actions models
 //! from the `github-actions-models` crate.
 
-use crate::finding::{Route, SymbolicLocation};
+use crate::finding::{
+    locate::{LocationCache, Locator},
+    Route, SymbolicLocation,
+};
 use crate::registry::WorkflowKey;
 use anyhow::{bail, Context, Result};
-use camino::Utf8Path;
+use camino::{Utf8Component, Utf8Path, Utf8PathBuf};
+use github_actions_models::action;
 use github_actions_models::common::expr::LoE;
-use github_actions_models::workflow::event::{BareEvent, OptionalBody};
+use github_actions_models::common::{BasePermission, Env, EnvValue, Permission, Permissions};
+use github_actions_models::workflow::event::{BareEvent, Cron, OptionalBody};
 use github_actions_models::workflow::job::{RunsOn, Strategy};
 use github_actions_models::workflow::{
     self, job,
@@ -17,9 +22,40 @@ use indexmap::IndexMap;
 use serde_json::{json, Value};
 use std::collections::HashMap;
 use std::fmt::Debug;
-use std::{iter::Enumerate, ops::Deref};
+use std::{
+    iter::Enumerate,
+    ops::{Deref, Range},
+};
 use terminal_link::Link;
 
+/// Common behavior for the different kinds of inputs that zizmor audits,
+/// i.e. workflows and actions.
+pub(crate) trait AuditInput {
+    /// This input's unique key into zizmor's runtime workflow registry.
+    fn key(&self) -> &WorkflowKey;
+
+    /// This input's parsed YAML document.
+    fn document(&self) -> &yamlpath::Document;
+
+    /// The locations already resolved within this input, shared by every
+    /// audit that reports findings against it.
+    fn locations(&self) -> &LocationCache;
+
+    /// A clickable (OSC 8) link to this input, if remote.
+    fn link(&self) -> Option<&str>;
+}
+
+/// Returns a clickable (OSC 8) link for the given key, if remote.
+fn link_for(key: &WorkflowKey) -> Option<String> {
+    match key {
+        WorkflowKey::Local(_) | WorkflowKey::Stdin => None,
+        WorkflowKey::Remote(_) => {
+            // NOTE: WorkflowKey's Display produces a URL, hence `key.to_string()`.
+            Some(Link::new(key.path(), &key.to_string()).to_string())
+        }
+    }
+}
+
 /// Represents an entire GitHub Actions workflow.
 ///
 /// This type implements [`Deref`] for [`workflow::Workflow`],
@@ -30,6 +66,7 @@ pub(crate) struct Workflow {
     /// A clickable (OSC 8) link to this workflow, if remote.
     pub(crate) link: Option<String>,
     pub(crate) document: yamlpath::Document,
+    locations: LocationCache,
     inner: workflow::Workflow,
 }
 
@@ -47,6 +84,83 @@ impl Deref for Workflow {
     }
 }
 
+impl AuditInput for Workflow {
+    fn key(&self) -> &WorkflowKey {
+        &self.key
+    }
+
+    fn document(&self) -> &yamlpath::Document {
+        &self.document
+    }
+
+    fn locations(&self) -> &LocationCache {
+        &self.locations
+    }
+
+    fn link(&self) -> Option<&str> {
+        self.link.as_deref()
+    }
+}
+
+/// Returns the byte ranges of each document within a YAML stream, excluding
+/// their `---` (or `...`) separator lines.
+fn split_documents(contents: &str) -> Vec<Range<usize>> {
+    let mut documents = vec![];
+    let mut start = 0;
+    let mut offset = 0;
+
+    for line in contents.split_inclusive('\n') {
+        let is_separator = ["---", "..."].iter().any(|marker| {
+            line.strip_prefix(marker)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with(char::is_whitespace))
+        });
+
+        offset += line.len();
+        if is_separator {
+            documents.push(start..offset - line.len());
+            start = offset;
+        }
+    }
+    documents.push(start..contents.len());
+
+    documents
+}
+
+/// Returns whether the given YAML document is empty, i.e. contains
+/// nothing but whitespace and comments.
+fn is_blank_document(document: &str) -> bool {
+    document
+        .lines()
+        .map(str::trim)
+        .all(|line| line.is_empty() || line.starts_with('#'))
+}
+
+/// Returns whether the given YAML document looks like a workflow, i.e. has
+/// a top-level `on:` or `jobs:` key.
+///
+/// Documents that aren't valid YAML are assumed to be (broken) workflows,
+/// so that loading them reports a useful error.
+fn looks_like_workflow(document: &str) -> bool {
+    match serde_yaml::from_str::<serde_yaml::Value>(document) {
+        Ok(serde_yaml::Value::Mapping(mapping)) => {
+            mapping.contains_key("on") || mapping.contains_key("jobs")
+        }
+        Ok(_) => false,
+        Err(_) => true,
+    }
+}
+
+/// Replaces everything but line breaks in `text` with spaces, preserving
+/// its length in bytes.
+fn blank(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '\n' | '\r' => c.to_string(),
+            _ => " ".repeat(c.len_utf8()),
+        })
+        .collect()
+}
+
 impl Workflow {
     /// Load a workflow from a buffer, with an assigned name.
     pub(crate) fn from_string(contents: String, key: WorkflowKey) -> Result<Self> {
@@ -55,28 +169,67 @@ impl Workflow {
 
         let document = yamlpath::Document::new(&contents)?;
 
-        let link = match key {
-            WorkflowKey::Local(_) => None,
-            WorkflowKey::Remote(_) => {
-                // NOTE: WorkflowKey's Display produces a URL, hence `key.to_string()`.
-                Some(Link::new(key.path(), &key.to_string()).to_string())
-            }
-        };
-
         Ok(Self {
-            link,
+            link: link_for(&key),
             key,
             document,
+            locations: LocationCache::default(),
             inner,
         })
     }
 
-    /// Load a workflow from the given file on disk.
-    pub(crate) fn from_file<P: AsRef<Utf8Path>>(p: P) -> Result<Self> {
+    /// Load every workflow from a buffer, with an assigned name.
+    ///
+    /// Unlike [`Workflow::from_string`], the buffer may contain several
+    /// `---`-separated YAML documents, each of which is loaded as its own
+    /// workflow. Documents that don't look like workflows at all (e.g. a
+    /// Dependabot config) are skipped rather than failing.
+    pub(crate) fn all_from_string(contents: String, key: WorkflowKey) -> Result<Vec<Self>> {
+        let documents = split_documents(&contents)
+            .into_iter()
+            .enumerate()
+            .filter(|(_, range)| !is_blank_document(&contents[range.clone()]))
+            .collect::<Vec<_>>();
+
+        // The common case: a single document, which keeps the file's key.
+        if let [(_, range)] = documents.as_slice() {
+            if !looks_like_workflow(&contents[range.clone()]) {
+                tracing::warn!("skipping {key}: not a workflow");
+                return Ok(vec![]);
+            }
+
+            return Ok(vec![Self::from_string(contents, key)?]);
+        }
+
+        let mut workflows = vec![];
+        for (index, range) in documents {
+            let document = &contents[range.clone()];
+            if !looks_like_workflow(document) {
+                tracing::warn!(
+                    "skipping document {n} of {key}: not a workflow",
+                    n = index + 1
+                );
+                continue;
+            }
+
+            // Blank out everything before the document rather than slicing
+            // it off, so that lines and offsets within the document still
+            // match the original file.
+            let source = blank(&contents[..range.start]) + document;
+            workflows.push(Self::from_string(source, key.clone().with_document(index))?);
+        }
+
+        Ok(workflows)
+    }
+
+    /// Load every workflow from the given file on disk.
+    ///
+    /// See [`Workflow::all_from_string`].
+    pub(crate) fn all_from_file<P: AsRef<Utf8Path>>(p: P) -> Result<Vec<Self>> {
         let contents = std::fs::read_to_string(p.as_ref())?;
         let path = p.as_ref().canonicalize_utf8()?;
 
-        Self::from_string(contents, WorkflowKey::local(path)?)
+        Self::all_from_string(contents, WorkflowKey::local(path)?)
     }
 
     /// Returns the filename (i.e. base component) of the loaded workflow.
@@ -88,12 +241,16 @@ impl Workflow {
     }
 
     /// This workflow's [`SymbolicLocation`].
-    pub(crate) fn location(&self) -> SymbolicLocation {
+    pub(crate) fn location(&self) -> SymbolicLocation<'_> {
         SymbolicLocation {
             key: &self.key,
             annotation: "this workflow".to_string(),
             link: None,
             route: Route::new(),
+            subfeature: None,
+            subfeature_within: None,
+            occurrence: 0,
+            fallback: None,
         }
     }
 
@@ -111,6 +268,15 @@ impl Workflow {
         }
     }
 
+    /// Whether this workflow's is triggered by pull_request.
+    pub(crate) fn has_pull_request(&self) -> bool {
+        match &self.on {
+            Trigger::BareEvent(event) => *event == BareEvent::PullRequest,
+            Trigger::BareEvents(events) => events.contains(&BareEvent::PullRequest),
+            Trigger::Events(events) => !matches!(events.pull_request, OptionalBody::Missing),
+        }
+    }
+
     /// Whether this workflow's is triggered by workflow_run.
     pub(crate) fn has_workflow_run(&self) -> bool {
         match &self.on {
@@ -119,6 +285,64 @@ impl Workflow {
             Trigger::Events(events) => !matches!(events.workflow_run, OptionalBody::Missing),
         }
     }
+
+    /// Whether this workflow's is triggered by workflow_dispatch.
+    pub(crate) fn has_workflow_dispatch(&self) -> bool {
+        match &self.on {
+            Trigger::BareEvent(event) => *event == BareEvent::WorkflowDispatch,
+            Trigger::BareEvents(events) => events.contains(&BareEvent::WorkflowDispatch),
+            Trigger::Events(events) => !matches!(events.workflow_dispatch, OptionalBody::Missing),
+        }
+    }
+
+    /// Whether this workflow's `pull_request` trigger is restricted by
+    /// branch or path filters.
+    pub(crate) fn has_filtered_pull_request(&self) -> bool {
+        match &self.on {
+            Trigger::Events(events) => match &events.pull_request {
+                OptionalBody::Body(pr) => pr.branch_filters.is_some() || pr.path_filters.is_some(),
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    /// Returns this workflow's `on.schedule` entries, if it's triggered
+    /// by `schedule`.
+    ///
+    /// `schedule` is never a bare event, so only the `on:` mapping
+    /// form is considered.
+    pub(crate) fn schedule(&self) -> Option<&[Cron]> {
+        match &self.on {
+            Trigger::Events(events) => match &events.schedule {
+                OptionalBody::Body(crons) => Some(crons),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Whether this workflow is triggered by the given event, e.g.
+    /// `pull_request_target`.
+    ///
+    /// NOTE: This reads `on:` from the workflow's source, since not every
+    /// event has a field in the `on:` mapping form's data model.
+    pub(crate) fn has_trigger(&self, event: &str) -> bool {
+        let Ok(serde_yaml::Value::Mapping(root)) =
+            serde_yaml::from_str::<serde_yaml::Value>(self.document.source())
+        else {
+            return false;
+        };
+
+        match root.get("on") {
+            Some(serde_yaml::Value::String(bare)) => bare == event,
+            Some(serde_yaml::Value::Sequence(events)) => {
+                events.iter().any(|e| e.as_str() == Some(event))
+            }
+            Some(serde_yaml::Value::Mapping(events)) => events.contains_key(event),
+            _ => false,
+        }
+    }
 }
 
 /// Represents a single GitHub Actions job.
@@ -442,41 +666,131 @@ impl<'w> Step<'w> {
         Uses::from_step(uses)
     }
 
+    /// Returns the effective `shell` and `working-directory` for this step,
+    /// as configured by the step itself or the `defaults.run` of its job
+    /// or workflow.
+    ///
+    /// Each setting is resolved independently: the step's own setting takes
+    /// precedence, followed by the job's default, followed by the entire
+    /// workflow's default. Only `run:` steps have their own settings, but
+    /// the defaults are resolved for any step.
+    pub(crate) fn run_defaults(&self) -> RunDefaults<'w> {
+        let (shell, working_directory) = match &self.inner.body {
+            StepBody::Run {
+                shell,
+                working_directory,
+                ..
+            } => (
+                shell.as_deref(),
+                working_directory
+                    .as_deref()
+                    .or_else(|| self.raw_working_directory()),
+            ),
+            StepBody::Uses { .. } => (None, None),
+        };
+
+        let job = RunDefaults::from_defaults(self.job().defaults.as_ref());
+        let workflow = RunDefaults::from_defaults(self.workflow().defaults.as_ref());
+
+        RunDefaults {
+            shell: shell.or(job.shell).or(workflow.shell),
+            working_directory: working_directory
+                .or(job.working_directory)
+                .or(workflow.working_directory),
+        }
+    }
+
+    /// Returns the environment variables that this step's `env:` scope
+    /// defines, along with the location of each one's definition.
+    ///
+    /// The scope is made up of the workflow's, job's, and (for `run:`
+    /// steps) step's own `env:` blocks, with closer blocks shadowing outer
+    /// ones, like the runner does. An `env:` block that's itself an
+    /// expression can't be resolved statically, and is skipped.
+    pub(crate) fn env_scope(&self) -> IndexMap<&'w str, (&'w EnvValue, SymbolicLocation<'w>)> {
+        let step_env = match &self.inner.body {
+            StepBody::Run {
+                env: LoE::Literal(env),
+                ..
+            } => Some(env),
+            _ => None,
+        };
+        let job_env = match &self.job().env {
+            LoE::Literal(env) => Some(env),
+            LoE::Expr(_) => None,
+        };
+
+        let scopes: [(Option<&'w Env>, SymbolicLocation<'w>); 3] = [
+            (Some(&self.workflow().env), self.workflow().location()),
+            (job_env, self.parent.location()),
+            (step_env, self.location()),
+        ];
+
+        let mut vars = IndexMap::new();
+        for (env, location) in scopes {
+            for (name, value) in env.into_iter().flatten() {
+                vars.insert(
+                    name.as_str(),
+                    (
+                        value,
+                        location.with_keys(&["env".into(), name.as_str().into()]),
+                    ),
+                );
+            }
+        }
+
+        vars
+    }
+
+    /// Returns this step's own `working-directory:`, as read directly from
+    /// the workflow's source.
+    ///
+    /// NOTE: github-actions-models doesn't currently deserialize a step's
+    /// `working-directory` (the `kebab-case` renaming on `StepBody` only
+    /// applies to its variants, not their fields), so we pull it out of the
+    /// document instead. Only plain and simply quoted scalars are handled,
+    /// which covers any reasonable working directory.
+    fn raw_working_directory(&self) -> Option<&'w str> {
+        let located = Locator::new()
+            .concretize_path(
+                self.workflow(),
+                Some(self.parent.id),
+                Some(self.index),
+                "working-directory",
+            )
+            .ok()
+            .filter(|located| located.exact)?;
+
+        // The path gives us the whole `working-directory: ...` pair.
+        let (_, raw) = located.feature.feature.split_once(':')?;
+        let raw = raw.trim();
+        let unquoted = raw
+            .strip_prefix('"')
+            .and_then(|raw| raw.strip_suffix('"'))
+            .or_else(|| {
+                raw.strip_prefix('\'')
+                    .and_then(|raw| raw.strip_suffix('\''))
+            })
+            .unwrap_or(raw);
+
+        // Anything needing real unescaping (or that isn't a string at all)
+        // won't round-trip, and is ignored.
+        (serde_yaml::from_str::<String>(raw).ok()? == unquoted).then_some(unquoted)
+    }
+
     /// Returns the name of the shell used by this step, or `None`
     /// if the shell can't be statically inferred.
     ///
     /// Invariant: panics if the step is not a `run:` step.
     pub(crate) fn shell(&self) -> Option<&str> {
-        let StepBody::Run {
-            run: _,
-            working_directory: _,
-            shell,
-            env: _,
-        } = &self.inner.body
-        else {
+        let StepBody::Run { .. } = &self.inner.body else {
             panic!("API misuse: can't call shell() on a uses: step")
         };
 
-        // The steps's own `shell:` takes precedence, followed by the
-        // job's default, followed by the entire workflow's default,
-        // followed by the runner's default.
-        let shell = shell
-            .as_deref()
-            .or_else(|| {
-                self.job()
-                    .defaults
-                    .as_ref()
-                    .and_then(|d| d.run.as_ref().and_then(|r| r.shell.as_deref()))
-            })
-            .or_else(|| {
-                self.workflow()
-                    .defaults
-                    .as_ref()
-                    .and_then(|d| d.run.as_ref().and_then(|r| r.shell.as_deref()))
-            })
-            .or_else(|| self.parent.runner_default_shell());
-
-        shell
+        // Without an explicit shell, the runner's default applies.
+        self.run_defaults()
+            .shell
+            .or_else(|| self.parent.runner_default_shell())
     }
 
     /// Returns a symbolic location for this [`Step`].
@@ -495,6 +809,26 @@ impl<'w> Step<'w> {
     }
 }
 
+/// The effective `defaults.run` settings for a [`Step`].
+///
+/// See [`Step::run_defaults`].
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct RunDefaults<'w> {
+    pub(crate) shell: Option<&'w str>,
+    pub(crate) working_directory: Option<&'w str>,
+}
+
+impl<'w> RunDefaults<'w> {
+    fn from_defaults(defaults: Option<&'w workflow::Defaults>) -> Self {
+        let run = defaults.and_then(|defaults| defaults.run.as_ref());
+
+        Self {
+            shell: run.and_then(|run| run.shell.as_deref()),
+            working_directory: run.and_then(|run| run.working_directory.as_deref()),
+        }
+    }
+}
+
 /// An iterable container for steps within a [`Job`].
 pub(crate) struct Steps<'w> {
     inner: Enumerate<std::slice::Iter<'w, github_actions_models::workflow::job::Step>>,
@@ -532,6 +866,193 @@ impl<'w> Iterator for Steps<'w> {
     }
 }
 
+/// Represents a GitHub Actions action definition, i.e. an `action.yml`
+/// or `action.yaml` file.
+///
+/// This type implements [`Deref`] for [`action::Action`],
+/// providing access to the underlying data model.
+pub(crate) struct Action {
+    /// This action's unique key into zizmor's runtime workflow registry.
+    pub(crate) key: WorkflowKey,
+    /// A clickable (OSC 8) link to this action, if remote.
+    pub(crate) link: Option<String>,
+    pub(crate) document: yamlpath::Document,
+    locations: LocationCache,
+    inner: action::Action,
+}
+
+impl Debug for Action {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{key}", key = self.key)
+    }
+}
+
+impl Deref for Action {
+    type Target = action::Action;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl AuditInput for Action {
+    fn key(&self) -> &WorkflowKey {
+        &self.key
+    }
+
+    fn document(&self) -> &yamlpath::Document {
+        &self.document
+    }
+
+    fn locations(&self) -> &LocationCache {
+        &self.locations
+    }
+
+    fn link(&self) -> Option<&str> {
+        self.link.as_deref()
+    }
+}
+
+impl Action {
+    /// Load an action from a buffer, with an assigned name.
+    pub(crate) fn from_string(contents: String, key: WorkflowKey) -> Result<Self> {
+        let inner = serde_yaml::from_str(&contents)
+            .with_context(|| format!("invalid GitHub Actions action: {key}"))?;
+
+        let document = yamlpath::Document::new(&contents)?;
+
+        Ok(Self {
+            link: link_for(&key),
+            key,
+            document,
+            locations: LocationCache::default(),
+            inner,
+        })
+    }
+
+    /// Load an action from the given file on disk.
+    pub(crate) fn from_file<P: AsRef<Utf8Path>>(p: P) -> Result<Self> {
+        let contents = std::fs::read_to_string(p.as_ref())?;
+        let path = p.as_ref().canonicalize_utf8()?;
+
+        Self::from_string(contents, WorkflowKey::local(path)?)
+    }
+
+    /// Returns whether the given path looks like an action definition,
+    /// i.e. is named `action.yml` or `action.yaml`.
+    pub(crate) fn is_action_path(path: &Utf8Path) -> bool {
+        matches!(path.file_name(), Some("action.yml" | "action.yaml"))
+    }
+
+    /// This action's [`SymbolicLocation`].
+    pub(crate) fn location(&self) -> SymbolicLocation<'_> {
+        SymbolicLocation {
+            key: &self.key,
+            annotation: "this action".to_string(),
+            link: None,
+            route: Route::new(),
+            subfeature: None,
+            subfeature_within: None,
+            occurrence: 0,
+            fallback: None,
+        }
+    }
+
+    /// A [`CompositeSteps`] iterator over this action's constituent steps.
+    ///
+    /// Only composite actions have steps; the iterator is empty for
+    /// JavaScript and Docker actions.
+    pub(crate) fn steps(&self) -> CompositeSteps<'_> {
+        CompositeSteps::new(self)
+    }
+}
+
+/// Represents a single step in a composite action.
+///
+/// This type implements [`Deref`] for [`action::Step`], which
+/// provides access to the step's actual fields.
+#[derive(Clone)]
+pub(crate) struct CompositeStep<'a> {
+    /// The step's index within its parent action.
+    pub(crate) index: usize,
+    /// The inner step model.
+    inner: &'a action::Step,
+    /// The parent [`Action`].
+    pub(crate) parent: &'a Action,
+}
+
+impl<'a> Deref for CompositeStep<'a> {
+    type Target = &'a action::Step;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl<'a> CompositeStep<'a> {
+    /// Returns a [`Uses`] for this [`CompositeStep`], if it has one.
+    pub(crate) fn uses(&self) -> Option<Uses<'a>> {
+        let action::Step::UseAction(action::UseAction { uses, .. }) = self.inner else {
+            return None;
+        };
+
+        Uses::from_step(uses)
+    }
+
+    /// Returns a symbolic location for this [`CompositeStep`].
+    pub(crate) fn location(&self) -> SymbolicLocation<'a> {
+        self.parent
+            .location()
+            .with_keys(&["runs".into(), "steps".into(), self.index.into()])
+    }
+
+    /// Like [`CompositeStep::location`], except with the step's `name`
+    /// key as the final path component if present.
+    pub(crate) fn location_with_name(&self) -> SymbolicLocation<'a> {
+        match self.inner {
+            action::Step::RunShell(action::RunShell { name: Some(_), .. }) => {
+                self.location().with_keys(&["name".into()])
+            }
+            _ => self.location(),
+        }
+        .annotated("this step")
+    }
+}
+
+/// An iterable container for steps within an [`Action`].
+pub(crate) struct CompositeSteps<'a> {
+    inner: Enumerate<std::slice::Iter<'a, action::Step>>,
+    parent: &'a Action,
+}
+
+impl<'a> CompositeSteps<'a> {
+    fn new(action: &'a Action) -> Self {
+        let steps = match &action.runs {
+            action::Runs::Composite(composite) => composite.steps.as_slice(),
+            action::Runs::JavaScript(_) | action::Runs::Docker(_) => &[],
+        };
+
+        Self {
+            inner: steps.iter().enumerate(),
+            parent: action,
+        }
+    }
+}
+
+impl<'a> Iterator for CompositeSteps<'a> {
+    type Item = CompositeStep<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (index, inner) = self.inner.next()?;
+
+        Some(CompositeStep {
+            index,
+            inner,
+            parent: self.parent,
+        })
+    }
+}
+
 /// The contents of a `uses: docker://` step stanza.
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub(crate) struct DockerUses<'a> {
@@ -541,6 +1062,38 @@ pub(crate) struct DockerUses<'a> {
     pub(crate) hash: Option<&'a str>,
 }
 
+/// The contents of a `uses: ./some/path` step stanza.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) struct LocalUses<'a> {
+    /// The action's path, relative to the root of the workspace
+    /// (including the leading `./` or `../`).
+    pub(crate) path: &'a str,
+}
+
+impl LocalUses<'_> {
+    /// Returns this action's path relative to the workspace root, with any
+    /// `.` and `..` components resolved.
+    ///
+    /// Returns `None` if the path escapes the workspace root.
+    pub(crate) fn normalized(&self) -> Option<Utf8PathBuf> {
+        let mut normalized = Utf8PathBuf::new();
+        for component in Utf8Path::new(self.path).components() {
+            match component {
+                Utf8Component::CurDir => {}
+                Utf8Component::ParentDir => {
+                    if !normalized.pop() {
+                        return None;
+                    }
+                }
+                Utf8Component::Normal(name) => normalized.push(name),
+                Utf8Component::RootDir | Utf8Component::Prefix(_) => return None,
+            }
+        }
+
+        Some(normalized)
+    }
+}
+
 /// The contents of a `uses: some/repo` step stanza.
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub(crate) struct RepositoryUses<'a> {
@@ -551,6 +1104,18 @@ pub(crate) struct RepositoryUses<'a> {
 }
 
 impl RepositoryUses<'_> {
+    /// Returns this `uses:` clause, pinned to the given ref instead of
+    /// its own.
+    ///
+    /// For example, `actions/checkout@v4` pinned to `main` is
+    /// `actions/checkout@main`.
+    pub(crate) fn with_ref(&self, git_ref: &str) -> String {
+        match self.subpath {
+            Some(subpath) => format!("{}/{}/{subpath}@{git_ref}", self.owner, self.repo),
+            None => format!("{}/{}@{git_ref}", self.owner, self.repo),
+        }
+    }
+
     /// Returns whether this `uses:` clause "matches" the given template.
     /// The template is itself formatted like a normal `uses:` clause.
     ///
@@ -591,13 +1156,126 @@ impl RepositoryUses<'_> {
     }
 }
 
+/// Every permission scope that can be granted to a `GITHUB_TOKEN`.
+///
+/// See: <https://docs.github.com/en/actions/writing-workflows/workflow-syntax-for-github-actions#permissions>
+pub(crate) const PERMISSION_SCOPES: &[&str] = &[
+    "actions",
+    "attestations",
+    "checks",
+    "contents",
+    "deployments",
+    "discussions",
+    "id-token",
+    "issues",
+    "packages",
+    "pages",
+    "pull-requests",
+    "repository-projects",
+    "security-events",
+    "statuses",
+];
+
+/// The access level granted to a single permission scope.
+///
+/// Levels are ordered by privilege, so `None < Read < Write`.
+#[derive(Copy, Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub(crate) enum ScopeLevel {
+    None,
+    Read,
+    Write,
+}
+
+impl From<&Permission> for ScopeLevel {
+    fn from(value: &Permission) -> Self {
+        match value {
+            Permission::None => Self::None,
+            Permission::Read => Self::Read,
+            Permission::Write => Self::Write,
+        }
+    }
+}
+
+impl std::fmt::Display for ScopeLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::None => write!(f, "none"),
+            Self::Read => write!(f, "read"),
+            Self::Write => write!(f, "write"),
+        }
+    }
+}
+
+/// A resolved set of permission scopes, as granted by a `permissions:` block.
+///
+/// Scopes that aren't mentioned in an explicit `permissions:` block are
+/// granted no access at all, per GitHub's semantics.
+#[derive(Debug, PartialEq)]
+pub(crate) struct PermissionScopes(HashMap<String, ScopeLevel>);
+
+impl PermissionScopes {
+    /// Resolves a `permissions:` block into its scopes.
+    ///
+    /// Returns `None` for the default permissions, since these are
+    /// determined by repository or organization settings rather than
+    /// by the workflow itself.
+    pub(crate) fn resolve(permissions: &Permissions) -> Option<Self> {
+        let uniform = |level| {
+            PERMISSION_SCOPES
+                .iter()
+                .map(|scope| (scope.to_string(), level))
+                .collect()
+        };
+
+        match permissions {
+            Permissions::Base(BasePermission::Default) => None,
+            Permissions::Base(BasePermission::ReadAll) => Some(Self(uniform(ScopeLevel::Read))),
+            Permissions::Base(BasePermission::WriteAll) => Some(Self(uniform(ScopeLevel::Write))),
+            Permissions::Explicit(perms) => Some(Self(
+                perms
+                    .iter()
+                    .map(|(scope, perm)| (scope.clone(), perm.into()))
+                    .collect(),
+            )),
+        }
+    }
+
+    /// Returns the access level granted to the given scope.
+    pub(crate) fn level(&self, scope: &str) -> ScopeLevel {
+        self.0.get(scope).copied().unwrap_or(ScopeLevel::None)
+    }
+
+    /// Returns whether any scope is granted `write` access.
+    pub(crate) fn grants_write(&self) -> bool {
+        self.0.values().any(|level| *level == ScopeLevel::Write)
+    }
+
+    /// Returns each scope that this set grants more access to than
+    /// `baseline` does, as `(scope, baseline level, level)`, sorted by scope.
+    pub(crate) fn widened_from<'a>(
+        &'a self,
+        baseline: &PermissionScopes,
+    ) -> Vec<(&'a str, ScopeLevel, ScopeLevel)> {
+        let mut widened = self
+            .0
+            .iter()
+            .filter(|(scope, level)| **level > baseline.level(scope))
+            .map(|(scope, level)| (scope.as_str(), baseline.level(scope), *level))
+            .collect::<Vec<_>>();
+
+        widened.sort();
+        widened
+    }
+}
+
 /// Represents the components of an "action ref", i.e. the value
 /// of a `uses:` clause in a normal job step or a reusable workflow job.
-/// Supports Docker (`docker://`) and repository (`actions/checkout`)
-/// style references, but not local (`./foo`) references.
+/// Supports Docker (`docker://`), local (`./foo`), and repository
+/// (`actions/checkout`) style references.
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub(crate) enum Uses<'a> {
     Docker(DockerUses<'a>),
+    Local(LocalUses<'a>),
     Repository(RepositoryUses<'a>),
 }
 
@@ -609,7 +1287,7 @@ impl<'a> Uses<'a> {
 
     /// Parses a Docker image reference.
     /// See: <https://docs.docker.com/reference/cli/docker/image/tag/>
-    fn from_image_ref(image: &'a str) -> Option<Self> {
+    pub(crate) fn from_image_ref(image: &'a str) -> Option<Self> {
         let (registry, image) = match image.split_once('/') {
             Some((registry, image)) if Self::is_registry(registry) => (Some(registry), image),
             _ => (None, image),
@@ -650,10 +1328,23 @@ impl<'a> Uses<'a> {
     }
 
     fn from_common(uses: &'a str) -> Option<Self> {
-        if uses.starts_with("./") {
+        if uses.contains("${{") {
+            // GitHub doesn't evaluate expressions in `uses:`, so a ref
+            // like `foo/bar@${{ matrix.version }}` is rejected outright
+            // rather than being expanded per matrix combination.
+            tracing::debug!("unsupported expression in `uses:` ref: {uses}");
             None
         } else if let Some(image) = uses.strip_prefix("docker://") {
             Self::from_image_ref(image)
+        } else if uses.starts_with("./") || uses.starts_with("../") {
+            // Local actions always come from the workspace itself,
+            // so GitHub rejects any attempt to pin them to a ref.
+            if uses.contains('@') {
+                tracing::debug!("unsupported ref in local `uses:` ref: {uses}");
+                return None;
+            }
+
+            Some(Self::Local(LocalUses { path: uses }))
         } else {
             // NOTE: Technically both git refs and action paths can contain `@`,
             // so this isn't guaranteed to be correct. In practice, however,
@@ -663,16 +1354,23 @@ impl<'a> Uses<'a> {
                 None => (uses, None),
             };
 
-            let components = path.splitn(3, '/').collect::<Vec<_>>();
-            if components.len() < 2 {
+            // Only `owner/repo` identifies the repository (and is all that
+            // the GitHub API wants); anything after it is a path within it.
+            let (owner, rest) = path.split_once('/').unwrap_or((path, ""));
+            let (repo, subpath) = match rest.split_once('/') {
+                Some((repo, subpath)) => (repo, Some(subpath.trim_end_matches('/'))),
+                None => (rest, None),
+            };
+
+            if owner.is_empty() || repo.is_empty() {
                 tracing::debug!("malformed `uses:` ref: {uses}");
                 return None;
             }
 
             Some(Self::Repository(RepositoryUses {
-                owner: components[0],
-                repo: components[1],
-                subpath: components.get(2).copied(),
+                owner,
+                repo,
+                subpath: subpath.filter(|subpath| !subpath.is_empty()),
                 git_ref,
             }))
         }
@@ -688,8 +1386,9 @@ impl<'a> Uses<'a> {
     /// can't be used in reusable workflows.
     pub(crate) fn from_reusable(uses: &'a str) -> Option<RepositoryUses<'a>> {
         match Self::from_common(uses) {
-            // Reusable workflows don't support Docker actions.
-            Some(Uses::Docker(DockerUses { .. })) => None,
+            // Reusable workflows don't support Docker actions, and local
+            // reusable workflows aren't resolved (yet).
+            Some(Uses::Docker(DockerUses { .. }) | Uses::Local(LocalUses { .. })) => None,
             // Reusable workflows require a git ref.
             Some(Uses::Repository(RepositoryUses {
                 owner: _,
@@ -702,16 +1401,23 @@ impl<'a> Uses<'a> {
         }
     }
 
+    /// Returns whether this `uses:` isn't pinned to anything at all.
+    ///
+    /// Local actions are always "pinned" to the workspace's own revision.
     pub(crate) fn unpinned(&self) -> bool {
         match self {
             Uses::Docker(docker) => docker.hash.is_none() && docker.tag.is_none(),
+            Uses::Local(_) => false,
             Uses::Repository(repo) => repo.git_ref.is_none(),
         }
     }
 
+    /// Returns whether this `uses:` isn't pinned to an immutable hash,
+    /// i.e. an image digest or a commit.
     pub(crate) fn unhashed(&self) -> bool {
         match self {
-            Uses::Docker(docker) => docker.hash.is_some(),
+            Uses::Docker(docker) => docker.hash.is_none(),
+            Uses::Local(_) => false,
             Uses::Repository(repo) => !repo.ref_is_commit(),
         }
     }
@@ -719,7 +1425,122 @@ impl<'a> Uses<'a> {
 
 #[cfg(test)]
 mod tests {
-    use super::{DockerUses, RepositoryUses, Uses};
+    use github_actions_models::common::{BasePermission, Permission, Permissions};
+
+    use super::AuditInput as _;
+    use super::{
+        split_documents, DockerUses, LocalUses, PermissionScopes, RepositoryUses, RunDefaults,
+        ScopeLevel, Uses, Workflow,
+    };
+    use crate::registry::WorkflowKey;
+
+    #[test]
+    fn permission_scopes_widened_from() {
+        let explicit = |perms: Vec<(&str, Permission)>| {
+            PermissionScopes::resolve(&Permissions::Explicit(
+                perms.into_iter().map(|(k, v)| (k.to_string(), v)).collect(),
+            ))
+            .unwrap()
+        };
+
+        assert!(PermissionScopes::resolve(&Permissions::Base(BasePermission::Default)).is_none());
+
+        let baseline = explicit(vec![("contents", Permission::Read)]);
+
+        // Narrowing or matching the baseline is fine.
+        assert!(explicit(vec![]).widened_from(&baseline).is_empty());
+        assert!(explicit(vec![("contents", Permission::Read)])
+            .widened_from(&baseline)
+            .is_empty());
+        assert!(explicit(vec![("contents", Permission::None)])
+            .widened_from(&baseline)
+            .is_empty());
+
+        // Upgrading an existing scope, or adding a new one, widens.
+        assert_eq!(
+            explicit(vec![
+                ("contents", Permission::Write),
+                ("issues", Permission::Read),
+                ("checks", Permission::None),
+            ])
+            .widened_from(&baseline),
+            [
+                ("contents", ScopeLevel::Read, ScopeLevel::Write),
+                ("issues", ScopeLevel::None, ScopeLevel::Read),
+            ]
+        );
+
+        let write_all =
+            PermissionScopes::resolve(&Permissions::Base(BasePermission::WriteAll)).unwrap();
+        assert!(write_all.grants_write());
+        assert_eq!(write_all.level("contents"), ScopeLevel::Write);
+        assert!(write_all.widened_from(&write_all).is_empty());
+        assert!(!baseline.grants_write());
+    }
+
+    #[test]
+    fn step_run_defaults() {
+        let workflow = Workflow::from_string(
+            r#"
+on: push
+
+defaults:
+  run:
+    shell: bash
+    working-directory: ./workflow
+
+jobs:
+  inherits:
+    runs-on: ubuntu-latest
+    steps:
+      - run: echo workflow
+      - uses: actions/checkout@v4
+
+  overrides:
+    runs-on: ubuntu-latest
+    defaults:
+      run:
+        working-directory: ./job
+    steps:
+      - run: echo job
+      - run: echo step
+        shell: pwsh
+        working-directory: "./step"
+      - run: echo mixed
+        shell: python
+"#
+            .into(),
+            WorkflowKey::local("/tmp/test.yml".into()).unwrap(),
+        )
+        .unwrap();
+
+        let defaults = workflow
+            .jobs()
+            .flat_map(|job| {
+                job.steps()
+                    .map(|step| step.run_defaults())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        let expected = |shell, working_directory| RunDefaults {
+            shell: Some(shell),
+            working_directory: Some(working_directory),
+        };
+        assert_eq!(
+            defaults,
+            [
+                // Workflow defaults apply to every step, `uses:` or not.
+                expected("bash", "./workflow"),
+                expected("bash", "./workflow"),
+                // Job defaults override the workflow's, per setting.
+                expected("bash", "./job"),
+                // Step settings override everything.
+                expected("pwsh", "./step"),
+                expected("python", "./job"),
+            ]
+        );
+    }
 
     #[test]
     fn uses_from_step() {
@@ -863,13 +1684,68 @@ mod tests {
                     hash: Some("hash"),
                 })),
             ),
+            (
+                // Valid: subpath, pinned with branch/tag
+                "github/codeql-action/init@v3",
+                Some(Uses::Repository(RepositoryUses {
+                    owner: "github",
+                    repo: "codeql-action",
+                    subpath: Some("init"),
+                    git_ref: Some("v3"),
+                })),
+            ),
+            (
+                // Valid: nested subpath, pinned with branch/tag
+                "example/foo/deep/sub@v1",
+                Some(Uses::Repository(RepositoryUses {
+                    owner: "example",
+                    repo: "foo",
+                    subpath: Some("deep/sub"),
+                    git_ref: Some("v1"),
+                })),
+            ),
+            (
+                // Valid: subpath, unpinned
+                "example/foo/deep/sub",
+                Some(Uses::Repository(RepositoryUses {
+                    owner: "example",
+                    repo: "foo",
+                    subpath: Some("deep/sub"),
+                    git_ref: None,
+                })),
+            ),
+            (
+                // Invalid, but allowed: trailing slash without a subpath
+                "example/foo/@v1",
+                Some(Uses::Repository(RepositoryUses {
+                    owner: "example",
+                    repo: "foo",
+                    subpath: None,
+                    git_ref: Some("v1"),
+                })),
+            ),
             // Invalid: missing user/repo
             ("checkout@8f4b7f84864484a7bf31766abe9204da3cbe65b3", None),
-            // Invalid: local action refs not supported
+            // Invalid: empty user or repo
+            ("/checkout@v4", None),
+            ("actions/@v4", None),
+            ("actions//sub@v4", None),
+            (
+                // Valid: local action
+                "./.github/actions/hello-world-action",
+                Some(Uses::Local(LocalUses {
+                    path: "./.github/actions/hello-world-action",
+                })),
+            ),
+            // Invalid: local actions can't be pinned to a ref
             (
                 "./.github/actions/hello-world-action@172239021f7ba04fe7327647b213799853a9eb89",
                 None,
             ),
+            // Invalid: expressions aren't evaluated in `uses:`
+            ("actions/checkout@${{ matrix.version }}", None),
+            ("${{ matrix.action }}@v4", None),
+            ("docker://alpine:${{ matrix.tag }}", None),
         ];
 
         for (input, expected) in vectors {
@@ -877,6 +1753,24 @@ mod tests {
         }
     }
 
+    #[test]
+    fn uses_with_ref() {
+        for (uses, expected) in [
+            ("actions/checkout@v4", "actions/checkout@abcd"),
+            ("actions/checkout", "actions/checkout@abcd"),
+            (
+                "github/codeql-action/init@v3",
+                "github/codeql-action/init@abcd",
+            ),
+            ("example/foo/deep/sub@v1", "example/foo/deep/sub@abcd"),
+        ] {
+            let Some(Uses::Repository(uses)) = Uses::from_step(uses) else {
+                panic!("{uses}: not a repository uses");
+            };
+            assert_eq!(uses.with_ref("abcd"), expected);
+        }
+    }
+
     #[test]
     fn uses_from_reusable() {
         let vectors = [
@@ -945,6 +1839,51 @@ mod tests {
             .ref_is_commit());
     }
 
+    #[test]
+    fn local_uses_normalized() {
+        for (path, normalized) in [
+            ("./.github/actions/setup", Some(".github/actions/setup")),
+            (
+                "./.github/actions/../actions/setup/",
+                Some(".github/actions/setup"),
+            ),
+            ("./", Some("")),
+            ("../sibling", None),
+            ("./../../etc", None),
+            ("./.github/../../outside", None),
+        ] {
+            let Some(Uses::Local(local)) = Uses::from_step(path) else {
+                panic!("{path} isn't a local action");
+            };
+            assert_eq!(
+                local.normalized().as_deref(),
+                normalized.map(Into::into),
+                "{path}"
+            );
+        }
+    }
+
+    #[test]
+    fn uses_unpinned_unhashed() {
+        for (uses, unpinned, unhashed) in [
+            ("actions/checkout", true, true),
+            ("actions/checkout@v4", false, true),
+            (
+                "actions/checkout@8f4b7f84864484a7bf31766abe9204da3cbe65b3",
+                false,
+                false,
+            ),
+            ("docker://alpine", true, true),
+            ("docker://alpine:3.8", false, true),
+            ("docker://alpine@sha256:abcd", false, false),
+            ("./.github/actions/setup", false, false),
+        ] {
+            let uses = Uses::from_step(uses).unwrap();
+            assert_eq!(uses.unpinned(), unpinned, "{uses:?}");
+            assert_eq!(uses.unhashed(), unhashed, "{uses:?}");
+        }
+    }
+
     #[test]
     fn test_repositoryuses_matches() {
         for (uses, template, matches) in [
@@ -973,4 +1912,66 @@ mod tests {
             assert_eq!(uses.matches(template), matches)
         }
     }
+
+    #[test]
+    fn test_split_documents() {
+        let contents = "a: 1\n---\nb: 2\n--- # comment\nc: 3\n...\n";
+        let documents = split_documents(contents)
+            .into_iter()
+            .map(|range| &contents[range])
+            .collect::<Vec<_>>();
+        assert_eq!(documents, ["a: 1\n", "b: 2\n", "c: 3\n", ""]);
+
+        // `---` only separates documents at the start of a line.
+        assert_eq!(split_documents("a: ---\n  ---b\n").len(), 1);
+    }
+
+    #[test]
+    fn test_all_from_string() {
+        let key = WorkflowKey::local("/tmp/ci.yml".into()).unwrap();
+
+        // A single document keeps the file's key, even with a leading `---`.
+        let workflows =
+            Workflow::all_from_string("---\non: push\njobs: {}\n".into(), key.clone()).unwrap();
+        assert_eq!(workflows.len(), 1);
+        assert_eq!(workflows[0].key().document(), None);
+
+        let workflows =
+            Workflow::all_from_string("version: 2\nupdates: []\n".into(), key.clone()).unwrap();
+        assert!(workflows.is_empty());
+
+        let workflows = Workflow::all_from_string(
+            "on: push\njobs: {}\n---\nversion: 2\n---\non: pull_request\njobs: {}\n".into(),
+            key,
+        )
+        .unwrap();
+        assert_eq!(
+            workflows
+                .iter()
+                .map(|w| w.key().document())
+                .collect::<Vec<_>>(),
+            [Some(0), Some(2)]
+        );
+
+        // Later documents keep their positions within the original file.
+        let source = workflows[1].document().source();
+        assert_eq!(source.lines().nth(5), Some("on: pull_request"));
+    }
+
+    #[test]
+    fn test_has_trigger() {
+        let key = WorkflowKey::local("/tmp/ci.yml".into()).unwrap();
+
+        for (on, event, expected) in [
+            ("push", "push", true),
+            ("push", "fork", false),
+            ("[push, fork]", "fork", true),
+            ("{ fork: null, push: { branches: [main] } }", "fork", true),
+            ("{ push: { branches: [main] } }", "pull_request", false),
+        ] {
+            let workflow =
+                Workflow::from_string(format!("on: {on}\njobs: {{}}\n"), key.clone()).unwrap();
+            assert_eq!(workflow.has_trigger(event), expected, "{on}: {event}");
+        }
+    }
 }
@@ -0,0 +1,170 @@
+//! Output destinations for zizmor's findings.
+
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Stdout, Write},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use anstream::{adapter::StripBytes, AutoStream};
+use anyhow::{Context, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+
+/// Distinguishes temporary files created by the same process.
+static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// A file that's written to a temporary path and only moved into place
+/// once [`AtomicFile::persist`] is called.
+///
+/// This ensures that a failed or interrupted run never leaves a truncated
+/// file at the destination. If the `AtomicFile` is dropped without being
+/// persisted, the temporary file is removed.
+pub(crate) struct AtomicFile {
+    dest: Utf8PathBuf,
+    temp: Utf8PathBuf,
+    file: Option<BufWriter<File>>,
+}
+
+impl AtomicFile {
+    pub(crate) fn create(dest: &Utf8Path) -> Result<Self> {
+        let filename = dest
+            .file_name()
+            .with_context(|| format!("invalid output path: {dest}"))?;
+
+        // The temporary file must live alongside the destination, since
+        // renames are only atomic within a single filesystem. The name
+        // is unique per process and per `AtomicFile`, and `create_new`
+        // refuses to clobber anything that's already there.
+        let temp = dest.with_file_name(format!(
+            ".{filename}.{pid}.{n}.tmp",
+            pid = std::process::id(),
+            n = TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+
+        let file = File::options()
+            .write(true)
+            .create_new(true)
+            .open(&temp)
+            .with_context(|| format!("couldn't create temporary output file: {temp}"))?;
+
+        Ok(Self {
+            dest: dest.into(),
+            temp,
+            file: Some(BufWriter::new(file)),
+        })
+    }
+
+    /// Flushes and syncs the temporary file, then moves it into place.
+    pub(crate) fn persist(mut self) -> Result<()> {
+        // NOTE: `file` is only ever `None` after `persist`, which consumes `self`.
+        let file = self.file.take().unwrap();
+        let file = file.into_inner().map_err(|e| e.into_error())?;
+        file.sync_all()?;
+
+        fs::rename(&self.temp, &self.dest)
+            .with_context(|| format!("couldn't write output file: {dest}", dest = self.dest))
+    }
+}
+
+impl Write for AtomicFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.file.as_mut().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.as_mut().unwrap().flush()
+    }
+}
+
+impl Drop for AtomicFile {
+    fn drop(&mut self) {
+        // Best-effort: after a successful `persist` there's nothing left
+        // to remove, and on failure there's nothing more we can do.
+        let _ = fs::remove_file(&self.temp);
+    }
+}
+
+/// Where zizmor writes its findings: either `stdout`, or a file given
+/// via `--output`. `--output -` also means `stdout`.
+///
+/// Output destined for a file never contains ANSI escapes, regardless
+/// of whether `stdout` is a terminal.
+pub(crate) enum Output {
+    Stdout(AutoStream<Stdout>),
+    /// The file, along with the state of ANSI escape stripping, since an
+    /// escape sequence may be split across writes.
+    File(AtomicFile, StripBytes),
+}
+
+impl Output {
+    pub(crate) fn new(path: Option<&Utf8Path>) -> Result<Self> {
+        match path {
+            Some(path) if path != "-" => {
+                Ok(Self::File(AtomicFile::create(path)?, StripBytes::new()))
+            }
+            _ => Ok(Self::Stdout(anstream::stdout())),
+        }
+    }
+
+    /// Completes all output, moving any output file into place.
+    pub(crate) fn finish(self) -> Result<()> {
+        match self {
+            Self::Stdout(mut stdout) => Ok(stdout.flush()?),
+            Self::File(file, _) => file.persist(),
+        }
+    }
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Self::Stdout(stdout) => stdout.write(buf),
+            Self::File(file, strip) => {
+                for printable in strip.strip_next(buf) {
+                    file.write_all(printable)?;
+                }
+                Ok(buf.len())
+            }
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Self::Stdout(stdout) => stdout.flush(),
+            Self::File(file, _) => file.flush(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use camino::Utf8PathBuf;
+
+    use super::AtomicFile;
+
+    #[test]
+    fn test_atomic_file_same_dest_same_process() {
+        let dir = Utf8PathBuf::try_from(std::env::temp_dir())
+            .unwrap()
+            .join(format!("zizmor-atomic-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let dest = dir.join("out.json");
+
+        // Two in-flight writers for the same destination mustn't share
+        // (or clobber) a temporary file.
+        let mut first = AtomicFile::create(&dest).unwrap();
+        let mut second = AtomicFile::create(&dest).unwrap();
+        assert_ne!(first.temp, second.temp);
+
+        first.write_all(b"first").unwrap();
+        second.write_all(b"second").unwrap();
+        first.persist().unwrap();
+        assert_eq!(std::fs::read_to_string(&dest).unwrap(), "first");
+        second.persist().unwrap();
+        assert_eq!(std::fs::read_to_string(&dest).unwrap(), "second");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,14 +1,17 @@
 //! Functionality for registering and managing the lifecycles of
 //! audits.
 
-use std::{fmt::Display, process::ExitCode};
+use std::{fmt::Display, process::ExitCode, time::Duration};
 
 use crate::{
-    audit::WorkflowAudit,
+    audit::{self, Network, WorkflowAudit},
+    baseline::Baseline,
+    cli::App,
     config::Config,
-    finding::{Confidence, Finding, Persona, Severity},
-    models::{RepositoryUses, Workflow},
-    App,
+    exclusions::Exclusions,
+    finding::{self, Confidence, Finding, Persona, Severity},
+    models::{Action, AuditInput, RepositoryUses, Workflow},
+    state::AuditState,
 };
 use anyhow::{anyhow, Context, Result};
 use camino::{Utf8Path, Utf8PathBuf};
@@ -19,6 +22,8 @@ use tracing::instrument;
 #[derive(Debug, Clone, Eq, Hash, PartialEq, Serialize)]
 pub(crate) struct LocalWorkflowKey {
     path: Utf8PathBuf,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    document: Option<usize>,
 }
 
 #[derive(Debug, Clone, Eq, Hash, PartialEq, Serialize)]
@@ -27,23 +32,31 @@ pub(crate) struct RemoteWorkflowKey {
     repo: String,
     git_ref: Option<String>,
     path: Utf8PathBuf,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    document: Option<usize>,
 }
 
+/// The synthetic path used for an input read from `stdin`.
+const STDIN_PATH: &str = "<stdin>";
+
 /// A unique identifying "key" for a workflow file in a given run of zizmor.
 ///
-/// zizmor currently knows two different kinds of keys: local keys
-/// are just canonical paths to files on disk, while remote keys are
-/// relative paths within a referenced GitHub repository.
+/// zizmor currently knows three different kinds of keys: local keys
+/// are just canonical paths to files on disk, remote keys are
+/// relative paths within a referenced GitHub repository, and the stdin
+/// key identifies the (single) input read from `stdin`.
 #[derive(Debug, Clone, Eq, Hash, PartialEq, Serialize)]
 pub(crate) enum WorkflowKey {
     Local(LocalWorkflowKey),
     Remote(RemoteWorkflowKey),
+    Stdin,
 }
 
 impl Display for WorkflowKey {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             WorkflowKey::Local(local) => write!(f, "file://{path}", path = local.path),
+            WorkflowKey::Stdin => f.write_str(STDIN_PATH),
             WorkflowKey::Remote(remote) => {
                 // No ref means assume HEAD, i.e. whatever's on the default branch.
                 let git_ref = remote.git_ref.as_deref().unwrap_or("HEAD");
@@ -66,7 +79,10 @@ impl WorkflowKey {
             return Err(anyhow!("invalid local workflow: no filename component"));
         }
 
-        Ok(Self::Local(LocalWorkflowKey { path }))
+        Ok(Self::Local(LocalWorkflowKey {
+            path,
+            document: None,
+        }))
     }
 
     pub(crate) fn remote(slug: &RepositoryUses, path: String) -> Result<Self> {
@@ -79,17 +95,91 @@ impl WorkflowKey {
             repo: slug.repo.into(),
             git_ref: slug.git_ref.map(Into::into),
             path: path.into(),
+            document: None,
         }))
     }
 
+    /// Returns a key for the `index`th (0-based) document within the file
+    /// that this [`WorkflowKey`] identifies.
+    ///
+    /// Only multi-document files have per-document keys. The stdin key is
+    /// always a single document, and is returned unchanged.
+    pub(crate) fn with_document(self, index: usize) -> Self {
+        match self {
+            WorkflowKey::Local(local) => WorkflowKey::Local(LocalWorkflowKey {
+                document: Some(index),
+                ..local
+            }),
+            WorkflowKey::Remote(remote) => WorkflowKey::Remote(RemoteWorkflowKey {
+                document: Some(index),
+                ..remote
+            }),
+            WorkflowKey::Stdin => WorkflowKey::Stdin,
+        }
+    }
+
+    /// Returns the (0-based) index of the document this [`WorkflowKey`]
+    /// identifies, if it's one of several within a multi-document file.
+    pub(crate) fn document(&self) -> Option<usize> {
+        match self {
+            WorkflowKey::Local(local) => local.document,
+            WorkflowKey::Remote(remote) => remote.document,
+            WorkflowKey::Stdin => None,
+        }
+    }
+
     /// Returns this [`WorkflowKey`]'s filepath component.
     ///
-    /// This will be an absolute path for local keys, and a relative
-    /// path for remote keys.
+    /// This will be an absolute path for local keys, a relative
+    /// path for remote keys, and `<stdin>` for the stdin key.
     pub(crate) fn path(&self) -> &str {
         match self {
             WorkflowKey::Local(local) => local.path.as_str(),
             WorkflowKey::Remote(remote) => remote.path.as_str(),
+            WorkflowKey::Stdin => STDIN_PATH,
+        }
+    }
+
+    /// Returns this [`WorkflowKey`]'s path on disk, if it's a local key.
+    pub(crate) fn local_path(&self) -> Option<&Utf8Path> {
+        match self {
+            WorkflowKey::Local(local) => Some(&local.path),
+            WorkflowKey::Remote(_) | WorkflowKey::Stdin => None,
+        }
+    }
+
+    /// Returns a subjective relative path for this [`WorkflowKey`].
+    ///
+    /// In general, this will be a relative path within the repository root,
+    /// e.g. if zizmor was told to scan `/tmp/src` then one of the discovered
+    /// workflows might be `.github/workflows/ci.yml` relative to `/tmp/src`,
+    /// and one of the discovered actions might be
+    /// `.github/actions/setup/action.yml`.
+    ///
+    /// The exceptional case here is when zizmor is asked to scan a single
+    /// input at some arbitrary location on disk. In that case, just
+    /// the base filename itself is returned.
+    pub(crate) fn relative_path(&self) -> &str {
+        let path = self.path();
+
+        match path.rfind(".github/") {
+            Some(start) => &path[start..],
+            None => self.filename(),
+        }
+    }
+
+    /// Returns the root of the workspace that this [`WorkflowKey`] belongs
+    /// to, i.e. the directory containing its `.github/` directory.
+    ///
+    /// This is only known for local keys beneath a `.github/` directory.
+    pub(crate) fn workspace_root(&self) -> Option<&Utf8Path> {
+        match self {
+            WorkflowKey::Local(local) => {
+                let path = local.path.as_str();
+                path.rfind("/.github/")
+                    .map(|end| Utf8Path::new(&path[..end]))
+            }
+            WorkflowKey::Remote(_) | WorkflowKey::Stdin => None,
         }
     }
 
@@ -100,23 +190,44 @@ impl WorkflowKey {
         match self {
             WorkflowKey::Local(local) => local.path.file_name().unwrap(),
             WorkflowKey::Remote(remote) => remote.path.file_name().unwrap(),
+            WorkflowKey::Stdin => STDIN_PATH,
+        }
+    }
+
+    /// Returns the owner of the repository this [`WorkflowKey`] belongs to,
+    /// if known.
+    ///
+    /// This is only known for remote keys.
+    pub(crate) fn owner(&self) -> Option<&str> {
+        match self {
+            WorkflowKey::Local(_) | WorkflowKey::Stdin => None,
+            WorkflowKey::Remote(remote) => Some(&remote.owner),
         }
     }
 }
 
 pub(crate) struct WorkflowRegistry {
     pub(crate) workflows: IndexMap<WorkflowKey, Workflow>,
+    pub(crate) actions: IndexMap<WorkflowKey, Action>,
+    /// Local paths that [`WorkflowRegistry::register_by_path`] skips.
+    exclusions: Exclusions,
+    /// The number of paths skipped due to [`WorkflowRegistry::exclusions`].
+    pub(crate) excluded: usize,
 }
 
 impl WorkflowRegistry {
-    pub(crate) fn new() -> Self {
+    pub(crate) fn new(exclusions: Exclusions) -> Self {
         Self {
             workflows: Default::default(),
+            actions: Default::default(),
+            exclusions,
+            excluded: 0,
         }
     }
 
+    /// The total number of registered inputs, i.e. workflows and actions.
     pub(crate) fn len(&self) -> usize {
-        self.workflows.len()
+        self.workflows.len() + self.actions.len()
     }
 
     #[instrument(skip(self))]
@@ -133,41 +244,114 @@ impl WorkflowRegistry {
         Ok(())
     }
 
+    /// Reads a single input from `stdin` and registers it as either a
+    /// workflow or an action, depending on which it parses as.
+    pub(crate) fn register_stdin(&mut self) -> Result<()> {
+        let contents =
+            std::io::read_to_string(std::io::stdin()).context("couldn't read from stdin")?;
+
+        match Workflow::from_string(contents.clone(), WorkflowKey::Stdin) {
+            Ok(workflow) => self.register(workflow),
+            Err(err) => match Action::from_string(contents, WorkflowKey::Stdin) {
+                Ok(action) => self.register_action(action),
+                // Most inputs are workflows, so that error is
+                // the more useful one to report.
+                Err(_) => Err(err),
+            },
+        }
+    }
+
+    #[instrument(skip(self))]
+    pub(crate) fn register_action(&mut self, action: Action) -> Result<()> {
+        if self.actions.contains_key(&action.key) {
+            return Err(anyhow!(
+                "can't register {key} more than once",
+                key = action.key
+            ));
+        }
+
+        self.actions.insert(action.key.clone(), action);
+
+        Ok(())
+    }
+
+    /// Registers the workflow or action at the given path.
+    ///
+    /// Files named `action.yml` or `action.yaml` are loaded as actions;
+    /// everything else is loaded as a workflow (or several, for
+    /// multi-document files). See [`Workflow::all_from_string`].
+    ///
+    /// Excluded paths are skipped (and counted) without being read at all.
     #[instrument(skip(self))]
     pub(crate) fn register_by_path(&mut self, path: &Utf8Path) -> Result<()> {
-        let workflow =
-            Workflow::from_file(path).with_context(|| "couldn't load workflow from file")?;
+        if self.exclusions.excludes(path) {
+            tracing::debug!("skipping {path}: excluded");
+            self.excluded += 1;
+            return Ok(());
+        }
 
-        self.register(workflow)
+        if Action::is_action_path(path) {
+            let action =
+                Action::from_file(path).with_context(|| "couldn't load action from file")?;
+
+            return self.register_action(action);
+        }
+
+        let workflows =
+            Workflow::all_from_file(path).with_context(|| "couldn't load workflow from file")?;
+
+        workflows
+            .into_iter()
+            .try_for_each(|workflow| self.register(workflow))
     }
 
     pub(crate) fn iter_workflows(&self) -> indexmap::map::Iter<'_, WorkflowKey, Workflow> {
         self.workflows.iter()
     }
 
-    pub(crate) fn get_workflow(&self, key: &WorkflowKey) -> &Workflow {
-        self.workflows
-            .get(key)
-            .expect("API misuse: requested an un-registered workflow")
+    pub(crate) fn iter_actions(&self) -> indexmap::map::Iter<'_, WorkflowKey, Action> {
+        self.actions.iter()
     }
 
-    /// Returns a subjective relative path for the given workflow.
-    ///
-    /// In general, this will be a relative path within the repository root,
-    /// e.g. if zizmor was told to scan `/tmp/src` then one of the discovered
-    /// workflows might be `.github/workflows/ci.yml` relative to `/tmp/src`.
+    /// Returns the registered workflow or action for the given key.
+    pub(crate) fn get_input(&self, key: &WorkflowKey) -> &dyn AuditInput {
+        match self.workflows.get(key) {
+            Some(workflow) => workflow,
+            None => self
+                .actions
+                .get(key)
+                .expect("API misuse: requested an un-registered input"),
+        }
+    }
+
+    /// Returns a subjective relative path for the given workflow or action.
     ///
-    /// The exceptional case here is when zizmor is asked to scan a single
-    /// workflow at some arbitrary location on disk. In that case, just
-    /// the base workflow filename itself is returned.
+    /// See [`WorkflowKey::relative_path`].
     pub(crate) fn get_workflow_relative_path<'a>(&self, key: &'a WorkflowKey) -> &'a str {
-        let path = key.path();
+        key.relative_path()
+    }
+}
 
-        match path.rfind(".github/workflows") {
-            Some(start) => &path[start..],
-            // NOTE: Unwraps are safe since file component is always present and
-            // all paths are UTF-8 by construction.
-            None => key.filename(),
+/// A single registered input to audit.
+#[derive(Clone, Copy)]
+pub(crate) enum Input<'w> {
+    Workflow(&'w Workflow),
+    Action(&'w Action),
+}
+
+impl<'w> Input<'w> {
+    pub(crate) fn key(&self) -> &'w WorkflowKey {
+        match self {
+            Input::Workflow(workflow) => workflow.key(),
+            Input::Action(action) => action.key(),
+        }
+    }
+
+    /// Runs the given audit over this input.
+    pub(crate) fn audit(&self, audit: &dyn WorkflowAudit) -> Result<Vec<Finding<'w>>> {
+        match self {
+            Input::Workflow(workflow) => audit.audit(workflow),
+            Input::Action(action) => audit.audit_composite(action),
         }
     }
 }
@@ -177,35 +361,126 @@ pub(crate) struct AuditRegistry {
 }
 
 impl AuditRegistry {
-    pub(crate) fn new() -> Self {
-        Self {
-            workflow_audits: Default::default(),
+    /// Constructs every audit in the [`audit::catalog`], except for those
+    /// deselected on the command line, those disabled by `config`, and
+    /// those that can't run (e.g. online audits without a GitHub API token).
+    pub(crate) fn new(app: &App, state: &AuditState, config: &Config) -> Result<Self> {
+        let catalog = audit::catalog();
+
+        for ident in app.select.iter().chain(&app.skip) {
+            if !catalog.iter().any(|meta| meta.ident == ident) {
+                return Err(anyhow!("unknown audit: {ident} (see --list-audits)"));
+            }
         }
+
+        let mut workflow_audits = IndexMap::new();
+        for meta in catalog {
+            let selected = app.select.iter().any(|ident| ident == meta.ident);
+
+            if (!app.select.is_empty() && !selected)
+                || app.skip.iter().any(|ident| ident == meta.ident)
+            {
+                tracing::debug!("skipping {audit}: not selected", audit = meta.ident);
+                continue;
+            }
+
+            if app.skip_network && meta.network == Network::Required {
+                tracing::debug!("skipping {audit}: requires network", audit = meta.ident);
+                continue;
+            }
+
+            if config.disables(meta.ident) {
+                // Not running an audit that was asked for by name is
+                // surprising enough to mention.
+                if selected {
+                    tracing::warn!("skipping {audit}: disabled by config", audit = meta.ident);
+                } else {
+                    tracing::debug!("skipping {audit}: disabled by config", audit = meta.ident);
+                }
+                continue;
+            }
+
+            match (meta.new)(state.clone()) {
+                Ok(audit) => {
+                    workflow_audits.insert(meta.ident, audit);
+                }
+                Err(e) => tracing::warn!("skipping {audit}: {e}", audit = meta.ident),
+            }
+        }
+
+        Ok(Self { workflow_audits })
+    }
+
+    /// Constructs every audit in the [`audit::catalog`] that doesn't require
+    /// GitHub's API, except for those disabled by `config`.
+    pub(crate) fn offline(state: &AuditState, config: &Config) -> Self {
+        let mut workflow_audits = IndexMap::new();
+        for meta in audit::catalog() {
+            if meta.network == Network::Required || config.disables(meta.ident) {
+                continue;
+            }
+
+            match (meta.new)(state.clone()) {
+                Ok(audit) => {
+                    workflow_audits.insert(meta.ident, audit);
+                }
+                Err(e) => tracing::debug!("skipping {audit}: {e}", audit = meta.ident),
+            }
+        }
+
+        Self { workflow_audits }
     }
 
     pub(crate) fn len(&self) -> usize {
         self.workflow_audits.len()
     }
 
-    pub(crate) fn register_workflow_audit(
-        &mut self,
-        ident: &'static str,
-        audit: Box<dyn WorkflowAudit>,
-    ) {
-        self.workflow_audits.insert(ident, audit);
+    pub(crate) fn iter_workflow_audits(
+        &self,
+    ) -> indexmap::map::Iter<'_, &'static str, Box<dyn WorkflowAudit>> {
+        self.workflow_audits.iter()
     }
+}
 
-    pub(crate) fn iter_workflow_audits(&self) -> indexmap::map::Iter<&str, Box<dyn WorkflowAudit>> {
-        self.workflow_audits.iter()
+/// Per-audit runtime statistics, accumulated across an entire run.
+#[derive(Default, Serialize)]
+pub(crate) struct RuleStats {
+    /// Each audit's statistics, in registration order.
+    #[serde(flatten)]
+    pub(crate) rules: IndexMap<&'static str, RuleStat>,
+}
+
+/// Runtime statistics for a single audit.
+#[derive(Default, Serialize)]
+pub(crate) struct RuleStat {
+    /// The total time spent in this audit across all workflows, in seconds.
+    #[serde(serialize_with = "serialize_secs")]
+    pub(crate) elapsed: Duration,
+
+    /// The number of GitHub API requests made by this audit.
+    pub(crate) api_calls: usize,
+}
+
+fn serialize_secs<S: serde::Serializer>(duration: &Duration, s: S) -> Result<S::Ok, S::Error> {
+    s.serialize_f64(duration.as_secs_f64())
+}
+
+impl RuleStats {
+    pub(crate) fn record(&mut self, ident: &'static str, elapsed: Duration, api_calls: usize) {
+        let stat = self.rules.entry(ident).or_default();
+        stat.elapsed += elapsed;
+        stat.api_calls += api_calls;
     }
 }
 
 /// A registry of all findings discovered during a `zizmor` run.
 pub(crate) struct FindingRegistry<'a> {
     config: &'a Config,
+    baseline: Option<&'a Baseline>,
     minimum_severity: Option<Severity>,
     minimum_confidence: Option<Confidence>,
     persona: Persona,
+    dedupe: bool,
     suppressed: Vec<Finding<'a>>,
     ignored: Vec<Finding<'a>>,
     findings: Vec<Finding<'a>>,
@@ -213,12 +488,27 @@ pub(crate) struct FindingRegistry<'a> {
 }
 
 impl<'a> FindingRegistry<'a> {
-    pub(crate) fn new(app: &App, config: &'a Config) -> Self {
+    pub(crate) fn new(app: &App, config: &'a Config, baseline: Option<&'a Baseline>) -> Self {
+        Self {
+            baseline,
+            // Command-line flags take precedence over the config file.
+            minimum_severity: app.min_severity.or(config.min_severity()),
+            minimum_confidence: app.min_confidence.or(config.min_confidence()),
+            dedupe: app.dedupe,
+            ..Self::for_persona(config, app.persona.or(config.persona()).unwrap_or_default())
+        }
+    }
+
+    /// Constructs a registry that reports every finding for the given
+    /// persona, other than those ignored inline or by `config`.
+    pub(crate) fn for_persona(config: &'a Config, persona: Persona) -> Self {
         Self {
             config,
-            minimum_severity: app.min_severity,
-            minimum_confidence: app.min_confidence,
-            persona: app.persona,
+            baseline: None,
+            minimum_severity: config.min_severity(),
+            minimum_confidence: config.min_confidence(),
+            persona,
+            dedupe: false,
             suppressed: Default::default(),
             ignored: Default::default(),
             findings: Default::default(),
@@ -228,7 +518,14 @@ impl<'a> FindingRegistry<'a> {
 
     /// Adds one or more findings to the current findings set,
     /// filtering with the configuration in the process.
+    ///
+    /// With `--dedupe`, reported findings of the same issue are
+    /// collapsed (see [`finding::dedupe`]). Only findings within the same
+    /// call are collapsed, so callers should pass all of an input's
+    /// findings at once.
     pub(crate) fn extend(&mut self, results: Vec<Finding<'a>>) {
+        let mut reported = vec![];
+
         // TODO: is it faster to iterate like this, or do `find_by_max`
         // and then `extend`?
         for finding in results {
@@ -237,23 +534,32 @@ impl<'a> FindingRegistry<'a> {
             } else if finding.ignored
                 || self
                     .minimum_severity
-                    .map_or(false, |min| min > finding.determinations.severity)
+                    .is_some_and(|min| min > finding.determinations.severity)
                 || self
                     .minimum_confidence
-                    .map_or(false, |min| min > finding.determinations.confidence)
+                    .is_some_and(|min| min > finding.determinations.confidence)
                 || self.config.ignores(&finding)
+                || self.baseline.is_some_and(|b| b.contains(&finding))
             {
                 self.ignored.push(finding);
             } else {
-                if self
-                    .highest_seen_severity
-                    .map_or(true, |s| finding.determinations.severity > s)
-                {
-                    self.highest_seen_severity = Some(finding.determinations.severity);
-                }
+                reported.push(finding);
+            }
+        }
+
+        if self.dedupe {
+            reported = finding::dedupe(reported);
+        }
 
-                self.findings.push(finding);
+        for finding in reported {
+            if self
+                .highest_seen_severity
+                .map_or(true, |s| finding.determinations.severity > s)
+            {
+                self.highest_seen_severity = Some(finding.determinations.severity);
             }
+
+            self.findings.push(finding);
         }
     }
 
@@ -267,6 +573,12 @@ impl<'a> FindingRegistry<'a> {
         &self.findings
     }
 
+    /// Consumes this registry, returning all non-ignored and
+    /// non-suppressed findings.
+    pub(crate) fn into_findings(self) -> Vec<Finding<'a>> {
+        self.findings
+    }
+
     /// All ignored findings.
     pub(crate) fn ignored(&self) -> &[Finding<'a>] {
         &self.ignored
@@ -287,6 +599,7 @@ impl From<FindingRegistry<'_>> for ExitCode {
                 Severity::Low => ExitCode::from(12),
                 Severity::Medium => ExitCode::from(13),
                 Severity::High => ExitCode::from(14),
+                Severity::Critical => ExitCode::from(15),
             },
             None => ExitCode::SUCCESS,
         }
@@ -1,13 +1,21 @@
 //! APIs for rendering zizmor's "plain" (i.e. terminal) output format.
 
-use std::collections::{hash_map::Entry, HashMap};
+use std::{
+    cmp::Reverse,
+    collections::{hash_map::Entry, HashMap},
+    io::{self, Write},
+    time::SystemTime,
+};
 
 use crate::{
+    audit,
     finding::{Finding, Location, Severity},
-    registry::{FindingRegistry, WorkflowKey, WorkflowRegistry},
+    github_api::RateLimit,
+    registry::{FindingRegistry, RuleStats, WorkflowKey, WorkflowRegistry},
 };
 use annotate_snippets::{Level, Renderer, Snippet};
-use anstream::{print, println};
+use anstream::eprintln;
+use indexmap::IndexMap;
 use owo_colors::OwoColorize;
 use terminal_link::Link;
 
@@ -18,7 +26,7 @@ impl From<&Severity> for Level {
             Severity::Informational => Level::Info,
             Severity::Low => Level::Help,
             Severity::Medium => Level::Warning,
-            Severity::High => Level::Error,
+            Severity::High | Severity::Critical => Level::Error,
         }
     }
 }
@@ -43,13 +51,13 @@ pub(crate) fn finding_snippet<'w>(
 
     let mut snippets = vec![];
     for (workflow_key, locations) in locations_by_workflow {
-        let workflow = registry.get_workflow(workflow_key);
+        let input = registry.get_input(workflow_key);
 
         snippets.push(
-            Snippet::source(workflow.document.source())
+            Snippet::source(input.document().source())
                 .fold(true)
                 .line_start(1)
-                .origin(workflow.link.as_deref().unwrap_or(workflow_key.path()))
+                .origin(input.link().unwrap_or(workflow_key.path()))
                 .annotations(locations.iter().map(|loc| {
                     let annotation = match loc.symbolic.link {
                         Some(ref link) => link,
@@ -66,12 +74,204 @@ pub(crate) fn finding_snippet<'w>(
     snippets
 }
 
-pub(crate) fn render_findings(registry: &WorkflowRegistry, findings: &FindingRegistry) {
+/// Renders each finding, grouped by the file that it's reported in.
+///
+/// When findings span more than one file, each group is preceded by a
+/// heading with the file's path and number of findings.
+pub(crate) fn render_findings(
+    registry: &WorkflowRegistry,
+    findings: &FindingRegistry,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    // NOTE: Every finding has at least one location, and findings are
+    // reported in their first location's file.
+    let mut findings_by_file: IndexMap<&WorkflowKey, Vec<&Finding>> = IndexMap::new();
     for finding in findings.findings() {
-        render_finding(registry, finding);
-        println!();
+        findings_by_file
+            .entry(finding.locations[0].symbolic.key)
+            .or_default()
+            .push(finding);
+    }
+
+    let headings = findings_by_file.len() > 1;
+    for (key, findings) in findings_by_file {
+        if headings {
+            let path = registry.get_input(key).link().unwrap_or(key.path());
+            writeln!(
+                out,
+                "{path} ({nfindings} finding{s})",
+                path = path.bold().underline(),
+                nfindings = findings.len(),
+                s = if findings.len() == 1 { "" } else { "s" },
+            )?;
+            writeln!(out)?;
+        }
+
+        for finding in findings {
+            render_finding(registry, finding, out)?;
+            writeln!(out)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Escapes a workflow command's message.
+fn escape_command_data(data: &str) -> String {
+    data.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Escapes a workflow command's property value.
+fn escape_command_property(property: &str) -> String {
+    escape_command_data(property)
+        .replace(':', "%3A")
+        .replace(',', "%2C")
+}
+
+/// Returns the path GitHub expects for a finding's file in a workflow
+/// command, i.e. relative to the repository (assumed to be `$CWD`) root.
+fn annotation_path(key: &WorkflowKey) -> String {
+    match key {
+        WorkflowKey::Local(_) => std::env::current_dir()
+            .ok()
+            .and_then(|cwd| cwd.canonicalize().ok())
+            .and_then(|cwd| {
+                std::path::Path::new(key.path())
+                    .strip_prefix(cwd)
+                    .ok()
+                    .map(|p| p.to_string_lossy().into_owned())
+            })
+            .unwrap_or_else(|| key.relative_path().into()),
+        // Remote keys are already relative to their repository's root.
+        WorkflowKey::Remote(_) | WorkflowKey::Stdin => key.path().into(),
     }
+}
+
+/// Renders each finding as a GitHub Actions workflow command, so that
+/// findings show up as annotations when zizmor runs in a workflow.
+///
+/// See: <https://docs.github.com/en/actions/writing-workflows/choosing-what-your-workflow-does/workflow-commands-for-github-actions>
+pub(crate) fn render_github(findings: &FindingRegistry, out: &mut impl Write) -> io::Result<()> {
+    for finding in findings.findings() {
+        let location = finding.primary_location();
+        let span = location.span;
+
+        let command = match finding.determinations.severity {
+            Severity::High | Severity::Critical => "error",
+            Severity::Medium => "warning",
+            _ => "notice",
+        };
+
+        let mut properties = format!(
+            "file={file},line={line},col={col}",
+            file = escape_command_property(&annotation_path(location.symbolic.key)),
+            line = span.start_line,
+            col = span.start_col,
+        );
+        if span.end_line != span.start_line {
+            properties.push_str(&format!(
+                ",endLine={line},endColumn={col}",
+                line = span.end_line,
+                col = span.end_col
+            ));
+        }
+
+        writeln!(
+            out,
+            "::{command} {properties},title={title}::{message}",
+            title = escape_command_property(finding.ident),
+            message = escape_command_data(&format!(
+                "{desc}: {annotation}",
+                desc = finding.summary(),
+                annotation = location.symbolic.annotation
+            )),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Renders each finding on a single line, as `path:line:col`, followed by
+/// the finding's rule, severity, and description.
+///
+/// The position is that of the finding's first location.
+pub(crate) fn render_concise(findings: &FindingRegistry, out: &mut impl Write) -> io::Result<()> {
+    for finding in findings.findings() {
+        let location = finding.primary_location();
+
+        writeln!(
+            out,
+            "{path}:{line}:{col}: {ident} [{severity}]: {desc}: {annotation}",
+            path = location.symbolic.key.path(),
+            line = location.span.start_line,
+            col = location.span.start_col,
+            ident = finding.ident.bold(),
+            severity = format!("{:?}", finding.determinations.severity).to_lowercase(),
+            desc = finding.summary(),
+            annotation = location.symbolic.annotation,
+        )?;
+    }
+
+    Ok(())
+}
 
+/// Renders one line per input with findings, as
+/// `path: N findings (H high, M medium, ...)`, and nothing else.
+///
+/// Each finding is attributed to the input of its first location.
+pub(crate) fn render_file_summary(
+    findings: &FindingRegistry,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    let mut by_path: IndexMap<_, HashMap<_, usize>> = IndexMap::new();
+    for finding in findings.findings() {
+        // NOTE: Every finding has at least one location.
+        let path = finding.locations[0].symbolic.key.path();
+        *by_path
+            .entry(path)
+            .or_default()
+            .entry(finding.determinations.severity)
+            .or_default() += 1;
+    }
+
+    for (path, by_severity) in by_path {
+        let nfindings = by_severity.values().sum::<usize>();
+        let counts = [
+            Severity::Critical,
+            Severity::High,
+            Severity::Medium,
+            Severity::Low,
+            Severity::Informational,
+            Severity::Unknown,
+        ]
+        .into_iter()
+        .filter_map(|severity| {
+            by_severity.get(&severity).map(|count| {
+                format!(
+                    "{count} {severity}",
+                    severity = format!("{severity:?}").to_lowercase()
+                )
+            })
+        })
+        .collect::<Vec<_>>();
+
+        writeln!(
+            out,
+            "{path}: {nfindings} finding{s} ({counts})",
+            path = path.bold(),
+            s = if nfindings == 1 { "" } else { "s" },
+            counts = counts.join(", "),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Renders the trailing summary of all findings, including how many
+/// were ignored or suppressed.
+pub(crate) fn render_summary(findings: &FindingRegistry, out: &mut impl Write) -> io::Result<()> {
     let mut qualifiers = vec![];
     if !findings.ignored().is_empty() {
         qualifiers.push(format!(
@@ -88,13 +288,14 @@ pub(crate) fn render_findings(registry: &WorkflowRegistry, findings: &FindingReg
 
     if findings.findings().is_empty() {
         if qualifiers.is_empty() {
-            println!("{}", "No findings to report. Good job!".green());
+            writeln!(out, "{}", "No findings to report. Good job!".green())?;
         } else {
-            println!(
+            writeln!(
+                out,
                 "{no_findings} ({qualifiers})",
                 no_findings = "No findings to report. Good job!".green(),
                 qualifiers = qualifiers.join(", ").bold(),
-            );
+            )?;
         }
     } else {
         let mut findings_by_severity = HashMap::new();
@@ -112,44 +313,157 @@ pub(crate) fn render_findings(registry: &WorkflowRegistry, findings: &FindingReg
 
         if qualifiers.is_empty() {
             let nfindings = findings.count();
-            print!(
+            write!(
+                out,
                 "{nfindings} finding{s}: ",
                 nfindings = nfindings.green(),
                 s = if nfindings == 1 { "" } else { "s" },
-            );
+            )?;
         } else {
-            print!(
+            write!(
+                out,
                 "{nfindings} findings ({qualifiers}): ",
                 nfindings = findings.count().green(),
                 qualifiers = qualifiers.join(", ").bold(),
-            );
+            )?;
         }
 
-        println!(
-            "{nunknown} unknown, {ninformational} informational, {nlow} low, {nmedium} medium, {nhigh} high",
+        writeln!(
+            out,
+            "{nunknown} unknown, {ninformational} informational, {nlow} low, {nmedium} medium, {nhigh} high, {ncritical} critical",
             nunknown = findings_by_severity.get(&Severity::Unknown).unwrap_or(&0),
             ninformational = findings_by_severity.get(&Severity::Informational).unwrap_or(&0).purple(),
             nlow = findings_by_severity.get(&Severity::Low).unwrap_or(&0).cyan(),
             nmedium = findings_by_severity.get(&Severity::Medium).unwrap_or(&0).yellow(),
             nhigh = findings_by_severity.get(&Severity::High).unwrap_or(&0).red(),
-        );
+            ncritical = findings_by_severity.get(&Severity::Critical).unwrap_or(&0).red().bold(),
+        )?;
     }
+
+    Ok(())
 }
 
-fn render_finding(registry: &WorkflowRegistry, finding: &Finding) {
+fn render_finding(
+    registry: &WorkflowRegistry,
+    finding: &Finding,
+    out: &mut impl Write,
+) -> io::Result<()> {
     let link = Link::new(finding.ident, finding.url).to_string();
     let confidence = format!(
         "audit confidence → {:?}",
         &finding.determinations.confidence
     );
     let confidence_footer = Level::Note.title(&confidence);
+    let summary = finding.summary();
+    let references = finding
+        .references
+        .iter()
+        .map(|url| format!("see: {url}"))
+        .collect::<Vec<_>>();
 
-    let message = Level::from(&finding.determinations.severity)
-        .title(finding.desc)
+    let mut message = Level::from(&finding.determinations.severity)
+        .title(&summary)
         .id(&link)
         .snippets(finding_snippet(registry, finding))
         .footer(confidence_footer);
+    if let Some(remediation) = &finding.remediation {
+        message = message.footer(Level::Help.title(&remediation.description));
+    }
+    message = message.footers(references.iter().map(|r| Level::Note.title(r)));
 
     let renderer = Renderer::styled();
-    println!("{}", renderer.render(message));
+    writeln!(out, "{}", renderer.render(message))?;
+
+    Ok(())
+}
+
+/// Renders a table of every audit in the [`audit::catalog`].
+pub(crate) fn render_audit_list(out: &mut impl Write) -> io::Result<()> {
+    let catalog = audit::catalog();
+
+    let width = catalog
+        .iter()
+        .map(|meta| meta.ident.len())
+        .max()
+        .unwrap_or(0);
+
+    writeln!(
+        out,
+        "{header}",
+        header = format!(
+            "{audit:width$}  {severity:13}  {network:8}  DESCRIPTION",
+            audit = "AUDIT",
+            severity = "SEVERITY",
+            network = "NETWORK"
+        )
+        .bold()
+    )?;
+    for meta in catalog {
+        // NOTE: Pad before colorizing, since escape codes have no width.
+        let ident = format!("{ident:width$}", ident = meta.ident);
+        writeln!(
+            out,
+            "{ident}  {severity:13}  {network:8}  {desc}",
+            ident = Link::new(&ident, meta.url).to_string().green(),
+            severity = format!("{:?}", meta.severity).to_lowercase(),
+            network = meta.network.to_string(),
+            desc = meta.desc,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Renders a per-audit runtime breakdown to `stderr`, slowest first.
+pub(crate) fn render_rule_stats(stats: &RuleStats) {
+    let mut rules = stats.rules.iter().collect::<Vec<_>>();
+    rules.sort_by_key(|(_, stat)| Reverse(stat.elapsed));
+
+    let width = rules
+        .iter()
+        .map(|(ident, _)| ident.len())
+        .max()
+        .unwrap_or(0);
+
+    eprintln!("{}", "Audit timings:".bold());
+    for (ident, stat) in rules {
+        eprintln!(
+            "  {ident:width$}  {elapsed:>9.3}s  {api_calls} API call{s}",
+            ident = ident.green(),
+            elapsed = stat.elapsed.as_secs_f64(),
+            api_calls = stat.api_calls,
+            s = if stat.api_calls == 1 { "" } else { "s" },
+        );
+    }
+}
+
+/// Renders the last known GitHub API rate limit state for each token
+/// to `stderr`.
+pub(crate) fn render_rate_limits(limits: &[Option<RateLimit>]) {
+    eprintln!("{}", "GitHub API rate limit:".bold());
+    if limits.is_empty() {
+        eprintln!("  no GitHub API tokens in use");
+    }
+    for (idx, limit) in limits.iter().enumerate() {
+        let token = format!("token {n}", n = idx + 1);
+        let Some(limit) = limit else {
+            eprintln!("  {token}: no requests made", token = token.green());
+            continue;
+        };
+
+        let reset = match limit.reset.duration_since(SystemTime::now()) {
+            Ok(wait) => format!(
+                "resets in {m}m{s:02}s",
+                m = wait.as_secs() / 60,
+                s = wait.as_secs() % 60
+            ),
+            Err(_) => "already reset".into(),
+        };
+        eprintln!(
+            "  {token}: {remaining} request{s} remaining, {reset}",
+            token = token.green(),
+            remaining = limit.remaining,
+            s = if limit.remaining == 1 { "" } else { "s" },
+        );
+    }
 }
@@ -1,12 +1,16 @@
 //! APIs for rendering SARIF outputs.
 
+use std::collections::{BTreeMap, HashSet};
+
 use serde_sarif::sarif::{
-    ArtifactContent, ArtifactLocation, Location as SarifLocation, LogicalLocation, Message,
-    PhysicalLocation, PropertyBag, Region, Result as SarifResult, Run, Sarif, Tool, ToolComponent,
+    ArtifactChange, ArtifactContent, ArtifactLocation, Fix, Location as SarifLocation,
+    LogicalLocation, Message, MultiformatMessageString, PhysicalLocation, PropertyBag, Region,
+    Replacement, ReportingDescriptor, Result as SarifResult, ResultLevel, Run, Sarif, Tool,
+    ToolComponent,
 };
 
 use crate::{
-    finding::{Finding, Location},
+    finding::{Finding, Location, Severity},
     registry::WorkflowRegistry,
 };
 
@@ -29,6 +33,7 @@ fn build_run(registry: &WorkflowRegistry, findings: &[Finding]) -> Run {
                         .semantic_version(env!("CARGO_PKG_VERSION"))
                         .download_uri(env!("CARGO_PKG_REPOSITORY"))
                         .information_uri(env!("CARGO_PKG_HOMEPAGE"))
+                        .rules(build_rules(findings))
                         .build(),
                 )
                 .build(),
@@ -37,18 +42,133 @@ fn build_run(registry: &WorkflowRegistry, findings: &[Finding]) -> Run {
         .build()
 }
 
+/// Builds one rule per distinct audit that produced a finding.
+fn build_rules(findings: &[Finding]) -> Vec<ReportingDescriptor> {
+    let mut seen = HashSet::new();
+
+    findings
+        .iter()
+        .filter(|finding| seen.insert(finding.ident))
+        .map(|finding| {
+            ReportingDescriptor::builder()
+                .id(finding.ident)
+                .name(finding.ident)
+                .short_description(
+                    MultiformatMessageString::builder()
+                        .text(finding.desc)
+                        .build(),
+                )
+                .help_uri(finding.url)
+                .build()
+        })
+        .collect()
+}
+
 fn build_results(registry: &WorkflowRegistry, findings: &[Finding]) -> Vec<SarifResult> {
     findings.iter().map(|f| build_result(registry, f)).collect()
 }
 
 fn build_result(registry: &WorkflowRegistry, finding: &Finding<'_>) -> SarifResult {
-    SarifResult::builder()
-        .message(finding.ident)
+    let mut result = SarifResult::builder()
+        .message(build_message(finding))
         .rule_id(finding.ident)
+        .level(level(finding.determinations.severity).to_string())
         .locations(build_locations(registry, &finding.locations))
+        .partial_fingerprints(BTreeMap::from([(
+            "zizmor/v1".to_string(),
+            finding.fingerprint().to_string(),
+        )]))
+        .build();
+    result.fixes = build_fixes(registry, finding);
+    result.properties = (!finding.properties.is_empty()).then(|| {
+        PropertyBag::builder()
+            .additional_properties(
+                finding
+                    .properties
+                    .iter()
+                    .map(|(name, value)| (name.to_string(), value.as_str().into()))
+                    .collect::<BTreeMap<_, _>>(),
+            )
+            .build()
+    });
+
+    result
+}
+
+/// Builds a result's message, linking to the finding's references (if
+/// any) in its Markdown form.
+fn build_message(finding: &Finding<'_>) -> Message {
+    if finding.references.is_empty() {
+        return finding.ident.into();
+    }
+
+    let references = finding
+        .references
+        .iter()
+        .map(|url| format!("- <{url}>"))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    Message::builder()
+        .text(finding.ident)
+        .markdown(format!(
+            "{ident}\n\nReferences:\n\n{references}",
+            ident = finding.ident
+        ))
+        .build()
+}
+
+/// Builds a SARIF fix from the finding's remediation, if it's mechanical.
+///
+/// SARIF fixes must change at least one artifact, so remediations without
+/// a replacement aren't included.
+fn build_fixes(registry: &WorkflowRegistry, finding: &Finding<'_>) -> Option<Vec<Fix>> {
+    let remediation = finding.remediation.as_ref()?;
+    let replacement = remediation.replacement.as_ref()?;
+    let location = finding.locations.first()?;
+
+    Some(vec![Fix::builder()
+        .description(Message::builder().text(&remediation.description).build())
+        .artifact_changes([ArtifactChange::builder()
+            .artifact_location(
+                ArtifactLocation::builder()
+                    .uri_base_id("%SRCROOT%")
+                    .uri(registry.get_workflow_relative_path(location.symbolic.key))
+                    .build(),
+            )
+            .replacements([Replacement::builder()
+                .deleted_region(region(location))
+                .inserted_content(ArtifactContent::builder().text(replacement).build())
+                .build()])
+            .build()])
+        .build()])
+}
+
+/// Returns the SARIF region for the given location.
+fn region(location: &Location<'_>) -> Region {
+    Region::builder()
+        // NOTE: SARIF lines/columns are 1-based.
+        .start_line(location.span.start_line as i64)
+        .end_line(location.span.end_line as i64)
+        .start_column(location.span.start_col as i64)
+        .end_column(location.span.end_col as i64)
+        .source_language("yaml")
+        .snippet(
+            ArtifactContent::builder()
+                .text(location.concrete.feature)
+                .build(),
+        )
         .build()
 }
 
+fn level(severity: Severity) -> ResultLevel {
+    match severity {
+        Severity::High | Severity::Critical => ResultLevel::Error,
+        Severity::Medium => ResultLevel::Warning,
+        Severity::Unknown | Severity::Informational | Severity::Low => ResultLevel::Note,
+    }
+}
+
 fn build_locations(registry: &WorkflowRegistry, locations: &[Location<'_>]) -> Vec<SarifLocation> {
     locations
         .iter()
@@ -72,25 +192,7 @@ fn build_locations(registry: &WorkflowRegistry, locations: &[Location<'_>]) -> V
                                 .uri(registry.get_workflow_relative_path(location.symbolic.key))
                                 .build(),
                         )
-                        .region(
-                            Region::builder()
-                                // NOTE: SARIF lines/columns are 1-based.
-                                .start_line((location.concrete.location.start_point.row as i64) + 1)
-                                .end_line((location.concrete.location.end_point.row as i64) + 1)
-                                .start_column(
-                                    (location.concrete.location.start_point.column as i64) + 1,
-                                )
-                                .end_column(
-                                    (location.concrete.location.end_point.column as i64) + 1,
-                                )
-                                .source_language("yaml")
-                                .snippet(
-                                    ArtifactContent::builder()
-                                        .text(location.concrete.feature)
-                                        .build(),
-                                )
-                                .build(),
-                        )
+                        .region(region(location))
                         .build(),
                 )
                 .message(
@@ -1,34 +1,229 @@
 //! zizmor's runtime state, including application-level caching.
 
+use std::{
+    env, fs,
+    io::Write as _,
+    process::{Command, Stdio},
+    sync::{Arc, OnceLock},
+    time::{Duration, SystemTime},
+};
+
+use anyhow::{anyhow, Result};
+use camino::Utf8PathBuf;
 use moka::sync::Cache;
+use serde::{de::DeserializeOwned, Serialize};
 
 use crate::{
-    github_api::{Branch, Client, ComparisonStatus, Tag},
-    App,
+    advisory_db::AdvisoryDb,
+    cli::App,
+    config::Config,
+    github_api::{Advisory, Branch, Client, ComparisonStatus, RetryPolicy, Tag, Tokens},
+    output::AtomicFile,
 };
 
 #[derive(Clone)]
 pub(crate) struct AuditState {
     pub(crate) no_online_audits: bool,
-    pub(crate) gh_token: Option<String>,
+    pub(crate) gh_tokens: Option<Arc<Tokens>>,
+    pub(crate) retry: RetryPolicy,
+    pub(crate) config: Config,
     pub(crate) caches: Caches,
+    /// A local advisory snapshot, used instead of GitHub's advisories API.
+    pub(crate) advisory_db: Option<Arc<AdvisoryDb>>,
+    /// Whether to check against withdrawn advisories too.
+    pub(crate) include_withdrawn: bool,
+    /// The outcome of validating `gh_tokens`, shared so that we only check once.
+    pub(crate) token_check: Arc<OnceLock<Result<(), String>>>,
+}
+
+/// Resolves the GitHub API tokens to use, if any.
+///
+/// Explicit `--gh-token`s always win, followed by `GITHUB_TOKEN`,
+/// `GH_TOKEN`, and finally the `gh` CLI's token. Either variable may hold
+/// several comma-separated tokens. With `--offline`, nothing is discovered:
+/// a token in the environment shouldn't quietly turn on network access.
+fn resolve_gh_tokens(
+    explicit: &[String],
+    offline: bool,
+    var: impl Fn(&str) -> Option<String>,
+    gh_cli: impl FnOnce() -> Option<String>,
+) -> Vec<String> {
+    if !explicit.is_empty() {
+        return explicit.to_vec();
+    }
+
+    if offline {
+        return vec![];
+    }
+
+    ["GITHUB_TOKEN", "GH_TOKEN"]
+        .into_iter()
+        .map(|name| {
+            var(name)
+                .unwrap_or_default()
+                .split(',')
+                .map(str::trim)
+                .filter(|token| !token.is_empty())
+                .map(String::from)
+                .collect::<Vec<_>>()
+        })
+        .find(|tokens| !tokens.is_empty())
+        .or_else(|| gh_cli().map(|token| vec![token]))
+        .unwrap_or_default()
+}
+
+/// Returns the token that the `gh` CLI is logged in with, if it's
+/// installed and logged in.
+fn gh_cli_token() -> Option<String> {
+    let output = Command::new("gh")
+        .args(["auth", "token"])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    let token = String::from_utf8(output.stdout).ok()?.trim().to_string();
+    (!token.is_empty()).then(|| {
+        tracing::debug!("using GitHub API token from `gh auth token`");
+        token
+    })
 }
 
 impl AuditState {
-    pub(crate) fn new(app: &App) -> Self {
-        Self {
-            caches: Caches::new(),
-            no_online_audits: app.no_online_audits,
-            gh_token: app.gh_token.clone(),
+    pub(crate) fn new(app: &App, config: &Config, advisory_db: Option<AdvisoryDb>) -> Self {
+        let mut caches = Caches::new();
+        if !app.no_cache {
+            caches.disk = DiskCache::discover(Duration::from_secs(app.cache_ttl * 60 * 60));
+        }
+
+        let mut builder = Self::builder()
+            .no_online_audits(app.no_online_audits)
+            .gh_tokens(&resolve_gh_tokens(
+                &app.gh_token,
+                app.offline,
+                |name| env::var(name).ok(),
+                gh_cli_token,
+            ))
+            .retry(RetryPolicy {
+                max_retries: app.max_retries,
+                wait_on_rate_limit: !app.no_rate_limit_wait,
+            })
+            .config(config.clone())
+            .caches(caches)
+            .include_withdrawn(app.include_withdrawn);
+        if let Some(advisory_db) = advisory_db {
+            builder = builder.advisory_db(advisory_db);
         }
+
+        builder.build()
+    }
+
+    /// Returns a builder for an [`AuditState`], starting from defaults:
+    /// online audits enabled, no GitHub API tokens, the default retry
+    /// policy and configuration, and fresh in-memory caches.
+    pub(crate) fn builder() -> AuditStateBuilder {
+        AuditStateBuilder::new()
     }
 
     /// Return a cache-configured GitHub API client, if
-    /// a GitHub API token is present.
+    /// any GitHub API tokens are present.
     pub(crate) fn github_client(&self) -> Option<Client> {
-        self.gh_token
+        self.gh_tokens
             .as_ref()
-            .map(|token| Client::new(token, self.caches.clone()))
+            .map(|tokens| Client::new(tokens.clone(), self.caches.clone(), self.retry))
+    }
+
+    /// Like [`AuditState::github_client`], but additionally checks (once
+    /// per run) that the token is actually usable.
+    pub(crate) fn validated_github_client(&self) -> Result<Option<Client>> {
+        let Some(client) = self.github_client() else {
+            return Ok(None);
+        };
+
+        self.token_check
+            .get_or_init(|| client.validate().map_err(|e| e.to_string()))
+            .clone()
+            .map_err(|e| anyhow!(e))?;
+
+        Ok(Some(client))
+    }
+}
+
+/// Builds an [`AuditState`]; see [`AuditState::builder`].
+pub(crate) struct AuditStateBuilder {
+    no_online_audits: bool,
+    gh_tokens: Option<Arc<Tokens>>,
+    retry: RetryPolicy,
+    config: Config,
+    caches: Caches,
+    advisory_db: Option<Arc<AdvisoryDb>>,
+    include_withdrawn: bool,
+}
+
+impl AuditStateBuilder {
+    fn new() -> Self {
+        Self {
+            no_online_audits: false,
+            gh_tokens: None,
+            retry: Default::default(),
+            config: Default::default(),
+            caches: Caches::new(),
+            advisory_db: None,
+            include_withdrawn: false,
+        }
+    }
+
+    pub(crate) fn no_online_audits(mut self, no_online_audits: bool) -> Self {
+        self.no_online_audits = no_online_audits;
+        self
+    }
+
+    /// Sets the GitHub API tokens to use. An empty list means no tokens.
+    pub(crate) fn gh_tokens(mut self, tokens: &[String]) -> Self {
+        self.gh_tokens = Tokens::new(tokens).map(Arc::new);
+        self
+    }
+
+    pub(crate) fn retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    pub(crate) fn config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    pub(crate) fn caches(mut self, caches: Caches) -> Self {
+        self.caches = caches;
+        self
+    }
+
+    pub(crate) fn advisory_db(mut self, advisory_db: AdvisoryDb) -> Self {
+        self.advisory_db = Some(Arc::new(advisory_db));
+        self
+    }
+
+    pub(crate) fn include_withdrawn(mut self, include_withdrawn: bool) -> Self {
+        self.include_withdrawn = include_withdrawn;
+        self
+    }
+
+    pub(crate) fn build(self) -> AuditState {
+        AuditState {
+            no_online_audits: self.no_online_audits,
+            gh_tokens: self.gh_tokens,
+            retry: self.retry,
+            config: self.config,
+            caches: self.caches,
+            advisory_db: self.advisory_db,
+            include_withdrawn: self.include_withdrawn,
+            token_check: Default::default(),
+        }
     }
 }
 
@@ -41,6 +236,9 @@ pub(crate) struct Caches {
     /// A cache of `(owner, repo) => tags`.
     pub(crate) tag_cache: Cache<(String, String), Vec<Tag>>,
 
+    /// A cache of `(owner, repo, ref) => commit`, for symbolic refs.
+    pub(crate) ref_cache: Cache<(String, String, String), Option<String>>,
+
     /// A cache of `(base_ref, head_ref) => status`.
     ///
     /// We don't bother disambiguating this cache by `owner/repo`, since
@@ -48,6 +246,15 @@ pub(crate) struct Caches {
     /// This is not technically true of Git SHAs due to SHAttered, but is
     /// effectively true for SHAs on GitHub due to GitHub's collision detection.
     pub(crate) ref_comparison_cache: Cache<(String, String), Option<ComparisonStatus>>,
+
+    /// A cache of `(owner, repo) => advisories`.
+    ///
+    /// Advisories are fetched for every version of an action at once,
+    /// so there's no need to key this by version as well.
+    pub(crate) advisory_cache: Cache<(String, String), Vec<Advisory>>,
+
+    /// An on-disk cache of GitHub API responses, shared between runs.
+    pub(crate) disk: Option<DiskCache>,
 }
 
 impl Caches {
@@ -57,7 +264,264 @@ impl Caches {
             // stats on how many unique repo slugs an average run sees.
             branch_cache: Cache::new(1000),
             tag_cache: Cache::new(1000),
+            ref_cache: Cache::new(10000),
             ref_comparison_cache: Cache::new(10000),
+            advisory_cache: Cache::new(1000),
+            disk: None,
+        }
+    }
+}
+
+/// A persistent cache of JSON-serialized values, keyed by a sequence of
+/// path components (e.g. `["tags", owner, repo]`).
+///
+/// Entries older than the cache's TTL are treated as missing. Entries are
+/// written atomically, so concurrent `zizmor` processes can safely share
+/// a single cache directory.
+#[derive(Clone)]
+pub(crate) struct DiskCache {
+    root: Utf8PathBuf,
+    ttl: Duration,
+}
+
+impl DiskCache {
+    /// Bumped whenever the layout or contents of cache entries change.
+    const VERSION: &'static str = "v1";
+
+    pub(crate) fn new(root: Utf8PathBuf, ttl: Duration) -> Self {
+        Self {
+            root: root.join(Self::VERSION),
+            ttl,
+        }
+    }
+
+    /// Returns a cache rooted in the user's cache directory, i.e.
+    /// `$XDG_CACHE_HOME/zizmor` or `~/.cache/zizmor`, if one can be found.
+    pub(crate) fn discover(ttl: Duration) -> Option<Self> {
+        let cache_home = env::var_os("XDG_CACHE_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(Into::into)
+            .or_else(|| {
+                env::var_os("HOME").map(|home| std::path::Path::new(&home).join(".cache"))
+            })?;
+
+        match Utf8PathBuf::from_path_buf(cache_home) {
+            Ok(cache_home) => Some(Self::new(cache_home.join("zizmor"), ttl)),
+            Err(cache_home) => {
+                tracing::debug!("not using non-UTF-8 cache directory: {cache_home:?}");
+                None
+            }
+        }
+    }
+
+    fn path(&self, key: &[&str]) -> Utf8PathBuf {
+        let mut path = self.root.clone();
+        for component in key {
+            path.push(escape_component(component));
         }
+        path.set_extension("json");
+        path
+    }
+
+    /// Returns the cached value for `key`, if present and fresh.
+    pub(crate) fn get<T: DeserializeOwned>(&self, key: &[&str]) -> Option<T> {
+        let path = self.path(key);
+
+        let modified = fs::metadata(&path).and_then(|m| m.modified()).ok()?;
+        let age = SystemTime::now()
+            .duration_since(modified)
+            .unwrap_or_default();
+        if age > self.ttl {
+            tracing::debug!("stale cache entry: {path}");
+            return None;
+        }
+
+        self.get_stale(key)
+    }
+
+    /// Returns the cached value for `key`, if present, regardless of
+    /// its age.
+    ///
+    /// This is only appropriate for values that are revalidated before use,
+    /// e.g. bodies that are cached alongside their `ETag`.
+    pub(crate) fn get_stale<T: DeserializeOwned>(&self, key: &[&str]) -> Option<T> {
+        let path = self.path(key);
+
+        let contents = fs::read(&path).ok()?;
+        match serde_json::from_slice(&contents) {
+            Ok(value) => Some(value),
+            Err(e) => {
+                tracing::debug!("ignoring corrupt cache entry {path}: {e}");
+                None
+            }
+        }
+    }
+
+    /// Caches `value` under `key`, replacing any existing entry.
+    pub(crate) fn put<T: Serialize>(&self, key: &[&str], value: &T) -> Result<()> {
+        let path = self.path(key);
+
+        // NOTE: unwrap is safe, since `path` always has at least the root
+        // as its parent.
+        fs::create_dir_all(path.parent().unwrap())?;
+
+        let mut file = AtomicFile::create(&path)?;
+        file.write_all(&serde_json::to_vec(value)?)?;
+        file.persist()
+    }
+}
+
+/// Escapes a cache key component into a single, portable path component.
+///
+/// Everything except ASCII alphanumerics, `-`, and `_` is percent-encoded,
+/// which rules out separators, `..`, and case-insensitive collisions
+/// between otherwise identical components.
+fn escape_component(component: &str) -> String {
+    let mut escaped = String::with_capacity(component.len());
+    for byte in component.bytes() {
+        match byte {
+            b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' => escaped.push(byte as char),
+            _ => escaped.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use camino::Utf8PathBuf;
+
+    use super::{escape_component, resolve_gh_tokens, AuditState, DiskCache};
+    use crate::github_api::RetryPolicy;
+
+    #[test]
+    fn test_escape_component() {
+        for (component, escaped) in &[
+            ("actions", "actions"),
+            ("setup-python", "setup-python"),
+            ("Foo", "%46oo"),
+            ("v1.2.3", "v1%2E2%2E3"),
+            ("feature/branch", "feature%2Fbranch"),
+            ("..", "%2E%2E"),
+            ("", ""),
+        ] {
+            assert_eq!(escape_component(component), *escaped);
+        }
+    }
+
+    #[test]
+    fn test_disk_cache_roundtrip() {
+        let root = std::env::temp_dir().join(format!(
+            "zizmor-test-disk-cache-{pid}",
+            pid = std::process::id()
+        ));
+        let root = Utf8PathBuf::from_path_buf(root).unwrap();
+
+        let cache = DiskCache::new(root.clone(), Duration::from_secs(60));
+        assert_eq!(cache.get::<Vec<String>>(&["tags", "foo", "bar"]), None);
+
+        cache
+            .put(&["tags", "foo", "bar"], &vec!["v1".to_string()])
+            .unwrap();
+        assert_eq!(
+            cache.get::<Vec<String>>(&["tags", "foo", "bar"]),
+            Some(vec!["v1".to_string()])
+        );
+
+        // A zero TTL means every entry is immediately stale.
+        let stale = DiskCache::new(root.clone(), Duration::ZERO);
+        std::thread::sleep(Duration::from_millis(10));
+        assert_eq!(stale.get::<Vec<String>>(&["tags", "foo", "bar"]), None);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_resolve_gh_tokens() {
+        let env = |vars: &'static [(&str, &str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(var, _)| *var == name)
+                    .map(|(_, value)| value.to_string())
+            }
+        };
+        let gh = || Some("gh".to_string());
+        let no_gh = || None;
+
+        let both = env(&[("GITHUB_TOKEN", "github"), ("GH_TOKEN", "gh-env")]);
+
+        // Explicit tokens beat the environment.
+        assert_eq!(
+            resolve_gh_tokens(&["explicit".into()], false, both, gh),
+            ["explicit"]
+        );
+        assert_eq!(
+            resolve_gh_tokens(&["one".into(), "two".into()], false, both, gh),
+            ["one", "two"]
+        );
+        assert_eq!(resolve_gh_tokens(&[], false, both, gh), ["github"]);
+        assert_eq!(
+            resolve_gh_tokens(&[], false, env(&[("GH_TOKEN", "gh-env")]), gh),
+            ["gh-env"]
+        );
+        // Variables may hold several tokens.
+        assert_eq!(
+            resolve_gh_tokens(&[], false, env(&[("GITHUB_TOKEN", "one, two,")]), gh),
+            ["one", "two"]
+        );
+        // Empty variables are treated as unset.
+        assert_eq!(
+            resolve_gh_tokens(
+                &[],
+                false,
+                env(&[("GITHUB_TOKEN", ""), ("GH_TOKEN", "gh-env")]),
+                gh
+            ),
+            ["gh-env"]
+        );
+        assert_eq!(
+            resolve_gh_tokens(&[], false, env(&[("GITHUB_TOKEN", ",")]), gh),
+            ["gh"]
+        );
+        assert_eq!(resolve_gh_tokens(&[], false, env(&[]), gh), ["gh"]);
+        assert!(resolve_gh_tokens(&[], false, env(&[]), no_gh).is_empty());
+
+        // `--offline` ignores the environment entirely.
+        assert!(resolve_gh_tokens(&[], true, both, gh).is_empty());
+    }
+
+    #[test]
+    fn test_audit_state_builder() {
+        let state = AuditState::builder().build();
+        assert!(!state.no_online_audits);
+        assert!(state.github_client().is_none());
+        assert!(state.advisory_db.is_none());
+        assert!(!state.include_withdrawn);
+        assert!(state.caches.disk.is_none());
+
+        let state = AuditState::builder()
+            .no_online_audits(true)
+            .gh_tokens(&["one".into(), "two".into()])
+            .retry(RetryPolicy {
+                max_retries: 0,
+                wait_on_rate_limit: false,
+            })
+            .build();
+        assert!(state.no_online_audits);
+        assert!(state.github_client().is_some());
+        assert_eq!(state.retry.max_retries, 0);
+
+        // Clones share the token pool, rather than copying it.
+        let clone = state.clone();
+        assert!(std::sync::Arc::ptr_eq(
+            state.gh_tokens.as_ref().unwrap(),
+            clone.gh_tokens.as_ref().unwrap()
+        ));
+
+        // No tokens means no client.
+        let state = AuditState::builder().gh_tokens(&[]).build();
+        assert!(state.github_client().is_none());
     }
 }
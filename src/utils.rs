@@ -1,5 +1,7 @@
 //! Helper routines.
 
+use std::cmp::Ordering;
+
 use github_actions_models::common::expr::ExplicitExpr;
 
 /// Convenience trait for inline transformations of `Self`.
@@ -19,6 +21,36 @@ pub(crate) trait PipeSelf<F> {
 
 impl<T, F> PipeSelf<F> for T where T: Sized {}
 
+/// Matches `text` against a pattern, where `*` matches any run of
+/// characters and `?` matches any single character.
+pub(crate) fn glob_matches(pattern: &str, text: &str) -> bool {
+    let (pattern, text) = (pattern.as_bytes(), text.as_bytes());
+
+    // The classic greedy wildcard match: on a mismatch, backtrack to just
+    // after the last `*` and let it swallow one more character.
+    let (mut p, mut c) = (0, 0);
+    let mut star = None;
+    while c < text.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                star = Some((p, c));
+                p += 1;
+            }
+            Some(b'?') => (p, c) = (p + 1, c + 1),
+            Some(&ch) if ch == text[c] => (p, c) = (p + 1, c + 1),
+            _ => match star {
+                Some((sp, sc)) => {
+                    star = Some((sp, sc + 1));
+                    (p, c) = (sp + 1, sc + 1);
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|ch| *ch == b'*')
+}
+
 /// Splits the given `patterns` string into one or more patterns, using
 /// approximately the same rules as GitHub's `@actions/glob` package.
 pub(crate) fn split_patterns(patterns: &str) -> impl Iterator<Item = &str> {
@@ -82,9 +114,198 @@ pub(crate) fn extract_expressions(text: &str) -> Vec<ExplicitExpr> {
     exprs
 }
 
+/// A loosely semantic version, like `v1`, `1.2`, or `v1.2.3-rc.1`.
+///
+/// Missing components are treated as zero, so `v1` and `1.0.0` are equal.
+/// Build metadata (`+...`) is ignored.
+#[derive(Debug)]
+pub(crate) struct Version {
+    release: [u64; 3],
+    prerelease: Option<String>,
+}
+
+impl Version {
+    pub(crate) fn parse(version: &str) -> Option<Self> {
+        let version = version.trim();
+        let version = version.strip_prefix('v').unwrap_or(version);
+        let version = version.split_once('+').map_or(version, |(v, _)| v);
+        let (version, prerelease) = match version.split_once('-') {
+            Some((version, prerelease)) => (version, Some(prerelease.into())),
+            None => (version, None),
+        };
+
+        let mut release = [0; 3];
+        let mut components = version.split('.');
+        for (idx, component) in components.by_ref().take(3).enumerate() {
+            release[idx] = component.parse().ok()?;
+        }
+
+        // More than three components isn't a version.
+        if components.next().is_some() {
+            return None;
+        }
+
+        Some(Self {
+            release,
+            prerelease,
+        })
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.release
+            .cmp(&other.release)
+            .then_with(|| match (&self.prerelease, &other.prerelease) {
+                (None, None) => Ordering::Equal,
+                // Prereleases come before their release.
+                (None, Some(_)) => Ordering::Greater,
+                (Some(_), None) => Ordering::Less,
+                (Some(lhs), Some(rhs)) => cmp_prerelease(lhs, rhs),
+            })
+    }
+}
+
+/// Compares two prerelease tags by semver precedence: dot-separated
+/// identifiers are compared in turn, numerically if both are numeric,
+/// and numeric identifiers sort before alphanumeric ones. If all else is
+/// equal, the tag with more identifiers is greater.
+fn cmp_prerelease(lhs: &str, rhs: &str) -> Ordering {
+    let mut lhs = lhs.split('.');
+    let mut rhs = rhs.split('.');
+
+    loop {
+        let ordering = match (lhs.next(), rhs.next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => Ordering::Less,
+            (Some(_), None) => Ordering::Greater,
+            (Some(l), Some(r)) => match (l.parse::<u64>(), r.parse::<u64>()) {
+                (Ok(l), Ok(r)) => l.cmp(&r),
+                (Ok(_), Err(_)) => Ordering::Less,
+                (Err(_), Ok(_)) => Ordering::Greater,
+                (Err(_), Err(_)) => l.cmp(r),
+            },
+        };
+
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+}
+
+impl PartialEq for Version {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Version {}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Returns whether `version` falls within the given version range,
+/// as expressed in GitHub's advisory database: one or more comma-separated
+/// constraints like `>= 1.0.0, < 1.4.2`, all of which must hold.
+///
+/// Returns `None` if the range can't be parsed.
+pub(crate) fn version_in_range(version: &Version, range: &str) -> Option<bool> {
+    let mut matches = true;
+
+    for constraint in range.split(',') {
+        let constraint = constraint.trim();
+        let (op, bound) = [">=", "<=", ">", "<", "="]
+            .into_iter()
+            .find_map(|op| constraint.strip_prefix(op).map(|bound| (op, bound)))
+            .unwrap_or(("=", constraint));
+        let bound = Version::parse(bound)?;
+
+        matches &= match op {
+            ">=" => *version >= bound,
+            "<=" => *version <= bound,
+            ">" => *version > bound,
+            "<" => *version < bound,
+            _ => *version == bound,
+        };
+    }
+
+    Some(matches)
+}
+
+/// Rewrites a single-line `uses: owner/repo@ref` feature so that it's
+/// pinned to `commit` instead of `git_ref`, with a trailing comment naming
+/// `comment` (typically the ref or tag that `commit` corresponds to).
+///
+/// Returns `None` if the feature isn't pinned to `git_ref`.
+pub(crate) fn repin_uses(
+    feature: &str,
+    git_ref: &str,
+    commit: &str,
+    comment: &str,
+) -> Option<String> {
+    if feature.contains('\n') {
+        return None;
+    }
+
+    // NOTE: The comment goes after the entire feature, so that it stays
+    // outside of any quotes around the `uses:` value.
+    let (head, tail) = feature.rsplit_once(&format!("@{git_ref}"))?;
+    Some(format!("{head}@{commit}{tail} # {comment}"))
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::utils::{extract_expression, extract_expressions};
+    use crate::utils::{
+        extract_expression, extract_expressions, glob_matches, repin_uses, version_in_range,
+        Version,
+    };
+
+    #[test]
+    fn test_version_parse() {
+        let v = |s| Version::parse(s).unwrap();
+
+        assert_eq!(v("v1"), v("1.0.0"));
+        assert_eq!(v("1.2"), v("v1.2.0"));
+        assert_eq!(v("1.2.3+build.5"), v("1.2.3"));
+        assert!(v("1.2.3-rc.1") < v("1.2.3"));
+        assert!(v("1.2.3-alpha") < v("1.2.3-beta"));
+        assert!(v("1.2.3-rc.9") < v("1.2.3-rc.10"));
+        assert!(v("1.2.3-rc.1") < v("1.2.3-rc.beta"));
+        assert!(v("1.2.3-alpha") < v("1.2.3-alpha.1"));
+        assert!(v("1.10.0") > v("1.9.9"));
+        assert!(v("2") > v("1.99.99"));
+
+        for invalid in ["", "main", "v1.x", "1.2.3.4", "release/v1"] {
+            assert!(Version::parse(invalid).is_none(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_version_in_range() {
+        for (version, range, expected) in [
+            ("1.3.0", "< 1.4.2", Some(true)),
+            ("1.4.2", "< 1.4.2", Some(false)),
+            ("v1.4.2-rc.1", "< 1.4.2", Some(true)),
+            ("1.3.0", ">= 1.0.0, < 1.4.2", Some(true)),
+            ("0.9.0", ">= 1.0.0, < 1.4.2", Some(false)),
+            ("2.0.0", "<= 2.0", Some(true)),
+            ("2.0.1", "<= 2.0", Some(false)),
+            ("2.0.1", "> 2.0", Some(true)),
+            ("1.2.3", "= 1.2.3", Some(true)),
+            ("1.2.3", "1.2.3", Some(true)),
+            ("1.2.4", "= 1.2.3", Some(false)),
+            ("1.2.3", "< main", None),
+        ] {
+            assert_eq!(
+                version_in_range(&Version::parse(version).unwrap(), range),
+                expected,
+                "{version} in {range}"
+            );
+        }
+    }
 
     #[test]
     fn split_patterns() {
@@ -156,4 +377,54 @@ mod tests {
             ]
         )
     }
+
+    #[test]
+    fn test_repin_uses() {
+        for (feature, expected) in [
+            (
+                "uses: actions/checkout@v4",
+                Some("uses: actions/checkout@abcd # v4"),
+            ),
+            (
+                "uses: \"actions/checkout@v4\"",
+                Some("uses: \"actions/checkout@abcd\" # v4"),
+            ),
+            (
+                "uses: github/codeql-action/init@v4",
+                Some("uses: github/codeql-action/init@abcd # v4"),
+            ),
+            ("uses: actions/checkout@v3", None),
+            ("uses: >-\n  actions/checkout@v4", None),
+        ] {
+            assert_eq!(
+                repin_uses(feature, "v4", "abcd", "v4").as_deref(),
+                expected,
+                "{feature}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_glob_matches() {
+        for (pattern, component, expected) in [
+            (".git", ".git", true),
+            (".git", ".github", false),
+            (".git", ".gitignore", false),
+            ("*.pem", "key.pem", true),
+            ("*.pem", ".pem", true),
+            ("*.pem", "key.pem.txt", false),
+            ("*.pem", "*.pem", true),
+            ("id_*", "id_rsa", true),
+            ("id_?sa", "id_rsa", true),
+            ("id_?sa", "id_ecdsa", false),
+            ("*a*b", "xaxxab", true),
+            ("*a*b", "xaxxa", false),
+        ] {
+            assert_eq!(
+                glob_matches(pattern, component),
+                expected,
+                "{pattern} vs. {component}"
+            );
+        }
+    }
 }
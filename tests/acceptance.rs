@@ -12,6 +12,11 @@ fn zizmor() -> Command {
     let mut cmd = Command::cargo_bin("zizmor").expect("Cannot create executable command");
     // All tests are currently offline, and we always need JSON output.
     cmd.args(["--offline", "--format", "json"]);
+    // Don't pick up the user's own config.
+    cmd.env(
+        "XDG_CONFIG_HOME",
+        workflow_under_test("config-discovery/no-user-config"),
+    );
     cmd
 }
 
@@ -54,7 +59,7 @@ fn audit_artipacked() -> anyhow::Result<()> {
 
     let findings = serde_json::from_slice(&execution.stdout)?;
 
-    assert_value_match(&findings, "$[0].determinations.confidence", "Low");
+    assert_value_match(&findings, "$[0].determinations.confidence", "low");
     assert_value_match(
         &findings,
         "$[0].locations[0].concrete.feature",
@@ -75,7 +80,7 @@ fn audit_excessive_permission() -> anyhow::Result<()> {
 
     let findings = serde_json::from_slice(&execution.stdout)?;
 
-    assert_value_match(&findings, "$[0].determinations.confidence", "High");
+    assert_value_match(&findings, "$[0].determinations.confidence", "high");
     assert_value_match(
         &findings,
         "$[0].locations[0].concrete.feature",
@@ -96,7 +101,7 @@ fn audit_hardcoded_credentials() -> anyhow::Result<()> {
 
     let findings = serde_json::from_slice(&execution.stdout)?;
 
-    assert_value_match(&findings, "$[0].determinations.confidence", "High");
+    assert_value_match(&findings, "$[0].determinations.confidence", "high");
     assert_value_match(
         &findings,
         "$[0].locations[0].concrete.feature",
@@ -117,7 +122,7 @@ fn audit_template_injection() -> anyhow::Result<()> {
 
     let findings = serde_json::from_slice(&execution.stdout)?;
 
-    assert_value_match(&findings, "$[0].determinations.confidence", "High");
+    assert_value_match(&findings, "$[0].determinations.confidence", "high");
     assert_value_match(
         &findings,
         "$[0].locations[0].concrete.feature",
@@ -138,7 +143,7 @@ fn audit_use_trusted_publishing() -> anyhow::Result<()> {
 
     let findings = serde_json::from_slice(&execution.stdout)?;
 
-    assert_value_match(&findings, "$[0].determinations.confidence", "High");
+    assert_value_match(&findings, "$[0].determinations.confidence", "high");
     assert_value_match(
         &findings,
         "$[0].locations[0].concrete.feature",
@@ -152,16 +157,15 @@ fn audit_use_trusted_publishing() -> anyhow::Result<()> {
 fn audit_self_hosted() -> anyhow::Result<()> {
     let auditable = workflow_under_test("self-hosted.yml");
 
-    // Note: self-hosted audit is auditor-only
-    let cli_args = ["--persona=auditor", &auditable];
+    let cli_args = [&auditable];
 
     let execution = zizmor().args(cli_args).output()?;
 
-    assert_eq!(execution.status.code(), Some(10));
+    assert_eq!(execution.status.code(), Some(13));
 
     let findings = serde_json::from_slice(&execution.stdout)?;
 
-    assert_value_match(&findings, "$[0].determinations.confidence", "High");
+    assert_value_match(&findings, "$[0].determinations.confidence", "high");
     assert_value_match(
         &findings,
         "$[0].locations[0].concrete.feature",
@@ -183,26 +187,32 @@ fn audit_unpinned_uses() -> anyhow::Result<()> {
 
     let findings = serde_json::from_slice(&execution.stdout)?;
 
-    assert_value_match(&findings, "$[0].determinations.confidence", "High");
-    assert_value_match(&findings, "$[0].determinations.severity", "Medium");
+    assert_value_match(&findings, "$[0].determinations.confidence", "high");
+    assert_value_match(&findings, "$[0].determinations.severity", "medium");
     assert_value_match(
         &findings,
         "$[0].locations[0].concrete.feature",
         "uses: actions/checkout",
     );
+    // Each location's 1-based position is resolved up front.
+    assert_value_match(&findings, "$[0].locations[0].span.start_line", "9");
+    assert_value_match(&findings, "$[0].locations[0].span.start_col", "9");
+    // Findings are ordered by position, so the `unpinned-actions`
+    // finding for the tag-pinned checkout is interleaved at index 1.
+    assert_value_match(&findings, "$[1].ident", "unpinned-actions");
     assert_value_match(
         &findings,
-        "$[1].locations[0].concrete.feature",
+        "$[2].locations[0].concrete.feature",
         "uses: github/codeql-action/upload-sarif",
     );
     assert_value_match(
         &findings,
-        "$[2].locations[0].concrete.feature",
+        "$[3].locations[0].concrete.feature",
         "uses: docker://ubuntu",
     );
     assert_value_match(
         &findings,
-        "$[3].locations[0].concrete.feature",
+        "$[4].locations[0].concrete.feature",
         "uses: docker://ghcr.io/pypa/gh-action-pypi-publish",
     );
 
@@ -221,7 +231,7 @@ fn audit_insecure_commands_allowed() -> anyhow::Result<()> {
 
     let findings = serde_json::from_slice(&execution.stdout)?;
 
-    assert_value_match(&findings, "$[0].determinations.confidence", "High");
+    assert_value_match(&findings, "$[0].determinations.confidence", "high");
     assert_value_match(
         &findings,
         "$[0].locations[0].concrete.feature",
@@ -243,7 +253,7 @@ fn audit_github_env_injection() -> anyhow::Result<()> {
 
     let findings = serde_json::from_slice(&execution.stdout)?;
 
-    assert_value_match(&findings, "$[0].determinations.confidence", "Low");
+    assert_value_match(&findings, "$[0].determinations.confidence", "low");
     assert_value_match(
         &findings,
         "$[0].locations[0].concrete.feature",
@@ -252,3 +262,554 @@ fn audit_github_env_injection() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn emits_jsonl() -> anyhow::Result<()> {
+    let auditable = workflow_under_test("template-injection.yml");
+
+    let execution = Command::cargo_bin("zizmor")?
+        .args(["--offline", "--format", "jsonl", &auditable])
+        .output()?;
+
+    assert_eq!(execution.status.code(), Some(14));
+
+    let stdout = String::from_utf8(execution.stdout)?;
+    let findings = stdout
+        .lines()
+        .map(serde_json::from_str::<Value>)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    assert!(!findings.is_empty());
+    for finding in &findings {
+        assert_value_match(finding, "$.determinations.confidence", "");
+        assert_value_match(finding, "$.locations[0].concrete.feature", "");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn emits_sarif() -> anyhow::Result<()> {
+    let auditable = workflow_under_test("template-injection.yml");
+
+    let execution = Command::cargo_bin("zizmor")?
+        .args(["--offline", "--format", "sarif", &auditable])
+        .output()?;
+
+    // SARIF output always exits successfully, so that the results
+    // can be uploaded.
+    assert_eq!(execution.status.code(), Some(0));
+
+    let sarif = serde_json::from_slice(&execution.stdout)?;
+
+    assert_value_match(&sarif, "$.version", "2.1.0");
+    assert_value_match(
+        &sarif,
+        "$.runs[0].tool.driver.rules[0].id",
+        "template-injection",
+    );
+    assert_value_match(&sarif, "$.runs[0].results[0].ruleId", "template-injection");
+    assert_value_match(&sarif, "$.runs[0].results[0].level", "error");
+    assert_value_match(
+        &sarif,
+        "$.runs[0].results[0].locations[0].physicalLocation.region.startLine",
+        "13",
+    );
+
+    Ok(())
+}
+
+#[test]
+fn parallel_audits_are_deterministic() -> anyhow::Result<()> {
+    let auditables = [
+        "artipacked.yml",
+        "excessive-permissions.yml",
+        "template-injection.yml",
+        "unpinned-uses.yml",
+    ]
+    .map(workflow_under_test);
+
+    let serial = zizmor().args(["--jobs", "1"]).args(&auditables).output()?;
+    let parallel = zizmor().args(["--jobs", "4"]).args(&auditables).output()?;
+
+    assert_eq!(serial.status.code(), parallel.status.code());
+    assert_eq!(
+        String::from_utf8(serial.stdout)?,
+        String::from_utf8(parallel.stdout)?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn writes_output_file() -> anyhow::Result<()> {
+    let auditable = workflow_under_test("template-injection.yml");
+    let output = std::env::temp_dir().join(format!(
+        "zizmor-writes-output-file-{pid}.json",
+        pid = std::process::id()
+    ));
+
+    let execution = zizmor()
+        .args(["--output", output.to_str().unwrap(), &auditable])
+        .output()?;
+
+    assert_eq!(execution.status.code(), Some(14));
+    assert!(execution.stdout.is_empty());
+
+    let findings = serde_json::from_str(&std::fs::read_to_string(&output)?)?;
+    std::fs::remove_file(&output)?;
+
+    assert_value_match(&findings, "$[0].determinations.confidence", "high");
+
+    Ok(())
+}
+
+#[test]
+fn writes_output_to_stdout() -> anyhow::Result<()> {
+    let auditable = workflow_under_test("template-injection.yml");
+
+    let execution = zizmor()
+        .args(["--output", "-", "--rule-stats", &auditable])
+        .output()?;
+
+    assert_eq!(execution.status.code(), Some(14));
+
+    // stdout is exactly the report, with everything else on stderr.
+    // With JSON output, the report includes the stats.
+    let report = serde_json::from_slice(&execution.stdout)?;
+    assert_value_match(&report, "$.findings[0].determinations.confidence", "high");
+    assert!(!String::from_utf8(execution.stderr)?.contains("rule_stats"));
+
+    Ok(())
+}
+
+#[test]
+fn emits_rule_stats() -> anyhow::Result<()> {
+    let auditable = workflow_under_test("template-injection.yml");
+
+    let execution = zizmor().args(["--rule-stats", &auditable]).output()?;
+
+    assert_eq!(execution.status.code(), Some(14));
+
+    // With JSON output, the stats are alongside the findings.
+    let report = serde_json::from_slice(&execution.stdout)?;
+
+    assert_value_match(&report, "$.findings[0].determinations.confidence", "high");
+    assert_value_match(&report, "$.rule_stats['template-injection'].api_calls", "0");
+    assert_value_match(&report, "$.rule_stats['template-injection'].elapsed", "");
+
+    // With other machine-readable formats, they're the last line on
+    // stderr, after any logging.
+    let execution = Command::cargo_bin("zizmor")?
+        .args(["--offline", "--format", "jsonl", "--rule-stats", &auditable])
+        .output()?;
+
+    let stderr = String::from_utf8(execution.stderr)?;
+    let stats = serde_json::from_str(stderr.lines().last().unwrap())?;
+
+    assert_value_match(&stats, "$.rule_stats['template-injection'].api_calls", "0");
+
+    Ok(())
+}
+
+#[test]
+fn min_severity_gates_exit_code() -> anyhow::Result<()> {
+    let auditable = workflow_under_test("artipacked.yml");
+
+    // artipacked.yml's highest finding is medium, so a medium threshold
+    // still fails...
+    let execution = zizmor()
+        .args(["--min-severity", "medium", &auditable])
+        .output()?;
+    assert_eq!(execution.status.code(), Some(13));
+
+    // ...while a high threshold filters everything out.
+    let execution = zizmor()
+        .args(["--min-severity", "high", &auditable])
+        .output()?;
+    assert_eq!(execution.status.code(), Some(0));
+    assert_eq!(String::from_utf8(execution.stdout)?, "[]");
+
+    Ok(())
+}
+
+#[test]
+fn min_severity_excludes_informational() -> anyhow::Result<()> {
+    let auditable = workflow_under_test("context-dump.yml");
+    let severities = |args: &[&str]| -> anyhow::Result<Vec<Value>> {
+        let execution = zizmor().args(args).arg(&auditable).output()?;
+        let findings: Value = serde_json::from_slice(&execution.stdout)?;
+
+        Ok(JsonPath::parse("$[*].determinations.severity")?
+            .query(&findings)
+            .all()
+            .into_iter()
+            .cloned()
+            .collect())
+    };
+
+    // context-dump.yml has an informational finding by default...
+    assert!(severities(&[])?.contains(&"informational".into()));
+
+    // ...which sits below low, and so is filtered out by it.
+    let severities = severities(&["--min-severity", "low"])?;
+    assert!(!severities.is_empty());
+    assert!(!severities.contains(&"informational".into()));
+    assert!(!severities.contains(&"unknown".into()));
+
+    Ok(())
+}
+
+#[test]
+fn min_confidence_gates_exit_code() -> anyhow::Result<()> {
+    let auditable = workflow_under_test("artipacked.yml");
+
+    // artipacked.yml's only finding is low-confidence.
+    let execution = zizmor()
+        .args(["--min-confidence", "medium", &auditable])
+        .output()?;
+    assert_eq!(execution.status.code(), Some(0));
+    assert_eq!(String::from_utf8(execution.stdout)?, "[]");
+
+    Ok(())
+}
+
+#[test]
+fn baseline_suppresses_known_findings() -> anyhow::Result<()> {
+    let auditable = workflow_under_test("template-injection.yml");
+    let scratch =
+        std::env::temp_dir().join(format!("zizmor-baseline-{pid}", pid = std::process::id()));
+    std::fs::create_dir_all(&scratch)?;
+    let baseline = scratch.join("baseline.json");
+
+    let execution = zizmor()
+        .args(["--write-baseline", baseline.to_str().unwrap(), &auditable])
+        .output()?;
+    assert_eq!(execution.status.code(), Some(14));
+
+    // Shift every finding down a few lines: the baseline should still match.
+    let edited = scratch.join("template-injection.yml");
+    std::fs::write(
+        &edited,
+        format!(
+            "# an unrelated change\n\n{original}",
+            original = std::fs::read_to_string(&auditable)?
+        ),
+    )?;
+
+    let execution = zizmor()
+        .args([
+            "--baseline",
+            baseline.to_str().unwrap(),
+            edited.to_str().unwrap(),
+        ])
+        .output()?;
+    std::fs::remove_dir_all(&scratch)?;
+
+    assert_eq!(execution.status.code(), Some(0));
+    assert_eq!(String::from_utf8(execution.stdout)?, "[]");
+
+    Ok(())
+}
+
+#[test]
+fn audits_stdin() -> anyhow::Result<()> {
+    let auditable = workflow_under_test("artipacked.yml");
+
+    let from_file = zizmor().args([&auditable]).output()?;
+    let from_stdin = zizmor()
+        .arg("-")
+        .write_stdin(std::fs::read(&auditable)?)
+        .output()?;
+
+    assert_eq!(from_stdin.status.code(), from_file.status.code());
+
+    let from_file: Value = serde_json::from_slice(&from_file.stdout)?;
+    let from_stdin: Value = serde_json::from_slice(&from_stdin.stdout)?;
+
+    assert_value_match(&from_stdin, "$[0].locations[0].symbolic.key", "Stdin");
+    assert_eq!(
+        from_stdin[0]["locations"][0]["concrete"],
+        from_file[0]["locations"][0]["concrete"]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn directory_skips_unparseable_workflows() -> anyhow::Result<()> {
+    let scratch =
+        std::env::temp_dir().join(format!("zizmor-directory-{pid}", pid = std::process::id()));
+    let workflows = scratch.join(".github/workflows");
+    std::fs::create_dir_all(&workflows)?;
+    std::fs::copy(
+        workflow_under_test("artipacked.yml"),
+        workflows.join("artipacked.yml"),
+    )?;
+    std::fs::write(workflows.join("broken.yml"), "on: push\njobs: [\n")?;
+    std::fs::write(workflows.join("README.md"), "not a workflow")?;
+
+    let execution = zizmor().arg(&scratch).output()?;
+    std::fs::remove_dir_all(&scratch)?;
+
+    // The broken workflow is skipped, but the rest are still audited.
+    assert_eq!(execution.status.code(), Some(13));
+    assert!(String::from_utf8(execution.stderr)?.contains("broken.yml"));
+
+    let findings = serde_json::from_slice(&execution.stdout)?;
+    assert_value_match(&findings, "$[0].ident", "artipacked");
+
+    Ok(())
+}
+
+#[test]
+fn findings_have_fingerprints() -> anyhow::Result<()> {
+    let auditable = workflow_under_test("artipacked.yml");
+
+    let execution = zizmor().args([&auditable]).output()?;
+    let findings: Value = serde_json::from_slice(&execution.stdout)?;
+    let fingerprint = &findings[0]["fingerprint"];
+    assert!(fingerprint.as_str().is_some_and(|fp| fp.len() == 16));
+
+    // SARIF results carry the same fingerprint.
+    let execution = Command::cargo_bin("zizmor")?
+        .args(["--offline", "--format", "sarif", &auditable])
+        .output()?;
+    let sarif = serde_json::from_slice(&execution.stdout)?;
+    assert_value_match(
+        &sarif,
+        "$.runs[0].results[0].partialFingerprints['zizmor/v1']",
+        fingerprint.as_str().unwrap(),
+    );
+
+    Ok(())
+}
+
+#[test]
+fn skips_non_workflow_yaml() -> anyhow::Result<()> {
+    let dependabot = workflow_under_test("non-workflow/dependabot.yml");
+    let multi_document = workflow_under_test("non-workflow/multi-document.yml");
+
+    // A lone non-workflow input is skipped, leaving nothing to audit.
+    let execution = zizmor().args([&dependabot]).output()?;
+    assert_eq!(execution.status.code(), Some(1));
+    let stderr = String::from_utf8(execution.stderr)?;
+    assert!(stderr.contains("not a workflow"));
+    assert!(stderr.contains("no workflow or action files collected"));
+
+    // Alongside a workflow, it's skipped without failing the run.
+    let execution = zizmor().args([&dependabot, &multi_document]).output()?;
+    assert_eq!(execution.status.code(), Some(14));
+    assert!(String::from_utf8(execution.stderr)?.contains("skipping document 2"));
+
+    let findings: Value = serde_json::from_slice(&execution.stdout)?;
+    let documents = findings
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|finding| &finding["locations"][0]["symbolic"]["key"]["Local"]["document"])
+        .collect::<Vec<_>>();
+    assert!(documents.contains(&&Value::from(0)));
+    assert!(documents.contains(&&Value::from(2)));
+
+    Ok(())
+}
+
+#[test]
+fn findings_have_references() -> anyhow::Result<()> {
+    let auditable = workflow_under_test("template-injection.yml");
+
+    let execution = zizmor().args([&auditable]).output()?;
+    let findings: Value = serde_json::from_slice(&execution.stdout)?;
+    assert_value_match(
+        &findings,
+        "$[0].references[0]",
+        "#understanding-the-risk-of-script-injections",
+    );
+
+    // Findings from audits without references don't have the key at all.
+    let execution = zizmor()
+        .args([&workflow_under_test("artipacked.yml")])
+        .output()?;
+    let findings: Value = serde_json::from_slice(&execution.stdout)?;
+    assert!(findings[0].get("references").is_none());
+
+    // SARIF results link to them in their Markdown message.
+    let execution = Command::cargo_bin("zizmor")?
+        .args(["--offline", "--format", "sarif", &auditable])
+        .output()?;
+    let sarif = serde_json::from_slice(&execution.stdout)?;
+    assert_value_match(
+        &sarif,
+        "$.runs[0].results[0].message.markdown",
+        "#understanding-the-risk-of-script-injections",
+    );
+
+    Ok(())
+}
+
+#[test]
+fn verbosity_only_changes_stderr() -> anyhow::Result<()> {
+    let dependabot = workflow_under_test("non-workflow/dependabot.yml");
+    let auditable = workflow_under_test("template-injection.yml");
+
+    let default = zizmor().args([&dependabot, &auditable]).output()?;
+    let quiet = zizmor().args(["-q", &dependabot, &auditable]).output()?;
+    let verbose = zizmor().args(["-v", &dependabot, &auditable]).output()?;
+
+    assert_eq!(default.stdout, quiet.stdout);
+    assert_eq!(default.stdout, verbose.stdout);
+
+    // Warnings are shown by default, but not with `--quiet`.
+    assert!(String::from_utf8(default.stderr)?.contains("not a workflow"));
+    assert!(String::from_utf8(quiet.stderr)?.is_empty());
+
+    // `--verbose` adds debug logs, including each location query.
+    let stderr = String::from_utf8(verbose.stderr)?;
+    assert!(stderr.contains("DEBUG"));
+    assert!(stderr.contains("querying"));
+
+    Ok(())
+}
+
+#[test]
+fn known_vulnerable_actions_resolved_version() -> anyhow::Result<()> {
+    let auditable = workflow_under_test("known-vulnerable-actions/offline.yml");
+    let advisory_db = workflow_under_test("known-vulnerable-actions/advisory-db.json");
+
+    let execution = zizmor()
+        .args([
+            "--select",
+            "known-vulnerable-actions",
+            "--advisory-db",
+            &advisory_db,
+            &auditable,
+        ])
+        .output()?;
+    let findings: Value = serde_json::from_slice(&execution.stdout)?;
+
+    // The tag-pinned action records both its `uses:` and the version that
+    // was checked against its advisories.
+    assert_value_match(
+        &findings,
+        "$[0].properties.uses",
+        "example/vulnerable@v1.3.0",
+    );
+    assert_value_match(&findings, "$[0].properties['resolved-version']", "v1.3.0");
+    assert_value_match(&findings, "$[0].properties.resolution", "offline");
+
+    Ok(())
+}
+
+#[test]
+fn config_discovery_precedence() -> anyhow::Result<()> {
+    let repo = workflow_under_test("config-discovery/repo");
+    let user_config_home = workflow_under_test("config-discovery/user");
+
+    let findings = |args: &[&str], config_home: Option<&str>| -> anyhow::Result<Vec<Value>> {
+        let mut cmd = zizmor();
+        if let Some(config_home) = config_home {
+            cmd.env("XDG_CONFIG_HOME", config_home);
+        }
+        let execution = cmd.args(["--select", "artipacked"]).args(args).output()?;
+        Ok(serde_json::from_slice(&execution.stdout)?)
+    };
+
+    // Without any config, the repository's medium-severity finding is reported.
+    assert_eq!(findings(&["--no-config", &repo], None)?.len(), 1);
+
+    // The repository's `zizmor.yml` sets `min-severity: high`, which beats
+    // the default...
+    assert!(findings(&[&repo], None)?.is_empty());
+
+    // ...as well as the user's config, which isn't loaded at all...
+    assert!(findings(&[&repo], Some(&user_config_home))?.is_empty());
+
+    // ...but not `--min-severity`, or an explicit `--config`.
+    assert_eq!(findings(&["--min-severity", "low", &repo], None)?.len(), 1);
+    let explicit = workflow_under_test("config-discovery/explicit.yml");
+    assert_eq!(findings(&["--config", &explicit, &repo], None)?.len(), 1);
+
+    // Without a config in the repository, the user's config (which
+    // disables artipacked) is loaded instead.
+    let auditable = workflow_under_test("artipacked.yml");
+    assert!(!findings(&[&auditable], None)?.is_empty());
+    assert!(findings(&[&auditable], Some(&user_config_home))?.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn exclusions() -> anyhow::Result<()> {
+    let repo = workflow_under_test("exclusions");
+
+    let run = |args: &[&str]| -> anyhow::Result<(Vec<Value>, Option<i32>, String)> {
+        let execution = zizmor()
+            .args(["--select", "artipacked", "--verbose"])
+            .args(args)
+            .output()?;
+        Ok((
+            serde_json::from_slice(&execution.stdout)?,
+            execution.status.code(),
+            String::from_utf8(execution.stderr)?,
+        ))
+    };
+
+    // Without any config, every workflow is audited.
+    let (findings, _, _) = run(&["--no-config", &repo])?;
+    assert_eq!(findings.len(), 3);
+
+    // The config's `exclude:` glob and the `.zizmorignore` entry each skip one.
+    let (findings, _, stderr) = run(&[&repo])?;
+    assert_eq!(findings.len(), 1);
+    assert!(
+        findings[0]["locations"][0]["symbolic"]["key"]["Local"]["path"]
+            .as_str()
+            .unwrap()
+            .ends_with("ci.yml")
+    );
+    assert!(stderr.contains("skipped 2 excluded files"));
+
+    // Excluded files are skipped even when passed explicitly, and don't
+    // affect the exit code.
+    let (findings, code, _) = run(&[&workflow_under_test(
+        "exclusions/.github/workflows/generated-release.yml",
+    )])?;
+    assert!(findings.is_empty());
+    assert_eq!(code, Some(0));
+
+    Ok(())
+}
+
+#[test]
+fn error_on_findings_exit_codes() -> anyhow::Result<()> {
+    let clean = workflow_under_test("inlined-ignores.yml");
+    let findings = workflow_under_test("artipacked.yml");
+
+    let exit_code = |args: &[&str]| -> anyhow::Result<Option<i32>> {
+        Ok(zizmor().args(args).output()?.status.code())
+    };
+
+    // A clean run always succeeds.
+    assert_eq!(exit_code(&[&clean])?, Some(0));
+    assert_eq!(exit_code(&["--error-on-findings=false", &clean])?, Some(0));
+
+    // By default (or explicitly), findings exit with their highest severity...
+    assert_eq!(exit_code(&[&findings])?, Some(13));
+    assert_eq!(exit_code(&["--error-on-findings", &findings])?, Some(13));
+
+    // ...but only findings that survive filtering count.
+    assert_eq!(exit_code(&["--min-severity", "high", &findings])?, Some(0));
+
+    // With the flag off, findings don't affect the exit code, but errors still do.
+    assert_eq!(
+        exit_code(&["--error-on-findings=false", &findings])?,
+        Some(0)
+    );
+    assert_eq!(
+        exit_code(&["--error-on-findings=false", "does-not-exist.yml"])?,
+        Some(1)
+    );
+
+    Ok(())
+}
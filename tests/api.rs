@@ -0,0 +1,84 @@
+use std::fs;
+
+use common::workflow_under_test;
+use zizmor::{audit_workflow, AuditConfig, Confidence, Persona, Severity};
+
+mod common;
+
+// Tests for zizmor's public library API.
+
+const WORKFLOW: &str = r#"
+on: pull_request_target
+
+jobs:
+  test:
+    runs-on: ubuntu-latest
+    steps:
+      - run: echo "${{ github.event.pull_request.title }}"
+"#;
+
+#[test]
+fn audits_workflow() -> anyhow::Result<()> {
+    let findings = audit_workflow(WORKFLOW, ".github/workflows/ci.yml", &AuditConfig::new())?;
+
+    let injection = findings
+        .iter()
+        .find(|f| f.ident == "template-injection")
+        .expect("missing template-injection finding");
+    assert_eq!(injection.severity, Severity::High);
+    assert_eq!(injection.confidence, Confidence::High);
+
+    let location = &injection.locations[0];
+    assert_eq!(location.path, ".github/workflows/ci.yml");
+    assert_eq!(location.start.line, 8);
+    assert!(location.feature.contains("github.event.pull_request.title"));
+
+    Ok(())
+}
+
+#[test]
+fn audits_action() -> anyhow::Result<()> {
+    let path = workflow_under_test("composite-action/action.yml");
+    let source = fs::read_to_string(&path)?;
+
+    let findings = audit_workflow(&source, &path, &AuditConfig::new())?;
+    assert!(findings.iter().all(|f| f.locations[0].path == path));
+
+    Ok(())
+}
+
+#[test]
+fn applies_config() -> anyhow::Result<()> {
+    let config = AuditConfig::from_yaml("rules:\n  template-injection:\n    disable: true\n")?;
+    let findings = audit_workflow(WORKFLOW, ".github/workflows/ci.yml", &config)?;
+    assert!(findings.iter().all(|f| f.ident != "template-injection"));
+
+    let config =
+        AuditConfig::from_yaml("rules:\n  template-injection:\n    ignore:\n      - ci.yml:8\n")?;
+    let findings = audit_workflow(WORKFLOW, ".github/workflows/ci.yml", &config)?;
+    assert!(findings.iter().all(|f| f.ident != "template-injection"));
+
+    assert!(AuditConfig::from_yaml("rules:\n  not-an-audit:\n    disable: true\n").is_err());
+
+    Ok(())
+}
+
+#[test]
+fn filters_by_persona() -> anyhow::Result<()> {
+    let regular = audit_workflow(WORKFLOW, "ci.yml", &AuditConfig::new())?;
+    let auditor = audit_workflow(
+        WORKFLOW,
+        "ci.yml",
+        &AuditConfig::new().persona(Persona::Auditor),
+    )?;
+
+    assert!(regular.iter().all(|f| f.persona >= Persona::Regular));
+    assert!(auditor.len() > regular.len());
+
+    Ok(())
+}
+
+#[test]
+fn rejects_invalid_workflow() {
+    assert!(audit_workflow("jobs: [", "ci.yml", &AuditConfig::new()).is_err());
+}
@@ -21,7 +21,12 @@ struct Zizmor {
 impl Zizmor {
     /// Create a new zizmor runner.
     fn new() -> Self {
-        let cmd = Command::cargo_bin("zizmor").unwrap();
+        let mut cmd = Command::cargo_bin("zizmor").unwrap();
+        // Don't pick up the user's own config.
+        cmd.env(
+            "XDG_CONFIG_HOME",
+            workflow_under_test("config-discovery/no-user-config"),
+        );
 
         Self {
             cmd,
@@ -100,29 +105,43 @@ fn test_cant_retrieve() -> Result<()> {
         .args(["pypa/sampleproject"])
         .run()?);
 
-    Ok(())
-}
-
-#[test]
-fn test_conflicting_online_options() -> Result<()> {
     insta::assert_snapshot!(zizmor()
         .output(OutputMode::Stderr)
-        .setenv("GH_TOKEN", "phony")
         .offline(true)
+        .args(["--repo", "pypa/sampleproject", "--ref", "main"])
         .run()?);
 
+    Ok(())
+}
+
+#[test]
+fn test_conflicting_online_options() -> Result<()> {
     insta::assert_snapshot!(zizmor()
         .output(OutputMode::Stderr)
         .offline(true)
         .args(["--gh-token=phony"])
         .run()?);
 
-    insta::assert_snapshot!(zizmor()
-        .output(OutputMode::Stderr)
-        .setenv("ZIZMOR_OFFLINE", "true")
-        .setenv("GH_TOKEN", "phony")
-        .offline(false) // explicitly disable so that we test ZIZMOR_OFFLINE above
-        .run()?);
+    Ok(())
+}
+
+#[test]
+fn test_offline_ignores_env_token() -> Result<()> {
+    // A token in the environment doesn't conflict with `--offline`;
+    // it's just ignored.
+    for var in ["GITHUB_TOKEN", "GH_TOKEN"] {
+        insta::assert_snapshot!(zizmor()
+            .setenv(var, "phony")
+            .workflow(workflow_under_test("artipacked.yml"))
+            .run()?);
+
+        insta::assert_snapshot!(zizmor()
+            .setenv("ZIZMOR_OFFLINE", "true")
+            .setenv(var, "phony")
+            .offline(false) // explicitly disable so that we test ZIZMOR_OFFLINE above
+            .workflow(workflow_under_test("artipacked.yml"))
+            .run()?);
+    }
 
     Ok(())
 }
@@ -198,6 +217,13 @@ fn self_hosted() -> Result<()> {
         .args(["--persona=auditor"])
         .run()?);
 
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test(
+            "self-hosted/self-hosted-custom-label.yml"
+        ))
+        .args(["--persona=auditor"])
+        .run()?);
+
     Ok(())
 }
 
@@ -215,6 +241,62 @@ fn unpinned_uses() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn unpinned_actions() -> Result<()> {
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("unpinned-actions.yml"))
+        .run()?);
+
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("unpinned-actions.yml"))
+        .args([
+            "--config",
+            &workflow_under_test("unpinned-actions/exempt-first-party.yml"),
+        ])
+        .run()?);
+
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("unpinned-actions.yml"))
+        .args([
+            "--config",
+            &workflow_under_test("unpinned-actions/trusted-actions.yml"),
+        ])
+        .run()?);
+
+    Ok(())
+}
+
+#[test]
+fn excessive_permissions() -> Result<()> {
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test(
+            "excessive-permissions/pull-request.yml"
+        ))
+        .run()?);
+
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test(
+            "excessive-permissions/pull-request.yml"
+        ))
+        .args(["--pedantic"])
+        .run()?);
+
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test(
+            "excessive-permissions/no-permissions.yml"
+        ))
+        .args(["--pedantic"])
+        .run()?);
+
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test(
+            "excessive-permissions/pull-request-workflow-level.yml"
+        ))
+        .run()?);
+
+    Ok(())
+}
+
 #[test]
 fn insecure_commands() -> Result<()> {
     insta::assert_snapshot!(zizmor()
@@ -226,6 +308,10 @@ fn insecure_commands() -> Result<()> {
         .workflow(workflow_under_test("insecure-commands.yml"))
         .run()?);
 
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("insecure-commands-emitted.yml"))
+        .run()?);
+
     Ok(())
 }
 
@@ -245,6 +331,12 @@ fn template_injection() -> Result<()> {
         .args(["--persona=auditor"])
         .run()?);
 
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test(
+            "template-injection/repeated-expression.yml"
+        ))
+        .run()?);
+
     // Fixed regressions
 
     insta::assert_snapshot!(zizmor()
@@ -253,3 +345,563 @@ fn template_injection() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn scheduled_workflow() -> Result<()> {
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("scheduled-workflow.yml"))
+        .args(["--pedantic"])
+        .run()?);
+
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("scheduled-workflow.yml"))
+        .run()?);
+
+    Ok(())
+}
+
+#[test]
+fn widened_permissions() -> Result<()> {
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("widened-permissions.yml"))
+        .run()?);
+
+    Ok(())
+}
+
+#[test]
+fn context_dump() -> Result<()> {
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("context-dump.yml"))
+        .run()?);
+
+    Ok(())
+}
+
+#[test]
+fn unpinned_reusable_workflow() -> Result<()> {
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("unpinned-reusable-workflow.yml"))
+        .run()?);
+
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("unpinned-reusable-workflow.yml"))
+        .args([
+            "--config",
+            &workflow_under_test("unpinned-reusable-workflow/trusted-actions.yml"),
+        ])
+        .run()?);
+
+    Ok(())
+}
+
+#[test]
+fn pull_request_target_checkout() -> Result<()> {
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("pull-request-target-checkout.yml"))
+        .args(["--persona=auditor"])
+        .run()?);
+
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("pull-request-target-checkout.yml"))
+        .run()?);
+
+    Ok(())
+}
+
+#[test]
+fn concise_format() -> Result<()> {
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("artipacked.yml"))
+        .args(["--format=concise"])
+        .run()?);
+
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("artipacked.yml"))
+        .args(["--format=concise", "--no-summary"])
+        .run()?);
+
+    Ok(())
+}
+
+#[test]
+fn sunset_actions() -> Result<()> {
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("sunset-actions.yml"))
+        .run()?);
+
+    Ok(())
+}
+
+#[test]
+fn composite_action() -> Result<()> {
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("composite-action"))
+        .run()?);
+
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("composite-action/action.yml"))
+        .run()?);
+
+    Ok(())
+}
+
+#[test]
+fn local_actions() -> Result<()> {
+    // Local actions used by the workflow are audited too, recursively.
+    // Their paths aren't the input's, so we redact the workspace root ourselves.
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test(
+            "local-actions/.github/workflows/ci.yml"
+        ))
+        .run()?
+        .replace(&workflow_under_test("local-actions"), "@@ROOT@@"));
+
+    Ok(())
+}
+
+#[test]
+fn local_action_path() -> Result<()> {
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test(
+            "local-action-path/.github/workflows/local-action-path.yml"
+        ))
+        .run()?);
+
+    Ok(())
+}
+
+#[test]
+fn github_format() -> Result<()> {
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("template-injection.yml"))
+        .args(["--format=github"])
+        .run()?);
+
+    Ok(())
+}
+
+#[test]
+fn run_shell() -> Result<()> {
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("run-shell.yml"))
+        .run()?);
+
+    Ok(())
+}
+
+#[test]
+fn secret_leak() -> Result<()> {
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("secret-leak.yml"))
+        .run()?);
+
+    Ok(())
+}
+
+#[test]
+fn known_vulnerable_actions_offline() -> Result<()> {
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("known-vulnerable-actions/offline.yml"))
+        .args([
+            "--advisory-db",
+            &workflow_under_test("known-vulnerable-actions/advisory-db.json"),
+        ])
+        .run()?);
+
+    // Tag-pinned vulnerable actions are also flagged by `unpinned-actions`.
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("known-vulnerable-actions/offline.yml"))
+        .args([
+            "--advisory-db",
+            &workflow_under_test("known-vulnerable-actions/advisory-db.json"),
+            "--dedupe",
+        ])
+        .run()?);
+
+    // Withdrawn advisories are only checked on request.
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("known-vulnerable-actions/offline.yml"))
+        .args([
+            "--advisory-db",
+            &workflow_under_test("known-vulnerable-actions/advisory-db.json"),
+            "--include-withdrawn",
+        ])
+        .run()?);
+
+    Ok(())
+}
+
+#[test]
+fn container_image() -> Result<()> {
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("container-image.yml"))
+        .run()?);
+
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("container-image.yml"))
+        .args([
+            "--config",
+            &workflow_under_test("container-image/trusted-registries.yml"),
+        ])
+        .run()?);
+
+    Ok(())
+}
+
+#[test]
+fn missing_timeout() -> Result<()> {
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("missing-timeout.yml"))
+        .args(["--pedantic"])
+        .run()?);
+
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("missing-timeout.yml"))
+        .args([
+            "--config",
+            &workflow_under_test("missing-timeout/max-timeout.yml"),
+        ])
+        .run()?);
+
+    Ok(())
+}
+
+#[test]
+fn explicit_shell() -> Result<()> {
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("explicit-shell.yml"))
+        .args(["--pedantic"])
+        .run()?);
+
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("explicit-shell/workflow-defaults.yml"))
+        .args(["--pedantic"])
+        .run()?);
+
+    Ok(())
+}
+
+#[test]
+fn workflow_run_artifacts() -> Result<()> {
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("workflow-run-artifacts.yml"))
+        .run()?);
+
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("workflow-run-artifacts/push.yml"))
+        .run()?);
+
+    Ok(())
+}
+
+#[test]
+fn oidc_permissions() -> Result<()> {
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("oidc-permissions.yml"))
+        .run()?);
+
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("oidc-permissions/unused.yml"))
+        .run()?);
+
+    Ok(())
+}
+
+#[test]
+fn job_graph() -> Result<()> {
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("job-graph.yml"))
+        .run()?);
+
+    Ok(())
+}
+
+#[test]
+fn list_audits() -> Result<()> {
+    insta::assert_snapshot!(zizmor().args(["--list-audits"]).run()?);
+
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("artipacked.yml"))
+        .args([
+            "--config",
+            &workflow_under_test("list-audits/unknown-audit.yml"),
+        ])
+        .output(OutputMode::Stderr)
+        .run()?);
+
+    Ok(())
+}
+
+#[test]
+fn select_and_skip() -> Result<()> {
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("oidc-permissions.yml"))
+        .args(["--select", "oidc-permissions,job-graph"])
+        .run()?);
+
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("oidc-permissions.yml"))
+        .args(["--skip", "oidc-permissions"])
+        .run()?);
+
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("oidc-permissions.yml"))
+        .args(["--select", "oidc-permisions"])
+        .output(OutputMode::Stderr)
+        .run()?);
+
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("oidc-permissions.yml"))
+        .args(["--select", "oidc-permissions", "--skip", "job-graph"])
+        .output(OutputMode::Stderr)
+        .run()?);
+
+    Ok(())
+}
+
+#[test]
+fn artifact_leak() -> Result<()> {
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("artifact-leak.yml"))
+        .run()?);
+
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("artifact-leak.yml"))
+        .args(["--config", &workflow_under_test("artifact-leak/config.yml")])
+        .run()?);
+
+    Ok(())
+}
+
+#[test]
+fn dispatch_bypass() -> Result<()> {
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("dispatch-bypass.yml"))
+        .run()?);
+
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test(
+            "dispatch-bypass/filtered-pull-request.yml"
+        ))
+        .args(["--persona", "auditor"])
+        .run()?);
+
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test(
+            "dispatch-bypass/unfiltered-pull-request.yml"
+        ))
+        .args(["--persona", "auditor"])
+        .run()?);
+
+    Ok(())
+}
+
+#[test]
+fn continue_on_error() -> Result<()> {
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("continue-on-error.yml"))
+        .run()?);
+
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("continue-on-error.yml"))
+        .args([
+            "--config",
+            &workflow_under_test("continue-on-error/config.yml")
+        ])
+        .run()?);
+
+    Ok(())
+}
+
+#[test]
+fn summary_format() -> Result<()> {
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("artipacked.yml"))
+        .args(["--format=summary"])
+        .run()?);
+
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("artipacked.yml"))
+        .args(["--format=summary", "--persona=pedantic"])
+        .run()?);
+
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("job-graph.yml"))
+        .args(["--format=summary", "--min-severity=high"])
+        .run()?);
+
+    Ok(())
+}
+
+#[test]
+fn github_path_injection() -> Result<()> {
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("github-path-injection.yml"))
+        .run()?);
+
+    Ok(())
+}
+
+#[test]
+fn anchors() -> Result<()> {
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("anchors.yml"))
+        .run()?);
+
+    Ok(())
+}
+
+#[test]
+fn if_condition() -> Result<()> {
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("if-condition.yml"))
+        .run()?);
+
+    Ok(())
+}
+
+#[test]
+fn environment_exposure() -> Result<()> {
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("environment-exposure.yml"))
+        .run()?);
+
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("environment-exposure/push-only.yml"))
+        .run()?);
+
+    Ok(())
+}
+
+#[test]
+fn token_passthrough() -> Result<()> {
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("token-passthrough.yml"))
+        .run()?);
+
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("token-passthrough.yml"))
+        .args([
+            "--config",
+            &workflow_under_test("token-passthrough/trusted-actions.yml"),
+        ])
+        .run()?);
+
+    Ok(())
+}
+
+#[test]
+fn tainted_env() -> Result<()> {
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("tainted-env.yml"))
+        .run()?);
+
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("tainted-env.yml"))
+        .args(["--persona=auditor"])
+        .run()?);
+
+    Ok(())
+}
+
+#[test]
+fn multi_document() -> Result<()> {
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("non-workflow/multi-document.yml"))
+        .run()?);
+
+    Ok(())
+}
+
+#[test]
+fn deprecated_commands() -> Result<()> {
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("deprecated-commands.yml"))
+        .run()?);
+
+    Ok(())
+}
+
+#[test]
+fn pr_cache_poisoning() -> Result<()> {
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("pr-cache-poisoning.yml"))
+        .run()?);
+
+    Ok(())
+}
+
+#[test]
+fn hardcoded_secret() -> Result<()> {
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("hardcoded-secret.yml"))
+        .run()?);
+
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("hardcoded-secret.yml"))
+        .args([
+            "--config",
+            &workflow_under_test("hardcoded-secret/prefixes-only.yml"),
+        ])
+        .run()?);
+
+    Ok(())
+}
+
+#[test]
+fn required_step() -> Result<()> {
+    // Nothing is required by default.
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("required-step.yml"))
+        .args(["--select", "required-step"])
+        .run()?);
+
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("required-step.yml"))
+        .args([
+            "--select",
+            "required-step",
+            "--config",
+            &workflow_under_test("required-step/harden-runner.yml"),
+        ])
+        .run()?);
+
+    Ok(())
+}
+
+#[test]
+fn invalid_config() -> Result<()> {
+    // The error names the offending key, rather than just failing to parse.
+    for config in ["invalid.yml", "invalid-value.yml"] {
+        insta::assert_snapshot!(zizmor()
+            .output(OutputMode::Stderr)
+            .workflow(workflow_under_test("config-discovery/repo"))
+            .args([
+                "--config",
+                &workflow_under_test(&format!("config-discovery/{config}")),
+            ])
+            .run()?
+            .replace(&workflow_under_test("config-discovery"), "@@ROOT@@"));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn shell_quoting() -> Result<()> {
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("shell-quoting.yml"))
+        .run()?);
+
+    Ok(())
+}
+
+#[test]
+fn cross_run_artifact() -> Result<()> {
+    insta::assert_snapshot!(zizmor()
+        .workflow(workflow_under_test("cross-run-artifact.yml"))
+        .run()?);
+
+    Ok(())
+}